@@ -0,0 +1,263 @@
+//! Biome storage. Biomes are stored for every 4x4x4 cell of blocks, which is
+//! what vanilla has used since 1.15. Clients before 1.15 only have one biome
+//! for each column of blocks, so the proxy will pick one of the cells in that
+//! column.
+
+use bb_macros::Transfer;
+
+/// Information about a single vanilla biome.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BiomeInfo {
+  /// The name of the biome (without the `minecraft:` prefix), as of 1.19.
+  pub name:          &'static str,
+  /// The numeric id used before 1.18. This is what is sent to 1.8-1.15
+  /// clients, and what is stored in pre-1.18 worlds. Biomes added in 1.18
+  /// use the id of the closest older biome.
+  pub legacy_id:     u8,
+  /// Used by the client to compute grass and foliage colors.
+  pub temperature:   f32,
+  /// Used by the client to compute grass and foliage colors.
+  pub downfall:      f32,
+  /// One of `none`, `rain` or `snow`.
+  pub precipitation: &'static str,
+  /// The biome category, which was removed from the codec in 1.19.
+  pub category:      &'static str,
+}
+
+macro_rules! biomes {
+  ( $( $name:literal, $legacy:literal, $temp:literal, $downfall:literal, $precip:literal, $cat:literal; )* ) => {
+    /// Every biome. Biomes are stored as indices into this list.
+    pub const BIOMES: &[BiomeInfo] = &[
+      $(
+        BiomeInfo {
+          name:          $name,
+          legacy_id:     $legacy,
+          temperature:   $temp,
+          downfall:      $downfall,
+          precipitation: $precip,
+          category:      $cat,
+        },
+      )*
+    ];
+  };
+}
+
+biomes! {
+  "the_void",                         127,  0.5,  0.5, "none", "none";
+  "ocean",                              0,  0.5,  0.5, "rain", "ocean";
+  "plains",                             1,  0.8,  0.4, "rain", "plains";
+  "desert",                             2,  2.0,  0.0, "none", "desert";
+  "windswept_hills",                    3,  0.2,  0.3, "rain", "extreme_hills";
+  "forest",                             4,  0.7,  0.8, "rain", "forest";
+  "taiga",                              5, 0.25,  0.8, "rain", "taiga";
+  "swamp",                              6,  0.8,  0.9, "rain", "swamp";
+  "river",                              7,  0.5,  0.5, "rain", "river";
+  "nether_wastes",                      8,  2.0,  0.0, "none", "nether";
+  "the_end",                            9,  0.5,  0.5, "none", "the_end";
+  "frozen_ocean",                      10,  0.0,  0.5, "snow", "ocean";
+  "frozen_river",                      11,  0.0,  0.5, "snow", "river";
+  "snowy_plains",                      12,  0.0,  0.5, "snow", "icy";
+  "snowy_mountains",                   13,  0.0,  0.5, "snow", "icy";
+  "mushroom_fields",                   14,  0.9,  1.0, "rain", "mushroom";
+  "mushroom_field_shore",              15,  0.9,  1.0, "rain", "mushroom";
+  "beach",                             16,  0.8,  0.4, "rain", "beach";
+  "desert_hills",                      17,  2.0,  0.0, "none", "desert";
+  "wooded_hills",                      18,  0.7,  0.8, "rain", "forest";
+  "taiga_hills",                       19, 0.25,  0.8, "rain", "taiga";
+  "mountain_edge",                     20,  0.2,  0.3, "rain", "extreme_hills";
+  "jungle",                            21, 0.95,  0.9, "rain", "jungle";
+  "jungle_hills",                      22, 0.95,  0.9, "rain", "jungle";
+  "sparse_jungle",                     23, 0.95,  0.8, "rain", "jungle";
+  "deep_ocean",                        24,  0.5,  0.5, "rain", "ocean";
+  "stony_shore",                       25,  0.2,  0.3, "rain", "beach";
+  "snowy_beach",                       26, 0.05,  0.3, "snow", "beach";
+  "birch_forest",                      27,  0.6,  0.6, "rain", "forest";
+  "birch_forest_hills",                28,  0.6,  0.6, "rain", "forest";
+  "dark_forest",                       29,  0.7,  0.8, "rain", "forest";
+  "snowy_taiga",                       30, -0.5,  0.4, "snow", "taiga";
+  "snowy_taiga_hills",                 31, -0.5,  0.4, "snow", "taiga";
+  "old_growth_pine_taiga",             32,  0.3,  0.8, "rain", "taiga";
+  "giant_tree_taiga_hills",            33,  0.3,  0.8, "rain", "taiga";
+  "windswept_forest",                  34,  0.2,  0.3, "rain", "extreme_hills";
+  "savanna",                           35,  1.2,  0.0, "none", "savanna";
+  "savanna_plateau",                   36,  1.0,  0.0, "none", "savanna";
+  "badlands",                          37,  2.0,  0.0, "none", "mesa";
+  "wooded_badlands",                   38,  2.0,  0.0, "none", "mesa";
+  "badlands_plateau",                  39,  2.0,  0.0, "none", "mesa";
+  "small_end_islands",                 40,  0.5,  0.5, "none", "the_end";
+  "end_midlands",                      41,  0.5,  0.5, "none", "the_end";
+  "end_highlands",                     42,  0.5,  0.5, "none", "the_end";
+  "end_barrens",                       43,  0.5,  0.5, "none", "the_end";
+  "warm_ocean",                        44,  0.5,  0.5, "rain", "ocean";
+  "lukewarm_ocean",                    45,  0.5,  0.5, "rain", "ocean";
+  "cold_ocean",                        46,  0.5,  0.5, "rain", "ocean";
+  "deep_warm_ocean",                   47,  0.5,  0.5, "rain", "ocean";
+  "deep_lukewarm_ocean",               48,  0.5,  0.5, "rain", "ocean";
+  "deep_cold_ocean",                   49,  0.5,  0.5, "rain", "ocean";
+  "deep_frozen_ocean",                 50,  0.5,  0.5, "rain", "ocean";
+  "sunflower_plains",                 129,  0.8,  0.4, "rain", "plains";
+  "desert_lakes",                     130,  2.0,  0.0, "none", "desert";
+  "windswept_gravelly_hills",         131,  0.2,  0.3, "rain", "extreme_hills";
+  "flower_forest",                    132,  0.7,  0.8, "rain", "forest";
+  "taiga_mountains",                  133, 0.25,  0.8, "rain", "taiga";
+  "swamp_hills",                      134,  0.8,  0.9, "rain", "swamp";
+  "ice_spikes",                       140,  0.0,  0.5, "snow", "icy";
+  "modified_jungle",                  149, 0.95,  0.9, "rain", "jungle";
+  "modified_jungle_edge",             151, 0.95,  0.8, "rain", "jungle";
+  "old_growth_birch_forest",          155,  0.6,  0.6, "rain", "forest";
+  "tall_birch_hills",                 156,  0.6,  0.6, "rain", "forest";
+  "dark_forest_hills",                157,  0.7,  0.8, "rain", "forest";
+  "snowy_taiga_mountains",            158, -0.5,  0.4, "snow", "taiga";
+  "old_growth_spruce_taiga",          160, 0.25,  0.8, "rain", "taiga";
+  "giant_spruce_taiga_hills",         161, 0.25,  0.8, "rain", "taiga";
+  "modified_gravelly_mountains",      162,  0.2,  0.3, "rain", "extreme_hills";
+  "windswept_savanna",                163,  1.1,  0.0, "none", "savanna";
+  "shattered_savanna_plateau",        164,  1.0,  0.0, "none", "savanna";
+  "eroded_badlands",                  165,  2.0,  0.0, "none", "mesa";
+  "modified_wooded_badlands_plateau", 166,  2.0,  0.0, "none", "mesa";
+  "modified_badlands_plateau",        167,  2.0,  0.0, "none", "mesa";
+  "bamboo_jungle",                    168, 0.95,  0.9, "rain", "jungle";
+  "bamboo_jungle_hills",              169, 0.95,  0.9, "rain", "jungle";
+  "soul_sand_valley",                 170,  2.0,  0.0, "none", "nether";
+  "crimson_forest",                   171,  2.0,  0.0, "none", "nether";
+  "warped_forest",                    172,  2.0,  0.0, "none", "nether";
+  "basalt_deltas",                    173,  2.0,  0.0, "none", "nether";
+  "dripstone_caves",                  174,  0.8,  0.4, "rain", "underground";
+  "lush_caves",                       175,  0.5,  0.5, "rain", "underground";
+  "meadow",                             1,  0.5,  0.8, "rain", "mountain";
+  "grove",                             30, -0.2,  0.8, "snow", "forest";
+  "snowy_slopes",                      13, -0.3,  0.9, "snow", "mountain";
+  "frozen_peaks",                      13, -0.7,  0.9, "snow", "mountain";
+  "jagged_peaks",                      13, -0.7,  0.9, "snow", "mountain";
+  "stony_peaks",                        3,  1.0,  0.3, "rain", "mountain";
+  "deep_dark",                          1,  0.8,  0.4, "rain", "underground";
+  "mangrove_swamp",                     6,  0.8,  0.9, "rain", "swamp";
+}
+
+/// The biome used for any cell that hasn't been set. This is `the_void`.
+pub const VOID: u8 = 0;
+
+/// Returns the info for the given biome. If the id is invalid, this returns
+/// the info for [`VOID`].
+pub fn info(id: u8) -> &'static BiomeInfo { BIOMES.get(id as usize).unwrap_or(&BIOMES[0]) }
+
+/// Looks up a biome by name. The `minecraft:` prefix is optional.
+pub fn from_name(name: &str) -> Option<u8> {
+  let name = name.strip_prefix("minecraft:").unwrap_or(name);
+  BIOMES.iter().position(|b| b.name == name).map(|id| id as u8)
+}
+
+/// Looks up a biome from the numeric id used before 1.18. If there are
+/// multiple biomes with the same legacy id, the older biome is returned.
+pub fn from_legacy(id: i32) -> Option<u8> {
+  BIOMES.iter().position(|b| i32::from(b.legacy_id) == id).map(|id| id as u8)
+}
+
+/// The biomes in a chunk column. Each chunk section has 64 biomes, one for
+/// every 4x4x4 cell. The biomes are indices into [`BIOMES`].
+///
+/// Any cells that haven't been set are [`VOID`].
+#[derive(Transfer, Debug, Default, Clone, PartialEq, Eq)]
+pub struct BiomeChunk {
+  /// Indexed by `y * 16 + z * 4 + x`, where `y` is the cell index from the
+  /// bottom of the chunk.
+  data: Vec<u8>,
+}
+
+impl BiomeChunk {
+  /// Creates an empty biome chunk, where every biome is [`VOID`].
+  pub fn new() -> Self { BiomeChunk { data: vec![] } }
+
+  /// Returns the number of cells that have been stored vertically. Every cell
+  /// above this is [`VOID`].
+  pub fn height(&self) -> u32 { (self.data.len() / 16) as u32 }
+
+  /// Returns the biome in the given cell. `x` and `z` must be within `0..4`,
+  /// and `y` is the cell index from the bottom of the chunk (so `y = 1` is
+  /// blocks 4 through 7).
+  pub fn get(&self, x: u8, y: u32, z: u8) -> u8 {
+    debug_assert!(x < 4 && z < 4, "invalid biome cell {x} {z}");
+    self.data.get(y as usize * 16 + z as usize * 4 + x as usize).copied().unwrap_or(VOID)
+  }
+  /// Sets the biome in the given cell. See [`get`](Self::get) for the
+  /// coordinates.
+  pub fn set(&mut self, x: u8, y: u32, z: u8, biome: u8) {
+    debug_assert!(x < 4 && z < 4, "invalid biome cell {x} {z}");
+    let idx = y as usize * 16 + z as usize * 4 + x as usize;
+    if idx >= self.data.len() {
+      if biome == VOID {
+        return;
+      }
+      self.data.resize((y as usize + 1) * 16, VOID);
+    }
+    self.data[idx] = biome;
+  }
+
+  /// Returns the 64 biomes in the given chunk section, indexed by
+  /// `y * 16 + z * 4 + x`.
+  pub fn section(&self, y: u32) -> [u8; 64] {
+    let mut out = [VOID; 64];
+    let start = (y as usize * 64).min(self.data.len());
+    let end = (start + 64).min(self.data.len());
+    out[..end - start].copy_from_slice(&self.data[start..end]);
+    out
+  }
+
+  /// Returns the biome for a column of blocks. `x` and `z` are block
+  /// coordinates within `0..16`. This is the top-most biome in the column that
+  /// isn't [`VOID`], which is what clients before 1.15 will see.
+  pub fn column(&self, x: u8, z: u8) -> u8 {
+    (0..self.height()).rev().map(|y| self.get(x / 4, y, z / 4)).find(|&b| b != VOID).unwrap_or(VOID)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn lookup() {
+    assert_eq!(BIOMES[VOID as usize].name, "the_void");
+    assert_eq!(from_name("minecraft:plains").map(|b| info(b).legacy_id), Some(1));
+    assert_eq!(from_name("desert").map(|b| info(b).legacy_id), Some(2));
+    assert_eq!(from_name("not_a_biome"), None);
+    // Meadows use the plains id for old clients, but plains should still be
+    // loaded as plains.
+    assert_eq!(from_legacy(1), from_name("plains"));
+    assert_eq!(from_legacy(127), Some(VOID));
+    assert_eq!(from_legacy(100), None);
+    // Every biome needs a unique name, and the ids must fit in a byte.
+    assert!(BIOMES.len() <= 256);
+    for (i, b) in BIOMES.iter().enumerate() {
+      assert_eq!(from_name(b.name), Some(i as u8));
+    }
+  }
+
+  #[test]
+  fn cells() {
+    let mut c = BiomeChunk::new();
+    let plains = from_name("plains").unwrap();
+    let desert = from_name("desert").unwrap();
+    assert_eq!(c.get(0, 0, 0), VOID);
+    assert_eq!(c.column(0, 0), VOID);
+    c.set(1, 2, 3, plains);
+    assert_eq!(c.height(), 3);
+    assert_eq!(c.get(1, 2, 3), plains);
+    assert_eq!(c.get(1, 1, 3), VOID);
+    assert_eq!(c.get(1, 200, 3), VOID);
+    c.set(1, 0, 3, desert);
+    // Columns use the highest biome.
+    assert_eq!(c.column(4, 12), plains);
+    assert_eq!(c.column(7, 15), plains);
+    assert_eq!(c.column(8, 12), VOID);
+
+    let section = c.section(0);
+    assert_eq!(section[2 * 16 + 3 * 4 + 1], plains);
+    assert_eq!(section[3 * 4 + 1], desert);
+    assert_eq!(c.section(1), [VOID; 64]);
+
+    // Setting void outside the stored cells doesn't allocate anything.
+    c.set(0, 100, 0, VOID);
+    assert_eq!(c.height(), 3);
+  }
+}
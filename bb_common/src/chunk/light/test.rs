@@ -200,3 +200,15 @@ fn basic_propagate() {
   ]]);
   assert_plane_matches(&mut light, &mut expected);
 }
+
+#[test]
+fn transfer_round_trip() {
+  use bb_transfer::{MessageReader, MessageWriter};
+
+  let (_, light) = chunk_from_str(&[&["    ###    ", "    #a#    ", "   1#9#1   "]]);
+
+  let mut data = vec![];
+  MessageWriter::new(&mut data).write(&light).unwrap();
+  let read: LightChunk<BlockLight> = MessageReader::new(&data).read().unwrap();
+  assert_eq!(light, read);
+}
@@ -1,3 +1,4 @@
+pub mod biome;
 pub mod fixed;
 mod light;
 pub mod paletted;
@@ -10,11 +11,8 @@ use crate::math::{PosError, RelPos};
 use std::cmp;
 
 /// A chunk column. This is not `Clone`, because that would mean duplicating an
-/// entire chunk, which you probably don't want to do.
-///
-/// Note that this should have a function called `duplicate` which does the same
-/// thing as clone, but I have not implemented it, as I have never needed to
-/// clone a chunk.
+/// entire chunk, which you probably don't want to do. If you really need a
+/// copy, use [`duplicate`](Self::duplicate).
 ///
 /// If you want to create a cross-versioned chunk, use `MultiChunk` (in
 /// `bb_server`) instead.
@@ -23,6 +21,14 @@ pub struct Chunk<S: Section> {
   max_bpe:  u8,
 }
 
+impl<S: Section + Clone> Chunk<S> {
+  /// Clones every section in this chunk. This is slow, and should only be used
+  /// when a full copy is actually needed.
+  pub fn duplicate(&self) -> Self {
+    Chunk { sections: self.sections.clone(), max_bpe: self.max_bpe }
+  }
+}
+
 impl<S: Section> Chunk<S> {
   /// Creates an empty chunk, that can be resized to any height.
   pub fn new(max_bpe: u8) -> Self { Chunk { sections: Vec::new(), max_bpe } }
@@ -9,8 +9,11 @@ pub struct Config {
 }
 
 pub struct ConfigSection {
-  config: Arc<Config>,
-  path:   Vec<String>,
+  config:   Arc<Config>,
+  path:     Vec<String>,
+  /// If set, any keys that are not present in the primary config at `path`
+  /// will be read from this section instead.
+  fallback: Option<Box<ConfigSection>>,
 }
 
 pub trait TomlValue<'a> {
@@ -212,27 +215,76 @@ impl Config {
     Some(val)
   }
 
+  /// Returns true if the primary config has a value of the right type at the
+  /// given key. This will not look at the default config.
+  fn has_primary_at<'a, 'b, I, T>(&'a self, key: I) -> bool
+  where
+    I: Iterator<Item = &'b str>,
+    T: TomlValue<'a>,
+  {
+    Self::get_val(&self.primary, key).and_then(T::from_toml).is_some()
+  }
+
+  /// Returns all the keys of the table at the given key. This includes keys
+  /// from both the primary and default config. If there is no table at the
+  /// given key, this returns an empty list.
+  pub fn keys_at<'b, I>(&self, key: I) -> Vec<String>
+  where
+    I: Iterator<Item = &'b str> + Clone,
+  {
+    let mut keys = vec![];
+    for toml in [&self.primary, &self.default] {
+      if let Some(Value::Table(map)) = Self::get_val(toml, key.clone()) {
+        for k in map.keys() {
+          if !keys.contains(k) {
+            keys.push(k.clone());
+          }
+        }
+      }
+    }
+    keys
+  }
+
   /// Returns a config section for the given key.
   pub fn section<K: ?Sized>(self: &Arc<Self>, key: &K) -> ConfigSection
   where
     K: Key,
   {
     ConfigSection {
-      config: self.clone(),
-      path:   key.sections().iter().map(|v| v.to_string()).collect(),
+      config:   self.clone(),
+      path:     key.sections().iter().map(|v| v.to_string()).collect(),
+      fallback: None,
     }
   }
 }
 
 impl ConfigSection {
   /// Gets the config value at the given key, prefixed by this reference's path.
+  ///
+  /// If this section has a fallback, and the key is not present in the primary
+  /// config, then the key will be read from the fallback section.
   pub fn get<'a, T>(&'a self, key: &str) -> T
   where
     T: TomlValue<'a>,
   {
-    self.config.get_at(self.path.iter().map(String::as_str).chain([key]))
+    let path = self.path.iter().map(String::as_str).chain([key]);
+    match &self.fallback {
+      Some(fallback) if !self.config.has_primary_at::<_, T>(path.clone()) => fallback.get(key),
+      _ => self.config.get_at(path),
+    }
+  }
+
+  /// Returns true if the given key is set in the primary config. This ignores
+  /// the default config, and any fallback section.
+  pub fn is_set(&self, key: &str) -> bool {
+    Config::get_val(&self.config.primary, self.path.iter().map(String::as_str).chain([key]))
+      .is_some()
   }
 
+  /// Returns all the keys of the table at this section. See
+  /// [`Config::keys_at`].
+  pub fn keys(&self) -> Vec<String> { self.config.keys_at(self.path.iter().map(String::as_str)) }
+
   /// Returns a config section for the given key. This new key will be appended
   /// to the current section's key.
   pub fn section<K: ?Sized>(&self, key: &K) -> ConfigSection
@@ -240,12 +292,25 @@ impl ConfigSection {
     K: Key,
   {
     ConfigSection {
-      config: self.config.clone(),
-      path:   {
+      config:   self.config.clone(),
+      path:     {
         let mut path = self.path.clone();
         path.extend(key.sections().iter().map(|v| v.to_string()));
         path
       },
+      fallback: self.fallback.as_ref().map(|f| Box::new(f.section(key))),
+    }
+  }
+
+  /// Returns a copy of this section, which will read from `fallback` for any
+  /// keys that are not set in the primary config. This is used for things like
+  /// per-world configs, where any keys that aren't set are copied from the
+  /// main `[world]` section.
+  pub fn with_fallback(&self, fallback: ConfigSection) -> ConfigSection {
+    ConfigSection {
+      config:   self.config.clone(),
+      path:     self.path.clone(),
+      fallback: Some(Box::new(fallback)),
     }
   }
 }
@@ -1,5 +1,8 @@
 use super::TomlValue;
-use crate::{math::FPos, util::GameMode};
+use crate::{
+  math::FPos,
+  util::{chat::Color, Chat, GameMode},
+};
 use log::{Level, LevelFilter};
 use std::str::FromStr;
 use toml::Value;
@@ -29,6 +32,42 @@ impl TomlValue<'_> for FPos {
   }
   fn name() -> String { "position".into() }
 }
+/// A chat message can either be a plain string, or a list of sections. Each
+/// section is a table with a `text` key, and optionally a `color` key, and any
+/// of the `bold`, `italic`, `underlined`, `strikethrough`, or `obfuscated`
+/// keys.
+impl TomlValue<'_> for Chat {
+  fn from_toml(v: &Value) -> Option<Self> {
+    if let Some(text) = v.as_str() {
+      return Some(Chat::new(text));
+    }
+    let mut chat = Chat::empty();
+    for section in v.as_array()? {
+      let map = section.as_table()?;
+      let s = chat.add(map.get("text")?.as_str()?);
+      if let Some(color) = map.get("color") {
+        s.color(Color::from_str(color.as_str()?).ok()?);
+      }
+      if map.get("bold").and_then(Value::as_bool).unwrap_or(false) {
+        s.bold();
+      }
+      if map.get("italic").and_then(Value::as_bool).unwrap_or(false) {
+        s.italic();
+      }
+      if map.get("underlined").and_then(Value::as_bool).unwrap_or(false) {
+        s.underlined();
+      }
+      if map.get("strikethrough").and_then(Value::as_bool).unwrap_or(false) {
+        s.strikethrough();
+      }
+      if map.get("obfuscated").and_then(Value::as_bool).unwrap_or(false) {
+        s.obfuscated();
+      }
+    }
+    Some(chat)
+  }
+  fn name() -> String { "chat message".into() }
+}
 impl TomlValue<'_> for Level {
   fn from_toml(v: &Value) -> Option<Self> { Level::from_str(v.as_str()?).ok() }
   fn name() -> String { "log level".into() }
@@ -202,6 +202,91 @@ impl Tag {
       _ => panic!("not a long array: {:?}", self),
     }
   }
+
+  /// Returns the inner value if this is a byte.
+  pub fn as_byte(&self) -> Option<i8> {
+    match self {
+      Self::Byte(v) => Some(*v),
+      _ => None,
+    }
+  }
+  /// Returns the inner value if this is a short.
+  pub fn as_short(&self) -> Option<i16> {
+    match self {
+      Self::Short(v) => Some(*v),
+      _ => None,
+    }
+  }
+  /// Returns the inner value if this is an int.
+  pub fn as_int(&self) -> Option<i32> {
+    match self {
+      Self::Int(v) => Some(*v),
+      _ => None,
+    }
+  }
+  /// Returns the inner value if this is a long.
+  pub fn as_long(&self) -> Option<i64> {
+    match self {
+      Self::Long(v) => Some(*v),
+      _ => None,
+    }
+  }
+  /// Returns the inner value if this is a float.
+  pub fn as_float(&self) -> Option<f32> {
+    match self {
+      Self::Float(v) => Some(*v),
+      _ => None,
+    }
+  }
+  /// Returns the inner value if this is a double.
+  pub fn as_double(&self) -> Option<f64> {
+    match self {
+      Self::Double(v) => Some(*v),
+      _ => None,
+    }
+  }
+  /// Returns the inner value if this is a string.
+  pub fn as_string(&self) -> Option<&str> {
+    match self {
+      Self::String(v) => Some(v),
+      _ => None,
+    }
+  }
+  /// Returns the inner value if this is a byte array.
+  pub fn as_byte_arr(&self) -> Option<&[u8]> {
+    match self {
+      Self::ByteArr(v) => Some(v),
+      _ => None,
+    }
+  }
+  /// Returns the inner value if this is a list.
+  pub fn as_list(&self) -> Option<&Vec<Tag>> {
+    match self {
+      Self::List(v) => Some(v),
+      _ => None,
+    }
+  }
+  /// Returns the inner value if this is a compound.
+  pub fn as_compound(&self) -> Option<&HashMap<String, Tag>> {
+    match self {
+      Self::Compound(v) => Some(v),
+      _ => None,
+    }
+  }
+  /// Returns the inner value if this is an int array.
+  pub fn as_int_arr(&self) -> Option<&Vec<i32>> {
+    match self {
+      Self::IntArray(v) => Some(v),
+      _ => None,
+    }
+  }
+  /// Returns the inner value if this is a long array.
+  pub fn as_long_arr(&self) -> Option<&Vec<i64>> {
+    match self {
+      Self::LongArray(v) => Some(v),
+      _ => None,
+    }
+  }
 }
 
 #[cfg(test)]
@@ -1,8 +1,8 @@
 use crate::{
-  chunk::{paletted::Section, BlockLight, LightChunk, SkyLight},
+  chunk::{biome::BiomeChunk, paletted::Section, BlockLight, LightChunk, SkyLight},
   math::{ChunkPos, FPos, Pos},
   metadata::Metadata,
-  util::{chat::Color, Chat, GameMode, Hand, Item, UUID},
+  util::{chat::Color, Chat, Effect, GameMode, Hand, Item, Property, UUID},
 };
 use bb_macros::Transfer;
 use std::{collections::HashMap, net::SocketAddr};
@@ -74,6 +74,18 @@ packets! {
   AckBlockUpdate { sequence: i32 },
   #[id = 1]
   BlockUpdate { pos: Pos, state: u32 },
+  /// Adds, updates or removes a boss bar. Boss bars only exist on 1.9+
+  /// clients, so this will be ignored for 1.8 clients.
+  #[id = 48]
+  BossBar {
+    id:     UUID,
+    #[must_exist]
+    action: BossBarAction,
+  },
+  /// Sets the hotbar slot the client is holding. `slot` must be within
+  /// `0..9`.
+  #[id = 49]
+  ChangeHeldItem { slot: u8 },
   #[id = 38]
   ChangeGameState {
     #[must_exist]
@@ -91,6 +103,9 @@ packets! {
     sections:    Vec<Option<Section>>,
     sky_light:   Option<LightChunk<SkyLight>>,
     block_light: LightChunk<BlockLight>,
+    /// The biomes in this chunk. Clients before 1.17 only use these if `full`
+    /// is set.
+    biomes:      BiomeChunk,
   },
   #[id = 4]
   CommandList {
@@ -140,6 +155,23 @@ packets! {
   EntityVelocity { eid: i32, x: i16, y: i16, z: i16 },
   #[id = 29]
   EntityMetadata { eid: i32, ty: u32, meta: Metadata },
+  /// Adds a status effect to an entity, or updates an existing one.
+  /// `amplifier` is the level of the effect minus one, and `duration` is in
+  /// ticks.
+  #[id = 46]
+  EntityEffect {
+    eid:       i32,
+    effect:    Effect,
+    amplifier: u8,
+    duration:  i32,
+    /// Ambient effects (from beacons) show fewer particles.
+    ambient:   bool,
+    particles: bool,
+    /// If set, the effect is shown in the top right of the client's screen.
+    icon:      bool,
+  },
+  #[id = 47]
+  RemoveEntityEffect { eid: i32, effect: Effect },
   #[id = 10]
   JoinGame {
     eid:                   i32,
@@ -156,6 +188,9 @@ packets! {
     world_height:          u32,
     /// Only applies to 1.18+ clients. Ignored for older clients.
     world_min_y:           i32,
+    /// The name of the world, such as `minecraft:overworld`. Only applies to
+    /// 1.16+ clients. Ignored for older clients.
+    world_name:            String,
   },
   /// A list of changed blocks in a chunk section. This is not for a chunk
   /// column. 1.8 clients have this block for a whole chunk column, but 1.17+
@@ -205,6 +240,23 @@ packets! {
   PluginMessage { channel: String, data: Vec<u8> },
   #[id = 30]
   RemoveEntities { eids: Vec<i32> },
+  /// Moves the client into a new world. The client will unload all of its
+  /// chunks and entities. 1.16+ clients will only do this if `world_name` is
+  /// different from the world they are currently in. For older clients, the
+  /// proxy will make sure the world is always reset.
+  #[id = 44]
+  Respawn {
+    game_mode:    GameMode,
+    dimension:    i8,
+    /// Only applies to 1.16+ clients. Ignored for older clients.
+    world_name:   String,
+    level_type:   String,
+    difficulty:   u8,
+    /// Only applies to 1.18+ clients. Ignored for older clients.
+    world_height: u32,
+    /// Only applies to 1.18+ clients. Ignored for older clients.
+    world_min_y:  i32,
+  },
   #[id = 25]
   ScoreboardDisplay {
     #[must_exist]
@@ -279,10 +331,15 @@ packets! {
   },
   /// A special packet. This will cause the proxy to start moving this player to
   /// a new server. If the new server accepts the connection, the proxy will
-  /// simply disconnect the player from the old server. If the connection
-  /// failed, then a `sb::SwitchServerFailed` packet will be sent to the server.
+  /// simply disconnect the player from the old server. If none of the ips
+  /// accept the connection, the player stays on this server, and the proxy
+  /// sends them an error message.
   #[id = 18]
   SwitchServer { ips: Vec<SocketAddr> },
+  /// The same as `SwitchServer`, but the server is looked up by its name in
+  /// the proxy's `[servers]` config.
+  #[id = 45]
+  SwitchServerNamed { name: String },
   /// The ids are for the latest version. It is up to the proxy to convert them
   /// to older versions.
   #[id = 41]
@@ -322,6 +379,71 @@ packets! {
   WindowItem { wid: u8, slot: i32, item: Item },
 }
 
+#[derive(Transfer, Debug, Clone, PartialEq)]
+pub enum BossBarAction {
+  #[id = 0]
+  Add {
+    title:  Chat,
+    /// The amount the bar is filled, from 0 to 1.
+    health: f32,
+    #[must_exist]
+    color:  BossBarColor,
+    #[must_exist]
+    style:  BossBarStyle,
+    /// A bitmask. 0x1 darkens the sky, 0x2 plays the dragon music, and 0x4
+    /// creates fog.
+    flags:  u8,
+  },
+  #[id = 1]
+  Remove,
+  #[id = 2]
+  UpdateHealth(f32),
+  #[id = 3]
+  UpdateTitle(Chat),
+  #[id = 4]
+  UpdateStyle {
+    #[must_exist]
+    color: BossBarColor,
+    #[must_exist]
+    style: BossBarStyle,
+  },
+  #[id = 5]
+  UpdateFlags(u8),
+}
+
+#[derive(Transfer, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BossBarColor {
+  #[id = 0]
+  Pink,
+  #[id = 1]
+  Blue,
+  #[id = 2]
+  Red,
+  #[id = 3]
+  Green,
+  #[id = 4]
+  Yellow,
+  #[id = 5]
+  Purple,
+  #[id = 6]
+  White,
+}
+
+/// The number of notches on a boss bar.
+#[derive(Transfer, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BossBarStyle {
+  #[id = 0]
+  Solid,
+  #[id = 1]
+  Notched6,
+  #[id = 2]
+  Notched10,
+  #[id = 3]
+  Notched12,
+  #[id = 4]
+  Notched20,
+}
+
 #[derive(Transfer, Debug, Clone, PartialEq)]
 pub enum ChangeGameStateKind {
   #[id = 0]
@@ -615,6 +737,9 @@ pub struct PlayerListAdd {
   /// An optional display name. If present, this will replace their username in
   /// the tab list.
   pub display_name: Option<String>,
+  /// The player's game profile properties. The client uses the `textures`
+  /// property to find this player's skin.
+  pub properties:   Vec<Property>,
 }
 
 /// See [`PlayerListAdd`]
@@ -51,6 +51,18 @@ pub enum Packet {
   PluginMessage { channel: String, data: Vec<u8> },
   #[id = 12]
   UseItem { hand: Hand },
+  /// Sent when the player stops using an item, such as when they stop blocking
+  /// with a shield, or stop eating.
+  #[id = 18]
+  ReleaseItem,
+  /// Sent when the player swaps the items in their main hand and off hand.
+  #[id = 19]
+  SwapHands,
+  /// Sent when the player clicks respawn on the death screen. This may also be
+  /// sent when the client requests statistics, so this should be ignored if
+  /// the player is alive.
+  #[id = 20]
+  Respawn,
   /// Sneaking will not be present on 1.8-1.15 clients. It should be used if it
   /// is present (it will produce more accurate results for shift-clicking on an
   /// entity).
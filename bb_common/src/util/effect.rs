@@ -0,0 +1,204 @@
+use bb_macros::Transfer;
+use std::{error::Error, fmt, str::FromStr};
+
+/// A status effect, such as speed or regeneration. These are given to players
+/// by potions, and can be applied to any living entity.
+///
+/// The ids match the ids used in the protocol, which have stayed the same
+/// since 1.8. Effects added in newer versions are not shown to older clients.
+#[derive(Transfer, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Effect {
+  #[id = 1]
+  Speed,
+  #[id = 2]
+  Slowness,
+  #[id = 3]
+  Haste,
+  #[id = 4]
+  MiningFatigue,
+  #[id = 5]
+  Strength,
+  #[id = 6]
+  InstantHealth,
+  #[id = 7]
+  InstantDamage,
+  #[id = 8]
+  JumpBoost,
+  #[id = 9]
+  Nausea,
+  #[id = 10]
+  Regeneration,
+  #[id = 11]
+  Resistance,
+  #[id = 12]
+  FireResistance,
+  #[id = 13]
+  WaterBreathing,
+  #[id = 14]
+  Invisibility,
+  #[id = 15]
+  Blindness,
+  #[id = 16]
+  NightVision,
+  #[id = 17]
+  Hunger,
+  #[id = 18]
+  Weakness,
+  #[id = 19]
+  Poison,
+  #[id = 20]
+  Wither,
+  #[id = 21]
+  HealthBoost,
+  #[id = 22]
+  Absorption,
+  #[id = 23]
+  Saturation,
+  #[id = 24]
+  Glowing,
+  #[id = 25]
+  Levitation,
+  #[id = 26]
+  Luck,
+  #[id = 27]
+  Unluck,
+  #[id = 28]
+  SlowFalling,
+  #[id = 29]
+  ConduitPower,
+  #[id = 30]
+  DolphinsGrace,
+  #[id = 31]
+  BadOmen,
+  #[id = 32]
+  HeroOfTheVillage,
+  #[id = 33]
+  Darkness,
+}
+
+const ALL: [Effect; 33] = [
+  Effect::Speed,
+  Effect::Slowness,
+  Effect::Haste,
+  Effect::MiningFatigue,
+  Effect::Strength,
+  Effect::InstantHealth,
+  Effect::InstantDamage,
+  Effect::JumpBoost,
+  Effect::Nausea,
+  Effect::Regeneration,
+  Effect::Resistance,
+  Effect::FireResistance,
+  Effect::WaterBreathing,
+  Effect::Invisibility,
+  Effect::Blindness,
+  Effect::NightVision,
+  Effect::Hunger,
+  Effect::Weakness,
+  Effect::Poison,
+  Effect::Wither,
+  Effect::HealthBoost,
+  Effect::Absorption,
+  Effect::Saturation,
+  Effect::Glowing,
+  Effect::Levitation,
+  Effect::Luck,
+  Effect::Unluck,
+  Effect::SlowFalling,
+  Effect::ConduitPower,
+  Effect::DolphinsGrace,
+  Effect::BadOmen,
+  Effect::HeroOfTheVillage,
+  Effect::Darkness,
+];
+
+impl Effect {
+  /// Returns the protocol id of this effect.
+  pub fn id(&self) -> u8 { ALL.iter().position(|e| e == self).unwrap() as u8 + 1 }
+  /// Returns the effect with the given protocol id, if it exists.
+  pub fn from_id(id: u8) -> Option<Self> { ALL.get(usize::from(id).checked_sub(1)?).copied() }
+
+  /// Returns the name of this effect, without the `minecraft:` prefix. For
+  /// example, this returns `jump_boost` for [`Effect::JumpBoost`].
+  pub fn name(&self) -> &'static str {
+    match self {
+      Self::Speed => "speed",
+      Self::Slowness => "slowness",
+      Self::Haste => "haste",
+      Self::MiningFatigue => "mining_fatigue",
+      Self::Strength => "strength",
+      Self::InstantHealth => "instant_health",
+      Self::InstantDamage => "instant_damage",
+      Self::JumpBoost => "jump_boost",
+      Self::Nausea => "nausea",
+      Self::Regeneration => "regeneration",
+      Self::Resistance => "resistance",
+      Self::FireResistance => "fire_resistance",
+      Self::WaterBreathing => "water_breathing",
+      Self::Invisibility => "invisibility",
+      Self::Blindness => "blindness",
+      Self::NightVision => "night_vision",
+      Self::Hunger => "hunger",
+      Self::Weakness => "weakness",
+      Self::Poison => "poison",
+      Self::Wither => "wither",
+      Self::HealthBoost => "health_boost",
+      Self::Absorption => "absorption",
+      Self::Saturation => "saturation",
+      Self::Glowing => "glowing",
+      Self::Levitation => "levitation",
+      Self::Luck => "luck",
+      Self::Unluck => "unluck",
+      Self::SlowFalling => "slow_falling",
+      Self::ConduitPower => "conduit_power",
+      Self::DolphinsGrace => "dolphins_grace",
+      Self::BadOmen => "bad_omen",
+      Self::HeroOfTheVillage => "hero_of_the_village",
+      Self::Darkness => "darkness",
+    }
+  }
+
+  /// Returns `true` if this effect is applied all at once, instead of lasting
+  /// for some time.
+  pub fn is_instant(&self) -> bool {
+    matches!(self, Self::InstantHealth | Self::InstantDamage | Self::Saturation)
+  }
+}
+
+#[derive(Debug)]
+pub struct InvalidEffect(String);
+
+impl fmt::Display for InvalidEffect {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "invalid effect name: {}", self.0)
+  }
+}
+
+impl Error for InvalidEffect {}
+
+impl FromStr for Effect {
+  type Err = InvalidEffect;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let name = s.strip_prefix("minecraft:").unwrap_or(s);
+    ALL.iter().find(|e| e.name() == name).copied().ok_or_else(|| InvalidEffect(s.into()))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn ids() {
+    for effect in ALL {
+      assert_eq!(Effect::from_id(effect.id()), Some(effect));
+      assert_eq!(effect.name().parse::<Effect>().unwrap(), effect);
+    }
+    assert_eq!(Effect::Speed.id(), 1);
+    assert_eq!(Effect::Darkness.id(), 33);
+    assert_eq!(Effect::from_id(0), None);
+    assert_eq!("minecraft:jump_boost".parse::<Effect>().unwrap(), Effect::JumpBoost);
+    assert!("flying".parse::<Effect>().is_err());
+  }
+}
@@ -5,6 +5,7 @@ pub use chat::Chat;
 pub use pool::ThreadPool;
 
 mod buffer;
+mod effect;
 mod item;
 
 use crate::math::Pos;
@@ -12,9 +13,10 @@ use bb_macros::Transfer;
 #[cfg(feature = "host")]
 use rand::{rngs::OsRng, RngCore};
 use serde::de::{self, Deserialize, Deserializer, Unexpected, Visitor};
-use std::{error::Error, fmt, io::Write, num::ParseIntError, str::FromStr};
+use std::{error::Error, fmt, io::Write, net::IpAddr, num::ParseIntError, str::FromStr};
 
 pub use buffer::{Buffer, BufferError, BufferErrorKind, Mode};
+pub use effect::{Effect, InvalidEffect};
 pub use item::Item;
 
 pub use num_cpus::get as num_cpus;
@@ -385,13 +387,36 @@ impl<'de> Deserialize<'de> for UUID {
 #[derive(Transfer, Debug, Clone)]
 pub struct JoinInfo {
   #[must_exist]
-  pub mode:     JoinMode,
+  pub mode:       JoinMode,
   #[must_exist]
-  pub username: String,
+  pub username:   String,
   #[must_exist]
-  pub uuid:     UUID,
+  pub uuid:       UUID,
+  #[must_exist]
+  pub ver:        u32,
+  /// The player's game profile properties, from the session server. This is
+  /// where the player's skin and cape are stored. This is empty in offline
+  /// mode.
+  pub properties: Vec<Property>,
+  /// The client's IP. If the proxy is behind another proxy, this is the IP
+  /// that was forwarded to it.
+  pub ip:         Option<IpAddr>,
+}
+
+/// A single game profile property. The only property the vanilla client uses
+/// is `textures`, which stores the player's skin and cape.
+#[derive(Transfer, Debug, Default, Clone, PartialEq, Eq)]
+pub struct Property {
+  /// The name of this property, such as `textures`.
   #[must_exist]
-  pub ver:      u32,
+  pub name:      String,
+  /// The value of this property. For `textures`, this is base64 encoded json.
+  #[must_exist]
+  pub value:     String,
+  /// A base64 encoded signature of `value`, signed by the session server. The
+  /// client will refuse to show skins with an invalid signature, but unsigned
+  /// skins will still be shown.
+  pub signature: Option<String>,
 }
 
 #[derive(Transfer, Debug, Clone)]
@@ -400,6 +425,33 @@ pub enum JoinMode {
   New,
   #[id = 1]
   Switch,
+  /// The proxy only wants the status of the server. The server will reply with
+  /// a [`ServerStatus`], and then close the connection. The `username` and
+  /// `uuid` in [`JoinInfo`] are ignored.
+  #[id = 2]
+  Status,
+}
+
+/// The status of a server. This is sent from the server to the proxy, and is
+/// then shown in the client's server list.
+#[derive(Transfer, Debug, Clone)]
+pub struct ServerStatus {
+  #[must_exist]
+  pub online: u32,
+  #[must_exist]
+  pub max:    u32,
+  /// A few of the players online. This is shown when hovering over the player
+  /// count in the server list.
+  #[must_exist]
+  pub sample: Vec<StatusPlayer>,
+}
+
+#[derive(Transfer, Debug, Clone)]
+pub struct StatusPlayer {
+  #[must_exist]
+  pub username: String,
+  #[must_exist]
+  pub uuid:     UUID,
 }
 
 #[cfg(test)]
@@ -173,6 +173,10 @@ impl ProtocolVersion {
       Err(_) => Self::Invalid,
     }
   }
+  /// Converts a version name, like `1.12.2`, to a protocol version. This is the
+  /// same format that [`Display`](fmt::Display) uses. This will return
+  /// [`Self::Invalid`] if the name could not be parsed.
+  pub fn parse_name(s: &str) -> Self { Self::parse_str(&format!("V{}", s.replace('.', "_"))) }
 
   /// Returns the protocol id. This is the version that is sent to the server
   /// from the client. If this is 0, then this is an invalid protocol.
@@ -121,6 +121,74 @@ pub struct CParticleType {
   pub data: CList<u8>,
 }
 
+#[ctype]
+#[derive(Debug)]
+pub struct CItemStack {
+  /// The name of the item, such as `diamond`. Doesn't contain a NUL at the end.
+  pub item:   CStr,
+  /// The number of items in this stack.
+  pub amount: u8,
+}
+
+#[ctype]
+#[derive(Debug)]
+pub struct CInventory {
+  /// The number of rows in this inventory. This must be within 1 and 6.
+  pub rows:  u32,
+  /// The items in this inventory. If this is shorter than `rows * 9`, the rest
+  /// of the inventory will be empty. Any extra items will be ignored.
+  pub items: CList<CItemStack>,
+}
+
+#[ctype]
+#[derive(Debug)]
+pub struct CSound {
+  /// The name of the sound, such as `entity.pig.ambient`. Doesn't contain a NUL
+  /// at the end.
+  pub name:     CStr,
+  /// The sound category. This controls which volume slider affects this sound.
+  ///
+  /// ```text
+  /// 0 -> master
+  /// 1 -> music
+  /// 2 -> record
+  /// 3 -> weather
+  /// 4 -> block
+  /// 5 -> hostile
+  /// 6 -> neutral
+  /// 7 -> player
+  /// 8 -> ambient
+  /// 9 -> voice
+  /// _ -> invalid sound
+  /// ```
+  pub category: u8,
+  /// Where the sound is played from.
+  pub pos:      CFPos,
+  /// The volume. `1.0` is normal volume.
+  pub volume:   f32,
+  /// The pitch. `1.0` is normal pitch.
+  pub pitch:    f32,
+}
+
+/// Which part of a plugin's persistent storage to access.
+#[ctype]
+#[derive(Debug)]
+pub struct CStoreScope {
+  /// The kind of scope.
+  ///
+  /// ```text
+  /// 0 -> global
+  /// 1 -> player (uses `player`)
+  /// 2 -> world (uses `wid`)
+  /// _ -> invalid scope
+  /// ```
+  pub kind:   u8,
+  /// The player to access. Only used if `kind` is 1.
+  pub player: CUUID,
+  /// The world to access. Only used if `kind` is 2.
+  pub wid:    u32,
+}
+
 #[ctype]
 #[derive(Debug)]
 pub struct CBlockData {
@@ -439,6 +507,72 @@ extern "C" {
   pub fn bb_player_send_message(player: *const CUUID, message: *const CChat);
   /// Sends the given particle to the player.
   pub fn bb_player_send_particle(player: *const CUUID, particle: *const CParticle);
+  /// Teleports the player to the given position, with the given yaw and pitch.
+  pub fn bb_player_teleport(player: *const CUUID, pos: *const CFPos, yaw: f32, pitch: f32);
+  /// Moves the player into the given world, at the given position. Returns -1
+  /// if the world doesn't exist.
+  pub fn bb_player_switch_world(player: *const CUUID, wid: u32, pos: *const CFPos) -> i32;
+  /// Sends the player to another server. Returns -1 if the address is invalid.
+  pub fn bb_player_switch_to(player: *const CUUID, ip_ptr: *const u8, ip_len: u32) -> i32;
+  /// Sets the title for this player. This will not be shown until
+  /// `bb_player_show_title` is called.
+  pub fn bb_player_set_title(player: *const CUUID, title: *const CChat);
+  /// Sets the subtitle for this player. This will not be shown until
+  /// `bb_player_show_title` is called.
+  pub fn bb_player_set_subtitle(player: *const CUUID, subtitle: *const CChat);
+  /// Shows the current title to the player. All arguments are in ticks.
+  pub fn bb_player_show_title(player: *const CUUID, fade_in: u32, stay: u32, fade_out: u32);
+  /// Shows the scoreboard to the player.
+  pub fn bb_player_show_scoreboard(player: *const CUUID);
+  /// Hides the scoreboard for the player.
+  pub fn bb_player_hide_scoreboard(player: *const CUUID);
+  /// Sets a line in the player's scoreboard. Returns -1 if the line is not
+  /// within `0..15`.
+  pub fn bb_player_set_scoreboard_line(
+    player: *const CUUID,
+    line: u8,
+    message: *const CChat,
+  ) -> i32;
+  /// Clears a line in the player's scoreboard. Returns -1 if the line is not
+  /// within `0..15`.
+  pub fn bb_player_clear_scoreboard_line(player: *const CUUID, line: u8) -> i32;
+  /// Gives the item stack to the player. Returns -1 if the item name is
+  /// invalid.
+  pub fn bb_player_give(player: *const CUUID, stack: *const CItemStack) -> i32;
+  /// Shows an inventory to the player. Returns -1 if the inventory has an
+  /// invalid number of rows, or if any item names are invalid.
+  pub fn bb_player_show_inventory(
+    player: *const CUUID,
+    inv: *const CInventory,
+    title: *const CChat,
+  ) -> i32;
+  /// Sets the player's skin. `value` and `signature` are the base64 encoded
+  /// `textures` property and its signature. If `sig_len` is 0, the skin will be
+  /// unsigned. Returns -1 if the player is offline.
+  pub fn bb_player_set_skin(
+    player: *const CUUID,
+    value_ptr: *const u8,
+    value_len: u32,
+    sig_ptr: *const u8,
+    sig_len: u32,
+  ) -> i32;
+  /// Restores the skin the player logged in with.
+  pub fn bb_player_reset_skin(player: *const CUUID);
+  /// Gives the player a status effect. `effect` is the protocol id of the
+  /// effect, and `duration` is in ticks. Returns -1 if the effect id is
+  /// invalid, or if the player is offline.
+  pub fn bb_player_add_effect(
+    player: *const CUUID,
+    effect: u32,
+    amplifier: u32,
+    duration: u32,
+  ) -> i32;
+  /// Removes a status effect from the player. Returns 1 if the player had the
+  /// effect, 0 if they didn't, and -1 if the effect id is invalid or the
+  /// player is offline.
+  pub fn bb_player_remove_effect(player: *const CUUID, effect: u32) -> i32;
+  /// Removes all status effects from the player.
+  pub fn bb_player_clear_effects(player: *const CUUID);
 
   /// Sets a block in the world. Returns -1 if the block position is invalid.
   pub fn bb_world_set_block(wid: u32, pos: *const CPos, id: u32) -> i32;
@@ -452,9 +586,49 @@ extern "C" {
   pub fn bb_world_players(wid: u32) -> *mut CList<CUUID>;
   /// Spawns a particle in the world.
   pub fn bb_world_spawn_particle(wid: u32, particle: *const CParticle);
+  /// Fills the rectangle between `min` and `max` (inclusive) with the given
+  /// block kind. Returns -1 if the positions are invalid.
+  pub fn bb_world_fill_rect_kind(wid: u32, min: *const CPos, max: *const CPos, kind: u32) -> i32;
+  /// Plays a sound in the world. All nearby players will hear it.
+  pub fn bb_world_play_sound(wid: u32, sound: *const CSound);
   /// Raycasts from the `from` position to `to`. Returns null if there is no
   /// collision.
-  pub fn bb_world_raycast(from: *const CFPos, to: *const CFPos, water: CBool) -> *mut CFPos;
+  pub fn bb_world_raycast(
+    wid: u32,
+    from: *const CFPos,
+    to: *const CFPos,
+    water: CBool,
+  ) -> *mut CFPos;
+
+  /// Creates a new team. Returns -1 if a team with that name already exists.
+  pub fn bb_team_create(name_ptr: *const u8, name_len: u32) -> i32;
+  /// Sets the color of a team. Returns -1 if the team doesn't exist, or if the
+  /// color is invalid.
+  pub fn bb_team_set_color(
+    name_ptr: *const u8,
+    name_len: u32,
+    color_ptr: *const u8,
+    color_len: u32,
+  ) -> i32;
+  /// Adds a player to a team. Returns -1 if the team doesn't exist.
+  pub fn bb_team_add_player(name_ptr: *const u8, name_len: u32, player: *const CUUID) -> i32;
+
+  /// Returns the value stored under the given key, or null if the key is not
+  /// present. Storage is saved to disk, so values will be kept after the server
+  /// restarts.
+  pub fn bb_store_get(scope: *const CStoreScope, key_ptr: *const u8, key_len: u32) -> *mut CStr;
+  /// Stores a value under the given key, overwriting any existing value.
+  /// Returns -1 if the scope is invalid.
+  pub fn bb_store_set(
+    scope: *const CStoreScope,
+    key_ptr: *const u8,
+    key_len: u32,
+    value_ptr: *const u8,
+    value_len: u32,
+  ) -> i32;
+  /// Removes the given key. Returns 1 if the key was present, 0 if it wasn't,
+  /// and -1 if the scope is invalid.
+  pub fn bb_store_remove(scope: *const CStoreScope, key_ptr: *const u8, key_len: u32) -> i32;
 
   /// Returns the number of nanoseconds since this function was called first.
   /// This is used to find the duration of a function.
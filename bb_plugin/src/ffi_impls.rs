@@ -1,9 +1,9 @@
 use crate::{math::Vec3, FromFfi, IntoFfi};
 use bb_common::{
   math::{FPos, Pos},
-  util::UUID,
+  util::{Chat, UUID},
 };
-use bb_ffi::{CBool, CFPos, CPos, CVec3, CUUID};
+use bb_ffi::{CBool, CChat, CFPos, CPos, CStr, CVec3, CUUID};
 
 impl FromFfi for Pos {
   type Ffi = CPos;
@@ -75,3 +75,9 @@ impl IntoFfi for UUID {
     CUUID { bytes: [n as u32, (n >> 32) as u32, (n >> (2 * 32)) as u32, (n >> (3 * 32)) as u32] }
   }
 }
+
+impl IntoFfi for Chat {
+  type Ffi = CChat;
+
+  fn into_ffi(self) -> CChat { CChat { message: CStr::new(self.to_codes()) } }
+}
@@ -1 +1,89 @@
+use crate::IntoFfi;
+use bb_ffi::{CInventory, CItemStack, CList, CStr};
 
+/// An item stack. Items are referred to by name, so that plugins don't need to
+/// know the item ids of the server's version.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stack {
+  name:   String,
+  amount: u8,
+}
+
+impl Stack {
+  /// Creates a stack with a single item. The name should be something like
+  /// `diamond`. If the name is invalid, the server will ignore this stack.
+  pub fn new(name: impl Into<String>) -> Self { Stack { name: name.into(), amount: 1 } }
+  /// Sets the number of items in this stack.
+  pub fn with_amount(mut self, amount: u8) -> Self {
+    self.amount = amount;
+    self
+  }
+
+  /// Returns the name of this item.
+  pub fn name(&self) -> &str { &self.name }
+  /// Returns the number of items in this stack.
+  pub fn amount(&self) -> u8 { self.amount }
+}
+
+impl IntoFfi for Stack {
+  type Ffi = CItemStack;
+
+  fn into_ffi(self) -> CItemStack { CItemStack { item: CStr::new(self.name), amount: self.amount } }
+}
+
+/// A chest-like inventory, which can be shown to players with
+/// [`Player::show_inventory`](crate::player::Player::show_inventory).
+#[derive(Debug, Clone)]
+pub struct Inventory {
+  rows:  u32,
+  items: Vec<Option<Stack>>,
+}
+
+impl Inventory {
+  /// Creates an empty inventory with the given number of rows.
+  ///
+  /// # Panics
+  ///
+  /// If `rows` is not within 1 and 6.
+  pub fn new(rows: u32) -> Self {
+    assert!((1..=6).contains(&rows), "inventories must have 1 to 6 rows, got {rows}");
+    Inventory { rows, items: vec![None; rows as usize * 9] }
+  }
+
+  /// Returns the number of rows in this inventory.
+  pub fn rows(&self) -> u32 { self.rows }
+  /// Returns the number of slots in this inventory.
+  pub fn size(&self) -> u32 { self.rows * 9 }
+
+  /// Sets the item in the given slot.
+  ///
+  /// # Panics
+  ///
+  /// If the slot is outside of this inventory.
+  pub fn set(&mut self, slot: u32, stack: Stack) { self.items[slot as usize] = Some(stack); }
+  /// Clears the item in the given slot.
+  ///
+  /// # Panics
+  ///
+  /// If the slot is outside of this inventory.
+  pub fn clear(&mut self, slot: u32) { self.items[slot as usize] = None; }
+  /// Returns the item in the given slot, if there is one.
+  pub fn get(&self, slot: u32) -> Option<&Stack> { self.items.get(slot as usize)?.as_ref() }
+}
+
+impl IntoFfi for Inventory {
+  type Ffi = CInventory;
+
+  fn into_ffi(self) -> CInventory {
+    CInventory {
+      rows:  self.rows,
+      items: CList::new(
+        self
+          .items
+          .into_iter()
+          .map(|it| it.unwrap_or_else(|| Stack::new("air").with_amount(0)).into_ffi())
+          .collect(),
+      ),
+    }
+  }
+}
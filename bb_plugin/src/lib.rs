@@ -2,7 +2,6 @@ pub use bb_ffi as ffi;
 pub use log::*;
 
 use bb_common::util::Chat;
-use bb_ffi::CChat;
 use std::marker::PhantomData;
 
 pub use bb_common::{chunk, transfer, util};
@@ -18,6 +17,7 @@ pub mod item;
 pub mod math;
 pub mod particle;
 pub mod player;
+pub mod storage;
 pub mod sync;
 pub mod time;
 pub mod world;
@@ -49,10 +49,26 @@ use sync::ConstLock;
 impl Bamboo {
   pub fn broadcast(&self, message: Chat) {
     unsafe {
-      let c_chat = CChat { message: bb_ffi::CStr::new(message.to_codes()) };
-      bb_ffi::bb_broadcast(&c_chat);
+      bb_ffi::bb_broadcast(&message.into_ffi());
     }
   }
+  /// Returns the world with the given id. The default world always has an id
+  /// of `0`.
+  pub fn world(&self, wid: u32) -> world::World { world::World::new(wid) }
+  /// Creates a new team. Returns `None` if a team with this name already
+  /// exists.
+  pub fn create_team(&self, name: &str) -> Option<player::Team> {
+    unsafe {
+      if bb_ffi::bb_team_create(name.as_ptr(), name.len() as u32) == 0 {
+        Some(player::Team::new(name))
+      } else {
+        None
+      }
+    }
+  }
+  /// Returns the team with the given name. This doesn't check if the team
+  /// exists, so any changes to a team that doesn't exist will be ignored.
+  pub fn team(&self, name: &str) -> player::Team { player::Team::new(name) }
 }
 
 use log::{Level, LevelFilter, Metadata, Record};
@@ -151,3 +167,109 @@ extern "C" fn on_tick() {
     }
   }
 }
+
+callback!(set_on_block_break, ON_BLOCK_BREAK, Fn(player::Player, math::Pos) -> bool);
+#[no_mangle]
+extern "C" fn on_block_break(id: ffi::CUUID, x: i32, y: i32, z: i32) -> bool {
+  if let Some(cb) = ON_BLOCK_BREAK.lock().as_ref() {
+    let p = player::Player::from_ffi(id);
+    let pos = math::Pos { x, y, z };
+    cb(p, pos)
+  } else {
+    true
+  }
+}
+
+callback!(set_on_click_window, ON_CLICK_WINDOW, Fn(player::Player, i32) -> bool);
+#[no_mangle]
+extern "C" fn on_click_window(id: ffi::CUUID, slot: i32) -> bool {
+  if let Some(cb) = ON_CLICK_WINDOW.lock().as_ref() {
+    cb(player::Player::from_ffi(id), slot)
+  } else {
+    true
+  }
+}
+
+callback!(set_on_interact, ON_INTERACT, Fn(player::Player, util::Hand, Option<math::Pos>) -> bool);
+#[no_mangle]
+extern "C" fn on_interact(
+  id: ffi::CUUID,
+  main_hand: bool,
+  has_block: bool,
+  x: i32,
+  y: i32,
+  z: i32,
+) -> bool {
+  if let Some(cb) = ON_INTERACT.lock().as_ref() {
+    let p = player::Player::from_ffi(id);
+    let hand = if main_hand { util::Hand::Main } else { util::Hand::Off };
+    let block = if has_block { Some(math::Pos { x, y, z }) } else { None };
+    cb(p, hand, block)
+  } else {
+    true
+  }
+}
+
+callback!(set_on_damage, ON_DAMAGE, Fn(player::Player, f32, bool) -> bool);
+#[no_mangle]
+extern "C" fn on_damage(id: ffi::CUUID, amount: f32, blockable: bool) -> bool {
+  if let Some(cb) = ON_DAMAGE.lock().as_ref() {
+    cb(player::Player::from_ffi(id), amount, blockable)
+  } else {
+    true
+  }
+}
+
+callback!(set_on_entity_damage, ON_ENTITY_DAMAGE, Fn(i32, f32, bool) -> bool);
+#[no_mangle]
+extern "C" fn on_entity_damage(eid: i32, amount: f32, blockable: bool) -> bool {
+  if let Some(cb) = ON_ENTITY_DAMAGE.lock().as_ref() {
+    cb(eid, amount, blockable)
+  } else {
+    true
+  }
+}
+
+callback!(set_on_death, ON_DEATH, Fn(player::Player) -> bool);
+#[no_mangle]
+extern "C" fn on_death(id: ffi::CUUID) -> bool {
+  if let Some(cb) = ON_DEATH.lock().as_ref() {
+    cb(player::Player::from_ffi(id))
+  } else {
+    true
+  }
+}
+
+callback!(set_on_chat, ON_CHAT, Fn(player::Player, String));
+#[no_mangle]
+extern "C" fn on_chat(id: ffi::CUUID, ptr: *const u8, len: u32) {
+  if let Some(cb) = ON_CHAT.lock().as_ref() {
+    // The server owns this string, and will free it once we return.
+    let text = unsafe { std::slice::from_raw_parts(ptr, len as usize) };
+    cb(player::Player::from_ffi(id), String::from_utf8_lossy(text).into_owned())
+  }
+}
+
+callback!(set_on_player_join, ON_PLAYER_JOIN, Fn(player::Player));
+#[no_mangle]
+extern "C" fn on_player_join(id: ffi::CUUID) {
+  if let Some(cb) = ON_PLAYER_JOIN.lock().as_ref() {
+    cb(player::Player::from_ffi(id))
+  }
+}
+
+callback!(set_on_player_leave, ON_PLAYER_LEAVE, Fn(player::Player));
+#[no_mangle]
+extern "C" fn on_player_leave(id: ffi::CUUID) {
+  if let Some(cb) = ON_PLAYER_LEAVE.lock().as_ref() {
+    cb(player::Player::from_ffi(id))
+  }
+}
+
+callback!(set_on_player_death, ON_PLAYER_DEATH, Fn(player::Player));
+#[no_mangle]
+extern "C" fn on_player_death(id: ffi::CUUID) {
+  if let Some(cb) = ON_PLAYER_DEATH.lock().as_ref() {
+    cb(player::Player::from_ffi(id))
+  }
+}
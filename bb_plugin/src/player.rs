@@ -1,5 +1,14 @@
-use crate::{math::Vec3, particle::Particle, world::World, FromFfi, IntoFfi};
-use bb_common::{math::FPos, util::UUID};
+use crate::{
+  item::{Inventory, Stack},
+  math::Vec3,
+  particle::Particle,
+  world::World,
+  FromFfi, IntoFfi,
+};
+use bb_common::{
+  math::FPos,
+  util::{Chat, Effect, UUID},
+};
 use bb_ffi::CUUID;
 
 #[derive(Debug)]
@@ -63,4 +72,183 @@ impl Player {
       Vec3::from_ffi(*cpos)
     }
   }
+
+  /// Sends the given chat message to this player.
+  ///
+  /// This will do nothing if the player has logged off.
+  pub fn send_message(&self, message: Chat) {
+    unsafe {
+      bb_ffi::bb_player_send_message(&self.id.into_ffi(), &message.into_ffi());
+    }
+  }
+  /// Teleports the player to the given position, with a yaw and pitch.
+  ///
+  /// This will do nothing if the player has logged off.
+  pub fn teleport(&self, pos: FPos, yaw: f32, pitch: f32) {
+    unsafe {
+      bb_ffi::bb_player_teleport(&self.id.into_ffi(), &pos.into_ffi(), yaw, pitch);
+    }
+  }
+  /// Moves the player into the given world, at the given position. If the
+  /// player is already in that world, this will just teleport them.
+  ///
+  /// This will do nothing if the player has logged off.
+  pub fn switch_world(&self, world: &World, pos: FPos) {
+    unsafe {
+      bb_ffi::bb_player_switch_world(&self.id.into_ffi(), world.id(), &pos.into_ffi());
+    }
+  }
+  /// Sends the player to another server. Returns `false` if the address is
+  /// invalid, or if the player has logged off.
+  pub fn switch_to(&self, ip: &str) -> bool {
+    unsafe { bb_ffi::bb_player_switch_to(&self.id.into_ffi(), ip.as_ptr(), ip.len() as u32) == 0 }
+  }
+
+  /// Sets the title for this player. To show the title and subtitle, call
+  /// [`show_title`](Self::show_title).
+  pub fn set_title(&self, title: Chat) {
+    unsafe {
+      bb_ffi::bb_player_set_title(&self.id.into_ffi(), &title.into_ffi());
+    }
+  }
+  /// Sets the subtitle for this player. To show the title and subtitle, call
+  /// [`show_title`](Self::show_title).
+  pub fn set_subtitle(&self, subtitle: Chat) {
+    unsafe {
+      bb_ffi::bb_player_set_subtitle(&self.id.into_ffi(), &subtitle.into_ffi());
+    }
+  }
+  /// Shows the current title to the player. The `fade_in`, `stay`, and
+  /// `fade_out` arguments are all in ticks.
+  pub fn show_title(&self, fade_in: u32, stay: u32, fade_out: u32) {
+    unsafe {
+      bb_ffi::bb_player_show_title(&self.id.into_ffi(), fade_in, stay, fade_out);
+    }
+  }
+
+  /// Shows a scoreboard to the player. Call
+  /// [`set_scoreboard_line`](Self::set_scoreboard_line) to display anything in
+  /// the scoreboard.
+  pub fn show_scoreboard(&self) {
+    unsafe {
+      bb_ffi::bb_player_show_scoreboard(&self.id.into_ffi());
+    }
+  }
+  /// Hides the scoreboard for the player.
+  pub fn hide_scoreboard(&self) {
+    unsafe {
+      bb_ffi::bb_player_hide_scoreboard(&self.id.into_ffi());
+    }
+  }
+  /// Sets a line in the scoreboard. If it is hidden, the updated lines will
+  /// show when the scoreboard is shown again. Returns `false` if the line is
+  /// not within `0..15`.
+  pub fn set_scoreboard_line(&self, line: u8, message: Chat) -> bool {
+    unsafe {
+      bb_ffi::bb_player_set_scoreboard_line(&self.id.into_ffi(), line, &message.into_ffi()) == 0
+    }
+  }
+  /// Clears a line in the scoreboard. Returns `false` if the line is not
+  /// within `0..15`.
+  pub fn clear_scoreboard_line(&self, line: u8) -> bool {
+    unsafe { bb_ffi::bb_player_clear_scoreboard_line(&self.id.into_ffi(), line) == 0 }
+  }
+
+  /// Gives the player the passed item. Returns `false` if the item name is
+  /// invalid, or if the player has logged off.
+  pub fn give(&self, stack: Stack) -> bool {
+    unsafe { bb_ffi::bb_player_give(&self.id.into_ffi(), &stack.into_ffi()) == 0 }
+  }
+  /// Shows an inventory to the player. Returns `false` if any of the items in
+  /// the inventory are invalid, or if the player has logged off.
+  pub fn show_inventory(&self, inv: Inventory, title: Chat) -> bool {
+    unsafe {
+      bb_ffi::bb_player_show_inventory(&self.id.into_ffi(), &inv.into_ffi(), &title.into_ffi()) == 0
+    }
+  }
+
+  /// Sets this player's skin. `value` and `signature` are the base64 encoded
+  /// `textures` property and its signature. If `signature` is empty, the skin
+  /// will be unsigned.
+  ///
+  /// Other players will see the new skin immediately. This player will only
+  /// see it after they respawn. Returns `false` if the player has logged off.
+  pub fn set_skin(&self, value: &str, signature: &str) -> bool {
+    unsafe {
+      bb_ffi::bb_player_set_skin(
+        &self.id.into_ffi(),
+        value.as_ptr(),
+        value.len() as u32,
+        signature.as_ptr(),
+        signature.len() as u32,
+      ) == 0
+    }
+  }
+  /// Restores the skin this player logged in with.
+  pub fn reset_skin(&self) {
+    unsafe {
+      bb_ffi::bb_player_reset_skin(&self.id.into_ffi());
+    }
+  }
+
+  /// Gives the player a status effect. `amplifier` is one less than the
+  /// level, and `duration` is in ticks. Returns `false` if the player has
+  /// logged off.
+  pub fn add_effect(&self, effect: Effect, amplifier: u8, duration: u32) -> bool {
+    unsafe {
+      bb_ffi::bb_player_add_effect(
+        &self.id.into_ffi(),
+        effect.id().into(),
+        amplifier.into(),
+        duration,
+      ) == 0
+    }
+  }
+  /// Removes a status effect from the player. Returns `true` if the player had
+  /// the effect.
+  pub fn remove_effect(&self, effect: Effect) -> bool {
+    unsafe { bb_ffi::bb_player_remove_effect(&self.id.into_ffi(), effect.id().into()) == 1 }
+  }
+  /// Removes all status effects from the player.
+  pub fn clear_effects(&self) {
+    unsafe {
+      bb_ffi::bb_player_clear_effects(&self.id.into_ffi());
+    }
+  }
+}
+
+/// A team. This is a group of players, which all share a set of properties,
+/// such as the color of their username.
+///
+/// This can be created with
+/// [`Bamboo::create_team`](crate::Bamboo::create_team).
+#[derive(Debug, Clone)]
+pub struct Team {
+  name: String,
+}
+
+impl Team {
+  pub(crate) fn new(name: &str) -> Self { Team { name: name.into() } }
+
+  /// Returns the name of this team.
+  pub fn name(&self) -> &str { &self.name }
+  /// Sets the color of this team. All players in this team will have their
+  /// usernames displayed in this color. Returns `false` if the color name is
+  /// invalid.
+  pub fn set_color(&self, color: &str) -> bool {
+    unsafe {
+      bb_ffi::bb_team_set_color(
+        self.name.as_ptr(),
+        self.name.len() as u32,
+        color.as_ptr(),
+        color.len() as u32,
+      ) == 0
+    }
+  }
+  /// Adds the player to this team.
+  pub fn add_player(&self, player: &Player) {
+    unsafe {
+      bb_ffi::bb_team_add_player(self.name.as_ptr(), self.name.len() as u32, &player.id.into_ffi());
+    }
+  }
 }
@@ -0,0 +1,63 @@
+//! Persistent storage. Unlike [`PluginStore`](crate::PluginStore), anything
+//! stored here is saved to disk by the server, so it will still be present
+//! after a restart.
+
+use crate::{player::Player, world::World, IntoFfi};
+use bb_ffi::{CStoreScope, CUUID};
+
+/// A handle to one part of this plugin's persistent storage. Keys and values
+/// are both strings.
+///
+/// ```
+/// let data = Storage::player(&player);
+/// let joins = data.get("joins").and_then(|v| v.parse().ok()).unwrap_or(0_u32);
+/// data.set("joins", &(joins + 1).to_string());
+/// ```
+#[derive(Debug)]
+pub struct Storage {
+  scope: CStoreScope,
+}
+
+impl Storage {
+  /// Returns the global storage for this plugin.
+  pub fn global() -> Self {
+    Storage { scope: CStoreScope { kind: 0, player: CUUID { bytes: [0; 4] }, wid: 0 } }
+  }
+  /// Returns the storage for the given player. This is kept while the player is
+  /// offline.
+  pub fn player(player: &Player) -> Self {
+    Storage { scope: CStoreScope { kind: 1, player: player.id().into_ffi(), wid: 0 } }
+  }
+  /// Returns the storage for the given world.
+  pub fn world(world: &World) -> Self {
+    Storage { scope: CStoreScope { kind: 2, player: CUUID { bytes: [0; 4] }, wid: world.id() } }
+  }
+
+  /// Returns the value for the given key, if present.
+  pub fn get(&self, key: &str) -> Option<String> {
+    unsafe {
+      let ptr = bb_ffi::bb_store_get(&self.scope, key.as_ptr(), key.len() as u32);
+      if ptr.is_null() {
+        None
+      } else {
+        Some(Box::from_raw(ptr).into_string())
+      }
+    }
+  }
+  /// Sets the given key to a new value. This overwrites any existing value.
+  pub fn set(&self, key: &str, value: &str) {
+    unsafe {
+      bb_ffi::bb_store_set(
+        &self.scope,
+        key.as_ptr(),
+        key.len() as u32,
+        value.as_ptr(),
+        value.len() as u32,
+      );
+    }
+  }
+  /// Removes the given key. Returns `true` if the key was present.
+  pub fn remove(&self, key: &str) -> bool {
+    unsafe { bb_ffi::bb_store_remove(&self.scope, key.as_ptr(), key.len() as u32) == 1 }
+  }
+}
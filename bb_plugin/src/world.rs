@@ -1,13 +1,34 @@
 use crate::{block, particle::Particle, player::Player, FromFfi, IntoFfi};
 use bb_common::math::{FPos, Pos, PosError};
+use bb_ffi::{CSound, CStr};
 
 pub struct World {
   wid: u32,
 }
 
+/// The category of a sound. This controls which volume slider on the client
+/// affects the sound.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SoundCategory {
+  Master,
+  Music,
+  Record,
+  Weather,
+  Block,
+  Hostile,
+  Neutral,
+  Player,
+  Ambient,
+  Voice,
+}
+
 impl World {
   pub fn new(wid: u32) -> Self { World { wid } }
 
+  /// Returns the id of this world. The default world always has an id of `0`.
+  pub fn id(&self) -> u32 { self.wid }
+
   pub fn get_block(&self, pos: Pos) -> Result<block::Type, PosError> {
     unsafe {
       let id =
@@ -45,9 +66,43 @@ impl World {
       bb_ffi::bb_world_spawn_particle(self.wid, &cparticle);
     }
   }
+  /// Fills the rectangle between `min` and `max` (inclusive) with the given
+  /// block kind. Returns an error if either position is outside the world.
+  pub fn fill_rect_kind(&self, min: Pos, max: Pos, kind: block::Kind) -> Result<(), PosError> {
+    unsafe {
+      if bb_ffi::bb_world_fill_rect_kind(self.wid, &min.into_ffi(), &max.into_ffi(), kind.id()) == 0
+      {
+        Ok(())
+      } else {
+        Err(min.err("invalid position".into()))
+      }
+    }
+  }
+  /// Plays the given sound at the given position. All nearby players will be
+  /// able to hear it.
+  pub fn play_sound(
+    &self,
+    sound: &str,
+    category: SoundCategory,
+    pos: FPos,
+    volume: f32,
+    pitch: f32,
+  ) {
+    unsafe {
+      let csound = CSound {
+        name: CStr::new(sound.into()),
+        category: category as u8,
+        pos: pos.into_ffi(),
+        volume,
+        pitch,
+      };
+      bb_ffi::bb_world_play_sound(self.wid, &csound);
+    }
+  }
   pub fn raycast(&self, from: FPos, to: FPos, water: bool) -> Option<FPos> {
     unsafe {
       let ptr = bb_ffi::bb_world_raycast(
+        self.wid,
         &bb_ffi::CFPos { x: from.x(), y: from.y(), z: from.z() },
         &bb_ffi::CFPos { x: to.x(), y: to.y(), z: to.z() },
         bb_ffi::CBool::new(water),
@@ -1,13 +1,16 @@
 use crate::{
+  forward::{self, ForwardedInfo, Forwarding},
   gnet::{cb as gcb, sb as gsb, tcp},
-  packet::{FromTcp, ToTcp, TypeConverter},
+  packet::{self, FromTcp, ToTcp, TypeConverter},
+  servers::{ClientState, Servers},
+  status::StatusCache,
   stream::PacketStream,
   Result,
 };
 use bb_common::{
   math,
   net::{cb as ccb, sb as csb},
-  util::{chat::Color, Chat, JoinInfo, JoinMode, UUID},
+  util::{chat::Color, Chat, JoinInfo, JoinMode, Property, UUID},
   version::ProtocolVersion,
 };
 use bb_transfer::{
@@ -18,12 +21,13 @@ use rand::{rngs::OsRng, RngCore};
 use rsa::{padding::PaddingScheme, RSAPrivateKey};
 use serde_derive::{Deserialize, Serialize};
 use sha1::{Digest, Sha1};
+use smallvec::SmallVec;
 use std::{
   cell::RefCell,
   convert::TryInto,
   fmt, io,
   io::{ErrorKind, Read, Write},
-  net::SocketAddr,
+  net::{IpAddr, SocketAddr},
   sync::Arc,
 };
 
@@ -49,17 +53,32 @@ impl State {
 }
 
 pub struct Conn<'a, S> {
-  client_stream: S,
-  state:         State,
-  ver:           ProtocolVersion,
-  icon:          &'a str,
+  client_stream:  S,
+  state:          State,
+  ver:            ProtocolVersion,
+  icon:           &'a str,
+  motd:           Option<&'a Chat>,
+  status:         Option<&'a StatusCache>,
+  /// The base url of the session server, without a trailing slash. Only used
+  /// if encryption is enabled.
+  session_server: &'a str,
   /// The name sent from the client. The mojang auth server also sends us a
   /// username; we use this to validate the client info with the mojang auth
   /// info.
-  username:      Option<String>,
-  info:          Option<LoginInfo>,
+  username:       Option<String>,
+  info:           Option<LoginInfo>,
   /// The four byte verify token, used by the client in encryption.
-  verify_token:  [u8; 4],
+  verify_token:   [u8; 4],
+  /// How clients reach this proxy.
+  forwarding:     &'a Forwarding,
+  /// The info from a BungeeCord handshake. This is parsed during the
+  /// handshake, and used once we receive the client's username.
+  forwarded:      Option<ForwardedInfo>,
+  /// The message id of the login plugin request sent to Velocity.
+  velocity_id:    i32,
+  /// The client's IP. If we are behind another proxy, this is the IP that
+  /// proxy forwarded to us.
+  client_ip:      Option<IpAddr>,
 
   /// The private key. Always present, even if encryption is disabled.
   key:                Arc<RSAPrivateKey>,
@@ -72,8 +91,24 @@ pub struct Conn<'a, S> {
   closed: bool,
 
   /// Server address. Used when we are done handshaking, and need to connect to
-  /// a server.
+  /// a server. After that, this is the address of the server we are connected
+  /// to.
   addr:          SocketAddr,
+  /// All the servers we can move this client to. If this is `None`, the client
+  /// is disconnected when the server goes down.
+  servers:       Option<&'a Servers>,
+  /// Servers we tried to move this client to, which went down before the
+  /// client joined. These are skipped when looking for a fallback server.
+  failed:        Vec<SocketAddr>,
+  /// Set when we connect to a new server, and cleared once that server sends
+  /// the join game packet.
+  switching:     bool,
+  /// Set when the server asks us to move the client to another server. We
+  /// cannot connect to the new server while parsing packets, so this is
+  /// handled at the end of `read_server`.
+  pending:       Option<Vec<SocketAddr>>,
+  /// The tab list, scoreboards and teams the client has been sent.
+  client_state:  ClientState,
   /// Used when we create the tcp stream connected to the server.
   server_token:  Token,
   /// A connection to the server. If none, then we haven't finished handshaking.
@@ -87,6 +122,9 @@ pub struct Conn<'a, S> {
 
   conv: Arc<TypeConverter>,
 }
+
+static NO_FORWARDING: Forwarding = Forwarding::None;
+
 thread_local! {
   // Used when reading from the server.
   static READ_GARBAGE: RefCell<Vec<u8>> = RefCell::new(vec![0; 64 * 1024]);
@@ -115,6 +153,22 @@ pub struct LoginInfo {
   pub properties: Vec<LoginProperty>,
 }
 
+impl LoginInfo {
+  /// Converts the properties from the session server into properties that can
+  /// be sent to the server.
+  pub fn properties(&self) -> Vec<Property> {
+    self
+      .properties
+      .iter()
+      .map(|p| Property {
+        name:      p.name.clone(),
+        value:     p.value.clone(),
+        signature: p.signature.clone(),
+      })
+      .collect()
+  }
+}
+
 #[derive(Deserialize, Debug)]
 pub struct LoginProperty {
   // Example: "textures"
@@ -166,14 +220,26 @@ impl<'a, S: PacketStream + Send + Sync> Conn<'a, S> {
       state: State::Handshake,
       ver: ProtocolVersion::Invalid,
       icon: "",
+      motd: None,
+      status: None,
+      session_server: "https://sessionserver.mojang.com",
       username: None,
       info: None,
       verify_token: [0u8; 4],
+      forwarding: &NO_FORWARDING,
+      forwarded: None,
+      velocity_id: 0,
+      client_ip: None,
       key,
       der_key,
       compression_target: 0,
       closed: false,
       addr,
+      servers: None,
+      failed: vec![],
+      switching: false,
+      pending: None,
+      client_state: ClientState::default(),
       server_stream: None,
       server_token,
       to_server: Vec::with_capacity(16 * 1024),
@@ -189,22 +255,62 @@ impl<'a, S: PacketStream + Send + Sync> Conn<'a, S> {
     self.icon = icon;
     self
   }
+  pub fn with_motd(mut self, motd: &'a Chat) -> Self {
+    self.motd = Some(motd);
+    self
+  }
+  pub fn with_status(mut self, status: &'a StatusCache) -> Self {
+    self.status = Some(status);
+    self
+  }
+  pub fn with_session_server(mut self, url: &'a str) -> Self {
+    self.session_server = url.trim_end_matches('/');
+    self
+  }
+  pub fn with_forwarding(mut self, forwarding: &'a Forwarding) -> Self {
+    self.forwarding = forwarding;
+    self
+  }
+  pub fn with_client_ip(mut self, ip: Option<IpAddr>) -> Self {
+    self.client_ip = ip;
+    self
+  }
+  pub fn with_servers(mut self, servers: &'a Servers) -> Self {
+    self.servers = Some(servers);
+    self
+  }
 
   pub fn ver(&self) -> ProtocolVersion { self.ver }
   pub fn closed(&self) -> bool { self.closed }
 
   fn connect_to_server(&mut self, reg: &Registry) -> Result<()> {
-    info!("connecting to server at {:?}", self.addr);
-    let mut stream = TcpStream::connect(self.addr)?;
-    reg.register(&mut stream, self.server_token, Interest::READABLE | Interest::WRITABLE).unwrap();
-    self.server_stream = Some(stream);
+    self.connect_to(self.addr, JoinMode::New, reg)
+  }
+
+  /// Connects to the server at `addr`, and sends the client's info to that
+  /// server. Any existing server connection is closed, which will make the old
+  /// server remove this player.
+  fn connect_to(&mut self, addr: SocketAddr, mode: JoinMode, reg: &Registry) -> Result<()> {
+    info!("connecting to server at {:?}", addr);
+    let mut stream = TcpStream::connect(addr)?;
+    reg.register(&mut stream, self.server_token, Interest::READABLE | Interest::WRITABLE)?;
+    if let Some(mut old) = self.server_stream.replace(stream) {
+      let _ = reg.deregister(&mut old);
+    }
+    self.addr = addr;
+    self.switching = matches!(mode, JoinMode::Switch);
+    // Anything left over is for the old server.
+    self.to_server.clear();
+    self.from_server.clear();
 
     self.write_data_to_server(|s, m| {
       m.write(&JoinInfo {
-        mode:     JoinMode::New,
+        mode,
         username: s.username.clone().unwrap(),
-        uuid:     s.info.as_ref().unwrap().id,
-        ver:      s.ver.id(),
+        uuid: s.info.as_ref().unwrap().id,
+        ver: s.ver.id(),
+        properties: s.info.as_ref().unwrap().properties(),
+        ip: s.client_ip,
       })?;
       Ok(())
     })
@@ -216,12 +322,30 @@ impl<'a, S: PacketStream + Send + Sync> Conn<'a, S> {
     Ok(())
   }
 
-  /// Reads as much data as possible from the server. Returns Ok(true) or Err(_)
-  /// if the connection should be terminated.
-  pub fn read_server(&mut self) -> Result<bool> {
+  /// Writes as much data as possible to the server. If the server has gone
+  /// down, this moves the client to a fallback server. Returns Ok(true) or
+  /// Err(_) if the connection should be terminated.
+  pub fn flush_server(&mut self, reg: &Registry) -> Result<bool> {
+    match self.write_server() {
+      Ok(()) => Ok(false),
+      Err(e) if e.io_kind() == Some(io::ErrorKind::WouldBlock) => Err(e),
+      Err(e) => {
+        warn!("lost connection to server at {:?}: {e}", self.addr);
+        Ok(!self.fallback(reg))
+      }
+    }
+  }
+
+  /// Reads as much data as possible from the server. If the server has gone
+  /// down, this moves the client to a fallback server. Returns Ok(true) or
+  /// Err(_) if the connection should be terminated.
+  pub fn read_server(&mut self, reg: &Registry) -> Result<bool> {
     loop {
       match self.poll_server() {
-        Ok(true) => return Ok(true),
+        Ok(true) => {
+          info!("server at {:?} closed the connection", self.addr);
+          return Ok(!self.fallback(reg));
+        }
         Ok(false) => loop {
           match self.read_server_packet() {
             Ok(true) => {}
@@ -231,11 +355,52 @@ impl<'a, S: PacketStream + Send + Sync> Conn<'a, S> {
           if self.closed() {
             return Ok(true);
           }
+          if let Some(addrs) = self.pending.take() {
+            self.switch_to_now(addrs, reg);
+            // The new server hasn't sent anything yet, and we don't want to
+            // read anything else from the old server.
+            return Ok(false);
+          }
         },
         Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(false),
-        Err(e) => return Err(e.into()),
+        Err(e) => {
+          warn!("lost connection to server at {:?}: {e}", self.addr);
+          return Ok(!self.fallback(reg));
+        }
+      }
+    }
+  }
+
+  /// Moves the client to the first fallback server we can connect to. Returns
+  /// `false` if there are no fallback servers left, in which case the client
+  /// should be disconnected.
+  fn fallback(&mut self, reg: &Registry) -> bool {
+    let servers = match self.servers {
+      Some(s) => s,
+      None => return false,
+    };
+    if let Some(mut old) = self.server_stream.take() {
+      let _ = reg.deregister(&mut old);
+    }
+    self.failed.push(self.addr);
+    let addrs: Vec<_> = servers.fallback().filter(|a| !self.failed.contains(a)).collect();
+    for addr in addrs {
+      match self.connect_to(addr, JoinMode::Switch, reg) {
+        Ok(()) => {
+          let mut msg = Chat::empty();
+          msg.add("The server you were on went down, moving you to ").color(Color::Yellow);
+          msg.add(servers.name_of(addr)).color(Color::Gold);
+          self.send_message(msg);
+          return true;
+        }
+        Err(e) => {
+          warn!("couldn't connect to fallback server at {addr:?}: {e}");
+          self.failed.push(addr);
+        }
       }
     }
+    error!("no fallback servers are available for {:?}", self.username);
+    false
   }
 
   /// Returns true if the connection should close.
@@ -273,7 +438,11 @@ impl<'a, S: PacketStream + Send + Sync> Conn<'a, S> {
           };
           let parsed = m.index();
           self.from_server.drain(0..parsed);
-          let packets = common.to_tcp(self).unwrap();
+          self.client_state.track(&common);
+          let packets = match common {
+            ccb::Packet::JoinGame(join) if self.switching => self.finish_switch(join),
+            common => common.to_tcp(self).unwrap(),
+          };
           if len as usize != parsed {
             return Err(io::Error::new(
               io::ErrorKind::InvalidData,
@@ -425,31 +594,90 @@ impl<'a, S: PacketStream + Send + Sync> Conn<'a, S> {
     }
   }
 
-  /// Switches this connection to a new server. If all of the ips are bad, this
-  /// doesn't change anything.
-  pub fn switch_to(&mut self, addrs: Vec<SocketAddr>) {
+  /// Switches this connection to a new server. The switch happens once we
+  /// have finished reading packets from the current server.
+  pub fn switch_to(&mut self, addrs: Vec<SocketAddr>) { self.pending = Some(addrs); }
+
+  /// Switches this connection to the server with the given name in
+  /// `proxy.toml`. If there is no such server, the client is sent an error
+  /// message.
+  pub fn switch_to_named(&mut self, name: &str) {
+    match self.servers.and_then(|s| s.get(name)) {
+      Some(addr) => self.switch_to(vec![addr]),
+      None => {
+        warn!("server tried to move {:?} to unknown server `{name}`", self.username);
+        let mut msg = Chat::empty();
+        msg.add(format!("Couldn't find server `{name}`")).color(Color::Red);
+        self.send_message(msg);
+      }
+    }
+  }
+
+  /// Connects to the first of `addrs` that works. If none of them work, the
+  /// client stays on the current server, and is sent an error message.
+  fn switch_to_now(&mut self, addrs: Vec<SocketAddr>, reg: &Registry) {
     for addr in addrs {
-      let conn = match TcpStream::connect(addr) {
-        Ok(v) => v,
-        Err(_) => continue,
-      };
-      let old_stream = std::mem::replace(&mut self.server_stream, Some(conn));
-      match self.write_data_to_server(|s, m| {
-        m.write(&JoinInfo {
-          mode:     JoinMode::Switch,
-          username: s.username.clone().unwrap(),
-          uuid:     s.info.as_ref().unwrap().id,
-          ver:      s.ver.id(),
-        })?;
-        Ok(())
-      }) {
-        Ok(()) => break,
-        Err(_) => {
-          self.server_stream = old_stream;
-          continue;
+      match self.connect_to(addr, JoinMode::Switch, reg) {
+        Ok(()) => {
+          // This is a new server, so every fallback server might work again.
+          self.failed.clear();
+          return;
         }
+        Err(e) => warn!("couldn't switch to server at {addr:?}: {e}"),
       }
     }
+    let mut msg = Chat::empty();
+    msg.add("Couldn't connect to that server").color(Color::Red);
+    self.send_message(msg);
+  }
+
+  /// Called when the new server sends the join game packet after a switch.
+  /// This clears everything the old server sent to the client, and then joins
+  /// the new world.
+  fn finish_switch(&mut self, join: ccb::packet::JoinGame) -> SmallVec<[gcb::Packet; 2]> {
+    self.switching = false;
+    self.failed.clear();
+    let mut out = SmallVec::new();
+    for p in self.client_state.clear() {
+      out.extend(p.to_tcp(self).unwrap());
+    }
+    let respawn = ccb::packet::Respawn {
+      game_mode:    join.game_mode,
+      dimension:    join.dimension,
+      world_name:   join.world_name.clone(),
+      level_type:   join.level_type.clone(),
+      difficulty:   join.difficulty,
+      world_height: join.world_height,
+      world_min_y:  join.world_min_y,
+    };
+    let dimension = join.dimension;
+    out.extend(join.to_tcp(self).unwrap());
+    // 1.16+ clients reset their world on join game. Older clients will keep
+    // some chunks and entities, so we respawn them in a different dimension,
+    // and then back into the dimension of the new server.
+    if self.ver < ProtocolVersion::V1_16_5 {
+      out.push(packet::respawn_in(&respawn, self.ver, packet::other_dimension(dimension)));
+      out.push(packet::respawn_in(&respawn, self.ver, dimension));
+    }
+    out
+  }
+
+  /// Sends a chat message to the client. This is used for messages from the
+  /// proxy itself, such as when the client is moved to a fallback server.
+  fn send_message(&mut self, msg: Chat) {
+    let p = ccb::packet::ChatMessage { msg, ty: 1 };
+    match ccb::Packet::from(p).to_tcp(self) {
+      Ok(packets) => {
+        for p in packets {
+          if let Err(e) = self.send_to_client(p) {
+            if e.io_kind() != Some(io::ErrorKind::WouldBlock) {
+              warn!("couldn't send message to client: {e}");
+            }
+          }
+        }
+      }
+      Err(e) => warn!("couldn't send message to client: {e}"),
+    }
   }
 
   /// Sends the login success packet, and sets the state to Play. The stream
@@ -462,7 +690,11 @@ impl<'a, S: PacketStream + Send + Sync> Conn<'a, S> {
     if ver >= ProtocolVersion::V1_19 {
       out.write_uuid(info.id);
       out.write_str(&info.name);
-      out.write_varint(0); // no properties. TODO: Skins
+      out.write_list(&info.properties, |out, prop| {
+        out.write_str(&prop.name);
+        out.write_str(&prop.value);
+        out.write_option(&prop.signature, |out, sig| out.write_str(sig));
+      });
     } else if ver >= ProtocolVersion::V1_16 {
       out.write_uuid(info.id);
       out.write_str(&info.name);
@@ -490,7 +722,7 @@ impl<'a, S: PacketStream + Send + Sync> Conn<'a, S> {
   fn send_disconnect<C: Into<Chat>>(&mut self, reason: C) {
     // Disconnect
     match self.state {
-      State::Handshake => {
+      State::Handshake | State::Login => {
         let mut out = tcp::Packet::new(0, self.ver);
         out.write_str(&reason.into().to_json());
         self.client_stream.write(out);
@@ -505,15 +737,56 @@ impl<'a, S: PacketStream + Send + Sync> Conn<'a, S> {
     }
   }
 
+  /// Handles the login start packet when we are behind another proxy. For
+  /// BungeeCord, the player's info was sent in the handshake, so this will
+  /// finish logging in. For Velocity, this asks for the player's info, and the
+  /// login is finished once Velocity replies.
+  fn start_forwarded_login(&mut self, name: String, reg: &Registry) -> Result<()> {
+    match self.forwarding {
+      Forwarding::None => unreachable!(),
+      Forwarding::Legacy { .. } => {
+        let info = self.forwarded.take().ok_or_else(|| {
+          io::Error::new(ErrorKind::InvalidInput, "client did not send forwarded info")
+        })?;
+        self.client_ip = Some(info.ip);
+        self.info = Some(LoginInfo { id: info.id, name, properties: info.properties });
+        self.send_compression();
+        self.finish_login(reg)
+      }
+      Forwarding::Velocity { .. } => {
+        // Login plugin messages were added in 1.13.
+        if self.ver < ProtocolVersion::V1_13 {
+          return Err(
+            io::Error::new(
+              ErrorKind::InvalidInput,
+              "velocity forwarding requires 1.13 or newer clients",
+            )
+            .into(),
+          );
+        }
+        self.velocity_id = (OsRng.next_u32() >> 1) as i32;
+        self.client_stream.write(forward::velocity_request(self.velocity_id, self.ver));
+        Ok(())
+      }
+    }
+  }
+
   /// Generates the json status for the server
   fn build_status(&self) -> JsonStatus {
-    let mut description = Chat::empty();
-    description.add("Bamboo").color(Color::BrightGreen);
-    description.add(" -- ").color(Color::Gray);
-    #[cfg(debug_assertions)]
-    description.add("Development mode").color(Color::Blue);
-    #[cfg(not(debug_assertions))]
-    description.add("Release mode").color(Color::Red);
+    // If the server is down, we still want to reply, so that the client knows the
+    // proxy is up.
+    let players = match self.status.and_then(|s| s.get()) {
+      Some(status) => JsonPlayers {
+        max:    status.max as i32,
+        online: status.online as i32,
+        sample: status
+          .sample
+          .into_iter()
+          .map(|p| JsonPlayer { name: p.username, id: p.uuid.as_dashed_str() })
+          .collect(),
+      },
+      None => JsonPlayers { max: 0, online: 0, sample: vec![] },
+    };
     JsonStatus {
       version: JsonVersion {
         name:     format!("1.8 - {}", ProtocolVersion::latest()),
@@ -523,15 +796,8 @@ impl<'a, S: PacketStream + Send + Sync> Conn<'a, S> {
           self.ver.id()
         } as i32,
       },
-      players: JsonPlayers {
-        max:    69,
-        online: 420,
-        sample: vec![JsonPlayer {
-          name: "macmv".into(),
-          id:   "a0ebbc8d-e0b0-4c23-a965-efba61ff0ae8".into(),
-        }],
-      },
-      description,
+      players,
+      description: self.motd.cloned().unwrap_or_default(),
       favicon: self.icon,
     }
   }
@@ -560,11 +826,18 @@ impl<'a, S: PacketStream + Send + Sync> Conn<'a, S> {
         }
         self.ver = ProtocolVersion::from(p.read_varint()?);
 
-        // Max len according to 1.17.1
-        let _addr = p.read_str(255)?;
+        // Max len according to 1.17.1. BungeeCord appends the player's info to the
+        // address, so it will be much longer.
+        let addr = match self.forwarding {
+          Forwarding::Legacy { .. } => p.read_str(32767)?,
+          _ => p.read_str(255)?,
+        };
         let _port = p.read_u16()?;
         let next = p.read_varint()?;
         self.state = State::from_next(next);
+        if let (Forwarding::Legacy { secret }, State::Login) = (self.forwarding, self.state) {
+          self.forwarded = Some(forward::parse_legacy(&addr, secret)?);
+        }
 
         match self.state {
           State::Handshake => {
@@ -636,6 +909,13 @@ impl<'a, S: PacketStream + Send + Sync> Conn<'a, S> {
             // Max length according to 1.17.1
             let name = p.read_str(16)?;
             self.username = Some(name.to_string());
+            if self.forwarding.is_enabled() {
+              // The front proxy has already authenticated this client, so we skip
+              // encryption entirely.
+              self.start_forwarded_login(name, reg)?;
+              self.client_stream.flush()?;
+              return Ok(());
+            }
             if self.der_key.is_none() {
               self.info = Some(LoginInfo {
                 // Generate uuid if we are in offline mode
@@ -726,38 +1006,94 @@ impl<'a, S: PacketStream + Send + Sync> Conn<'a, S> {
             hash.update("");
             hash.update(secret);
             hash.update(self.der_key.as_ref().unwrap());
-            self.info = match ureq::get(&format!(
-              "https://sessionserver.mojang.com/session/minecraft/hasJoined?username={}&serverId={}",
-              self.username.as_ref().unwrap(),
-              math::hexdigest(hash)
-            )).call() {
+            let res = ureq::get(&format!("{}/session/minecraft/hasJoined", self.session_server))
+              .query("username", self.username.as_ref().unwrap())
+              .query("serverId", &math::hexdigest(hash))
+              .call();
+            let info: LoginInfo = match res {
               Ok(v) => {
                 info!("got status code: {}", v.status());
                 // No content
                 if v.status() == 204 {
-                  self.send_disconnect("Invalid auth token! Please re-login (restart your game and launcher)");
+                  self.send_disconnect(
+                    "Invalid auth token! Please re-login (restart your game and launcher)",
+                  );
                   self.client_stream.flush()?;
                   // Disconnect client; they are not authenticated
                   self.closed = true;
                   return Ok(());
                 }
                 match serde_json::from_reader(v.into_reader()) {
-                  Ok(v) => Some(v),
-                  Err(e) => return Err(io::Error::new(
-                    ErrorKind::InvalidData,
-                    format!("invalid json data received from session server: {}", e),
-                  ).into())
+                  Ok(v) => v,
+                  Err(e) => {
+                    return Err(
+                      io::Error::new(
+                        ErrorKind::InvalidData,
+                        format!("invalid json data received from session server: {}", e),
+                      )
+                      .into(),
+                    )
+                  }
                 }
-              },
-              Err(e) => return Err(io::Error::new(
-                ErrorKind::Other,
-                format!("failed to authenticate client: {}", e),
-              ).into())
+              }
+              Err(e) => {
+                return Err(
+                  io::Error::new(ErrorKind::Other, format!("failed to authenticate client: {}", e))
+                    .into(),
+                )
+              }
             };
+            // The session server should never give us a different player, but if it does,
+            // we don't want the client to end up logged in as someone else.
+            if !info.name.eq_ignore_ascii_case(self.username.as_ref().unwrap()) {
+              return Err(
+                io::Error::new(
+                  ErrorKind::InvalidData,
+                  format!(
+                    "session server returned username {}, but client sent {}",
+                    info.name,
+                    self.username.as_ref().unwrap()
+                  ),
+                )
+                .into(),
+              );
+            }
+            self.info = Some(info);
 
             self.send_compression();
             self.finish_login(reg)?;
           }
+          // Login plugin response
+          2 if matches!(self.forwarding, Forwarding::Velocity { .. }) => {
+            let secret = match self.forwarding {
+              Forwarding::Velocity { secret } => secret,
+              _ => unreachable!(),
+            };
+            let id = p.read_varint()?;
+            if self.username.is_none() || id != self.velocity_id {
+              return Err(
+                io::Error::new(
+                  ErrorKind::InvalidInput,
+                  format!("unexpected login plugin response {id}"),
+                )
+                .into(),
+              );
+            }
+            if !p.read_bool()? {
+              // The client connected directly, or Velocity has modern forwarding disabled.
+              self.send_disconnect("This server requires Velocity modern forwarding");
+              self.client_stream.flush()?;
+              self.closed = true;
+              return Ok(());
+            }
+            let info = forward::parse_velocity(p.read_all(), secret, self.ver)?;
+            let name = info.name.unwrap();
+            self.username = Some(name.clone());
+            self.client_ip = Some(info.ip);
+            self.info = Some(LoginInfo { id: info.id, name, properties: info.properties });
+            self.send_compression();
+            self.finish_login(reg)?;
+          }
           _ => {
             return Err(
               io::Error::new(ErrorKind::InvalidInput, format!("unknown login packet {}", p.id()))
@@ -0,0 +1,231 @@
+//! Player info forwarding. When `bb_proxy` is placed behind another proxy
+//! (such as Velocity or BungeeCord), the front proxy authenticates clients, and
+//! then forwards the client's real IP, UUID and skin to us. This module parses
+//! that forwarded info.
+
+use crate::{conn::LoginProperty, gnet::tcp, Result};
+use bb_common::{config::Config, util::UUID, version::ProtocolVersion};
+use hmac::{Hmac, Mac, NewMac};
+use sha2::Sha256;
+use std::{
+  io,
+  io::ErrorKind,
+  net::{IpAddr, SocketAddr},
+  str::FromStr,
+};
+
+/// The channel Velocity uses for forwarding.
+pub const VELOCITY_CHANNEL: &str = "velocity:player_info";
+/// The forwarding version we request from Velocity. Version 1 is supported by
+/// every version of Velocity that has modern forwarding.
+pub const VELOCITY_VERSION: u8 = 1;
+
+/// How clients reach this proxy. This is set with the `[forwarding]` section
+/// of `proxy.toml`.
+#[derive(Debug, Clone)]
+pub enum Forwarding {
+  /// Clients connect directly to this proxy. This is the default.
+  None,
+  /// BungeeCord's legacy forwarding. The client's IP, UUID and properties are
+  /// appended to the server address in the handshake packet. If `secret` is
+  /// not empty, a matching `bungeeguard-token` property is required.
+  Legacy { secret: String },
+  /// Velocity's modern forwarding. The client's info is sent in a login plugin
+  /// message, which is signed with the shared secret.
+  Velocity { secret: Vec<u8> },
+}
+
+/// The player info sent from the front proxy.
+#[derive(Debug)]
+pub struct ForwardedInfo {
+  /// The client's real IP.
+  pub ip:         IpAddr,
+  pub id:         UUID,
+  /// The client's username. Only Velocity sends this, as BungeeCord relies on
+  /// the login start packet.
+  pub name:       Option<String>,
+  pub properties: Vec<LoginProperty>,
+}
+
+fn invalid(msg: impl Into<String>) -> crate::Error {
+  io::Error::new(ErrorKind::InvalidData, msg.into()).into()
+}
+
+impl Forwarding {
+  /// Reads the `[forwarding]` section of the config. Returns an error if the
+  /// config would leave the proxy open to anyone, which should stop the proxy
+  /// from starting.
+  pub fn from_config(config: &Config) -> Result<Self> {
+    let secret: String = config.get_at(["forwarding", "secret"].into_iter());
+    let mode: &str = config.get_at(["forwarding", "mode"].into_iter());
+    Ok(match mode {
+      "none" => Forwarding::None,
+      "legacy" | "bungeecord" => {
+        if secret.is_empty() {
+          warn!("legacy forwarding is enabled without a secret");
+          warn!("anyone who can connect to this proxy can log in as any player");
+        }
+        Forwarding::Legacy { secret }
+      }
+      "velocity" => {
+        if secret.is_empty() {
+          return Err(invalid("velocity forwarding requires `forwarding.secret` to be set"));
+        }
+        Forwarding::Velocity { secret: secret.into_bytes() }
+      }
+      _ => {
+        error!("unknown forwarding mode `{mode}`, expected `none`, `legacy`, or `velocity`");
+        Forwarding::None
+      }
+    })
+  }
+
+  /// Returns `true` if clients are authenticated by a front proxy. If so, we
+  /// should never authenticate them ourselves.
+  pub fn is_enabled(&self) -> bool { !matches!(self, Forwarding::None) }
+}
+
+/// Parses the server address from a BungeeCord handshake. This is in the
+/// format `host\0ip\0uuid\0properties`, where properties is a json list.
+pub fn parse_legacy(addr: &str, secret: &str) -> Result<ForwardedInfo> {
+  let mut sections = addr.split('\0');
+  let _host = sections.next();
+  let (ip, id) = match (sections.next(), sections.next()) {
+    (Some(ip), Some(id)) => (ip, id),
+    _ => return Err(invalid("missing forwarded info, is ip forwarding enabled in BungeeCord?")),
+  };
+  let ip = IpAddr::from_str(ip)
+    .or_else(|_| SocketAddr::from_str(ip).map(|a| a.ip()))
+    .map_err(|e| invalid(format!("invalid forwarded ip `{ip}`: {e}")))?;
+  let id = UUID::from_str(id).map_err(|e| invalid(format!("invalid forwarded uuid: {e}")))?;
+  let mut properties: Vec<LoginProperty> = match sections.next() {
+    Some(json) => serde_json::from_str(json)
+      .map_err(|e| invalid(format!("invalid forwarded properties: {e}")))?,
+    None => vec![],
+  };
+  // BungeeGuard sends the token as a property. This should never be sent to
+  // clients, so we always remove it.
+  let token = properties.iter().position(|p| p.name == "bungeeguard-token");
+  let token = token.map(|i| properties.remove(i));
+  if !secret.is_empty() && token.map(|t| t.value) != Some(secret.into()) {
+    return Err(invalid("invalid or missing bungeeguard token"));
+  }
+  Ok(ForwardedInfo { ip, id, name: None, properties })
+}
+
+/// Creates the login plugin request that asks Velocity for the player's info.
+pub fn velocity_request(message_id: i32, ver: ProtocolVersion) -> tcp::Packet {
+  let mut out = tcp::Packet::new(4, ver);
+  out.write_varint(message_id);
+  out.write_str(VELOCITY_CHANNEL);
+  out.write_u8(VELOCITY_VERSION);
+  out
+}
+
+/// Parses the data from Velocity's login plugin response. The first 32 bytes
+/// are an HMAC-SHA256 signature of the rest of the data.
+pub fn parse_velocity(data: Vec<u8>, secret: &[u8], ver: ProtocolVersion) -> Result<ForwardedInfo> {
+  if data.len() < 32 {
+    return Err(invalid("velocity forwarding data is too short"));
+  }
+  let (sig, payload) = data.split_at(32);
+  let mut mac = Hmac::<Sha256>::new_from_slice(secret).unwrap();
+  mac.update(payload);
+  if mac.verify(sig).is_err() {
+    return Err(invalid("invalid velocity forwarding signature, check `forwarding.secret`"));
+  }
+
+  let mut p = tcp::Packet::from_buf_id(payload.to_vec(), 2, ver);
+  let version = p.read_varint()?;
+  if version < 1 {
+    return Err(invalid(format!("unsupported velocity forwarding version {version}")));
+  }
+  let ip = p.read_str(255)?;
+  let ip = IpAddr::from_str(&ip).map_err(|e| invalid(format!("invalid forwarded ip: {e}")))?;
+  let id = p.read_uuid()?;
+  let name = p.read_str(16)?;
+  let properties = p.read_list(|p| {
+    Ok(LoginProperty {
+      name:      p.read_str(32767)?,
+      value:     p.read_str(32767)?,
+      signature: p.read_option(|p| p.read_str(32767))?,
+    })
+  })?;
+  // Anything after this (such as the player's chat key in newer versions) is
+  // ignored.
+  Ok(ForwardedInfo { ip, id, name: Some(name), properties })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn legacy() {
+    let addr = "localhost\0127.0.0.1\000000000000000000000000000000001\0[]";
+    let info = parse_legacy(addr, "").unwrap();
+    assert_eq!(info.ip, IpAddr::from([127, 0, 0, 1]));
+    assert_eq!(info.id, UUID::from_u128(1));
+    assert!(info.properties.is_empty());
+
+    let addr = "localhost\0127.0.0.1\000000000000000000000000000000001\0\
+                [{\"name\":\"bungeeguard-token\",\"value\":\"hello\"}]";
+    assert!(parse_legacy(addr, "hello").unwrap().properties.is_empty());
+    assert!(parse_legacy(addr, "world").is_err());
+    assert!(parse_legacy("localhost", "").is_err());
+  }
+  /// A forwarding response for `127.0.0.1`, with the UUID `1`, the name
+  /// `macmv`, and a single unsigned `textures` property.
+  fn velocity_payload() -> Vec<u8> {
+    let mut payload = vec![1, 9];
+    payload.extend_from_slice(b"127.0.0.1");
+    payload.extend_from_slice(&1_u128.to_be_bytes());
+    payload.push(5);
+    payload.extend_from_slice(b"macmv");
+    payload.extend_from_slice(&[1, 8]);
+    payload.extend_from_slice(b"textures");
+    payload.push(3);
+    payload.extend_from_slice(b"abc");
+    payload.push(0);
+    payload
+  }
+
+  #[test]
+  fn velocity() {
+    // HMAC-SHA256 of `velocity_payload()`, with the key `secret`.
+    let sig: Vec<u8> = (0..32)
+      .map(|i| {
+        let hex = "e7f8e063eccfd6f0979d4b6bf620bb759f6218c1d3f5c80d58163a6f1d32796e";
+        u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).unwrap()
+      })
+      .collect();
+    let mut data = sig;
+    data.extend(velocity_payload());
+
+    let info = parse_velocity(data, b"secret", ProtocolVersion::V1_8).unwrap();
+    assert_eq!(info.ip, IpAddr::from([127, 0, 0, 1]));
+    assert_eq!(info.id, UUID::from_u128(1));
+    assert_eq!(info.name.as_deref(), Some("macmv"));
+    assert_eq!(info.properties.len(), 1);
+    assert_eq!(info.properties[0].name, "textures");
+    assert_eq!(info.properties[0].value, "abc");
+    assert_eq!(info.properties[0].signature, None);
+  }
+
+  #[test]
+  fn velocity_bad_signature() {
+    let mut mac = Hmac::<Sha256>::new_from_slice(b"secret").unwrap();
+    mac.update(&velocity_payload());
+    let mut data = mac.finalize().into_bytes().to_vec();
+    data.extend(velocity_payload());
+    assert!(parse_velocity(data.clone(), b"secret", ProtocolVersion::V1_8).is_ok());
+    // The wrong secret.
+    assert!(parse_velocity(data.clone(), b"other", ProtocolVersion::V1_8).is_err());
+    // A modified payload.
+    let last = data.len() - 2;
+    data[last] = b'x';
+    assert!(parse_velocity(data, b"secret", ProtocolVersion::V1_8).is_err());
+    // Too short to contain a signature.
+    assert!(parse_velocity(vec![0; 31], b"secret", ProtocolVersion::V1_8).is_err());
+  }
+}
@@ -213,12 +213,8 @@ impl Packet {
           };
         }
       }
-      Item::new(
-        conv.item_to_new(id as u32, damage as u32, self.ver.block()) as i32,
-        count,
-        damage,
-        nbt,
-      )
+      let (id, damage) = conv.item_to_new_damage(id as u32, damage, self.ver.block());
+      Item::new(id as i32, count, damage, nbt)
     } else if self.read_bool()? {
       let id = self.read_varint()?;
       let count = self.read_u8()?;
@@ -410,3 +406,26 @@ fn test_index_update() {
   assert_eq!(p.read_u8().unwrap(), 3);
   p.read_u8().unwrap_err();
 }
+
+#[test]
+fn test_read_damaged_item() {
+  let conv = TypeConverter::new();
+  let read = |id: i16, damage: i16| {
+    let mut data = vec![];
+    data.extend_from_slice(&id.to_be_bytes());
+    data.push(1); // count
+    data.extend_from_slice(&damage.to_be_bytes());
+    data.push(0); // no nbt
+    let mut p = Packet::from_buf_id(data, 0, ProtocolVersion::V1_8);
+    let item = p.read_item(&conv).unwrap();
+    (item.id(), item.damage)
+  };
+  // A diamond sword has no variants, so the damage is its durability.
+  let sword = conv.item_to_new(276, 0, ProtocolVersion::V1_8.block()) as i32;
+  assert_ne!(sword, 0);
+  assert_eq!(read(276, 100), (sword, 100));
+  // Wool uses the damage for its color.
+  let lime_wool = conv.item_to_new(35, 5, ProtocolVersion::V1_8.block()) as i32;
+  assert_ne!(lime_wool, conv.item_to_new(35, 0, ProtocolVersion::V1_8.block()) as i32);
+  assert_eq!(read(35, 5), (lime_wool, 0));
+}
@@ -7,13 +7,16 @@ extern crate smallvec;
 
 pub mod conn;
 mod error;
+pub mod forward;
 pub mod gnet;
 pub mod packet;
+pub mod servers;
+pub mod status;
 pub mod stream;
 
 pub use error::{Error, Result};
 
-use bb_common::{config::Config, math::der};
+use bb_common::{config::Config, math::der, util::Chat};
 use mio::{
   event::Event,
   net::{TcpListener, TcpStream},
@@ -21,9 +24,12 @@ use mio::{
 };
 use rand::rngs::OsRng;
 use rsa::RSAPrivateKey;
-use std::{collections::HashMap, io, net::SocketAddr, sync::Arc};
+use std::{collections::HashMap, io, sync::Arc, time::Duration};
 
-use crate::{conn::Conn, packet::TypeConverter, stream::java::stream::JavaStream};
+use crate::{
+  conn::Conn, forward::Forwarding, packet::TypeConverter, servers::Servers, status::StatusCache,
+  stream::java::stream::JavaStream,
+};
 
 pub fn load_icon(path: &str) -> String {
   let mut icon = match image::open(path).map_err(|e| error!("error loading icon: {}", e)) {
@@ -58,6 +64,10 @@ pub fn run(config: Config) -> Result<()> {
   bb_common::init_with_level("proxy", level);
 
   let icon = Arc::new(load_icon(config.get("icon")));
+  let motd: Chat = config.get("motd");
+  let servers = Servers::from_config(&config);
+  let forwarding = Forwarding::from_config(&config)?;
+  let status = StatusCache::new(servers.lobby(), Duration::from_secs(config.get("status-cache")));
 
   let addr = config.get::<&str>("address");
   info!("listening for java clients on {}", addr);
@@ -70,8 +80,8 @@ pub fn run(config: Config) -> Result<()> {
   // The vanilla server uses 1024 bits for this.
   let key = Arc::new(RSAPrivateKey::new(&mut OsRng, 1024).expect("failed to generate a key"));
   let der_key = if config.get("encryption") { Some(der::encode(&key)) } else { None };
-  let server_ip: SocketAddr = config.get::<&str>("server").parse().unwrap();
   let compression = config.get("compression-thresh");
+  let session_server: String = config.get("session-server");
 
   let mut events = Events::with_capacity(1024);
 
@@ -88,15 +98,22 @@ pub fn run(config: Config) -> Result<()> {
 
     for event in &events {
       listener.handle(event, |client, server_token| {
+        let ip = client.peer_addr().ok().map(|addr| addr.ip());
         Conn::new(
           JavaStream::new(client),
-          server_ip,
+          servers.lobby(),
           key.clone(),
           der_key.clone(),
           server_token,
           conv.clone(),
         )
         .with_icon(&icon)
+        .with_motd(&motd)
+        .with_status(&status)
+        .with_session_server(&session_server)
+        .with_forwarding(&forwarding)
+        .with_client_ip(ip)
+        .with_servers(&servers)
         .with_compression(compression)
       })?;
     }
@@ -156,15 +173,15 @@ impl<'a> Listener<'a> {
         if is_server {
           if event.is_readable() {
             if let Some(conn) = handler.get() {
-              let res = conn.read_server();
+              let res = conn.read_server(self.poll.registry());
               handler.handle_bool(res);
             }
           }
 
           if event.is_writable() {
             if let Some(conn) = handler.get() {
-              let res = conn.write_server();
-              handler.handle_unit(res);
+              let res = conn.flush_server(self.poll.registry());
+              handler.handle_bool(res);
             }
           }
         } else {
@@ -1,4 +1,4 @@
-use bb_common::{nbt, util::Buffer, version::ProtocolVersion};
+use bb_common::{chunk::biome, nbt, util::Buffer, version::ProtocolVersion};
 use serde::Serialize;
 
 #[derive(Debug, Clone, Serialize)]
@@ -76,15 +76,8 @@ struct CodecItem<T> {
   element: T,
 }
 
-pub fn write_dimensions<T>(
-  out: &mut Buffer<T>,
-  ver: ProtocolVersion,
-  world_height: u32,
-  world_min_y: i32,
-) where
-  std::io::Cursor<T>: std::io::Write,
-{
-  let dimension = Dimension {
+fn overworld(world_height: u32, world_min_y: i32) -> Dimension {
+  Dimension {
     piglin_safe:          false,
     natural:              true,
     ambient_light:        0.0,
@@ -104,38 +97,54 @@ pub fn write_dimensions<T>(
 
     monster_spawn_light_level:       7,
     monster_spawn_block_light_limit: 7,
-  };
-  let biome = Biome {
-    precipitation: "rain".into(),
-    depth:         1.0,
-    temperature:   1.0,
-    scale:         1.0,
-    downfall:      1.0,
-    category:      "none".into(),
-    effects:       BiomeEffects {
-      sky_color:       0x78a7ff,
-      fog_color:       0xc0d8ff,
-      water_fog_color: 0x050533,
-      water_color:     0x3f76e4,
-      foliage_color:   None,
-      grass_color:     None,
-      mood_sound:      Some(MoodSound {
-        block_search_extent: 8,
-        offset:              2.0,
-        sound:               "minecraft:ambient.cave".into(),
-        tick_delay:          6000,
-      }),
-      // sky_color:       0xff00ff,
-      // water_color:     0xff00ff,
-      // fog_color:       0xff00ff,
-      // water_fog_color: 0xff00ff,
-      // grass_color:     0xff00ff,
-      // foliage_color:   0x00ffe5,
-      // grass_color:     0xff5900,
-    },
-  };
-  let dimension_tag = nbt::to_nbt("", &dimension).unwrap();
+  }
+}
 
+pub fn write_dimensions<T>(
+  out: &mut Buffer<T>,
+  ver: ProtocolVersion,
+  world_height: u32,
+  world_min_y: i32,
+  world_name: &str,
+) where
+  std::io::Cursor<T>: std::io::Write,
+{
+  let dimension = overworld(world_height, world_min_y);
+  // Biome ids in chunk packets are indices into `BIOMES`, so every biome is
+  // registered with its index as the id.
+  let biomes = biome::BIOMES.iter().enumerate().map(|(id, info)| CodecItem {
+    name:    format!("minecraft:{}", info.name),
+    id:      id as i32,
+    element: Biome {
+      precipitation: info.precipitation.into(),
+      depth:         1.0,
+      temperature:   info.temperature,
+      scale:         1.0,
+      downfall:      info.downfall,
+      category:      info.category.into(),
+      effects:       BiomeEffects {
+        sky_color:       0x78a7ff,
+        fog_color:       0xc0d8ff,
+        water_fog_color: 0x050533,
+        water_color:     0x3f76e4,
+        foliage_color:   None,
+        grass_color:     None,
+        mood_sound:      Some(MoodSound {
+          block_search_extent: 8,
+          offset:              2.0,
+          sound:               "minecraft:ambient.cave".into(),
+          tick_delay:          6000,
+        }),
+        // sky_color:       0xff00ff,
+        // water_color:     0xff00ff,
+        // fog_color:       0xff00ff,
+        // water_fog_color: 0xff00ff,
+        // grass_color:     0xff00ff,
+        // foliage_color:   0x00ffe5,
+        // grass_color:     0xff5900,
+      },
+    },
+  });
   let info = LoginInfo {
     dimensions: Codec {
       ty:    "minecraft:dimension_type".into(),
@@ -145,25 +154,36 @@ pub fn write_dimensions<T>(
         element: dimension,
       }],
     },
-    biomes:     Codec {
-      ty:    "minecraft:worldgen/biome".into(),
-      value: vec![CodecItem { name: "minecraft:plains".into(), id: 0, element: biome }],
-    },
+    biomes:     Codec { ty: "minecraft:worldgen/biome".into(), value: biomes.collect() },
   };
 
   // Dimension codec
   out.write_buf(&nbt::to_nbt("", &info).unwrap().serialize());
+  write_dimension(out, ver, world_height, world_min_y, world_name);
+}
+
+/// Writes the current dimension type, and the current world name. This is
+/// used in both the join game and respawn packets.
+pub fn write_dimension<T>(
+  out: &mut Buffer<T>,
+  ver: ProtocolVersion,
+  world_height: u32,
+  world_min_y: i32,
+  world_name: &str,
+) where
+  std::io::Cursor<T>: std::io::Write,
+{
   if ver >= ProtocolVersion::V1_19 {
-    // Current dimension type (key in dimension codec)
-    out.write_str("minecraft:overworld");
-    // Current world
+    // Current dimension type (key in dimension codec). 1.19 clients can only
+    // use dimensions from the codec, so the height cannot change here.
     out.write_str("minecraft:overworld");
   } else {
     // World codec (included in dimension)
-    out.write_buf(&dimension_tag.serialize());
-    // Current world
-    out.write_str("minecraft:overworld");
+    let dimension = overworld(world_height, world_min_y);
+    out.write_buf(&nbt::to_nbt("", &dimension).unwrap().serialize());
   }
+  // Current world
+  out.write_str(world_name);
 }
 
 #[test]
@@ -9,7 +9,7 @@ use crate::{
 };
 use bb_common::{
   net::{cb, cb::packet},
-  util::{chat, Buffer, GameMode, Hand, UUID},
+  util::{chat, Buffer, Effect, GameMode, Hand, UUID},
   version::ProtocolVersion,
 };
 use smallvec::SmallVec;
@@ -126,6 +126,65 @@ to_tcp_manual!(Chunk => (self, conn, ver) {
     conn.conv(),
   ))
 });
+to_tcp_manual!(BossBar => (self, conn, ver) {
+  use bb_common::net::cb::BossBarAction;
+
+  if ver < ProtocolVersion::V1_9_4 {
+    return Ok(smallvec![]);
+  }
+  let mut data = vec![];
+  let mut buf = Buffer::new(&mut data);
+  buf.write_uuid(self.id);
+  match self.action {
+    BossBarAction::Add { title, health, color, style, flags } => {
+      buf.write_varint(0);
+      buf.write_str(&title.to_json());
+      buf.write_f32(health);
+      buf.write_varint(boss_bar_color(color));
+      buf.write_varint(boss_bar_style(style));
+      buf.write_u8(flags);
+    }
+    BossBarAction::Remove => buf.write_varint(1),
+    BossBarAction::UpdateHealth(health) => {
+      buf.write_varint(2);
+      buf.write_f32(health);
+    }
+    BossBarAction::UpdateTitle(title) => {
+      buf.write_varint(3);
+      buf.write_str(&title.to_json());
+    }
+    BossBarAction::UpdateStyle { color, style } => {
+      buf.write_varint(4);
+      buf.write_varint(boss_bar_color(color));
+      buf.write_varint(boss_bar_style(style));
+    }
+    BossBarAction::UpdateFlags(flags) => {
+      buf.write_varint(5);
+      buf.write_u8(flags);
+    }
+  }
+  Ok(smallvec![gpacket!(BossBar V9 { unknown: data })])
+});
+fn boss_bar_color(color: cb::BossBarColor) -> i32 {
+  match color {
+    cb::BossBarColor::Pink => 0,
+    cb::BossBarColor::Blue => 1,
+    cb::BossBarColor::Red => 2,
+    cb::BossBarColor::Green => 3,
+    cb::BossBarColor::Yellow => 4,
+    cb::BossBarColor::Purple => 5,
+    cb::BossBarColor::White => 6,
+  }
+}
+fn boss_bar_style(style: cb::BossBarStyle) -> i32 {
+  match style {
+    cb::BossBarStyle::Solid => 0,
+    cb::BossBarStyle::Notched6 => 1,
+    cb::BossBarStyle::Notched10 => 2,
+    cb::BossBarStyle::Notched12 => 3,
+    cb::BossBarStyle::Notched20 => 4,
+  }
+}
 to_tcp!(BlockUpdate => (self, conn, ver) {
   if ver >= ProtocolVersion::V1_19 {
     gpacket!(BlockUpdate V19 { pos: self.pos, state: self.state as i32 })
@@ -136,6 +195,9 @@ to_tcp!(BlockUpdate => (self, conn, ver) {
     gpacket!(BlockUpdate V8 { block_position: self.pos, unknown: data })
   }
 });
+to_tcp!(ChangeHeldItem => (self, conn, _ver) {
+  gpacket!(HeldItemChange V8 { held_item_hotbar_index: self.slot as i8 })
+});
 to_tcp!(ChangeGameState => (self, conn, ver) {
   use bb_common::net::cb::ChangeGameStateKind as Action;
 
@@ -469,6 +531,76 @@ to_tcp!(EntityMetadata => (self, conn, ver) {
     },
   })
 });
+/// Returns the first version that has the given effect. Older clients will
+/// crash if they are sent an effect they don't know about.
+fn effect_ver(effect: Effect) -> ProtocolVersion {
+  match effect.id() {
+    0..=23 => ProtocolVersion::V1_8,
+    24..=27 => ProtocolVersion::V1_9,
+    28..=32 => ProtocolVersion::V1_14,
+    _ => ProtocolVersion::V1_19,
+  }
+}
+to_tcp!(EntityEffect => (self, conn, ver) {
+  if ver < effect_ver(self.effect) {
+    return Ok(smallvec![]);
+  }
+  let flags = (self.ambient as u8) | (self.particles as u8) << 1 | (self.icon as u8) << 2;
+  if ver >= ProtocolVersion::V1_17_1 {
+    let mut data = vec![];
+    let mut buf = Buffer::new(&mut data);
+    buf.write_varint(self.eid);
+    if ver >= ProtocolVersion::V1_18_2 {
+      buf.write_varint(self.effect.id().into());
+    } else {
+      buf.write_u8(self.effect.id());
+    }
+    buf.write_u8(self.amplifier);
+    buf.write_varint(self.duration);
+    buf.write_u8(flags);
+    if ver >= ProtocolVersion::V1_19 {
+      // No factor data, which is only used for darkness.
+      buf.write_bool(false);
+      gpacket!(EntityEffect V19 { unknown: data })
+    } else {
+      gpacket!(EntityEffect V17 { unknown: data })
+    }
+  } else if ver >= ProtocolVersion::V1_9 {
+    gpacket!(EntityEffect V9 {
+      entity_id: self.eid,
+      effect_id: self.effect.id() as i8,
+      amplifier: self.amplifier as i8,
+      duration:  self.duration,
+      flags:     flags as i8,
+    })
+  } else {
+    gpacket!(EntityEffect V8 {
+      entity_id:      self.eid,
+      effect_id:      self.effect.id() as i8,
+      amplifier:      self.amplifier as i8,
+      duration:       self.duration,
+      hide_particles: !self.particles as i8,
+    })
+  }
+});
+to_tcp!(RemoveEntityEffect => (self, conn, ver) {
+  if ver < effect_ver(self.effect) {
+    return Ok(smallvec![]);
+  }
+  if ver >= ProtocolVersion::V1_17_1 {
+    let mut data = vec![];
+    let mut buf = Buffer::new(&mut data);
+    buf.write_varint(self.eid);
+    if ver >= ProtocolVersion::V1_18_2 {
+      buf.write_varint(self.effect.id().into());
+    } else {
+      buf.write_u8(self.effect.id());
+    }
+    gpacket!(RemoveEntityEffect V17 { unknown: data })
+  } else {
+    gpacket!(RemoveEntityEffect V8 { entity_id: self.eid, effect_id: self.effect.id() as i8 })
+  }
+});
 to_tcp!(EntityVelocity => (self, conn, _ver) {
   gpacket!(EntityVelocity V8 {
     entity_id: self.eid,
@@ -486,9 +618,15 @@ to_tcp!(JoinGame => (self, conn, ver) {
 
     // List of worlds
     buf.write_varint(1);
-    buf.write_str("minecraft:overworld");
+    buf.write_str(&self.world_name);
 
-    super::dimensions::write_dimensions(&mut buf, ver, self.world_height, self.world_min_y);
+    super::dimensions::write_dimensions(
+      &mut buf,
+      ver,
+      self.world_height,
+      self.world_min_y,
+      &self.world_name,
+    );
 
     // Hashed world seed, used for biomes client side.
     buf.write_u64(0);
@@ -629,7 +767,11 @@ to_tcp!(PlayerList => (self, conn, ver) {
       buf.write_list(&v, |buf, v| {
         buf.write_uuid(v.id);
         buf.write_str(&v.name);
-        buf.write_varint(0);
+        buf.write_list(&v.properties, |buf, p| {
+          buf.write_str(&p.name);
+          buf.write_str(&p.value);
+          buf.write_option(&p.signature, |buf, sig| buf.write_str(sig));
+        });
         buf.write_varint(v.game_mode.id().into());
         buf.write_varint(v.ping);
         buf.write_option(&v.display_name, |buf, v| buf.write_str(v));
@@ -768,6 +910,66 @@ to_tcp!(RemoveEntities => (self, conn, ver) {
     gpacket!(DestroyEntities V8 { unknown: data })
   }
 });
+/// Returns a dimension id that is different from `dimension`. Older clients
+/// won't reload anything if they respawn into the dimension they are already
+/// in, so we respawn them in this dimension first.
+pub fn other_dimension(dimension: i8) -> i8 {
+  if dimension == 0 {
+    -1
+  } else {
+    0
+  }
+}
+/// Converts a single respawn packet, which moves the client into `dimension`.
+/// The `dimension` field of `p` is ignored. Unlike `Respawn::to_tcp`, this
+/// will never move older clients through another dimension first.
+pub fn respawn_in(p: &packet::Respawn, ver: ProtocolVersion, dimension: i8) -> GPacket {
+  let mut data = vec![];
+  let mut buf = Buffer::new(&mut data);
+  if ver >= ProtocolVersion::V1_16_5 {
+    super::dimensions::write_dimension(&mut buf, ver, p.world_height, p.world_min_y, &p.world_name);
+    // Hashed world seed, used for biomes client side.
+    buf.write_u64(0);
+    buf.write_u8(p.game_mode.id());
+    buf.write_i8(-1); // no previous_game_mode
+    buf.write_bool(false); // Is debug; cannot be modified, has preset blocks
+    buf.write_bool(false); // Is flat; changes fog
+    buf.write_bool(false); // Copy metadata; we resend all of it anyway
+    if ver >= ProtocolVersion::V1_19 {
+      // Last death location.
+      buf.write_option(&None, |_, _: &()| {});
+    }
+    gpacket!(Respawn V16 { unknown: data })
+  } else if ver >= ProtocolVersion::V1_14_4 {
+    buf.write_i32(dimension.into());
+    if ver >= ProtocolVersion::V1_15_2 {
+      // Hashed world seed, used for biomes
+      buf.write_u64(0);
+    }
+    buf.write_u8(p.game_mode.id());
+    buf.write_str(&p.level_type);
+    gpacket!(Respawn V14 { unknown: data })
+  } else {
+    gpacket!(Respawn V8 {
+      dimension_id: dimension.into(),
+      difficulty:   p.difficulty.into(),
+      game_type:    p.game_mode.id(),
+      world_type:   p.level_type.clone(),
+    })
+  }
+}
+to_tcp_manual!(Respawn => (self, conn, ver) {
+  if ver >= ProtocolVersion::V1_16_5 {
+    Ok(smallvec![respawn_in(&self, ver, self.dimension)])
+  } else {
+    // Move older clients into another dimension first, which makes sure all
+    // their chunks and entities are cleared.
+    Ok(smallvec![
+      respawn_in(&self, ver, other_dimension(self.dimension)),
+      respawn_in(&self, ver, self.dimension),
+    ])
+  }
+});
 to_tcp!(ScoreboardDisplay => (self, conn, ver) {
   use bb_common::net::cb::ScoreboardDisplayPosition;
 
@@ -1114,6 +1316,10 @@ to_tcp_manual!(SwitchServer => (self, conn, _ver) {
   conn.switch_to(self.ips);
   Ok(smallvec![])
 });
+to_tcp_manual!(SwitchServerNamed => (self, conn, _ver) {
+  conn.switch_to_named(&self.name);
+  Ok(smallvec![])
+});
 to_tcp_manual!(Tags => (self, conn, ver) {
   if ver >= ProtocolVersion::V1_14_4 {
     let mut data = vec![];
@@ -1384,7 +1590,12 @@ to_tcp!(WindowOpen => (self, conn, ver) {
     })
   }
 });
-to_tcp!(WindowItems => (self, conn, ver) {
+to_tcp!(WindowItems => (mut self, conn, ver) {
+  // 1.8 clients don't have an off hand slot, so the player's inventory only has
+  // 45 slots.
+  if ver < ProtocolVersion::V1_9 && self.wid == 0 {
+    self.items.truncate(45);
+  }
   if ver >= ProtocolVersion::V1_17_1 {
     let mut buf = tcp::Packet::from_buf_id(vec![], 0, ver);
     buf.write_varint(self.items.len() as i32);
@@ -1405,6 +1616,9 @@ to_tcp!(WindowItems => (self, conn, ver) {
   }
 });
 to_tcp!(WindowItem => (mut self, conn, ver) {
+  if ver < ProtocolVersion::V1_9 && self.wid == 0 && self.slot == 45 {
+    return Ok(smallvec![]);
+  }
   let mut buf = tcp::Packet::from_buf_id(vec![], 0, ver);
   conn.conv().item(&mut self.item, ver.block());
   buf.write_item(&self.item);
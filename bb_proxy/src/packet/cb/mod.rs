@@ -7,6 +7,8 @@ use std::{error::Error, fmt};
 pub mod dimensions;
 mod impls;
 
+pub use impls::{other_dimension, respawn_in};
+
 #[derive(Debug, Clone)]
 pub enum WriteError {
   InvalidVer,
@@ -39,6 +41,8 @@ impl ToTcp for Packet {
       Packet::Animation(p) => p.to_tcp(conn),
       Packet::Chunk(p) => p.to_tcp(conn),
       Packet::BlockUpdate(p) => p.to_tcp(conn),
+      Packet::BossBar(p) => p.to_tcp(conn),
+      Packet::ChangeHeldItem(p) => p.to_tcp(conn),
       Packet::ChangeGameState(p) => p.to_tcp(conn),
       Packet::ChatMessage(p) => p.to_tcp(conn),
       Packet::CommandList(p) => p.to_tcp(conn),
@@ -51,6 +55,8 @@ impl ToTcp for Packet {
       Packet::EntityPos(p) => p.to_tcp(conn),
       Packet::EntityStatus(p) => p.to_tcp(conn),
       Packet::EntityMetadata(p) => p.to_tcp(conn),
+      Packet::EntityEffect(p) => p.to_tcp(conn),
+      Packet::RemoveEntityEffect(p) => p.to_tcp(conn),
       Packet::EntityVelocity(p) => p.to_tcp(conn),
       Packet::JoinGame(p) => p.to_tcp(conn),
       Packet::KeepAlive(p) => p.to_tcp(conn),
@@ -61,6 +67,7 @@ impl ToTcp for Packet {
       Packet::PlaySound(p) => p.to_tcp(conn),
       Packet::PluginMessage(p) => p.to_tcp(conn),
       Packet::RemoveEntities(p) => p.to_tcp(conn),
+      Packet::Respawn(p) => p.to_tcp(conn),
       Packet::ScoreboardDisplay(p) => p.to_tcp(conn),
       Packet::ScoreboardObjective(p) => p.to_tcp(conn),
       Packet::ScoreboardUpdate(p) => p.to_tcp(conn),
@@ -68,6 +75,7 @@ impl ToTcp for Packet {
       Packet::SpawnEntity(p) => p.to_tcp(conn),
       Packet::SpawnPlayer(p) => p.to_tcp(conn),
       Packet::SwitchServer(p) => p.to_tcp(conn),
+      Packet::SwitchServerNamed(p) => p.to_tcp(conn),
       Packet::Tags(p) => p.to_tcp(conn),
       Packet::Title(p) => p.to_tcp(conn),
       Packet::Teams(p) => p.to_tcp(conn),
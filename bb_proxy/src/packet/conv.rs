@@ -141,7 +141,26 @@ impl TypeConverter {
   pub fn item(&self, item: &mut Item, ver: BlockVersion) {
     let (id, damage) = self.item_to_old(item.id as u32, ver);
     item.id = id as i32;
-    item.damage = damage as i16;
+    // Older clients use the damage for both item variants and durability.
+    item.damage += damage as i16;
+  }
+  /// The inverse of [`item`](Self::item). Converts an item id and damage from
+  /// the given version into the latest id and the durability the item has
+  /// lost.
+  ///
+  /// Items that have variants (like wool) never have durability, so their
+  /// damage is only used to pick the variant. Items with a single variant (like
+  /// swords and armor) use the damage for durability.
+  pub fn item_to_new_damage(&self, id: u32, damage: i16, ver: BlockVersion) -> (u32, i16) {
+    if ver == BlockVersion::latest() {
+      return (id, damage);
+    }
+    let variants = self.items[ver.to_index() as usize].to_new.get(id as usize).map(|v| v.len());
+    if variants == Some(1) {
+      (self.item_to_new(id, 0, ver), damage)
+    } else {
+      (self.item_to_new(id, damage as u32, ver), 0)
+    }
   }
 
   /// Converts an entity id into the latest version. It should work the same as
@@ -18,7 +18,7 @@ mod conv;
 mod metadata;
 mod sb;
 
-pub use cb::{ToTcp, WriteError};
+pub use cb::{other_dimension, respawn_in, ToTcp, WriteError};
 pub use conv::TypeConverter;
 pub use sb::FromTcp;
 
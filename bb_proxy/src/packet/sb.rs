@@ -30,6 +30,7 @@ impl FromTcp<GPacket> for Packet {
       GPacket::CloseWindow(g) => Packet::from_tcp(g, ver, conv),
       GPacket::CloseHandledScreen(g) => Packet::from_tcp(g, ver, conv),
       GPacket::ClickSlot(g) => Packet::from_tcp(g, ver, conv),
+      GPacket::ClientStatus(g) => Packet::from_tcp(g, ver, conv),
       GPacket::CreativeInventoryAction(g) => Packet::from_tcp(g, ver, conv),
       GPacket::HeldItemChange(g) => Packet::from_tcp(g, ver, conv),
       GPacket::KeepAlive(g) => Packet::from_tcp(g, ver, conv),
@@ -183,14 +184,8 @@ from_tcp!(PlayerDig, ver, _conv, {
     },
     3 => Packet::ClickWindow { wid: u8::MAX, slot: 0, mode: ClickWindow::DropAll },
     4 => Packet::ClickWindow { wid: u8::MAX, slot: 0, mode: ClickWindow::Drop },
-    5 => {
-      return Err(io::Error::new(ErrorKind::Other, "need to implement eating packet").into())
-    }
-    6 => {
-      return Err(
-        io::Error::new(ErrorKind::Other, "need to implement swap item packet").into(),
-      )
-    }
+    5 => Packet::ReleaseItem,
+    6 => Packet::SwapHands,
     _ => return Err(io::Error::new(ErrorKind::Other, "invalid player dig action").into()),
   },
 });
@@ -286,6 +281,18 @@ from_tcp!(PlayerCommand, _ver, _conv, {
     },
   },
 });
+impl FromTcp<gpacket::ClientStatus> for Packet {
+  fn from_tcp(
+    _: gpacket::ClientStatus,
+    _ver: ProtocolVersion,
+    _conv: &TypeConverter,
+  ) -> Result<Self> {
+    // This is either a respawn request, or a request for statistics. We don't
+    // support statistics, and the server ignores respawn requests from players
+    // that are alive, so we treat both as a respawn.
+    Ok(Packet::Respawn)
+  }
+}
 from_tcp!(PlayerInteractItem, _ver, _conv, {
   V9(g) => Packet::UseItem { hand: Hand::from_id(g.hand as u8) },
   // TODO: Use `g.sequence`.
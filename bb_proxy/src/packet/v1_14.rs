@@ -1,6 +1,7 @@
 use super::{ChunkWithPos, TypeConverter};
 use crate::gnet::cb::{packet, Packet};
 use bb_common::{
+  chunk::biome,
   nbt::{Tag, NBT},
   util::Buffer,
   version::BlockVersion,
@@ -34,8 +35,10 @@ pub fn chunk(chunk: ChunkWithPos, conv: &TypeConverter) -> Packet {
   }
 
   if biomes {
-    for _ in 0..256 {
-      chunk_buf.write_i32(127); // Void biome
+    for i in 0..256 {
+      chunk_buf.write_i32(
+        biome::info(chunk.biomes.column((i % 16) as u8, (i / 16) as u8)).legacy_id.into(),
+      );
     }
   }
 
@@ -1,6 +1,7 @@
 use super::{ChunkWithPos, TypeConverter};
 use crate::gnet::cb::{packet, Packet};
 use bb_common::{
+  chunk::biome,
   nbt::{Tag, NBT},
   util::Buffer,
   version::BlockVersion,
@@ -33,8 +34,9 @@ pub fn chunk(chunk: ChunkWithPos, conv: &TypeConverter) -> Packet {
   let mut biome_data = vec![];
   let mut biome_buf = Buffer::new(&mut biome_data);
   if biomes {
-    for _ in 0..1024 {
-      biome_buf.write_i32(127); // Void biome
+    for i in 0..1024 {
+      let b = chunk.biomes.get((i % 4) as u8, i / 16, ((i / 4) % 4) as u8);
+      biome_buf.write_i32(biome::info(b).legacy_id.into());
     }
   }
 
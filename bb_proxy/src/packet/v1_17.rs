@@ -33,9 +33,10 @@ pub fn chunk(chunk: ChunkWithPos, conv: &TypeConverter) -> Packet {
   let mut biome_buf = Buffer::new(&mut biome_data);
   if biomes {
     biome_buf.write_varint(1024); // Length of biomes
-    for _ in 0..1024 {
-      // The first biome in the list of biomes in the dimension codec
-      biome_buf.write_varint(0);
+    for i in 0..1024 {
+      // Biome ids are indices into the biomes in the dimension codec
+      let b = chunk.biomes.get((i % 4) as u8, i / 16, ((i / 4) % 4) as u8);
+      biome_buf.write_varint(b.into());
     }
   }
 
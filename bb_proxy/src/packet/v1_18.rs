@@ -1,7 +1,7 @@
 use super::{ChunkWithPos, TypeConverter};
 use crate::gnet::cb::{packet, Packet};
 use bb_common::{
-  chunk::Chunk,
+  chunk::{biome, Chunk},
   nbt::{Tag, NBT},
   util::Buffer,
   version::ProtocolVersion,
@@ -13,7 +13,6 @@ use bb_common::{
 //   section (before it was part of the chunk column).
 // - Light update packet was merged into this packet.
 pub fn chunk(chunk: ChunkWithPos, ver: ProtocolVersion, conv: &TypeConverter) -> Packet {
-  let biomes = chunk.full;
   let _skylight = true; // Assume overworld
 
   let mut chunk_data = vec![];
@@ -24,7 +23,7 @@ pub fn chunk(chunk: ChunkWithPos, ver: ProtocolVersion, conv: &TypeConverter) ->
   // chunk_data.write_u64(bit_map.into());
 
   // 1.18 requires all chunk sections to be sent
-  for s in &chunk.sections {
+  for (y, s) in chunk.sections.iter().enumerate() {
     if let Some(s) = s {
       chunk_buf.write_u16(s.non_air_blocks() as u16);
 
@@ -39,31 +38,6 @@ pub fn chunk(chunk: ChunkWithPos, ver: ProtocolVersion, conv: &TypeConverter) ->
       let longs = s.data().long_array();
       chunk_buf.write_varint(longs.len() as i32);
       longs.iter().for_each(|v| chunk_buf.write_buf(&v.to_be_bytes()));
-
-      // Paletted container for biome data
-      if biomes {
-        /*
-        let len = 64; // 64 entries
-        let num_longs = len / (64 / 4);
-        chunk_data.write_u8(4); // 4 bits per entry
-        chunk_data.write_varint(1); // Palette has 1 entry
-        chunk_data.write_varint(0); // The entry is `0`
-
-        chunk_data.write_varint(num_longs); // Number of longs in the following array.
-        for _ in 0..num_longs {
-          // Every 4 bits refers to a `0` in the palette, so we can just write it all as
-          // zeros.
-          chunk_data.write_u64(0);
-        }
-        */
-        // New special 'single value' palette
-        chunk_buf.write_u8(0); // 0 bits per entry
-        chunk_buf.write_varint(0); // The single entry is `0`
-        chunk_buf.write_varint(0); // The data length is `0`
-
-        // No data follows, as this signifies that the entire section is just
-        // that one biome.
-      }
     } else {
       chunk_buf.write_u16(0); // No non air blocks
 
@@ -71,12 +45,10 @@ pub fn chunk(chunk: ChunkWithPos, ver: ProtocolVersion, conv: &TypeConverter) ->
       chunk_buf.write_u8(0); // 0 bpe
       chunk_buf.write_varint(0); // our one value is 0
       chunk_buf.write_varint(0); // no data
-
-      // Paletted container for biome data
-      chunk_buf.write_u8(0); // 0 bpe
-      chunk_buf.write_varint(0); // our one value is 0
-      chunk_buf.write_varint(0); // no data
     }
+
+    // Paletted container for biome data
+    write_biomes(&mut chunk_buf, &chunk.biomes.section(y as u32));
   }
 
   let c = Chunk::from_sections(chunk.packet.sections, 15);
@@ -161,3 +133,44 @@ pub fn chunk(chunk: ChunkWithPos, ver: ProtocolVersion, conv: &TypeConverter) ->
   }
   .into()
 }
+
+/// Writes the paletted container for the biomes in a single section. There are
+/// no partial chunks in 1.18, so this is always written.
+fn write_biomes(buf: &mut Buffer<&mut Vec<u8>>, biomes: &[u8; 64]) {
+  let mut palette: Vec<u8> = vec![];
+  for &b in biomes {
+    if !palette.contains(&b) {
+      palette.push(b);
+    }
+  }
+  if palette.len() == 1 {
+    // The special 'single value' palette, which has no data.
+    buf.write_u8(0); // 0 bits per entry
+    buf.write_varint(palette[0].into());
+    buf.write_varint(0); // no data
+    return;
+  }
+  // Indirect palettes can use 1 to 3 bits per entry. Anything larger must use
+  // the biome ids directly, with enough bits for every biome in the codec.
+  let bits = |len: usize| (usize::BITS - (len - 1).leading_zeros()) as usize;
+  let (bpe, ids): (usize, Vec<u64>) = if palette.len() <= 8 {
+    let ids = biomes.iter().map(|b| palette.iter().position(|p| p == b).unwrap() as u64).collect();
+    (bits(palette.len()), ids)
+  } else {
+    (bits(biome::BIOMES.len()), biomes.iter().map(|&b| b.into()).collect())
+  };
+  buf.write_u8(bpe as u8);
+  if palette.len() <= 8 {
+    buf.write_varint(palette.len() as i32);
+    for &b in &palette {
+      buf.write_varint(b.into());
+    }
+  }
+  let epl = 64 / bpe;
+  let mut longs = vec![0_u64; (64 + epl - 1) / epl];
+  for (i, id) in ids.into_iter().enumerate() {
+    longs[i / epl] |= id << ((i % epl) * bpe);
+  }
+  buf.write_varint(longs.len() as i32);
+  longs.iter().for_each(|v| buf.write_buf(&v.to_be_bytes()));
+}
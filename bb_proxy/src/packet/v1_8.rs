@@ -1,7 +1,7 @@
 use super::{ChunkWithPos, TypeConverter};
 use crate::gnet::cb::{packet, Packet};
 use bb_common::{
-  chunk::Section as _,
+  chunk::{biome, Section as _},
   math::{ChunkPos, SectionRelPos},
   util::Buffer,
   version::{BlockVersion, ProtocolVersion},
@@ -55,8 +55,9 @@ pub fn chunk(chunk: ChunkWithPos, conv: &TypeConverter) -> Packet {
     }
   }
   if biomes {
-    for _ in 0..256 {
-      chunk_buf.write_u8(127); // Void biome
+    for i in 0..256 {
+      chunk_buf
+        .write_u8(biome::info(chunk.biomes.column((i % 16) as u8, (i / 16) as u8)).legacy_id);
     }
   }
   // This is going to pop at most 4 elements.
@@ -1,6 +1,6 @@
 use super::{ChunkWithPos, TypeConverter};
 use crate::gnet::cb::{packet, Packet};
-use bb_common::{util::Buffer, version::ProtocolVersion};
+use bb_common::{chunk::biome, util::Buffer, version::ProtocolVersion};
 
 // Applies to 1.9 - 1.12, but 1.10 doesn't work, so idk
 pub fn chunk(chunk: ChunkWithPos, ver: ProtocolVersion, conv: &TypeConverter) -> Packet {
@@ -33,8 +33,9 @@ pub fn chunk(chunk: ChunkWithPos, ver: ProtocolVersion, conv: &TypeConverter) ->
   }
 
   if biomes {
-    for _ in 0..256 {
-      chunk_buf.write_u8(127); // Void biome
+    for i in 0..256 {
+      chunk_buf
+        .write_u8(biome::info(chunk.biomes.column((i % 16) as u8, (i / 16) as u8)).legacy_id);
     }
   }
 
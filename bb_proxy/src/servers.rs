@@ -0,0 +1,219 @@
+//! The backend servers a client can be connected to. Clients always join the
+//! server set by `server` in `proxy.toml`, and can then be moved between any
+//! of the servers listed in `[servers]`.
+//!
+//! This also keeps track of the state the client has been sent that isn't
+//! cleared when the client joins a new world, so that we can clear it when a
+//! client is moved to another server.
+
+use bb_common::{
+  config::Config,
+  net::cb::{
+    packet as cp, BossBarAction, ObjectiveAction, Packet, PlayerListAction, PlayerListRemove,
+    TeamAction,
+  },
+  util::UUID,
+};
+use std::{collections::HashMap, net::SocketAddr};
+
+/// The name of the server clients join first. This can be used to switch
+/// clients back to the `server` in `proxy.toml`, unless `[servers]` contains
+/// a server with this name.
+pub const LOBBY: &str = "lobby";
+
+/// All the backend servers the proxy knows about.
+#[derive(Debug, Clone)]
+pub struct Servers {
+  /// The server clients join first.
+  lobby:    SocketAddr,
+  /// Every server in `[servers]`.
+  named:    HashMap<String, SocketAddr>,
+  /// The servers clients are moved to when the server they are on goes down,
+  /// in order. The lobby is always tried last.
+  fallback: Vec<SocketAddr>,
+}
+
+impl Servers {
+  pub fn from_config(config: &Config) -> Self {
+    let lobby = config.get::<&str>("server").parse().unwrap();
+    let mut named = HashMap::new();
+    for name in config.keys_at(["servers"].into_iter()) {
+      let addr: &str = config.get_at(["servers", name.as_str()].into_iter());
+      match addr.parse() {
+        Ok(addr) => {
+          named.insert(name, addr);
+        }
+        Err(e) => error!("invalid address `{addr}` for server `{name}`: {e}"),
+      }
+    }
+    let mut servers = Servers { lobby, named, fallback: vec![] };
+    for name in config.get::<Vec<&str>>("fallback") {
+      match servers.get(name) {
+        Some(addr) => servers.fallback.push(addr),
+        None => error!("unknown fallback server `{name}`"),
+      }
+    }
+    servers
+  }
+
+  /// Returns the address of the server clients join first.
+  pub fn lobby(&self) -> SocketAddr { self.lobby }
+
+  /// Returns the address of the server with the given name.
+  pub fn get(&self, name: &str) -> Option<SocketAddr> {
+    match self.named.get(name) {
+      Some(addr) => Some(*addr),
+      None if name == LOBBY => Some(self.lobby),
+      None => None,
+    }
+  }
+
+  /// Returns the name of the server at `addr`. This is only used for logging
+  /// and messages, so this falls back to the address if the server has no
+  /// name.
+  pub fn name_of(&self, addr: SocketAddr) -> String {
+    if addr == self.lobby && !self.named.contains_key(LOBBY) {
+      return LOBBY.into();
+    }
+    match self.named.iter().find(|(_, a)| **a == addr) {
+      Some((name, _)) => name.clone(),
+      None => addr.to_string(),
+    }
+  }
+
+  /// Returns the servers to try when the server a client is on goes down. This
+  /// is the `fallback` list, followed by the lobby.
+  pub fn fallback(&self) -> impl Iterator<Item = SocketAddr> + '_ {
+    self.fallback.iter().copied().chain(std::iter::once(self.lobby))
+  }
+}
+
+/// Everything a server has sent to a client that the client keeps when it
+/// joins a new world. The client forgets all of its chunks and entities when
+/// it joins a world, but the tab list, scoreboard objectives, teams and boss
+/// bars are kept. So, when we move a client to a new server, we send packets to
+/// remove all of these.
+#[derive(Debug, Default)]
+pub struct ClientState {
+  players:    Vec<UUID>,
+  objectives: Vec<String>,
+  teams:      Vec<String>,
+  boss_bars:  Vec<UUID>,
+}
+
+impl ClientState {
+  /// Updates the tracked state for a packet that was sent to the client.
+  pub fn track(&mut self, p: &Packet) {
+    match p {
+      Packet::PlayerList(cp::PlayerList { action: PlayerListAction::Add(list) }) => {
+        for e in list {
+          if !self.players.contains(&e.id) {
+            self.players.push(e.id);
+          }
+        }
+      }
+      Packet::PlayerList(cp::PlayerList { action: PlayerListAction::Remove(list) }) => {
+        self.players.retain(|id| !list.iter().any(|e| e.id == *id));
+      }
+      Packet::ScoreboardObjective(cp::ScoreboardObjective { objective, mode }) => match mode {
+        ObjectiveAction::Create { .. } => {
+          if !self.objectives.contains(objective) {
+            self.objectives.push(objective.clone());
+          }
+        }
+        ObjectiveAction::Remove => self.objectives.retain(|o| o != objective),
+        ObjectiveAction::Update { .. } => {}
+      },
+      Packet::Teams(cp::Teams { team, action }) => match action {
+        TeamAction::Create { .. } => {
+          if !self.teams.contains(team) {
+            self.teams.push(team.clone());
+          }
+        }
+        TeamAction::Remove => self.teams.retain(|t| t != team),
+        _ => {}
+      },
+      Packet::BossBar(cp::BossBar { id, action }) => match action {
+        BossBarAction::Add { .. } => {
+          if !self.boss_bars.contains(id) {
+            self.boss_bars.push(*id);
+          }
+        }
+        BossBarAction::Remove => self.boss_bars.retain(|b| b != id),
+        _ => {}
+      },
+      _ => {}
+    }
+  }
+
+  /// Returns the packets needed to remove everything we have tracked. This
+  /// also resets the tracked state.
+  pub fn clear(&mut self) -> Vec<Packet> {
+    let mut out = vec![];
+    if !self.players.is_empty() {
+      out.push(
+        cp::PlayerList {
+          action: PlayerListAction::Remove(
+            self.players.drain(..).map(|id| PlayerListRemove { id }).collect(),
+          ),
+        }
+        .into(),
+      );
+    }
+    for objective in self.objectives.drain(..) {
+      out.push(cp::ScoreboardObjective { objective, mode: ObjectiveAction::Remove }.into());
+    }
+    for team in self.teams.drain(..) {
+      out.push(cp::Teams { team, action: TeamAction::Remove }.into());
+    }
+    for id in self.boss_bars.drain(..) {
+      out.push(cp::BossBar { id, action: BossBarAction::Remove }.into());
+    }
+    out
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use bb_common::{
+    net::cb::{BossBarColor, BossBarStyle, PlayerListAdd},
+    util::Chat,
+  };
+
+  #[test]
+  fn client_state() {
+    let mut state = ClientState::default();
+    let id = UUID::from_u128(1);
+    state.track(
+      &cp::PlayerList {
+        action: PlayerListAction::Add(vec![PlayerListAdd { id, ..Default::default() }]),
+      }
+      .into(),
+    );
+    state.track(&cp::Teams { team: "a".into(), action: TeamAction::Remove }.into());
+    state.track(
+      &cp::ScoreboardObjective { objective: "kills".into(), mode: ObjectiveAction::Remove }.into(),
+    );
+    let bar = |id, action| -> Packet { cp::BossBar { id: UUID::from_u128(id), action }.into() };
+    let add = || BossBarAction::Add {
+      title:  Chat::new("boss"),
+      health: 1.0,
+      color:  BossBarColor::Red,
+      style:  BossBarStyle::Solid,
+      flags:  0,
+    };
+    state.track(&bar(2, add()));
+    state.track(&bar(3, add()));
+    state.track(&bar(3, BossBarAction::UpdateHealth(0.5)));
+    state.track(&bar(2, BossBarAction::Remove));
+    assert_eq!(
+      state.clear(),
+      vec![
+        cp::PlayerList { action: PlayerListAction::Remove(vec![PlayerListRemove { id }]) }.into(),
+        bar(3, BossBarAction::Remove),
+      ]
+    );
+    assert!(state.clear().is_empty());
+  }
+}
@@ -0,0 +1,122 @@
+//! Handles server list pings. Whenever a client pings the proxy, we need to
+//! know how many players are online. This asks the server for that
+//! information, and caches it for a short time, so that a client refreshing
+//! their server list doesn't open a bunch of connections to the server.
+
+use bb_common::{
+  util::{JoinInfo, JoinMode, ServerStatus, UUID},
+  version::ProtocolVersion,
+};
+use bb_transfer::{MessageReader, MessageWriter};
+use parking_lot::Mutex;
+use std::{
+  io,
+  io::{ErrorKind, Read, Write},
+  net::{SocketAddr, TcpStream},
+  sync::Arc,
+  thread,
+  time::{Duration, Instant},
+};
+
+/// The longest we will wait for the server to reply to a status request.
+const TIMEOUT: Duration = Duration::from_secs(1);
+
+pub struct StatusCache {
+  addr:  SocketAddr,
+  ttl:   Duration,
+  state: Arc<Mutex<CacheState>>,
+}
+
+#[derive(Default)]
+struct CacheState {
+  status:     Option<ServerStatus>,
+  updated:    Option<Instant>,
+  refreshing: bool,
+}
+
+impl StatusCache {
+  /// Creates a new status cache. This will immediately start fetching the
+  /// status in the background, so that the first client to ping the proxy gets
+  /// a valid status.
+  pub fn new(addr: SocketAddr, ttl: Duration) -> Self {
+    let cache = StatusCache { addr, ttl, state: Arc::new(Mutex::new(CacheState::default())) };
+    cache.refresh();
+    cache
+  }
+
+  /// Returns the last status we received from the server. If that status is
+  /// older than the cache time, this will start a refresh in the background.
+  /// We never block here, as this is called from the main proxy thread.
+  ///
+  /// If the server has never replied, this returns `None`.
+  pub fn get(&self) -> Option<ServerStatus> {
+    let state = self.state.lock();
+    let stale = state.updated.map(|t| t.elapsed() > self.ttl).unwrap_or(true);
+    let status = state.status.clone();
+    drop(state);
+    if stale {
+      self.refresh();
+    }
+    status
+  }
+
+  /// Fetches the status from the server on another thread. If there is already
+  /// a refresh running, this does nothing.
+  fn refresh(&self) {
+    {
+      let mut state = self.state.lock();
+      if state.refreshing {
+        return;
+      }
+      state.refreshing = true;
+    }
+    let addr = self.addr;
+    let state = self.state.clone();
+    thread::spawn(move || {
+      let res = fetch(addr);
+      let mut state = state.lock();
+      state.refreshing = false;
+      state.updated = Some(Instant::now());
+      match res {
+        Ok(status) => state.status = Some(status),
+        Err(e) => {
+          warn!("could not get status from server at {addr}: {e}");
+          state.status = None;
+        }
+      }
+    });
+  }
+}
+
+/// Connects to the server, and asks for its status. This will block until the
+/// server replies, or until [`TIMEOUT`] has passed.
+fn fetch(addr: SocketAddr) -> io::Result<ServerStatus> {
+  let mut stream = TcpStream::connect_timeout(&addr, TIMEOUT)?;
+  stream.set_read_timeout(Some(TIMEOUT))?;
+  stream.set_write_timeout(Some(TIMEOUT))?;
+
+  let mut data = vec![];
+  let mut m = MessageWriter::new(&mut data);
+  m.write(&JoinInfo {
+    mode:       JoinMode::Status,
+    username:   "".into(),
+    uuid:       UUID::from_u128(0),
+    ver:        ProtocolVersion::latest().id(),
+    properties: vec![],
+    ip:         None,
+  })
+  .map_err(|e| io::Error::new(ErrorKind::InvalidData, e.to_string()))?;
+  let mut prefix = [0; 5];
+  let mut m = MessageWriter::new(prefix.as_mut_slice());
+  m.write_u32(data.len() as u32).unwrap();
+  let prefix_len = m.index();
+  stream.write_all(&prefix[..prefix_len])?;
+  stream.write_all(&data)?;
+
+  // The server closes the connection once it has sent the status.
+  let mut buf = vec![];
+  stream.read_to_end(&mut buf)?;
+  let mut m = MessageReader::new(&buf);
+  m.read_u32().map_err(|e| io::Error::new(ErrorKind::InvalidData, e.to_string()))?;
+  m.read().map_err(|e| io::Error::new(ErrorKind::InvalidData, e.to_string()))
+}
@@ -5,16 +5,17 @@ use super::{
 use crate::{
   entity,
   event::EventFlow::{self, *},
-  item::SharedInventory,
+  item::{SharedInventory, Stack},
   player::{BlockClick, Player, Window},
   world::World,
 };
 use bb_common::{
   math::Pos,
+  nbt::Tag,
   util::{Chat, Face},
 };
 use bb_transfer::{MessageRead, MessageWrite, MessageWriter};
-use std::{any::Any, sync::Arc};
+use std::{any::Any, collections::HashMap, sync::Arc};
 
 pub struct Log;
 impl Behavior for Log {
@@ -116,6 +117,42 @@ impl Behavior for Chest {
       Err(e) => Err(e),
     })
   }
+  fn load_te_nbt(&self, nbt: &HashMap<String, Tag>) -> Option<Arc<dyn TileEntity>> {
+    let te = ChestTE { inv: SharedInventory::new() };
+    if let Some(items) = nbt.get("Items").and_then(Tag::as_list) {
+      let mut inv = te.inv.lock();
+      for it in items.iter().filter_map(Tag::as_compound) {
+        let slot = match it.get("Slot").and_then(Tag::as_byte) {
+          Some(slot @ 0..=26) => slot as u32,
+          _ => continue,
+        };
+        if let Some(stack) = Stack::from_nbt(it) {
+          inv.inv.set(slot, stack);
+        }
+      }
+    }
+    Some(Arc::new(te))
+  }
+  fn save_te_nbt(&self, te: &dyn TileEntity) -> Option<HashMap<String, Tag>> {
+    let te = te.as_any().downcast_ref::<ChestTE>()?;
+    let inv = te.inv.lock();
+    let items = (0..27)
+      .filter_map(|slot| {
+        let stack = inv.inv.get(slot)?;
+        if stack.is_empty() {
+          return None;
+        }
+        let mut nbt = stack.to_nbt();
+        nbt.insert("Slot".into(), Tag::Byte(slot as i8));
+        Some(Tag::Compound(nbt))
+      })
+      .collect();
+    Some(
+      [("id".into(), Tag::String("minecraft:chest".into())), ("Items".into(), Tag::List(items))]
+        .into_iter()
+        .collect(),
+    )
+  }
   fn interact(&self, block: Block, player: &Arc<Player>) -> EventFlow {
     block.te(|chest: &ChestTE| {
       player.show_inventory(
@@ -133,6 +170,61 @@ impl TileEntity for ChestTE {
   fn as_any(&self) -> &dyn Any { self }
 }
 
+pub struct Sign;
+#[derive(bb_macros::Transfer, Default, Debug, Clone)]
+pub struct SignTE {
+  /// The 4 lines of text on the sign. Each line is a json chat message.
+  lines: Vec<String>,
+}
+impl SignTE {
+  /// Returns the lines on this sign, as json chat messages.
+  pub fn lines(&self) -> &[String] { &self.lines }
+}
+impl Behavior for Sign {
+  fn create_te(&self) -> Option<Arc<dyn TileEntity>> { Some(Arc::new(SignTE::default())) }
+  fn load_te(
+    &self,
+    r: &mut bb_transfer::MessageReader,
+  ) -> Option<Result<Arc<dyn TileEntity>, bb_transfer::ReadError>> {
+    Some(match SignTE::read(r) {
+      Ok(v) => Ok(Arc::new(v)),
+      Err(e) => Err(e),
+    })
+  }
+  fn load_te_nbt(&self, nbt: &HashMap<String, Tag>) -> Option<Arc<dyn TileEntity>> {
+    // 1.20+ stores text for both sides of the sign. We only keep the front.
+    let lines = if let Some(front) = nbt.get("front_text").and_then(Tag::as_compound) {
+      front
+        .get("messages")
+        .and_then(Tag::as_list)
+        .map(|l| l.iter().filter_map(Tag::as_string).map(String::from).collect())
+        .unwrap_or_default()
+    } else {
+      ["Text1", "Text2", "Text3", "Text4"]
+        .iter()
+        .map(|k| nbt.get(*k).and_then(Tag::as_string).unwrap_or_default().into())
+        .collect()
+    };
+    Some(Arc::new(SignTE { lines }))
+  }
+  fn save_te_nbt(&self, te: &dyn TileEntity) -> Option<HashMap<String, Tag>> {
+    let te = te.as_any().downcast_ref::<SignTE>()?;
+    let mut nbt: HashMap<String, Tag> =
+      [("id".into(), Tag::String("minecraft:sign".into()))].into_iter().collect();
+    for (i, key) in ["Text1", "Text2", "Text3", "Text4"].iter().enumerate() {
+      let line = te.lines.get(i).map(String::as_str).unwrap_or(r#"{"text":""}"#);
+      nbt.insert(key.to_string(), Tag::String(line.into()));
+    }
+    Some(nbt)
+  }
+}
+impl TileEntity for SignTE {
+  fn save(&self, w: &mut MessageWriter<&mut Vec<u8>>) -> Result<(), bb_transfer::WriteError> {
+    self.write(w)
+  }
+  fn as_any(&self) -> &dyn Any { self }
+}
+
 pub struct Trapdoor;
 impl Behavior for Trapdoor {
   fn place<'a>(&self, data: &'a Data, _: Pos, click: BlockClick) -> Type<'a> {
@@ -5,9 +5,9 @@ use crate::{
   player::{BlockClick, Player},
   world::World,
 };
-use bb_common::math::Pos;
+use bb_common::{math::Pos, nbt::Tag};
 use bb_transfer::{MessageReader, MessageWriter};
-use std::{any::Any, sync::Arc};
+use std::{any::Any, collections::HashMap, sync::Arc};
 
 mod impls;
 
@@ -53,6 +53,21 @@ pub trait Behavior: Send + Sync {
     let _ = r;
     None
   }
+  /// Loads the tile entity for this block from vanilla NBT data. This is used
+  /// when importing vanilla worlds. If this returns `None`, the block entity in
+  /// the vanilla world will be ignored.
+  fn load_te_nbt(&self, nbt: &HashMap<String, Tag>) -> Option<Arc<dyn TileEntity>> {
+    let _ = nbt;
+    None
+  }
+  /// Converts the given tile entity into vanilla NBT data. This is used when
+  /// exporting worlds. The returned compound should contain the `id` of the
+  /// block entity, but not its position. If this returns `None`, the block
+  /// entity will not be exported.
+  fn save_te_nbt(&self, te: &dyn TileEntity) -> Option<HashMap<String, Tag>> {
+    let _ = te;
+    None
+  }
 
   /// Called when a player right clicks on this block. If this returns `true`,
   /// the event was handled, and a block should not be placed.
@@ -118,6 +133,9 @@ pub fn make_behaviors() -> BehaviorList {
     *color*Bed => impls::Bed;
 
     Chest => impls::Chest;
+
+    *wood*Sign | CrimsonSign | WarpedSign => impls::Sign;
+    *wood*WallSign | CrimsonWallSign | WarpedWallSign => impls::Sign;
   };
   out
 }
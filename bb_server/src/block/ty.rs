@@ -128,6 +128,20 @@ impl Type<'_> {
     }
     Ok(())
   }
+  /// Sets the given property from a string, such as `true`, `3`, or `north`.
+  /// This is the format used in vanilla NBT data, and in block states like
+  /// `oak_stairs[facing=north]`. Returns `false` if the property doesn't exist,
+  /// or if the value is invalid for that property.
+  pub fn try_set_prop_str(&mut self, name: &str, val: &str) -> bool {
+    match val {
+      "true" => self.try_set_prop(name, true).is_ok(),
+      "false" => self.try_set_prop(name, false).is_ok(),
+      _ => match val.parse::<u32>() {
+        Ok(v) => self.try_set_prop(name, v).is_ok(),
+        Err(_) => self.try_set_prop(name, val).is_ok(),
+      },
+    }
+  }
   pub fn with<'a>(mut self, name: &str, val: impl Into<PropValue<'a>>) -> Self {
     self.set_prop(name, val);
     self
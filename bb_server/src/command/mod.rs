@@ -45,7 +45,15 @@ type Handler = Box<dyn Fn(&Arc<WorldManager>, Option<&Arc<Player>>, Vec<Arg>) +
 /// parsing that needs to be done for callbacks to work.
 #[derive(Default)]
 pub struct CommandTree {
-  commands: Mutex<HashMap<String, (Command, Handler)>>,
+  commands: Mutex<HashMap<String, Entry>>,
+}
+
+struct Entry {
+  command: Command,
+  handler: Handler,
+  /// The name of the plugin that added this command, if any. This is used to
+  /// remove a plugin's commands when it is reloaded.
+  plugin:  Option<String>,
 }
 
 impl CommandTree {
@@ -59,7 +67,29 @@ impl CommandTree {
   where
     F: (Fn(&Arc<WorldManager>, Option<&Arc<Player>>, Vec<Arg>)) + Send + Sync + 'static,
   {
-    self.commands.lock().insert(c.name().into(), (c, Box::new(handler)));
+    self.insert(c, Box::new(handler), None);
+  }
+  /// Adds a new command to the tree, which is owned by the given plugin. This
+  /// is the same as [`add`](Self::add), but the command will be removed when
+  /// [`remove_plugin`](Self::remove_plugin) is called with the same plugin
+  /// name.
+  pub fn add_for_plugin<F>(&self, plugin: &str, c: Command, handler: F)
+  where
+    F: (Fn(&Arc<WorldManager>, Option<&Arc<Player>>, Vec<Arg>)) + Send + Sync + 'static,
+  {
+    self.insert(c, Box::new(handler), Some(plugin.into()));
+  }
+  fn insert(&self, command: Command, handler: Handler, plugin: Option<String>) {
+    self.commands.lock().insert(command.name().into(), Entry { command, handler, plugin });
+  }
+  /// Removes all the commands added by the given plugin. Returns the number of
+  /// commands removed. Players will not see this change until the command tree
+  /// is sent to them again.
+  pub fn remove_plugin(&self, plugin: &str) -> usize {
+    let mut commands = self.commands.lock();
+    let len = commands.len();
+    commands.retain(|_, entry| entry.plugin.as_deref() != Some(plugin));
+    len - commands.len()
   }
   /// Called whenever a command should be executed. This can also be used to act
   /// like a player sent a command, even if they didn't. The text passed in
@@ -71,7 +101,7 @@ impl CommandTree {
       Ok(v) => v,
       Err(_) => return,
     };
-    let (command, handler) = match &commands.get(&command_name) {
+    let Entry { command, handler, .. } = match &commands.get(&command_name) {
       Some(v) => v,
       None => {
         let mut msg = Chat::empty();
@@ -392,4 +422,19 @@ pub mod tests {
     );
     Ok(())
   }
+
+  #[test]
+  fn remove_plugin() {
+    let tree = CommandTree::new();
+    tree.add(Command::new("say"), |_, _, _| {});
+    tree.add_for_plugin("foo", Command::new("a"), |_, _, _| {});
+    tree.add_for_plugin("foo", Command::new("b"), |_, _, _| {});
+    tree.add_for_plugin("bar", Command::new("c"), |_, _, _| {});
+    assert_eq!(tree.remove_plugin("foo"), 2);
+    assert_eq!(tree.remove_plugin("foo"), 0);
+    let commands = tree.commands.lock();
+    assert!(commands.contains_key("say"));
+    assert!(commands.contains_key("c"));
+    assert!(!commands.contains_key("a"));
+  }
 }
@@ -19,7 +19,7 @@ impl CommandTree {
     let c = Command {
       name:     "".into(),
       ty:       NodeType::Root,
-      children: commands.values().map(|(command, _)| command.clone()).collect(),
+      children: commands.values().map(|entry| entry.command.clone()).collect(),
       optional: false,
     };
     c.write_nodes(&mut nodes);
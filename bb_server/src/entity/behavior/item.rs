@@ -28,7 +28,9 @@ impl Behavior for ItemBehavior {
       let pos = p.aabb.pos;
       let chunk = pos.block().chunk();
       for player in ent.world.read().players().iter().in_view(chunk) {
-        if player.pos().dist_squared(p.aabb.pos) < 1.5_f64.powi(2) {
+        // Dead players can't pick up items, or they would pick up everything they
+        // dropped when they died.
+        if player.alive() && player.pos().dist_squared(p.aabb.pos) < 1.5_f64.powi(2) {
           let stack: Stack = ent.metadata().get_item(8).into();
           let amount = stack.amount();
           let remaining = player.lock_inventory().give(stack);
@@ -0,0 +1,310 @@
+//! The goals that the built in mobs use.
+
+use super::{path::Terrain, Controls, Goal, MobContext};
+use crate::{
+  entity,
+  entity::EntityRef,
+  item,
+  math::Vec3,
+  player::{DamageCause, Player},
+  RNG,
+};
+use bb_common::{
+  math::FPos,
+  net::cb,
+  util::{Hand, UUID},
+};
+use rand::Rng;
+use std::sync::Arc;
+
+/// Walks to a random nearby block every now and then.
+#[derive(Debug)]
+pub struct Wander {
+  speed: f64,
+}
+
+impl Wander {
+  /// Creates a wander goal. `speed` is multiplied by the mob's base speed.
+  pub fn new(speed: f64) -> Self { Wander { speed } }
+}
+
+impl Goal for Wander {
+  fn controls(&self) -> Controls { Controls::MOVE }
+  fn can_start(&mut self, ctx: &mut MobContext) -> bool {
+    if !ctx.nav.is_idle() || !RNG.with(|rng| rng.borrow_mut().gen_range(0..120) == 0) {
+      return false;
+    }
+    let start = ctx.fpos().block();
+    // Try a few times to find somewhere to stand, so that we don't give up
+    // just because we picked a block inside a wall.
+    for _ in 0..10 {
+      let (x, y, z) = RNG.with(|rng| {
+        let mut rng = rng.borrow_mut();
+        (rng.gen_range(-10..=10), rng.gen_range(-3..=3), rng.gen_range(-10..=10))
+      });
+      let pos = start.add_x(x).add_y(y).add_z(z);
+      if ctx.world.can_stand(pos) {
+        return ctx.move_to(pos.center(), self.speed);
+      }
+    }
+    false
+  }
+  fn can_continue(&mut self, ctx: &mut MobContext) -> bool { !ctx.nav.is_idle() }
+  fn stop(&mut self, ctx: &mut MobContext) { ctx.nav.stop(); }
+}
+
+/// Looks at the closest player for a few seconds.
+pub struct LookAtPlayer {
+  range:  f64,
+  player: Option<Arc<Player>>,
+  ticks:  u32,
+}
+
+impl LookAtPlayer {
+  /// Creates a goal that looks at players within `range` blocks.
+  pub fn new(range: f64) -> Self { LookAtPlayer { range, player: None, ticks: 0 } }
+}
+
+impl Goal for LookAtPlayer {
+  fn controls(&self) -> Controls { Controls::LOOK }
+  fn can_start(&mut self, ctx: &mut MobContext) -> bool {
+    if !RNG.with(|rng| rng.borrow_mut().gen_bool(0.02)) {
+      return false;
+    }
+    self.player = ctx.nearest_player(self.range, |_| true);
+    self.player.is_some()
+  }
+  fn can_continue(&mut self, ctx: &mut MobContext) -> bool {
+    let pos = ctx.fpos();
+    match &self.player {
+      Some(p) => self.ticks > 0 && p.pos().dist_squared(pos) < self.range * self.range,
+      None => false,
+    }
+  }
+  fn start(&mut self, _: &mut MobContext) {
+    self.ticks = RNG.with(|rng| rng.borrow_mut().gen_range(40..80));
+  }
+  fn stop(&mut self, _: &mut MobContext) { self.player = None; }
+  fn tick(&mut self, ctx: &mut MobContext) {
+    self.ticks = self.ticks.saturating_sub(1);
+    if let Some(p) = &self.player {
+      ctx.look_at(p.pos().add_y(1.62));
+    }
+  }
+}
+
+/// Walks up to the mob's target, and hits it.
+#[derive(Debug)]
+pub struct MeleeAttack {
+  speed:    f64,
+  damage:   f32,
+  /// The number of ticks until we find a new path to the target.
+  repath:   u32,
+  /// The number of ticks until we can attack again.
+  cooldown: u32,
+}
+
+impl MeleeAttack {
+  /// Creates an attack goal. `speed` is multiplied by the mob's base speed,
+  /// and `damage` is the amount of damage dealt with each hit.
+  pub fn new(speed: f64, damage: f32) -> Self {
+    MeleeAttack { speed, damage, repath: 0, cooldown: 0 }
+  }
+
+  /// Returns the position of the target, if it is still valid.
+  fn target_pos(ctx: &mut MobContext) -> Option<FPos> {
+    let eid = (*ctx.target)?;
+    match ctx.entity_pos(eid) {
+      Some((pos, true)) if pos.dist_squared(ctx.fpos()) < 32.0 * 32.0 => Some(pos),
+      _ => {
+        *ctx.target = None;
+        None
+      }
+    }
+  }
+}
+
+impl Goal for MeleeAttack {
+  fn controls(&self) -> Controls { Controls::BOTH }
+  fn can_start(&mut self, ctx: &mut MobContext) -> bool { Self::target_pos(ctx).is_some() }
+  fn can_continue(&mut self, ctx: &mut MobContext) -> bool { Self::target_pos(ctx).is_some() }
+  fn start(&mut self, _: &mut MobContext) { self.repath = 0; }
+  fn stop(&mut self, ctx: &mut MobContext) { ctx.nav.stop(); }
+  fn tick(&mut self, ctx: &mut MobContext) {
+    let (eid, pos) = match (*ctx.target, Self::target_pos(ctx)) {
+      (Some(eid), Some(pos)) => (eid, pos),
+      _ => return,
+    };
+    ctx.look_at(pos.add_y(1.62));
+    self.cooldown = self.cooldown.saturating_sub(1);
+    if self.repath == 0 {
+      ctx.move_to(pos, self.speed);
+      self.repath = RNG.with(|rng| rng.borrow_mut().gen_range(10..20));
+    } else {
+      self.repath -= 1;
+    }
+
+    let me = ctx.fpos();
+    if self.cooldown > 0 || me.dist_squared(pos) > 2.0 * 2.0 {
+      return;
+    }
+    // We can't hold onto the entities lock while damaging a player, as killing
+    // them will spawn their items.
+    let player = match ctx.world.entities().get_player(eid) {
+      Some(p) => p,
+      None => return,
+    };
+    let dir = Vec3::from(pos - me);
+    let len = (dir.x * dir.x + dir.z * dir.z).sqrt().max(0.001);
+    let knockback = Vec3::new(dir.x / len * 0.4, 0.4, dir.z / len * 0.4);
+    let cause = DamageCause::Mob { eid: ctx.ent.eid(), name: ctx.info.name.into() };
+    if EntityRef::Player(player).damage_by(cause, self.damage, true, knockback) {
+      let eid = ctx.ent.eid();
+      for p in ctx.world.players().iter().in_view(me.chunk()) {
+        p.send(cb::packet::Animation { eid, kind: cb::AnimationKind::Swing(Hand::Main) });
+      }
+    }
+    self.cooldown = 20;
+  }
+}
+
+/// What a [`Flee`] goal runs away from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FleeFrom {
+  /// Runs away from whatever last damaged this mob, for a few seconds after
+  /// being hurt.
+  Attacker,
+  /// Runs away from any entity of this type within the given range.
+  Type(entity::Type, f64),
+}
+
+/// Runs away from something.
+#[derive(Debug)]
+pub struct Flee {
+  from:  FleeFrom,
+  speed: f64,
+}
+
+impl Flee {
+  /// Creates a flee goal. `speed` is multiplied by the mob's base speed.
+  pub fn new(from: FleeFrom, speed: f64) -> Self { Flee { from, speed } }
+
+  /// Returns the position to run away from. If this returns `Some(None)`, the
+  /// mob should run in a random direction.
+  fn danger(&self, ctx: &MobContext) -> Option<Option<FPos>> {
+    let pos = ctx.fpos();
+    match self.from {
+      FleeFrom::Attacker => {
+        if ctx.hurt_ago.map_or(true, |t| t > 100) {
+          return None;
+        }
+        Some(ctx.attacker.and_then(|eid| ctx.entity_pos(eid)).map(|(pos, _)| pos))
+      }
+      FleeFrom::Type(ty, range) => {
+        let entities = ctx.world.entities();
+        let danger = entities
+          .iter()
+          .not(ctx.ent.eid())
+          .filter(|ent| ent.ty() == ty)
+          .map(|ent| ent.pos())
+          .find(|other| other.dist_squared(pos) < range * range);
+        danger.map(Some)
+      }
+    }
+  }
+}
+
+impl Goal for Flee {
+  fn controls(&self) -> Controls { Controls::MOVE }
+  fn can_start(&mut self, ctx: &mut MobContext) -> bool {
+    let danger = match self.danger(ctx) {
+      Some(d) => d,
+      None => return false,
+    };
+    let pos = ctx.fpos();
+    let (x, z) = match danger {
+      Some(danger) if danger.with_y(0.0).dist(pos.with_y(0.0)) > 0.01 => {
+        (pos.x() - danger.x(), pos.z() - danger.z())
+      }
+      _ => RNG.with(|rng| {
+        let angle = rng.borrow_mut().gen_range(0.0..std::f64::consts::TAU);
+        (angle.cos(), angle.sin())
+      }),
+    };
+    let len = (x * x + z * z).sqrt();
+    let to = pos.add_x(x / len * 8.0).add_z(z / len * 8.0);
+    ctx.move_to(to, self.speed)
+  }
+  fn can_continue(&mut self, ctx: &mut MobContext) -> bool { !ctx.nav.is_idle() }
+  fn stop(&mut self, ctx: &mut MobContext) { ctx.nav.stop(); }
+}
+
+/// Who a [`Follow`] goal walks towards.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FollowTarget {
+  /// Follows the closest player holding this item, like cows following
+  /// players holding wheat.
+  Holding(item::Type),
+  /// Follows the given player.
+  Player(UUID),
+}
+
+/// Follows a player around.
+pub struct Follow {
+  target: FollowTarget,
+  speed:  f64,
+  player: Option<Arc<Player>>,
+  repath: u32,
+}
+
+impl Follow {
+  /// Creates a follow goal. `speed` is multiplied by the mob's base speed.
+  pub fn new(target: FollowTarget, speed: f64) -> Self {
+    Follow { target, speed, player: None, repath: 0 }
+  }
+
+  fn find_player(&self, ctx: &MobContext) -> Option<Arc<Player>> {
+    match self.target {
+      FollowTarget::Holding(ty) => ctx.nearest_player(10.0, |p| {
+        let inv = p.lock_inventory();
+        inv.main_hand().item() == ty || inv.off_hand().item() == ty
+      }),
+      FollowTarget::Player(id) => ctx.nearest_player(16.0, |p| p.id() == id),
+    }
+  }
+}
+
+impl Goal for Follow {
+  fn controls(&self) -> Controls { Controls::BOTH }
+  fn can_start(&mut self, ctx: &mut MobContext) -> bool {
+    self.player = self.find_player(ctx);
+    self.player.is_some()
+  }
+  fn can_continue(&mut self, ctx: &mut MobContext) -> bool {
+    // The player might have stopped holding the item, or walked away.
+    self.player = self.find_player(ctx);
+    self.player.is_some()
+  }
+  fn start(&mut self, _: &mut MobContext) { self.repath = 0; }
+  fn stop(&mut self, ctx: &mut MobContext) {
+    self.player = None;
+    ctx.nav.stop();
+  }
+  fn tick(&mut self, ctx: &mut MobContext) {
+    let pos = match &self.player {
+      Some(p) => p.pos(),
+      None => return,
+    };
+    ctx.look_at(pos.add_y(1.62));
+    if pos.dist_squared(ctx.fpos()) < 2.5 * 2.5 {
+      ctx.nav.stop();
+      return;
+    }
+    if self.repath == 0 {
+      ctx.move_to(pos, self.speed);
+      self.repath = 10;
+    } else {
+      self.repath -= 1;
+    }
+  }
+}
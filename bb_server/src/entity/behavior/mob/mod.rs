@@ -0,0 +1,418 @@
+//! A goal based AI for mobs.
+//!
+//! A [`Mob`] has a list of goals, each with a priority. Every tick, goals that
+//! aren't running are asked if they can start. A goal will start if nothing
+//! with a higher priority (a lower number) is using the same [`Controls`].
+//! Goals with a lower priority are stopped to make room for it.
+//!
+//! Goals walk around using the mob's [`Navigator`], which follows paths found
+//! with A* pathfinding.
+
+mod goal;
+mod nav;
+pub mod path;
+
+pub use goal::{Flee, FleeFrom, Follow, FollowTarget, LookAtPlayer, MeleeAttack, Wander};
+pub use nav::Navigator;
+
+use super::{Behavior, EntityData, EntityPos, ShouldDespawn};
+use crate::{entity, item, math::Vec3, player::Player, world::World, RNG};
+use bb_common::{math::FPos, net::cb};
+use rand::Rng;
+use std::{cmp::Ordering, sync::Arc};
+
+/// The parts of a mob that a goal controls. Two goals that control the same
+/// thing cannot run at the same time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Controls(u8);
+
+impl Controls {
+  pub const NONE: Controls = Controls(0x00);
+  /// The goal moves the mob around.
+  pub const MOVE: Controls = Controls(0x01);
+  /// The goal changes where the mob is looking.
+  pub const LOOK: Controls = Controls(0x02);
+  pub const BOTH: Controls = Controls(0x03);
+
+  /// Returns `true` if both of these control the same thing.
+  pub fn overlaps(self, other: Controls) -> bool { self.0 & other.0 != 0 }
+}
+
+/// Something a mob wants to do, like wandering around or attacking a player.
+pub trait Goal {
+  /// Returns what this goal controls.
+  fn controls(&self) -> Controls;
+  /// Returns `true` if this goal should start running. This is called every
+  /// tick while the goal isn't running.
+  fn can_start(&mut self, ctx: &mut MobContext) -> bool;
+  /// Returns `true` if this goal should keep running. This is called every
+  /// tick while the goal is running.
+  fn can_continue(&mut self, ctx: &mut MobContext) -> bool;
+  /// Called when the goal starts running.
+  fn start(&mut self, ctx: &mut MobContext) { let _ = ctx; }
+  /// Called when the goal stops running, either because `can_continue`
+  /// returned `false`, or because a goal with a higher priority started.
+  fn stop(&mut self, ctx: &mut MobContext) { let _ = ctx; }
+  /// Called every tick while the goal is running.
+  fn tick(&mut self, ctx: &mut MobContext) { let _ = ctx; }
+}
+
+/// Everything a goal can access while it runs.
+pub struct MobContext<'a> {
+  pub world:    &'a Arc<World>,
+  pub ent:      &'a EntityData,
+  pub pos:      &'a mut EntityPos,
+  pub nav:      &'a mut Navigator,
+  pub info:     &'a MobInfo,
+  /// The entity id this mob is attacking.
+  pub target:   &'a mut Option<i32>,
+  /// The entity id of whatever last damaged this mob, if it was an entity.
+  pub attacker: Option<i32>,
+  /// The number of ticks since this mob was last damaged.
+  pub hurt_ago: Option<u32>,
+  look_at:      &'a mut Option<FPos>,
+}
+
+impl MobContext<'_> {
+  /// Returns the position of the mob's feet.
+  pub fn fpos(&self) -> FPos { self.pos.aabb.pos }
+  /// Returns the position of the mob's eyes.
+  pub fn eye_pos(&self) -> FPos { self.fpos().add_y(self.pos.aabb.size.y * 0.85) }
+  /// Makes the mob look at the given position on this tick.
+  pub fn look_at(&mut self, pos: FPos) { *self.look_at = Some(pos); }
+  /// Walks to the given position. `speed` is multiplied by the mob's base
+  /// speed. Returns `false` if no path could be found.
+  pub fn move_to(&mut self, pos: FPos, speed: f64) -> bool {
+    let from = self.fpos();
+    self.nav.move_to(self.world, from, pos, self.info.speed * speed)
+  }
+
+  /// Returns the closest player within `range` blocks that matches `filter`.
+  pub fn nearest_player(
+    &self,
+    range: f64,
+    filter: impl Fn(&Player) -> bool,
+  ) -> Option<Arc<Player>> {
+    let pos = self.fpos();
+    let mut best: Option<(f64, &Arc<Player>)> = None;
+    let players = self.world.players();
+    for p in players.iter().in_view(pos.chunk()) {
+      let dist = p.pos().dist_squared(pos);
+      if dist < range * range && best.map_or(true, |(d, _)| dist < d) && filter(p) {
+        best = Some((dist, p));
+      }
+    }
+    best.map(|(_, p)| p.clone())
+  }
+
+  /// Returns the position of the given entity, and if it is still alive. This
+  /// returns `None` if the entity doesn't exist.
+  pub fn entity_pos(&self, eid: i32) -> Option<(FPos, bool)> {
+    let ent = self.world.entities().get_owned(eid)?;
+    let ent = ent.as_entity_ref(self.world)?;
+    let alive = match ent.as_player() {
+      Some(p) => p.damageable(),
+      None => ent.health() > 0.0,
+    };
+    Some((ent.pos(), alive))
+  }
+}
+
+/// The stats for a kind of mob.
+#[derive(Debug, Clone)]
+pub struct MobInfo {
+  /// The name shown in death messages, like `Zombie`.
+  pub name:       &'static str,
+  /// The name used for sounds. For example, this is `zombie` for the sound
+  /// `entity.zombie.hurt`.
+  pub sound:      &'static str,
+  pub category:   cb::SoundCategory,
+  pub max_health: f32,
+  /// The walking speed, in blocks per tick.
+  pub speed:      f64,
+  /// The amount of exp dropped when this mob is killed.
+  pub exp:        i32,
+  /// If set, this mob will target the closest player in survival.
+  pub hostile:    bool,
+  /// If set, this mob will target any entity that damages it.
+  pub retaliate:  bool,
+}
+
+struct GoalEntry {
+  priority: u32,
+  goal:     Box<dyn Goal + Send>,
+  running:  bool,
+}
+
+/// A list of goals, sorted by priority.
+#[derive(Default)]
+struct GoalSelector {
+  goals: Vec<GoalEntry>,
+}
+
+/// A mob controlled by a list of goals. This implements [`Behavior`], so it
+/// can be passed to [`EntityData::new_custom`].
+pub struct Mob {
+  info:     MobInfo,
+  goals:    GoalSelector,
+  nav:      Navigator,
+  target:   Option<i32>,
+  attacker: Option<i32>,
+  hurt_ago: Option<u32>,
+  /// Counts up once the mob dies, so that clients have time to show the death
+  /// animation.
+  dead_for: u32,
+}
+
+impl Mob {
+  /// Creates a mob with no goals. It will stand still until goals are added
+  /// with [`with_goal`](Self::with_goal).
+  pub fn new(info: MobInfo) -> Self {
+    Mob {
+      info,
+      goals: GoalSelector::default(),
+      nav: Navigator::new(),
+      target: None,
+      attacker: None,
+      hurt_ago: None,
+      dead_for: 0,
+    }
+  }
+
+  /// Adds a goal to this mob. Goals with a lower `priority` take precedence.
+  pub fn with_goal(mut self, priority: u32, goal: impl Goal + Send + 'static) -> Self {
+    let goals = &mut self.goals.goals;
+    let idx = goals.iter().position(|g| g.priority > priority).unwrap_or(goals.len());
+    goals.insert(idx, GoalEntry { priority, goal: Box::new(goal), running: false });
+    self
+  }
+
+  /// A zombie. It attacks players in survival, and wanders around otherwise.
+  pub fn zombie() -> Self {
+    Mob::new(MobInfo {
+      name:       "Zombie",
+      sound:      "zombie",
+      category:   cb::SoundCategory::Hostile,
+      max_health: 20.0,
+      speed:      0.115,
+      exp:        5,
+      hostile:    true,
+      retaliate:  true,
+    })
+    .with_goal(2, MeleeAttack::new(1.0, 3.0))
+    .with_goal(7, Wander::new(0.8))
+    .with_goal(8, LookAtPlayer::new(8.0))
+  }
+
+  /// A skeleton. Bows aren't implemented yet, so this attacks players up close,
+  /// like a skeleton that isn't holding a bow.
+  pub fn skeleton() -> Self {
+    Mob::new(MobInfo {
+      name:       "Skeleton",
+      sound:      "skeleton",
+      category:   cb::SoundCategory::Hostile,
+      max_health: 20.0,
+      speed:      0.125,
+      exp:        5,
+      hostile:    true,
+      retaliate:  true,
+    })
+    .with_goal(2, MeleeAttack::new(1.0, 2.0))
+    .with_goal(5, Wander::new(0.8))
+    .with_goal(6, LookAtPlayer::new(8.0))
+  }
+
+  /// A cow. It runs away when hurt, and follows players holding wheat.
+  pub fn cow() -> Self {
+    Mob::new(MobInfo {
+      name:       "Cow",
+      sound:      "cow",
+      category:   cb::SoundCategory::Neutral,
+      max_health: 10.0,
+      speed:      0.1,
+      exp:        2,
+      hostile:    false,
+      retaliate:  false,
+    })
+    .with_goal(1, Flee::new(FleeFrom::Attacker, 2.0))
+    .with_goal(3, Follow::new(FollowTarget::Holding(item::Type::Wheat), 1.25))
+    .with_goal(5, Wander::new(1.0))
+    .with_goal(6, LookAtPlayer::new(6.0))
+  }
+
+  /// A villager. This is mostly useful as an NPC: it wanders slowly, looks at
+  /// nearby players, and runs away from zombies.
+  pub fn villager() -> Self {
+    Mob::new(MobInfo {
+      name:       "Villager",
+      sound:      "villager",
+      category:   cb::SoundCategory::Neutral,
+      max_health: 20.0,
+      speed:      0.1,
+      exp:        0,
+      hostile:    false,
+      retaliate:  false,
+    })
+    .with_goal(1, Flee::new(FleeFrom::Type(entity::Type::Zombie, 8.0), 1.5))
+    .with_goal(4, LookAtPlayer::new(8.0))
+    .with_goal(6, Wander::new(0.6))
+  }
+
+  /// Finds a player to attack, if this mob is hostile.
+  fn find_target(&mut self, world: &Arc<World>, pos: FPos) {
+    if let Some(eid) = self.target {
+      if world.entities().get_owned(eid).is_some() {
+        return;
+      }
+      self.target = None;
+    }
+    if !self.info.hostile || !RNG.with(|rng| rng.borrow_mut().gen_bool(0.1)) {
+      return;
+    }
+    let players = world.players();
+    self.target = players
+      .iter()
+      .in_view(pos.chunk())
+      .filter(|p| p.damageable() && p.pos().dist_squared(pos) < 16.0 * 16.0)
+      .min_by(|a, b| {
+        a.pos().dist_squared(pos).partial_cmp(&b.pos().dist_squared(pos)).unwrap_or(Ordering::Equal)
+      })
+      .map(|p| p.eid());
+  }
+
+  fn play_sound(&self, world: &World, name: &str, pos: FPos) {
+    let pitch = RNG.with(|rng| rng.borrow_mut().gen_range(0.8..1.2));
+    world.play_sound(
+      format!("entity.{}.{}", self.info.sound, name),
+      self.info.category,
+      pos,
+      1.0,
+      pitch,
+    );
+  }
+}
+
+impl GoalSelector {
+  /// Starts and stops goals, and then ticks all the running goals.
+  fn tick(&mut self, ctx: &mut MobContext) {
+    for entry in &mut self.goals {
+      if entry.running && !entry.goal.can_continue(ctx) {
+        entry.goal.stop(ctx);
+        entry.running = false;
+      }
+    }
+    for i in 0..self.goals.len() {
+      if self.goals[i].running {
+        continue;
+      }
+      let controls = self.goals[i].goal.controls();
+      let priority = self.goals[i].priority;
+      let blocked = self
+        .goals
+        .iter()
+        .any(|g| g.running && g.priority <= priority && g.goal.controls().overlaps(controls));
+      if blocked || !self.goals[i].goal.can_start(ctx) {
+        continue;
+      }
+      for other in &mut self.goals {
+        if other.running && other.goal.controls().overlaps(controls) {
+          other.goal.stop(ctx);
+          other.running = false;
+        }
+      }
+      self.goals[i].goal.start(ctx);
+      self.goals[i].running = true;
+    }
+    for entry in &mut self.goals {
+      if entry.running {
+        entry.goal.tick(ctx);
+      }
+    }
+  }
+}
+
+impl Behavior for Mob {
+  fn max_health(&self) -> f32 { self.info.max_health }
+  // Dead mobs are removed in `tick`, after the death animation.
+  fn should_despawn(&self, _: f32) -> ShouldDespawn { ShouldDespawn(false) }
+  fn exp_count(&self) -> i32 { self.info.exp }
+
+  fn damaged(&mut self, world: &Arc<World>, ent: &EntityData, attacker: Option<i32>) {
+    self.hurt_ago = Some(0);
+    if attacker.is_some() {
+      self.attacker = attacker;
+      if self.info.retaliate {
+        self.target = attacker;
+      }
+    }
+    let sound = if ent.health() <= 0.0 { "death" } else { "hurt" };
+    self.play_sound(world, sound, ent.fpos());
+  }
+
+  fn tick(&mut self, world: &Arc<World>, ent: &EntityData, p: &mut EntityPos) -> ShouldDespawn {
+    let old_look = (p.yaw, p.pitch);
+    let grounded = p.grounded;
+    if ent.health() <= 0.0 {
+      self.dead_for += 1;
+      if self.dead_for >= 20 {
+        return ShouldDespawn(true);
+      }
+      p.vel.x = 0.0;
+      p.vel.z = 0.0;
+    } else {
+      self.hurt_ago = self.hurt_ago.map(|t| t + 1);
+      self.find_target(world, p.aabb.pos);
+      let mut look_at = None;
+      let mut ctx = MobContext {
+        world,
+        ent,
+        pos: p,
+        nav: &mut self.nav,
+        info: &self.info,
+        target: &mut self.target,
+        attacker: self.attacker,
+        hurt_ago: self.hurt_ago,
+        look_at: &mut look_at,
+      };
+      self.goals.tick(&mut ctx);
+
+      // Knockback would be cancelled out if we kept walking.
+      let walking = if ent.hit_delay() > 0 { None } else { self.nav.tick(p) };
+      if let Some(pos) = look_at.or_else(|| walking.map(|w| w.add_y(p.aabb.size.y * 0.85))) {
+        look_towards(p, pos);
+      }
+      if RNG.with(|rng| rng.borrow_mut().gen_range(0..240)) == 0 {
+        self.play_sound(world, "ambient", p.aabb.pos);
+      }
+    }
+
+    let vel = p.vel;
+    p.aabb.pos += vel;
+    if !grounded {
+      p.vel.y -= 0.08;
+    }
+    p.vel.y *= 0.98;
+    let friction = if grounded { 0.546 } else { 0.91 };
+    p.vel.x *= friction;
+    p.vel.z *= friction;
+    // The collision checks will set this back to `true` if we hit the ground.
+    p.grounded = false;
+
+    if (p.yaw, p.pitch) != old_look {
+      world.send_entity_look(p.aabb.pos.chunk(), ent.eid(), p.yaw, p.pitch);
+    }
+    ShouldDespawn(false)
+  }
+}
+
+/// Sets the yaw and pitch of `p` to look at `target`.
+fn look_towards(p: &mut EntityPos, target: FPos) {
+  let eye = p.aabb.pos.add_y(p.aabb.size.y * 0.85);
+  let d = Vec3::from(target - eye);
+  let horz = (d.x * d.x + d.z * d.z).sqrt();
+  if horz < 0.001 && d.y.abs() < 0.001 {
+    return;
+  }
+  p.yaw = (-d.x.atan2(d.z)).to_degrees() as f32;
+  p.pitch = (-d.y.atan2(horz)).to_degrees() as f32;
+}
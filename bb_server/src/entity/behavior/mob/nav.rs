@@ -0,0 +1,98 @@
+use super::path;
+use crate::{entity::EntityPos, world::World};
+use bb_common::math::{FPos, Pos};
+
+/// The most blocks a single path search will look through. This keeps mobs
+/// from lagging the server when they can't reach their target.
+const MAX_NODES: usize = 400;
+
+/// Moves a mob along a path. Goals call [`move_to`](Self::move_to), and the
+/// mob will then walk towards that position every tick until it gets there.
+#[derive(Debug, Default)]
+pub struct Navigator {
+  path:  Vec<Pos>,
+  idx:   usize,
+  speed: f64,
+  /// The number of ticks since we last reached a block on the path.
+  stuck: u32,
+}
+
+impl Navigator {
+  pub fn new() -> Self { Navigator::default() }
+
+  /// Finds a path from `from` to `to`, and starts walking along it. `speed`
+  /// is in blocks per tick. Returns `false` if no path could be found.
+  pub fn move_to(&mut self, world: &World, from: FPos, to: FPos, speed: f64) -> bool {
+    match path::find_path(world, from.block(), to.block(), MAX_NODES) {
+      Some(path) => {
+        self.path = path;
+        self.idx = 0;
+        self.speed = speed;
+        self.stuck = 0;
+        true
+      }
+      None => {
+        self.stop();
+        false
+      }
+    }
+  }
+
+  /// Stops following the current path.
+  pub fn stop(&mut self) {
+    self.path.clear();
+    self.idx = 0;
+  }
+
+  /// Returns `true` if the mob isn't following a path.
+  pub fn is_idle(&self) -> bool { self.idx >= self.path.len() }
+
+  /// Returns the end of the current path.
+  pub fn destination(&self) -> Option<Pos> {
+    if self.is_idle() {
+      None
+    } else {
+      self.path.last().copied()
+    }
+  }
+
+  /// Sets the mob's velocity to walk along the path. Returns the position the
+  /// mob is walking towards, if it is following a path.
+  pub(super) fn tick(&mut self, p: &mut EntityPos) -> Option<FPos> {
+    if self.is_idle() {
+      return None;
+    }
+    let pos = p.aabb.pos;
+    let mut target = self.path[self.idx].center();
+    let dx = target.x() - pos.x();
+    let dz = target.z() - pos.z();
+    if dx * dx + dz * dz < 0.3 * 0.3 && (pos.y() - target.y()).abs() < 1.0 {
+      self.idx += 1;
+      self.stuck = 0;
+      if self.is_idle() {
+        return None;
+      }
+      target = self.path[self.idx].center();
+    }
+    self.stuck += 1;
+    if self.stuck > 60 {
+      // Something is in the way, like a block that was placed after the path
+      // was found.
+      self.stop();
+      return None;
+    }
+    let dx = target.x() - pos.x();
+    let dz = target.z() - pos.z();
+    let len = (dx * dx + dz * dz).sqrt();
+    if len > 0.001 {
+      let speed = self.speed.min(len);
+      p.vel.x = dx / len * speed;
+      p.vel.z = dz / len * speed;
+    }
+    if target.y() > pos.y() + 0.5 && p.grounded {
+      // Jump up onto the next block.
+      p.vel.y = 0.42;
+    }
+    Some(target)
+  }
+}
@@ -0,0 +1,257 @@
+//! A* pathfinding over the blocks in a world.
+//!
+//! Mobs are assumed to be at most two blocks tall. They can walk on any solid
+//! block, step up one block at a time, and drop down up to three blocks.
+
+use crate::{block, world::World};
+use bb_common::math::Pos;
+use std::{
+  cmp::Reverse,
+  collections::{BinaryHeap, HashMap, HashSet},
+};
+
+/// The blocks a path can go through. This is implemented for [`World`], and
+/// can be implemented for anything else to test pathfinding.
+pub trait Terrain {
+  /// Returns `true` if a mob can stand on top of this block.
+  fn is_solid(&self, pos: Pos) -> bool;
+  /// Returns `true` if a mob can walk through this block.
+  fn is_open(&self, pos: Pos) -> bool;
+
+  /// Returns `true` if a mob can stand with its feet in the given block.
+  fn can_stand(&self, pos: Pos) -> bool {
+    self.is_open(pos) && self.is_open(pos.add_y(1)) && self.is_solid(pos.add_y(-1))
+  }
+}
+
+impl Terrain for World {
+  fn is_solid(&self, pos: Pos) -> bool {
+    !matches!(
+      self.get_kind(pos),
+      Ok(block::Kind::Air | block::Kind::Water | block::Kind::Lava) | Err(_)
+    )
+  }
+  // Entity collisions treat every block other than air as solid, so we do the
+  // same here.
+  fn is_open(&self, pos: Pos) -> bool { matches!(self.get_kind(pos), Ok(block::Kind::Air)) }
+}
+
+/// The furthest a mob will drop down in a single step.
+const MAX_DROP: i32 = 3;
+
+struct Node {
+  pos:    Pos,
+  cost:   u32,
+  parent: Option<usize>,
+}
+
+/// Finds a path from `start` to `end`. Both positions are the block a mob's
+/// feet are in. The returned path does not include `start`, and ends at `end`.
+///
+/// At most `max_nodes` blocks will be searched. If `end` cannot be reached
+/// within that limit, this returns a path to the closest block that was found.
+/// If no block closer than `start` was found, this returns `None`. If `start`
+/// is `end`, this returns an empty path.
+pub fn find_path(
+  terrain: &impl Terrain,
+  start: Pos,
+  end: Pos,
+  max_nodes: usize,
+) -> Option<Vec<Pos>> {
+  if start == end {
+    return Some(vec![]);
+  }
+  let mut nodes = vec![Node { pos: start, cost: 0, parent: None }];
+  let mut visited = HashMap::new();
+  visited.insert(start, 0);
+  let mut open = BinaryHeap::new();
+  open.push(Reverse((heuristic(start, end), 0)));
+  let mut closed = HashSet::new();
+
+  let mut closest = 0;
+  let mut closest_dist = heuristic(start, end);
+  while let Some(Reverse((_, idx))) = open.pop() {
+    let pos = nodes[idx].pos;
+    // Skip entries for blocks that have already been searched, and entries that
+    // were replaced by a cheaper path to the same block.
+    if visited[&pos] != idx || !closed.insert(pos) {
+      continue;
+    }
+    if pos == end {
+      closest = idx;
+      break;
+    }
+    if nodes.len() >= max_nodes {
+      break;
+    }
+    let dist = heuristic(pos, end);
+    if dist < closest_dist {
+      closest = idx;
+      closest_dist = dist;
+    }
+    for (next, step) in neighbors(terrain, pos) {
+      if closed.contains(&next) {
+        continue;
+      }
+      let cost = nodes[idx].cost + step;
+      match visited.get(&next) {
+        Some(&other) if nodes[other].cost <= cost => continue,
+        _ => {}
+      }
+      let next_idx = nodes.len();
+      nodes.push(Node { pos: next, cost, parent: Some(idx) });
+      visited.insert(next, next_idx);
+      open.push(Reverse((cost + heuristic(next, end), next_idx)));
+    }
+  }
+  if closest == 0 {
+    return None;
+  }
+  let mut path = vec![];
+  let mut idx = Some(closest);
+  while let Some(i) = idx {
+    if i == 0 {
+      break;
+    }
+    path.push(nodes[i].pos);
+    idx = nodes[i].parent;
+  }
+  path.reverse();
+  Some(path)
+}
+
+/// The estimated cost to get from `a` to `b`. This never overestimates, as
+/// moving diagonally costs 14 and moving straight costs 10.
+fn heuristic(a: Pos, b: Pos) -> u32 {
+  let dx = (a.x - b.x).unsigned_abs();
+  let dz = (a.z - b.z).unsigned_abs();
+  let dy = (a.y - b.y).unsigned_abs();
+  let (min, max) = if dx < dz { (dx, dz) } else { (dz, dx) };
+  14 * min + 10 * (max - min) + 5 * dy
+}
+
+/// Returns all the blocks a mob standing at `pos` can move to, and the cost of
+/// moving there.
+fn neighbors(terrain: &impl Terrain, pos: Pos) -> Vec<(Pos, u32)> {
+  let mut out = vec![];
+  for dx in -1..=1 {
+    for dz in -1..=1 {
+      if dx == 0 && dz == 0 {
+        continue;
+      }
+      let diagonal = dx != 0 && dz != 0;
+      // Don't cut corners, as the mob would get stuck on them.
+      if diagonal
+        && (!terrain.is_open(pos.add_x(dx))
+          || !terrain.is_open(pos.add_x(dx).add_y(1))
+          || !terrain.is_open(pos.add_z(dz))
+          || !terrain.is_open(pos.add_z(dz).add_y(1)))
+      {
+        continue;
+      }
+      let cost = if diagonal { 14 } else { 10 };
+      let next = pos.add_x(dx).add_z(dz);
+      if terrain.can_stand(next) {
+        out.push((next, cost));
+      } else if !diagonal && terrain.can_stand(next.add_y(1)) && terrain.is_open(pos.add_y(2)) {
+        // Jumping up a block.
+        out.push((next.add_y(1), cost + 10));
+      } else if terrain.is_open(next) && terrain.is_open(next.add_y(1)) {
+        for drop in 1..=MAX_DROP {
+          let below = next.add_y(-drop);
+          if terrain.can_stand(below) {
+            out.push((below, cost + 5 * drop as u32));
+            break;
+          }
+          if !terrain.is_open(below) {
+            break;
+          }
+        }
+      }
+    }
+  }
+  out
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::cell::RefCell;
+
+  struct Blocks(HashSet<Pos>);
+
+  impl Terrain for Blocks {
+    fn is_solid(&self, pos: Pos) -> bool { self.0.contains(&pos) }
+    fn is_open(&self, pos: Pos) -> bool { !self.0.contains(&pos) }
+  }
+
+  #[test]
+  fn around_wall() {
+    let mut blocks = HashSet::new();
+    for x in -5..=5 {
+      for z in -5..=5 {
+        blocks.insert(Pos::new(x, 0, z));
+      }
+    }
+    // A wall that is too tall to jump over, with a gap at z = 3.
+    for z in -5..=2 {
+      blocks.insert(Pos::new(0, 1, z));
+      blocks.insert(Pos::new(0, 2, z));
+    }
+    let terrain = Blocks(blocks);
+    let start = Pos::new(-2, 1, 0);
+    let end = Pos::new(2, 1, 0);
+    let path = find_path(&terrain, start, end, 1000).unwrap();
+    assert_eq!(path.last(), Some(&end));
+    assert!(path.iter().all(|p| terrain.can_stand(*p)));
+    assert!(path.iter().any(|p| p.x == 0 && p.z >= 3));
+
+    // Steps up onto a single block.
+    let mut blocks = terrain.0;
+    blocks.insert(Pos::new(3, 1, 0));
+    let terrain = Blocks(blocks);
+    let path = find_path(&terrain, end, Pos::new(3, 2, 0), 1000).unwrap();
+    assert_eq!(path, vec![Pos::new(3, 2, 0)]);
+  }
+
+  #[test]
+  fn same_start_and_end() {
+    let terrain = Blocks([Pos::new(0, 0, 0)].into_iter().collect());
+    let pos = Pos::new(0, 1, 0);
+    assert_eq!(find_path(&terrain, pos, pos, 1000), Some(vec![]));
+  }
+
+  /// Counts how many times each block is checked with `can_stand`.
+  struct Counted(Blocks, RefCell<HashMap<Pos, u32>>);
+
+  impl Terrain for Counted {
+    fn is_solid(&self, pos: Pos) -> bool { self.0.is_solid(pos) }
+    fn is_open(&self, pos: Pos) -> bool { self.0.is_open(pos) }
+    fn can_stand(&self, pos: Pos) -> bool {
+      *self.1.borrow_mut().entry(pos).or_default() += 1;
+      self.0.can_stand(pos)
+    }
+  }
+
+  #[test]
+  fn searches_blocks_once() {
+    let mut blocks = HashSet::new();
+    for x in -10..=10 {
+      for z in -10..=10 {
+        blocks.insert(Pos::new(x, 0, z));
+      }
+    }
+    for z in -10..=5 {
+      blocks.insert(Pos::new(0, 1, z));
+      blocks.insert(Pos::new(0, 2, z));
+    }
+    let terrain = Counted(Blocks(blocks), RefCell::new(HashMap::new()));
+    let end = Pos::new(4, 1, -8);
+    let path = find_path(&terrain, Pos::new(-4, 1, -8), end, 10000).unwrap();
+    assert_eq!(path.last(), Some(&end));
+    // Each block is checked once by each neighbor that gets searched. If a block
+    // was searched twice, some of its neighbors would be checked more than 8
+    // times.
+    assert!(terrain.1.borrow().values().all(|&n| n <= 8));
+  }
+}
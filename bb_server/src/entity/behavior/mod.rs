@@ -1,9 +1,11 @@
 mod falling_block;
 mod item;
+pub mod mob;
 mod snowball;
 
 pub use falling_block::FallingBlock;
 pub use item::ItemBehavior;
+pub use mob::Mob;
 pub use snowball::SnowballBehavior;
 
 use super::{EntityData, EntityPos, Type};
@@ -28,6 +30,13 @@ pub trait Behavior {
   /// killed.
   fn exp_count(&self) -> i32 { 1 }
 
+  /// Called after the entity takes damage. `attacker` is the entity id of
+  /// whatever dealt the damage, if it was an entity or player. The entity's
+  /// health has already been updated when this is called.
+  fn damaged(&mut self, world: &Arc<World>, ent: &EntityData, attacker: Option<i32>) {
+    let _ = (world, ent, attacker);
+  }
+
   /// Any extra functionality needed. Called every tick, after movement and
   /// collision checks have been completed.
   fn tick(&mut self, world: &Arc<World>, ent: &EntityData, p: &mut EntityPos) -> ShouldDespawn {
@@ -61,6 +70,10 @@ pub fn for_entity(ty: Type) -> Box<dyn Behavior + Send> {
     Type::Snowball => Box::new(SnowballBehavior::default()),
     Type::Item => Box::new(ItemBehavior::default()),
     Type::FallingBlock => Box::new(FallingBlock::default()),
+    Type::Zombie => Box::new(Mob::zombie()),
+    Type::Skeleton => Box::new(Mob::skeleton()),
+    Type::Cow => Box::new(Mob::cow()),
+    Type::Villager => Box::new(Mob::villager()),
     _ => Box::new(DefaultBehavior::default()),
   }
 }
@@ -0,0 +1,145 @@
+//! Stores the status effects on an entity or player.
+
+use bb_common::{net::cb, util::Effect};
+
+/// A single status effect, with a level and duration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EffectInstance {
+  effect:    Effect,
+  amplifier: u8,
+  duration:  u32,
+  ambient:   bool,
+  particles: bool,
+  icon:      bool,
+}
+
+impl EffectInstance {
+  /// Creates a new effect. `amplifier` is one less than the level shown to the
+  /// client, so an amplifier of 0 is `Speed I`. `duration` is in ticks.
+  ///
+  /// By default, the effect shows particles and an icon.
+  pub fn new(effect: Effect, amplifier: u8, duration: u32) -> Self {
+    EffectInstance { effect, amplifier, duration, ambient: false, particles: true, icon: true }
+  }
+  /// Makes this effect show fewer particles, like effects from a beacon.
+  pub fn with_ambient(mut self, ambient: bool) -> Self {
+    self.ambient = ambient;
+    self
+  }
+  /// Sets if this effect shows particles around the entity.
+  pub fn with_particles(mut self, particles: bool) -> Self {
+    self.particles = particles;
+    self
+  }
+  /// Sets if this effect shows an icon in the top right of the client's
+  /// screen.
+  pub fn with_icon(mut self, icon: bool) -> Self {
+    self.icon = icon;
+    self
+  }
+
+  pub fn effect(&self) -> Effect { self.effect }
+  pub fn amplifier(&self) -> u8 { self.amplifier }
+  /// Returns the level of this effect. This is the amplifier plus one.
+  pub fn level(&self) -> u32 { u32::from(self.amplifier) + 1 }
+  /// Returns the number of ticks until this effect runs out.
+  pub fn duration(&self) -> u32 { self.duration }
+
+  /// Returns the packet that shows this effect on the given entity.
+  pub fn to_packet(&self, eid: i32) -> cb::packet::EntityEffect {
+    cb::packet::EntityEffect {
+      eid,
+      effect: self.effect,
+      amplifier: self.amplifier,
+      duration: self.duration.min(i32::MAX as u32) as i32,
+      ambient: self.ambient,
+      particles: self.particles,
+      icon: self.icon,
+    }
+  }
+}
+
+/// All the effects on an entity. There is at most one instance of each
+/// effect.
+#[derive(Debug, Clone, Default)]
+pub struct Effects {
+  list: Vec<EffectInstance>,
+}
+
+impl Effects {
+  pub fn new() -> Self { Effects { list: vec![] } }
+
+  /// Returns the given effect, if it is active.
+  pub fn get(&self, effect: Effect) -> Option<&EffectInstance> {
+    self.list.iter().find(|e| e.effect == effect)
+  }
+  /// Returns the amplifier of the given effect, if it is active.
+  pub fn amplifier(&self, effect: Effect) -> Option<u8> { self.get(effect).map(|e| e.amplifier) }
+
+  /// Adds the given effect. If the effect is already active, this only
+  /// replaces it if the new effect is stronger, or the same level and lasts
+  /// longer. This matches drinking potions in vanilla.
+  ///
+  /// Returns `true` if the effect was added.
+  pub fn insert(&mut self, inst: EffectInstance) -> bool {
+    match self.list.iter_mut().find(|e| e.effect == inst.effect) {
+      Some(e) => {
+        if inst.amplifier > e.amplifier
+          || (inst.amplifier == e.amplifier && inst.duration > e.duration)
+        {
+          *e = inst;
+          true
+        } else {
+          false
+        }
+      }
+      None => {
+        self.list.push(inst);
+        true
+      }
+    }
+  }
+  /// Removes the given effect. Returns the effect, if it was active.
+  pub fn remove(&mut self, effect: Effect) -> Option<EffectInstance> {
+    let idx = self.list.iter().position(|e| e.effect == effect)?;
+    Some(self.list.remove(idx))
+  }
+  /// Removes all effects, and returns them.
+  pub fn clear(&mut self) -> Vec<EffectInstance> { std::mem::take(&mut self.list) }
+
+  /// Counts down the duration of every effect, and removes the ones that have
+  /// run out. The removed effects are returned.
+  pub fn tick(&mut self) -> Vec<Effect> {
+    for e in &mut self.list {
+      e.duration = e.duration.saturating_sub(1);
+    }
+    let expired = self.list.iter().filter(|e| e.duration == 0).map(|e| e.effect).collect();
+    self.list.retain(|e| e.duration > 0);
+    expired
+  }
+
+  /// Iterates through all active effects.
+  pub fn iter(&self) -> impl Iterator<Item = &EffectInstance> { self.list.iter() }
+  pub fn is_empty(&self) -> bool { self.list.is_empty() }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn insert_and_tick() {
+    let mut effects = Effects::new();
+    assert!(effects.insert(EffectInstance::new(Effect::Speed, 1, 2)));
+    // Weaker effects don't replace stronger ones.
+    assert!(!effects.insert(EffectInstance::new(Effect::Speed, 0, 100)));
+    assert!(effects.insert(EffectInstance::new(Effect::Speed, 1, 3)));
+    assert!(effects.insert(EffectInstance::new(Effect::Haste, 0, 1)));
+    assert_eq!(effects.amplifier(Effect::Speed), Some(1));
+
+    assert_eq!(effects.tick(), vec![Effect::Haste]);
+    assert_eq!(effects.tick(), vec![]);
+    assert_eq!(effects.tick(), vec![Effect::Speed]);
+    assert!(effects.is_empty());
+  }
+}
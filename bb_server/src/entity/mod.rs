@@ -1,15 +1,22 @@
+mod effect;
 mod ty;
 mod version;
 
+pub use effect::{EffectInstance, Effects};
 pub use ty::{Data, Type};
 pub use version::TypeConverter;
 
 use crate::{
   math::{Vec3, AABB},
-  player::Player,
+  player::{DamageCause, Player},
   world::World,
 };
-use bb_common::{math::FPos, metadata::Metadata, util::UUID};
+use bb_common::{
+  math::FPos,
+  metadata::Metadata,
+  net::cb,
+  util::{Effect, UUID},
+};
 use parking_lot::{Mutex, MutexGuard, RwLock};
 use std::sync::Arc;
 
@@ -72,19 +79,24 @@ pub enum Entity {
 pub struct EntityData {
   /// The unique id for this entity. This is the key used to store entities in
   /// the World.
-  eid:      i32,
+  eid:       i32,
   /// The position of this entity. Must be valid for all entities.
-  pos:      Mutex<EntityPos>,
+  pos:       Mutex<EntityPos>,
   /// The type of this entity.
-  ty:       Type,
+  ty:        Type,
   /// For some entities, such as projectiles, this field is ignored. To make the
   /// entity not disappear when it hits 0 health, overwrite the
   /// `should_despawn` function in `EntityData`.
-  health:   Mutex<f32>,
+  health:    Mutex<f32>,
+  /// The number of ticks until this entity can be damaged again. This is set
+  /// to 10 whenever the entity is damaged.
+  hit_delay: Mutex<u32>,
+  /// The status effects on this entity, such as regeneration or poison.
+  effects:   Mutex<Effects>,
   /// The world this entity is in. Used whenever something changes, and nearby
   /// players need to be notified. This can change if the entity is teleported.
-  world:    RwLock<Arc<World>>,
-  behavior: Mutex<Box<dyn Behavior + Send>>,
+  world:     RwLock<Arc<World>>,
+  behavior:  Mutex<Box<dyn Behavior + Send>>,
 
   /// Entity metadata
   meta: Mutex<Metadata>,
@@ -221,6 +233,31 @@ impl EntityRef<'_> {
     }
   }
 
+  /// Gives this entity a status effect. See [`Player::add_effect`].
+  pub fn add_effect(&self, inst: EffectInstance) {
+    match self {
+      Self::Entity(e) => e.add_effect(inst),
+      Self::Player(p) => p.add_effect(inst),
+    }
+  }
+
+  /// Removes a status effect from this entity. Returns `true` if the entity
+  /// had the effect.
+  pub fn remove_effect(&self, effect: Effect) -> bool {
+    match self {
+      Self::Entity(e) => e.remove_effect(effect),
+      Self::Player(p) => p.remove_effect(effect),
+    }
+  }
+
+  /// Returns the amplifier of the given effect, if this entity has it.
+  pub fn effect_amplifier(&self, effect: Effect) -> Option<u8> {
+    match self {
+      Self::Entity(e) => e.effects.lock().amplifier(effect),
+      Self::Player(p) => p.effect_amplifier(effect),
+    }
+  }
+
   /// Returns all of this entity's metadata.
   pub fn metadata(&self) -> MutexGuard<'_, Metadata> {
     match self {
@@ -234,11 +271,44 @@ impl EntityRef<'_> {
   /// this will deal exactly `damage` amount to the player.
   ///
   /// Returns `true` if the entity was able to be damaged. This will only return
-  /// false for dead or invulnerable entities.
+  /// false for dead or invulnerable entities, or if a plugin cancelled the
+  /// damage.
   pub fn damage(&self, amount: f32, blockable: bool, knockback: Vec3) -> bool {
+    self.damage_by(DamageCause::Generic, amount, blockable, knockback)
+  }
+
+  /// Damages this entity. This is the same as [`damage`](Self::damage), but
+  /// the `cause` is used for the death message if this kills a player.
+  pub fn damage_by(
+    &self,
+    cause: DamageCause,
+    amount: f32,
+    blockable: bool,
+    knockback: Vec3,
+  ) -> bool {
     match self {
-      Self::Entity(_) => true, // TODO
-      Self::Player(p) => p.damage(amount, blockable, knockback),
+      Self::Entity(e) => {
+        let world = e.world.read().clone();
+        if !world.events().entity_damage(e.eid(), amount, blockable) {
+          return false;
+        }
+        e.damage(&cause, amount, knockback)
+      }
+      Self::Player(p) => {
+        if !p.damageable() {
+          return false;
+        }
+        let world = p.world();
+        let events = world.events();
+        if !events.player_damage(p.clone(), amount, blockable) {
+          return false;
+        }
+        let damaged = p.damage(amount, blockable, knockback);
+        if damaged && !p.alive() {
+          p.die(cause);
+        }
+        damaged
+      }
     }
   }
 
@@ -262,6 +332,8 @@ impl EntityData {
       pos: Mutex::new(EntityPos::new(pos, world.entity_converter().get_data(ty).size())),
       ty,
       health: Mutex::new(behavior.max_health()),
+      hit_delay: Mutex::new(0),
+      effects: Mutex::new(Effects::new()),
       world: RwLock::new(world),
       behavior: Mutex::new(behavior),
       meta: Mutex::new(meta),
@@ -285,6 +357,8 @@ impl EntityData {
       pos: Mutex::new(EntityPos::new(pos, world.entity_converter().get_data(ty).size())),
       ty,
       health: Mutex::new(behavior.max_health()),
+      hit_delay: Mutex::new(0),
+      effects: Mutex::new(Effects::new()),
       world: RwLock::new(world),
       behavior: Mutex::new(Box::new(behavior)),
       meta: Mutex::new(meta),
@@ -295,9 +369,128 @@ impl EntityData {
   pub fn data(&self) -> i32 { self.data }
   pub fn fpos(&self) -> FPos { self.pos.lock().aabb.pos }
   pub fn health(&self) -> f32 { *self.health.lock() }
+  /// Returns the number of ticks until this entity can be damaged again.
+  pub fn hit_delay(&self) -> u32 { *self.hit_delay.lock() }
   pub fn eid(&self) -> i32 { self.eid }
   pub fn metadata(&self) -> MutexGuard<'_, Metadata> { self.meta.lock() }
+
+  /// Gives this entity a status effect. Instant health and instant damage
+  /// change the entity's health right away.
+  pub fn add_effect(&self, inst: EffectInstance) {
+    if inst.effect().is_instant() {
+      let amount = 2.0_f32.powi(i32::from(inst.amplifier()));
+      let max = self.max_health();
+      let mut health = self.health.lock();
+      match inst.effect() {
+        Effect::InstantHealth => *health = (*health + 4.0 * amount).min(max),
+        Effect::InstantDamage => *health -= 6.0 * amount,
+        _ => {}
+      }
+      return;
+    }
+    if self.effects.lock().insert(inst) {
+      self.send_in_view(inst.to_packet(self.eid));
+    }
+  }
+  /// Removes a status effect from this entity. Returns `true` if the entity
+  /// had the effect.
+  pub fn remove_effect(&self, effect: Effect) -> bool {
+    if self.effects.lock().remove(effect).is_none() {
+      return false;
+    }
+    self.send_in_view(cb::packet::RemoveEntityEffect { eid: self.eid, effect });
+    true
+  }
+  /// Returns the packets needed to show this entity's effects to a client that
+  /// just spawned it in.
+  pub(crate) fn effect_packets(&self) -> Vec<cb::packet::EntityEffect> {
+    self.effects.lock().iter().map(|e| e.to_packet(self.eid)).collect()
+  }
+
+  /// Damages this entity, and knocks it back. Only living entities can be
+  /// damaged, and they can't be damaged again for 10 ticks after being hit.
+  ///
+  /// Returns `true` if the entity was damaged.
+  fn damage(&self, cause: &DamageCause, amount: f32, knockback: Vec3) -> bool {
+    if !self.ty.is_living() {
+      return false;
+    }
+    {
+      let mut hit_delay = self.hit_delay.lock();
+      let mut health = self.health.lock();
+      if *hit_delay > 0 || *health <= 0.0 {
+        return false;
+      }
+      *health -= amount;
+      *hit_delay = 10;
+    }
+    let world = self.world.read().clone();
+    let (pos, vel) = {
+      let mut p = self.pos.lock();
+      p.vel += knockback;
+      p.vel.y = p.vel.y.min(0.4);
+      (p.aabb.pos, p.vel)
+    };
+    world.send_entity_vel(pos.chunk(), self.eid, vel);
+    self.send_in_view(cb::packet::Animation { eid: self.eid, kind: cb::AnimationKind::Damage });
+    if self.health() <= 0.0 {
+      // Plays the death animation.
+      self.send_in_view(cb::packet::EntityStatus { eid: self.eid, status: 3 });
+    }
+    self.behavior.lock().damaged(&world, self, cause.attacker());
+    true
+  }
+
+  fn max_health(&self) -> f32 { self.behavior.lock().max_health() }
+  fn send_in_view(&self, p: impl Into<cb::Packet>) {
+    let p = p.into();
+    let w = self.world.read();
+    for other in w.players().iter().in_view(self.fpos().chunk()) {
+      other.send(p.clone());
+    }
+  }
+
+  /// Counts down this entity's effects, and applies regeneration and poison.
+  fn tick_effects(&self) {
+    let (expired, active) = {
+      let mut effects = self.effects.lock();
+      if effects.is_empty() {
+        return;
+      }
+      let expired = effects.tick();
+      (expired, effects.iter().copied().collect::<Vec<_>>())
+    };
+    for effect in expired {
+      self.send_in_view(cb::packet::RemoveEntityEffect { eid: self.eid, effect });
+    }
+    for inst in active {
+      let interval = |base: u32| (base >> inst.amplifier().min(31)).max(1);
+      match inst.effect() {
+        Effect::Regeneration if inst.duration() % interval(50) == 0 => {
+          let max = self.max_health();
+          let mut health = self.health.lock();
+          *health = (*health + 1.0).min(max);
+        }
+        Effect::Poison if inst.duration() % interval(25) == 0 => {
+          let mut health = self.health.lock();
+          if *health > 1.0 {
+            *health -= 1.0;
+          }
+        }
+        Effect::Wither if inst.duration() % interval(40) == 0 => {
+          *self.health.lock() -= 1.0;
+        }
+        _ => {}
+      }
+    }
+  }
+
   fn tick(&self) -> bool {
+    self.tick_effects();
+    {
+      let mut hit_delay = self.hit_delay.lock();
+      *hit_delay = hit_delay.saturating_sub(1);
+    }
     // We don't actually have a race condition here, unless tick() is called at the
     // same time from multiple places (which would be a Bad Thing). Because we can't
     // modify `self.pos` from anywhere else (simply because the functions don't
@@ -306,7 +499,11 @@ impl EntityData {
     let mut p = *self.pos.lock();
     let old = p.aabb;
     let old_vel = p.vel;
-    if self.behavior.lock().tick(&*self.world.read(), self, &mut p).0 {
+    let despawn = {
+      let mut behavior = self.behavior.lock();
+      behavior.tick(&*self.world.read(), self, &mut p).0 || behavior.should_despawn(self.health()).0
+    };
+    if despawn {
       return true;
     }
     let w = self.world.read();
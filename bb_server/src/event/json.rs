@@ -1,6 +1,9 @@
 use crate::{block, player::Player};
-use bb_common::{math::Pos, util::UUID};
-use serde::{Deserialize, Serialize, Serializer};
+use bb_common::{
+  math::{FPos, Pos},
+  util::UUID,
+};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use std::{collections::HashMap, sync::Arc};
 
 #[derive(Debug, Clone, Serialize)]
@@ -28,6 +31,13 @@ pub struct JsonPos {
   pub z: i32,
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct JsonFPos {
+  pub x: f64,
+  pub y: f64,
+  pub z: f64,
+}
+
 impl Serialize for JsonUUID {
   fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
   where
@@ -36,6 +46,18 @@ impl Serialize for JsonUUID {
     serializer.serialize_str(&self.uuid.as_dashed_str())
   }
 }
+impl<'de> Deserialize<'de> for JsonUUID {
+  fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+  where
+    D: Deserializer<'de>,
+  {
+    let s = String::deserialize(deserializer)?;
+    match UUID::from_dashed_str(&s) {
+      Ok(uuid) => Ok(JsonUUID { uuid }),
+      Err(e) => Err(de::Error::custom(format!("invalid uuid `{s}`: {e:?}"))),
+    }
+  }
+}
 
 impl From<Arc<Player>> for JsonPlayer {
   fn from(p: Arc<Player>) -> Self {
@@ -52,6 +74,12 @@ impl From<Pos> for JsonPos {
 impl From<JsonPos> for Pos {
   fn from(pos: JsonPos) -> Self { Pos { x: pos.x, y: pos.y, z: pos.z } }
 }
+impl From<FPos> for JsonFPos {
+  fn from(pos: FPos) -> Self { JsonFPos { x: pos.x, y: pos.y, z: pos.z } }
+}
+impl From<JsonFPos> for FPos {
+  fn from(pos: JsonFPos) -> Self { FPos::new(pos.x, pos.y, pos.z) }
+}
 impl From<block::TypeStore> for JsonBlock {
   fn from(ty: block::TypeStore) -> Self {
     JsonBlock { kind: ty.kind().to_str().into(), id: ty.id(), props: ty.props() }
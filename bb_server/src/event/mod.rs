@@ -40,6 +40,7 @@ use bb_common::{
 use parking_lot::Mutex;
 use std::sync::Arc;
 
+pub use json::{JsonBlock, JsonFPos, JsonPlayer, JsonPos, JsonUUID};
 pub use types::*;
 
 pub struct Events<'a> {
@@ -61,8 +62,14 @@ macro_rules! try_event {
 
 use EventFlow::*;
 impl Events<'_> {
-  pub fn interact(&self, player: &Arc<Player>, _hand: Hand, click: Click) -> EventFlow {
-    // self.req(player, ServerRequest::Interact { hand, click });
+  pub fn interact(&self, player: &Arc<Player>, hand: Hand, click: Click) -> EventFlow {
+    let block = match click {
+      Click::Block(click) => Some(click.block.pos),
+      Click::Air(_) => None,
+    };
+    if !self.req(player.clone(), ServerRequest::Interact { hand, block }) {
+      return Handled;
+    }
     let stack = player.lock_inventory().main_hand().clone();
     try_event!(self
       .wm
@@ -80,24 +87,30 @@ impl Events<'_> {
   }
 
   fn global_event(&self, ev: GlobalServerEvent) { self.wm.plugins().global_event(ev); }
-  fn req(&self, player: Arc<Player>, req: ServerRequest) { self.wm.plugins().req(player, req); }
+  fn req(&self, player: Arc<Player>, req: ServerRequest) -> bool {
+    self.wm.plugins().req(player, req)
+  }
+  fn req_global(&self, req: GlobalServerRequest) -> bool { self.wm.plugins().req_global(req) }
   fn event(&self, player: Arc<Player>, ev: ServerEvent) { self.wm.plugins().event(player, ev); }
 
   pub fn tick(&self) { self.global_event(GlobalServerEvent::Tick); }
   pub fn generate_chunk(&self, generator: &str, chunk: Arc<Mutex<MultiChunk>>, pos: ChunkPos) {
     self.global_event(GlobalServerEvent::GenerateChunk { generator: generator.into(), chunk, pos });
   }
+  /// Called before a block is placed. If this returns `false`, a plugin has
+  /// cancelled the place, and the block should not be placed.
   pub fn block_place(&self, player: Arc<Player>, pos: Pos, block: block::Type) -> bool {
-    self.req(player, ServerRequest::BlockPlace { pos, block: block.to_store() });
-    true
+    self.req(player, ServerRequest::BlockPlace { pos, block: block.to_store() })
   }
+  /// Called before a block is broken. If this returns `false`, a plugin has
+  /// cancelled the break, and the block should not be removed.
   pub fn block_break(&self, player: Arc<Player>, pos: Pos, block: block::Type) -> bool {
-    self.req(player, ServerRequest::BlockBreak { pos, block: block.to_store() });
-    true
+    self.req(player, ServerRequest::BlockBreak { pos, block: block.to_store() })
   }
+  /// Called before a window click is handled. If this returns `false`, a plugin
+  /// has cancelled the click, and the window should be resynced.
   pub fn click_window(&self, player: Arc<Player>, slot: i32, mode: ClickWindow) -> bool {
-    self.req(player, ServerRequest::ClickWindow { slot, mode });
-    true
+    self.req(player, ServerRequest::ClickWindow { slot, mode })
   }
   pub fn chat_message(&self, player: Arc<Player>, message: Chat) {
     self.event(player, ServerEvent::Chat { text: message.to_plain() });
@@ -106,6 +119,26 @@ impl Events<'_> {
   pub fn player_leave(&self, player: Arc<Player>) {
     self.event(player, ServerEvent::PlayerLeave {});
   }
+  /// Called before a player is damaged. If this returns `false`, a plugin has
+  /// cancelled the damage, and the player's health should not change.
+  pub fn player_damage(&self, player: Arc<Player>, amount: f32, blockable: bool) -> bool {
+    self.req(player, ServerRequest::Damage { amount, blockable })
+  }
+  /// Called before a non-player entity is damaged. If this returns `false`, a
+  /// plugin has cancelled the damage, and the entity's health should not
+  /// change.
+  pub fn entity_damage(&self, eid: i32, amount: f32, blockable: bool) -> bool {
+    self.req_global(GlobalServerRequest::EntityDamage { eid, amount, blockable })
+  }
+  /// Called before a player dies. If this returns `false`, a plugin has
+  /// cancelled the death, and the player should be kept alive.
+  pub fn player_die(&self, player: Arc<Player>, message: &Chat) -> bool {
+    self.req(player, ServerRequest::Death { message: message.to_plain() })
+  }
+  /// Called after a player has died, and their items have been dropped.
+  pub fn player_death(&self, player: Arc<Player>) {
+    self.event(player, ServerEvent::PlayerDeath {});
+  }
 }
 
 impl EventFlow {
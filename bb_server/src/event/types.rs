@@ -3,6 +3,7 @@ use crate::{block, player::Player, world::MultiChunk};
 use bb_common::{
   math::{ChunkPos, Pos},
   net::sb::ClickWindow,
+  util::Hand,
 };
 use parking_lot::Mutex;
 use std::sync::Arc;
@@ -29,14 +30,128 @@ pub enum PluginMessage {
 
 /// A message to the server. The server cannot reply to this message. Once sent,
 /// the plugin should forget about it.
+///
+/// Any `world` field may be left out, in which case the default world is used.
 #[non_exhaustive]
 #[derive(Debug, Clone, serde::Deserialize)]
 #[serde(tag = "type")]
 pub enum PluginEvent {
-  Register { ty: String },
+  /// Subscribes to an event. `ty` is the `type` of a [ServerEvent],
+  /// [GlobalServerEvent], or [ServerRequest]. The server will not send any
+  /// events to a plugin until it has registered for them. Requests that the
+  /// plugin has not registered for are always allowed.
+  Register {
+    ty: String,
+  },
   Ready,
+  /// The plugin is about to close the connection. The server will stop sending
+  /// messages, and remove the plugin. It will be started again on the next
+  /// reload.
+  Disconnect,
 
-  SendChat { text: String },
+  /// Sends a chat message to every player on the server.
+  SendChat {
+    text: String,
+  },
+  /// Sends a chat message to a single player.
+  SendMessage {
+    player: JsonUUID,
+    text:   String,
+  },
+  SetBlock {
+    #[serde(default)]
+    world: String,
+    pos:   JsonPos,
+    /// The name of a block kind, like `stone`. The default type of this kind
+    /// will be placed.
+    kind:  String,
+  },
+  /// Fills the region between `min` and `max` (inclusive).
+  Fill {
+    #[serde(default)]
+    world: String,
+    min:   JsonPos,
+    max:   JsonPos,
+    kind:  String,
+  },
+  Teleport {
+    player: JsonUUID,
+    pos:    JsonFPos,
+    #[serde(default)]
+    yaw:    f32,
+    #[serde(default)]
+    pitch:  f32,
+  },
+  GiveItem {
+    player: JsonUUID,
+    /// The name of an item, like `diamond_sword`.
+    item:   String,
+    #[serde(default = "one")]
+    amount: u8,
+  },
+  /// Shows a title and subtitle. The times are all in ticks.
+  ShowTitle {
+    player:   JsonUUID,
+    title:    String,
+    #[serde(default)]
+    subtitle: String,
+    fade_in:  u32,
+    stay:     u32,
+    fade_out: u32,
+  },
+  ShowScoreboard {
+    player: JsonUUID,
+  },
+  HideScoreboard {
+    player: JsonUUID,
+  },
+  /// Sets a line of the player's sidebar. `line` must be within `0..15`.
+  SetScoreboardLine {
+    player: JsonUUID,
+    line:   u8,
+    text:   String,
+  },
+  ClearScoreboardLine {
+    player: JsonUUID,
+    line:   u8,
+  },
+  /// Overrides a player's skin. `value` and `signature` are the base64 encoded
+  /// `textures` property and its signature.
+  SetSkin {
+    player:    JsonUUID,
+    value:     String,
+    #[serde(default)]
+    signature: Option<String>,
+  },
+  /// Restores the skin a player logged in with.
+  ResetSkin {
+    player: JsonUUID,
+  },
+  /// Adds a command with the given name. Everything after the name is passed
+  /// to the plugin in a [ServerEvent::Command], split on whitespace.
+  RegisterCommand {
+    name: String,
+  },
+  /// Stores a value in this plugin's persistent storage. If `player` is set,
+  /// this is stored for that player. Otherwise, if `world` is set, this is
+  /// stored for that world. If neither are set, this is stored globally.
+  SetData {
+    #[serde(default)]
+    player: Option<JsonUUID>,
+    #[serde(default)]
+    world:  Option<String>,
+    key:    String,
+    value:  String,
+  },
+  /// Removes a value from this plugin's persistent storage. See
+  /// [PluginEvent::SetData] for how `player` and `world` are used.
+  RemoveData {
+    #[serde(default)]
+    player: Option<JsonUUID>,
+    #[serde(default)]
+    world:  Option<String>,
+    key:    String,
+  },
 }
 /// A request from the plugin to the server. The id to reply with is stored in
 /// [PluginMessage]. Once sent, the plugin should expect a reply from the server
@@ -45,7 +160,24 @@ pub enum PluginEvent {
 #[derive(Debug, Clone, serde::Deserialize)]
 #[serde(tag = "type")]
 pub enum PluginRequest {
-  GetBlock { pos: JsonPos },
+  GetBlock {
+    #[serde(default)]
+    world: String,
+    pos:   JsonPos,
+  },
+  /// Lists all the players on the server.
+  GetPlayers,
+  /// Gets the position and world of a player.
+  GetPlayer { player: JsonUUID },
+  /// Reads a value from this plugin's persistent storage. See
+  /// [PluginEvent::SetData] for how `player` and `world` are used.
+  GetData {
+    #[serde(default)]
+    player: Option<JsonUUID>,
+    #[serde(default)]
+    world:  Option<String>,
+    key:    String,
+  },
 }
 /// A response to a request from the server. See also [ServerRequest].
 #[non_exhaustive]
@@ -76,6 +208,11 @@ pub enum ServerMessage {
     #[serde(flatten)]
     request:  ServerRequest,
   },
+  GlobalRequest {
+    reply_id: u32,
+    #[serde(flatten)]
+    request:  GlobalServerRequest,
+  },
   Reply {
     reply_id: u32,
     #[serde(flatten)]
@@ -89,9 +226,19 @@ pub enum ServerMessage {
 #[derive(Debug, Clone, serde::Serialize)]
 #[serde(tag = "type")]
 pub enum ServerEvent {
-  Chat { text: String },
+  Chat {
+    text: String,
+  },
   PlayerJoin,
   PlayerLeave,
+  /// The player's health has reached zero.
+  PlayerDeath,
+  /// A command added by the plugin was run. This is only sent to the plugin
+  /// that added the command.
+  Command {
+    name: String,
+    args: Vec<String>,
+  },
 }
 /// An event from the server to the plugin. This is very similar to
 /// [ServerEvent], but there is no player specified with this event.
@@ -133,6 +280,32 @@ pub enum ServerRequest {
     #[serde(skip)]
     mode: ClickWindow,
   },
+  /// The player right clicked. If they clicked on a block, `block` is the
+  /// position of that block.
+  Interact {
+    #[serde(skip)]
+    hand:  Hand,
+    #[serde(serialize_with = "opt_to_json_ty::<_, JsonPos, _>")]
+    block: Option<Pos>,
+  },
+  /// The player is about to take damage. If cancelled, the player will not be
+  /// damaged at all.
+  Damage { amount: f32, blockable: bool },
+  /// The player is about to die. `message` is the death message that will be
+  /// shown in chat. If cancelled, the player will be left with half a heart,
+  /// and will not drop their items.
+  Death { message: String },
+}
+/// A request from the server to the plugin. This is very similar to
+/// [ServerRequest], but there is no player specified with this request.
+#[non_exhaustive]
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(tag = "type")]
+pub enum GlobalServerRequest {
+  /// A non-player entity is about to take damage. If cancelled, the entity
+  /// will not be damaged at all. Players are sent [ServerRequest::Damage]
+  /// instead.
+  EntityDamage { eid: i32, amount: f32, blockable: bool },
 }
 
 /// A reply from the server to the plugin. This is a response to a
@@ -147,11 +320,85 @@ pub enum ServerReply {
     #[serde(serialize_with = "to_json_ty::<_, JsonBlock, _>")]
     block: block::TypeStore,
   },
+  Players {
+    players: Vec<JsonPlayer>,
+  },
+  Player {
+    player: JsonPlayer,
+    world:  String,
+    pos:    JsonFPos,
+  },
+  /// A value from persistent storage. `value` is `null` if the key was not
+  /// present.
+  Data {
+    value: Option<String>,
+  },
+  /// The request could not be completed. For example, the player is offline,
+  /// or the position is outside the world.
+  Error {
+    message: String,
+  },
+}
+
+impl ServerEvent {
+  /// Returns the `type` of this event. Plugins use this name to register for
+  /// this event.
+  pub fn name(&self) -> &'static str {
+    match self {
+      Self::Chat { .. } => "Chat",
+      Self::PlayerJoin => "PlayerJoin",
+      Self::PlayerLeave => "PlayerLeave",
+      Self::PlayerDeath => "PlayerDeath",
+      Self::Command { .. } => "Command",
+    }
+  }
+}
+impl GlobalServerEvent {
+  /// Returns the `type` of this event. Plugins use this name to register for
+  /// this event.
+  pub fn name(&self) -> &'static str {
+    match self {
+      Self::Tick => "Tick",
+      Self::GenerateChunk { .. } => "GenerateChunk",
+    }
+  }
+}
+impl ServerRequest {
+  /// Returns the `type` of this request. Plugins use this name to register for
+  /// this request.
+  pub fn name(&self) -> &'static str {
+    match self {
+      Self::BlockPlace { .. } => "BlockPlace",
+      Self::BlockBreak { .. } => "BlockBreak",
+      Self::ClickWindow { .. } => "ClickWindow",
+      Self::Interact { .. } => "Interact",
+      Self::Damage { .. } => "Damage",
+      Self::Death { .. } => "Death",
+    }
+  }
+}
+impl GlobalServerRequest {
+  /// Returns the `type` of this request. Plugins use this name to register for
+  /// this request.
+  pub fn name(&self) -> &'static str {
+    match self {
+      Self::EntityDamage { .. } => "EntityDamage",
+    }
+  }
 }
 
+fn one() -> u8 { 1 }
+
 fn to_json_ty<T: Clone + Into<U>, U: serde::Serialize, S: serde::Serializer>(
   v: &T,
   ser: S,
 ) -> Result<S::Ok, S::Error> {
   Into::<U>::into(v.clone()).serialize(ser)
 }
+
+fn opt_to_json_ty<T: Clone + Into<U>, U: serde::Serialize, S: serde::Serializer>(
+  v: &Option<T>,
+  ser: S,
+) -> Result<S::Ok, S::Error> {
+  serde::Serialize::serialize(&v.clone().map(Into::<U>::into), ser)
+}
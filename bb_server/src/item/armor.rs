@@ -0,0 +1,172 @@
+//! Implements armor values, and the durability of armor and shields.
+
+use super::Type;
+use bb_common::net::cb::ArmorSlot;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Armor {
+  slot:     ArmorSlot,
+  material: ArmorMaterial,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArmorMaterial {
+  Leather,
+  Chainmail,
+  Iron,
+  Gold,
+  Diamond,
+  Netherite,
+  Turtle,
+}
+
+impl Type {
+  /// If this is a piece of armor, this returns the slot it is worn in and the
+  /// material it is made of.
+  pub fn armor(&self) -> Option<Armor> {
+    let (slot, material) = match self {
+      Type::LeatherHelmet => (ArmorSlot::Helmet, ArmorMaterial::Leather),
+      Type::LeatherChestplate => (ArmorSlot::Chestplate, ArmorMaterial::Leather),
+      Type::LeatherLeggings => (ArmorSlot::Leggings, ArmorMaterial::Leather),
+      Type::LeatherBoots => (ArmorSlot::Boots, ArmorMaterial::Leather),
+
+      Type::ChainmailHelmet => (ArmorSlot::Helmet, ArmorMaterial::Chainmail),
+      Type::ChainmailChestplate => (ArmorSlot::Chestplate, ArmorMaterial::Chainmail),
+      Type::ChainmailLeggings => (ArmorSlot::Leggings, ArmorMaterial::Chainmail),
+      Type::ChainmailBoots => (ArmorSlot::Boots, ArmorMaterial::Chainmail),
+
+      Type::IronHelmet => (ArmorSlot::Helmet, ArmorMaterial::Iron),
+      Type::IronChestplate => (ArmorSlot::Chestplate, ArmorMaterial::Iron),
+      Type::IronLeggings => (ArmorSlot::Leggings, ArmorMaterial::Iron),
+      Type::IronBoots => (ArmorSlot::Boots, ArmorMaterial::Iron),
+
+      Type::GoldenHelmet => (ArmorSlot::Helmet, ArmorMaterial::Gold),
+      Type::GoldenChestplate => (ArmorSlot::Chestplate, ArmorMaterial::Gold),
+      Type::GoldenLeggings => (ArmorSlot::Leggings, ArmorMaterial::Gold),
+      Type::GoldenBoots => (ArmorSlot::Boots, ArmorMaterial::Gold),
+
+      Type::DiamondHelmet => (ArmorSlot::Helmet, ArmorMaterial::Diamond),
+      Type::DiamondChestplate => (ArmorSlot::Chestplate, ArmorMaterial::Diamond),
+      Type::DiamondLeggings => (ArmorSlot::Leggings, ArmorMaterial::Diamond),
+      Type::DiamondBoots => (ArmorSlot::Boots, ArmorMaterial::Diamond),
+
+      Type::NetheriteHelmet => (ArmorSlot::Helmet, ArmorMaterial::Netherite),
+      Type::NetheriteChestplate => (ArmorSlot::Chestplate, ArmorMaterial::Netherite),
+      Type::NetheriteLeggings => (ArmorSlot::Leggings, ArmorMaterial::Netherite),
+      Type::NetheriteBoots => (ArmorSlot::Boots, ArmorMaterial::Netherite),
+
+      Type::TurtleHelmet => (ArmorSlot::Helmet, ArmorMaterial::Turtle),
+      _ => return None,
+    };
+    Some(Armor { slot, material })
+  }
+
+  /// Returns the number of times this item can be damaged before it breaks.
+  /// This is only implemented for armor and shields, and will return `None`
+  /// for anything else.
+  pub fn max_durability(&self) -> Option<u16> {
+    if let Some(armor) = self.armor() {
+      Some(armor.max_durability())
+    } else if *self == Type::Shield {
+      Some(336)
+    } else {
+      None
+    }
+  }
+}
+
+impl Armor {
+  /// Returns the slot this armor is worn in.
+  pub fn slot(&self) -> ArmorSlot { self.slot }
+  /// Returns the armor points this piece gives. This is the number of half
+  /// chestplates shown above the hotbar.
+  pub fn defense(&self) -> f32 {
+    use ArmorMaterial::*;
+    use ArmorSlot::*;
+
+    match (self.material, self.slot) {
+      (Leather, Helmet) => 1.0,
+      (Leather, Chestplate) => 3.0,
+      (Leather, Leggings) => 2.0,
+      (Leather, Boots) => 1.0,
+      (Chainmail, Helmet) => 2.0,
+      (Chainmail, Chestplate) => 5.0,
+      (Chainmail, Leggings) => 4.0,
+      (Chainmail, Boots) => 1.0,
+      (Iron, Helmet) => 2.0,
+      (Iron, Chestplate) => 6.0,
+      (Iron, Leggings) => 5.0,
+      (Iron, Boots) => 2.0,
+      (Gold, Helmet) => 2.0,
+      (Gold, Chestplate) => 5.0,
+      (Gold, Leggings) => 3.0,
+      (Gold, Boots) => 1.0,
+      (Diamond | Netherite, Helmet) => 3.0,
+      (Diamond | Netherite, Chestplate) => 8.0,
+      (Diamond | Netherite, Leggings) => 6.0,
+      (Diamond | Netherite, Boots) => 3.0,
+      (Turtle, _) => 2.0,
+    }
+  }
+  /// Returns the armor toughness of this piece. Toughness makes armor less
+  /// effective against large amounts of damage.
+  pub fn toughness(&self) -> f32 {
+    match self.material {
+      ArmorMaterial::Diamond => 2.0,
+      ArmorMaterial::Netherite => 3.0,
+      _ => 0.0,
+    }
+  }
+  /// Returns the number of times this armor can be damaged before it breaks.
+  pub fn max_durability(&self) -> u16 {
+    let base = match self.material {
+      ArmorMaterial::Leather => 5,
+      ArmorMaterial::Chainmail | ArmorMaterial::Iron => 15,
+      ArmorMaterial::Gold => 7,
+      ArmorMaterial::Diamond => 33,
+      ArmorMaterial::Netherite => 37,
+      ArmorMaterial::Turtle => 25,
+    };
+    base
+      * match self.slot {
+        ArmorSlot::Helmet => 11,
+        ArmorSlot::Chestplate => 16,
+        ArmorSlot::Leggings => 15,
+        ArmorSlot::Boots => 13,
+      }
+  }
+}
+
+/// Applies vanilla armor damage reduction. `defense` and `toughness` are the
+/// totals for all the armor a player is wearing. Returns the damage that
+/// should be dealt after armor is taken into account.
+pub fn reduce_damage(amount: f32, defense: f32, toughness: f32) -> f32 {
+  let effective = (defense - amount / (2.0 + toughness / 4.0)).max(defense / 5.0).min(20.0);
+  amount * (1.0 - effective / 25.0)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn armor_values() {
+    let chest = Type::DiamondChestplate.armor().unwrap();
+    assert_eq!(chest.slot(), ArmorSlot::Chestplate);
+    assert_eq!(chest.defense(), 8.0);
+    assert_eq!(chest.max_durability(), 528);
+    assert_eq!(Type::LeatherHelmet.max_durability(), Some(55));
+    assert_eq!(Type::Shield.max_durability(), Some(336));
+    assert_eq!(Type::Stone.max_durability(), None);
+  }
+
+  #[test]
+  fn damage_reduction() {
+    assert_eq!(reduce_damage(10.0, 0.0, 0.0), 10.0);
+    // Full diamond armor (20 defense, 8 toughness) against 10 damage.
+    let reduced = reduce_damage(10.0, 20.0, 8.0);
+    assert!((reduced - 3.0).abs() < 0.001, "{reduced}");
+    // Armor can never reduce more than 80% of the damage.
+    assert!((reduce_damage(1.0, 30.0, 0.0) - 0.2).abs() < 0.001);
+  }
+}
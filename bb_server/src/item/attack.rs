@@ -67,6 +67,8 @@ impl Type {
 }
 
 impl Weapon {
+  /// Returns `true` if this is a sword. Only swords can perform sweep attacks.
+  pub fn is_sword(&self) -> bool { self.ty == WeaponType::Sword }
   pub fn base_damage(&self) -> f32 {
     if self.ty == WeaponType::Sword {
       self.grade.base_damage()
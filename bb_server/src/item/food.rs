@@ -0,0 +1,116 @@
+//! Implements food values for items that can be eaten.
+
+use super::Type;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Food {
+  hunger:              i32,
+  saturation_modifier: f32,
+  always_edible:       bool,
+  fast:                bool,
+  leftover:            Type,
+}
+
+impl Type {
+  /// If this item can be eaten, this returns the food points and saturation
+  /// it gives.
+  pub fn food(&self) -> Option<Food> {
+    let (hunger, saturation_modifier) = match self {
+      Type::Apple => (4, 0.3),
+      Type::BakedPotato => (5, 0.6),
+      Type::Beef => (3, 0.3),
+      Type::Beetroot => (1, 0.6),
+      Type::BeetrootSoup => (6, 0.6),
+      Type::Bread => (5, 0.6),
+      Type::Carrot => (3, 0.6),
+      Type::Chicken => (2, 0.3),
+      Type::ChorusFruit => (4, 0.3),
+      Type::Cod => (2, 0.1),
+      Type::CookedBeef => (8, 0.8),
+      Type::CookedChicken => (6, 0.6),
+      Type::CookedCod => (5, 0.6),
+      Type::CookedMutton => (6, 0.8),
+      Type::CookedPorkchop => (8, 0.8),
+      Type::CookedRabbit => (5, 0.6),
+      Type::CookedSalmon => (6, 0.8),
+      Type::Cookie => (2, 0.1),
+      Type::DriedKelp => (1, 0.3),
+      Type::EnchantedGoldenApple => (4, 1.2),
+      Type::GlowBerries => (2, 0.1),
+      Type::GoldenApple => (4, 1.2),
+      Type::GoldenCarrot => (6, 1.2),
+      Type::HoneyBottle => (6, 0.1),
+      Type::MelonSlice => (2, 0.3),
+      Type::MushroomStew => (6, 0.6),
+      Type::Mutton => (2, 0.3),
+      Type::PoisonousPotato => (2, 0.3),
+      Type::Porkchop => (3, 0.3),
+      Type::Potato => (1, 0.3),
+      Type::Pufferfish => (1, 0.1),
+      Type::PumpkinPie => (8, 0.3),
+      Type::Rabbit => (3, 0.3),
+      Type::RabbitStew => (10, 0.6),
+      Type::RottenFlesh => (4, 0.1),
+      Type::Salmon => (2, 0.1),
+      Type::SpiderEye => (2, 0.8),
+      Type::SuspiciousStew => (6, 0.6),
+      Type::SweetBerries => (2, 0.1),
+      Type::TropicalFish => (1, 0.1),
+      _ => return None,
+    };
+    Some(Food {
+      hunger,
+      saturation_modifier,
+      always_edible: matches!(
+        self,
+        Type::ChorusFruit | Type::GoldenApple | Type::EnchantedGoldenApple | Type::SuspiciousStew
+      ),
+      fast: matches!(self, Type::DriedKelp),
+      leftover: match self {
+        Type::BeetrootSoup | Type::MushroomStew | Type::RabbitStew | Type::SuspiciousStew => {
+          Type::Bowl
+        }
+        Type::HoneyBottle => Type::GlassBottle,
+        _ => Type::Air,
+      },
+    })
+  }
+}
+
+impl Food {
+  /// Returns the number of food points this gives. This is the number of half
+  /// drumsticks shown above the hotbar.
+  pub fn hunger(&self) -> i32 { self.hunger }
+  /// Returns the amount of saturation this gives.
+  pub fn saturation(&self) -> f32 { self.hunger as f32 * self.saturation_modifier * 2.0 }
+  /// Returns `true` if this can be eaten when the player's food bar is full.
+  pub fn always_edible(&self) -> bool { self.always_edible }
+  /// Returns the number of ticks it takes to eat this.
+  pub fn eat_ticks(&self) -> u32 {
+    if self.fast {
+      16
+    } else {
+      32
+    }
+  }
+  /// Returns the item left over after eating this. For example, eating
+  /// mushroom stew leaves behind a bowl. This is air for most food.
+  pub fn leftover(&self) -> Type { self.leftover }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn food_values() {
+    let steak = Type::CookedBeef.food().unwrap();
+    assert_eq!(steak.hunger(), 8);
+    assert!((steak.saturation() - 12.8).abs() < 0.001);
+    assert!(!steak.always_edible());
+    assert!(Type::GoldenApple.food().unwrap().always_edible());
+    assert_eq!(Type::DriedKelp.food().unwrap().eat_ticks(), 16);
+    assert_eq!(Type::MushroomStew.food().unwrap().leftover(), Type::Bowl);
+    assert_eq!(Type::Stone.food(), None);
+  }
+}
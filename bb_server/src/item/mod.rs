@@ -1,6 +1,8 @@
+mod armor;
 mod attack;
 mod behavior;
 mod dig;
+mod food;
 mod inventory;
 mod shared;
 mod stack;
@@ -8,7 +10,9 @@ mod ty;
 mod ui;
 mod version;
 
+pub use armor::{reduce_damage, Armor, ArmorMaterial};
 pub use behavior::Behavior;
+pub use food::Food;
 pub use inventory::{Inventory, SingleInventory, WrappedInventory};
 pub use shared::SharedInventory;
 pub use stack::Stack;
@@ -1,15 +1,21 @@
 use super::Type;
-use bb_common::{nbt::NBT, util::Item};
+use bb_common::{
+  nbt::{Tag, NBT},
+  util::Item,
+};
 use bb_transfer::{
   MessageRead, MessageReader, MessageWrite, MessageWriter, ReadError, StructRead, StructReader,
   WriteError,
 };
-use std::num::NonZeroU8;
+use std::{collections::HashMap, num::NonZeroU8, str::FromStr};
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Stack {
   item:   Type,
   amount: NonZeroU8,
+  /// The durability this item has lost. Only used for items with a
+  /// [`max_durability`](Type::max_durability).
+  damage: u16,
 }
 
 impl From<Item> for Stack {
@@ -32,14 +38,15 @@ impl MessageRead<'_> for Stack {
 }
 impl StructRead<'_> for Stack {
   fn read_struct(mut r: StructReader) -> Result<Self, ReadError> {
-    Ok(Stack::new(Type::from_u32(r.read(0)?)).with_amount(r.read(1)?))
+    Ok(Stack::new(Type::from_u32(r.read(0)?)).with_amount(r.read(1)?).with_damage(r.read(2)?))
   }
 }
 impl MessageWrite for Stack {
   fn write<W: std::io::Write>(&self, w: &mut MessageWriter<W>) -> Result<(), WriteError> {
-    w.write_struct(2, |w| {
+    w.write_struct(3, |w| {
       w.write(&self.item.id())?;
-      w.write(&self.amount())
+      w.write(&self.amount())?;
+      w.write(&self.damage)
     })
   }
 }
@@ -50,9 +57,9 @@ impl Stack {
   pub const EMPTY: Stack = Stack::empty();
   /// Creates an empty item stck. This has the type set to air, and the count
   /// set to 0.
-  pub const fn empty() -> Self { Stack { item: Type::Air, amount: ONE } }
+  pub const fn empty() -> Self { Stack { item: Type::Air, amount: ONE, damage: 0 } }
   /// Creates an item stack containing a single item with the given type.
-  pub fn new(item: Type) -> Self { Stack { item, amount: ONE } }
+  pub fn new(item: Type) -> Self { Stack { item, amount: ONE, damage: 0 } }
 
   /// Sets the amount in self, and returns the modified self. If the stack is
   /// air, this will do nothing.
@@ -66,6 +73,7 @@ impl Stack {
     if amount == 0 {
       self.item = Type::Air;
       self.amount = ONE;
+      self.damage = 0;
       // Keep amount at 1 if we are air.
     } else if self.item != Type::Air {
       self.amount = NonZeroU8::new(amount).unwrap();
@@ -83,16 +91,79 @@ impl Stack {
   /// Returns the item that is in this item stack.
   pub fn item(&self) -> Type { self.item }
 
+  /// Sets the durability this item has lost, and returns the modified self. If
+  /// the stack is air, this will do nothing.
+  pub fn with_damage(mut self, damage: u16) -> Self {
+    if self.item != Type::Air {
+      self.damage = damage;
+    }
+    self
+  }
+  /// Returns the durability this item has lost. This is 0 for new items, and
+  /// for items that cannot be damaged.
+  pub fn damage(&self) -> u16 { self.damage }
+  /// Damages this item by the given amount. If the item runs out of
+  /// durability, it is replaced with air, and this returns `true`. Items
+  /// without a [`max_durability`](Type::max_durability) are not affected.
+  pub fn add_damage(&mut self, amount: u16) -> bool {
+    let max = match self.item.max_durability() {
+      Some(max) => max,
+      None => return false,
+    };
+    self.damage = self.damage.saturating_add(amount);
+    if self.damage >= max {
+      *self = Stack::empty();
+      true
+    } else {
+      false
+    }
+  }
+
   /// Returns true if this item stack is considered "empty". This is true
   /// whenever the type is Air, or the count is zero.
   pub fn is_empty(&self) -> bool { self.item == Type::Air }
 
+  /// Parses an item stack from vanilla NBT data. This is the format used for
+  /// items in chests and other containers. Returns `None` if the item id is
+  /// missing, or if it isn't a known item.
+  pub fn from_nbt(nbt: &HashMap<String, Tag>) -> Option<Stack> {
+    let id = nbt.get("id")?.as_string()?;
+    let ty = Type::from_str(id.strip_prefix("minecraft:").unwrap_or(id)).ok()?;
+    let count = nbt.get("Count").and_then(Tag::as_byte).unwrap_or(1);
+    let damage = match nbt.get("tag") {
+      Some(Tag::Compound(tag)) => tag.get("Damage").and_then(Tag::as_int).unwrap_or(0),
+      _ => 0,
+    };
+    Some(Stack::new(ty).with_amount(count.max(0) as u8).with_damage(damage.max(0) as u16))
+  }
+
+  /// Converts this item stack into vanilla NBT data. This is the inverse of
+  /// [`from_nbt`](Self::from_nbt). The slot is not included, as that depends
+  /// on the container this item is in.
+  pub fn to_nbt(&self) -> HashMap<String, Tag> {
+    let mut nbt: HashMap<String, Tag> = [
+      ("id".into(), Tag::String(format!("minecraft:{}", self.item.to_str()))),
+      ("Count".into(), Tag::Byte(self.amount() as i8)),
+    ]
+    .into_iter()
+    .collect();
+    if self.damage > 0 {
+      nbt.insert("tag".into(), Tag::compound(&[("Damage", Tag::Int(self.damage.into()))]));
+    }
+    nbt
+  }
+
   pub fn to_item(&self) -> Item {
     Item {
       id:     self.item().id() as i32,
       count:  self.amount(),
-      damage: 0,
-      nbt:    NBT::empty(""),
+      // 1.8-1.12 clients use this for durability. Newer clients use the NBT tag.
+      damage: self.damage as i16,
+      nbt:    if self.damage > 0 {
+        NBT::new("", Tag::compound(&[("Damage", Tag::Int(self.damage.into()))]))
+      } else {
+        NBT::empty("")
+      },
     }
   }
 }
@@ -133,4 +204,38 @@ mod tests {
     );
     item_eq(Stack::new(Type::Stone), Item { id: 1, count: 1, damage: 0, nbt: NBT::empty("") });
   }
+
+  #[test]
+  fn test_from_nbt() {
+    let nbt = Tag::compound(&[
+      ("id", Tag::String("minecraft:stone".into())),
+      ("Count", Tag::Byte(12)),
+      ("Slot", Tag::Byte(3)),
+    ]);
+    assert_eq!(
+      Stack::from_nbt(nbt.unwrap_compound()),
+      Some(Stack::new(Type::Stone).with_amount(12))
+    );
+    let nbt = Tag::compound(&[("id", Tag::String("minecraft:not_an_item".into()))]);
+    assert_eq!(Stack::from_nbt(nbt.unwrap_compound()), None);
+  }
+
+  #[test]
+  fn test_to_nbt() {
+    let stack = Stack::new(Type::Stone).with_amount(12);
+    assert_eq!(Stack::from_nbt(&stack.to_nbt()), Some(stack));
+  }
+
+  #[test]
+  fn test_damage() {
+    let mut stack = Stack::new(Type::IronHelmet);
+    assert!(!stack.add_damage(100));
+    assert_eq!(stack.damage(), 100);
+    assert!(stack.add_damage(100));
+    assert_eq!(stack, Stack::empty());
+
+    let mut stack = Stack::new(Type::Stone);
+    assert!(!stack.add_damage(100));
+    assert_eq!(stack.damage(), 0);
+  }
 }
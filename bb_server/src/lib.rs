@@ -31,7 +31,7 @@ pub fn generate_panda_docs() {
   use std::sync::Arc;
 
   info!("generating panda docs...",);
-  let plugin = PandaPlugin::new(0, "".into(), Arc::new(WorldManager::new(false)));
+  let plugin = PandaPlugin::new("".into(), Arc::new(WorldManager::new(false)));
   let mut pd = Panda::new();
   plugin.add_builtins(&mut pd);
   plugin.generate_docs(&pd);
@@ -2,9 +2,13 @@
 extern crate log;
 
 use bb_common::config::Config;
-use bb_server::{net::ConnectionManager, rcon::RCon, world::WorldManager};
+use bb_server::{
+  net::ConnectionManager,
+  rcon::RCon,
+  world::{parse_export_version, WorldManager},
+};
 use clap::Parser;
-use std::{sync::Arc, thread};
+use std::{path::Path, sync::Arc, thread};
 
 #[derive(Parser, Debug)]
 #[clap(about, version, author)]
@@ -23,6 +27,11 @@ struct Args {
   /// the existing config.
   #[clap(long)]
   write_default_config: bool,
+
+  /// Exports the world to a vanilla world in the `export` folder, and then
+  /// exits. The value is the version to export for, such as `1.12.2`.
+  #[clap(long, value_name = "VERSION")]
+  export: Option<String>,
 }
 
 // #[derive(Clone)]
@@ -94,7 +103,22 @@ fn main() {
 
   let wm = Arc::new(WorldManager::new_with_config(config));
   wm.stop_on_ctrlc();
-  wm.add_world();
+  wm.load_worlds();
+
+  if let Some(name) = args.export {
+    let ver = match parse_export_version(&name) {
+      Some(v) => v,
+      None => {
+        error!("invalid export version: {name}");
+        return;
+      }
+    };
+    if let Err(e) = wm.default_world().export_to_disk(Path::new("export"), ver) {
+      error!("could not export world: {e}");
+    }
+    return;
+  }
+
   wm.load_plugins();
   wm.default_world().init();
 
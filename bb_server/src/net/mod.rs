@@ -1,7 +1,8 @@
 use crate::{player::Player, world::WorldManager};
 use bb_common::{
+  math::ChunkPos,
   net::{cb, sb},
-  util::{JoinInfo, ThreadPool},
+  util::{JoinInfo, JoinMode, ServerStatus, ThreadPool},
   version::ProtocolVersion,
 };
 use bb_transfer::{
@@ -30,7 +31,7 @@ pub mod packet;
 pub(crate) mod serialize;
 
 #[cfg(test)]
-mod tests;
+pub(crate) mod tests;
 
 pub struct Connection {
   stream: TcpStream,
@@ -38,8 +39,8 @@ pub struct Connection {
   closed: AtomicBool,
 
   /// Sending on this will send a packet to the client.
-  tx:    Sender<cb::Packet>,
-  rx:    Receiver<cb::Packet>,
+  tx:    Sender<Outgoing>,
+  rx:    Receiver<Outgoing>,
   wake:  Sender<WakeEvent>,
   waker: Arc<Waker>,
   tok:   Token,
@@ -51,12 +52,68 @@ pub struct Connection {
 
 #[derive(Debug, Clone)]
 pub struct ConnSender {
-  tx:    Sender<cb::Packet>,
+  tx:    Sender<Outgoing>,
   wake:  Sender<WakeEvent>,
   waker: Arc<Waker>,
   tok:   Token,
 }
 
+/// Something queued to be sent to the proxy.
+#[derive(Debug, Clone)]
+pub(crate) enum Outgoing {
+  Packet(cb::Packet),
+  /// A chunk which has already been encoded. Only the position needs to be
+  /// written when this is sent.
+  Chunk(ChunkPos, Arc<EncodedChunk>),
+}
+
+/// A chunk packet that has already been encoded. The proxy converts chunks for
+/// each client version, so this encoding is the same for every client. The
+/// bytes before and after the position are stored separately, so that the
+/// same chunk can be sent at any position without serializing it again.
+#[derive(Debug)]
+pub(crate) struct EncodedChunk {
+  head: Vec<u8>,
+  tail: Vec<u8>,
+}
+
+impl EncodedChunk {
+  pub fn new(p: &cb::packet::Chunk) -> Self {
+    let mut packet = vec![];
+    cb::Packet::Chunk(p.clone()).write(&mut MessageWriter::new(&mut packet)).unwrap();
+    let mut pos = vec![];
+    MessageWriter::new(&mut pos).write(&p.pos).unwrap();
+    // These must be written in the same order as the fields of `cb::packet::Chunk`.
+    let mut tail = vec![];
+    let mut m = MessageWriter::new(&mut tail);
+    m.write(&p.full).unwrap();
+    m.write(&p.sections).unwrap();
+    m.write(&p.sky_light).unwrap();
+    m.write(&p.block_light).unwrap();
+    m.write(&p.biomes).unwrap();
+    let head = packet[..packet.len() - pos.len() - tail.len()].to_vec();
+    EncodedChunk { head, tail }
+  }
+
+  /// Writes this chunk at the given position into `out`, with a length prefix.
+  fn write(&self, pos: ChunkPos, out: &mut Vec<u8>) {
+    let mut pos_buf = [0; 16];
+    let mut m = MessageWriter::new(pos_buf.as_mut_slice());
+    m.write(&pos).unwrap();
+    let pos_len = m.index();
+
+    let mut prefix = [0; 5];
+    let mut m = MessageWriter::new(prefix.as_mut_slice());
+    m.write_u32((self.head.len() + pos_len + self.tail.len()).try_into().unwrap()).unwrap();
+    let prefix_len = m.index();
+
+    out.extend_from_slice(&prefix[..prefix_len]);
+    out.extend_from_slice(&self.head);
+    out.extend_from_slice(&pos_buf[..pos_len]);
+    out.extend_from_slice(&self.tail);
+  }
+}
+
 #[derive(Debug, Clone)]
 struct EventWrapper {
   pub is_readable: bool,
@@ -82,7 +139,7 @@ impl EventWrapper {
 
 impl ConnSender {
   #[cfg(test)]
-  pub(crate) fn mock(poll: &Poll) -> (Receiver<cb::Packet>, Receiver<WakeEvent>, Self) {
+  pub(crate) fn mock(poll: &Poll) -> (Receiver<Outgoing>, Receiver<WakeEvent>, Self) {
     const WAKE: Token = Token(0xfffffffe);
 
     let (tx, rx) = crossbeam_channel::bounded(2048);
@@ -101,8 +158,11 @@ impl ConnSender {
   ///
   /// This will panic if the waker thread used globally has been closed. The
   /// only way for this to close is if the network manager stops working.
-  pub fn send(&self, p: impl Into<cb::Packet>) {
-    if let Ok(()) = self.tx.send(p.into()) {
+  pub fn send(&self, p: impl Into<cb::Packet>) { self.send_outgoing(Outgoing::Packet(p.into())); }
+  /// Same as [`send`](Self::send), but this can also send chunks that have
+  /// already been encoded.
+  pub(crate) fn send_outgoing(&self, p: Outgoing) {
+    if let Ok(()) = self.tx.send(p) {
       self.wake.send(WakeEvent::Clientbound(self.tok)).unwrap();
       self.waker.wake().unwrap();
     }
@@ -157,7 +217,7 @@ impl Connection {
   /// This will panic if the waker thread used globally has been closed. The
   /// only way for this to close is if the network manager stops working.
   pub fn send(&self, p: cb::Packet) {
-    if let Ok(()) = self.tx.send(p) {
+    if let Ok(()) = self.tx.send(Outgoing::Packet(p)) {
       self.wake.send(WakeEvent::Clientbound(self.tok)).unwrap();
       self.waker.wake().unwrap();
     }
@@ -191,7 +251,11 @@ impl Connection {
   fn try_send(&mut self) -> io::Result<()> {
     loop {
       match self.rx.try_recv() {
-        Ok(p) => self.send_to_client(p)?,
+        Ok(Outgoing::Packet(p)) => self.send_to_client(p)?,
+        Ok(Outgoing::Chunk(pos, c)) => {
+          c.write(pos, &mut self.outgoing);
+          self.try_flush()?;
+        }
         Err(TryRecvError::Empty) => break,
         Err(_e) => unreachable!(),
       }
@@ -214,6 +278,24 @@ impl Connection {
     self.try_flush()
   }
 
+  /// Sends the status of the server to the proxy. This is the only message
+  /// sent on a status connection, so the connection should be closed after
+  /// this.
+  fn send_status(&mut self, status: &ServerStatus) -> io::Result<()> {
+    let mut m = MessageWriter::new(self.garbage.as_mut_slice());
+    m.write(status).unwrap();
+    let len = m.index();
+
+    let mut prefix = [0; 5];
+    let mut m = MessageWriter::new(prefix.as_mut_slice());
+    m.write_u32(len.try_into().unwrap()).unwrap();
+    let prefix_len = m.index();
+
+    self.outgoing.extend_from_slice(&prefix[..prefix_len]);
+    self.outgoing.extend_from_slice(&self.garbage[..len]);
+    self.try_flush()
+  }
+
   fn try_flush(&mut self) -> io::Result<()> {
     while !self.outgoing.is_empty() {
       let n = match self.stream.write(&self.outgoing) {
@@ -526,6 +608,18 @@ impl ConnectionManager {
             // The player must be created after we drop the `conn.lock()`, so that sending
             // login packets doesn't deadlock.
             if let Some(new_conn) = new_conn {
+              // The proxy only wants the server status, so we reply and close the
+              // connection, without creating a player.
+              if matches!(new_conn.info.mode, JoinMode::Status) {
+                let status = wm.status();
+                if let Some(player) = c.read().get(&token) {
+                  if let Err(e) = player.conn.lock().send_status(&status) {
+                    warn!("could not send status to proxy: {e}");
+                  }
+                }
+                c.write().remove(&token);
+                return false;
+              }
               let new_player = wm.new_player(new_conn.sender, new_conn.info);
               {
                 let mut wl = c.write();
@@ -73,12 +73,13 @@ pub(crate) fn handle(wm: &Arc<WorldManager>, mut player: &Arc<Player>, p: sb::Pa
             sb::DigStatus::Finish => player.finish_digging(pos),
           },
           GameMode::Creative => {
-            if let Ok(looking_at) = player.world().get_block(pos) {
+            let world = player.world();
+            if let Ok(looking_at) = world.get_block(pos) {
               let click = BlockClick {
                 player,
                 face,
                 dir: player.look_as_vec(),
-                block: Block::new(player.world(), pos, looking_at.ty()),
+                block: Block::new(&world, pos, looking_at.ty()),
                 cursor: FPos::new(0.0, 0.0, 0.0),
               };
               let inv = player.lock_inventory();
@@ -95,6 +96,12 @@ pub(crate) fn handle(wm: &Arc<WorldManager>, mut player: &Arc<Player>, p: sb::Pa
                 }
               }
             }
+            if let Ok(ty) = player.world().get_block(pos) {
+              if !player.world().events().block_break(player.clone(), pos, ty.ty()) {
+                player.sync_block_at(pos);
+                return;
+              }
+            }
             // Make sure to sync this block if the world is locked, or if the position is
             // invalid.
             if matches!(player.world().set_kind(pos, block::Kind::Air), Ok(false) | Err(_)) {
@@ -124,9 +131,17 @@ pub(crate) fn handle(wm: &Arc<WorldManager>, mut player: &Arc<Player>, p: sb::Pa
       player.lock_inventory().click_window(slot.into(), mode, allow);
     }
     sb::Packet::ChangeHeldItem { slot } => {
+      player.stop_using();
       player.lock_inventory().set_selected(slot);
     }
+    sb::Packet::ReleaseItem => player.stop_using(),
+    sb::Packet::Respawn => player.respawn(),
+    sb::Packet::SwapHands => {
+      player.stop_using();
+      player.lock_inventory().swap_hands();
+    }
     sb::Packet::UseItem { hand } => {
+      player.start_using(hand);
       wm.events().interact(
         player,
         hand,
@@ -144,13 +159,14 @@ pub(crate) fn handle(wm: &Arc<WorldManager>, mut player: &Arc<Player>, p: sb::Pa
       */
 
       dbg!(cursor);
-      match player.world().get_block(pos) {
+      let world = player.world();
+      match world.get_block(pos) {
         Ok(looking_at) => {
           let click = BlockClick {
             player,
             face,
             dir: player.look_as_vec(),
-            block: Block::new(player.world(), pos, looking_at.ty()),
+            block: Block::new(&world, pos, looking_at.ty()),
             cursor,
           };
           if !player.is_crouching() {
@@ -164,9 +180,9 @@ pub(crate) fn handle(wm: &Arc<WorldManager>, mut player: &Arc<Player>, p: sb::Pa
 
           // TODO: Data generator should store which items are blockitems, and what blocks
           // they place.
-          let mut inv = player.lock_inventory();
+          let inv = player.lock_inventory();
           let stack = inv.main_hand();
-          let item_data = player.world().item_converter().get_data(stack.item());
+          let item_data = world.item_converter().get_data(stack.item());
           let kind = block::Kind::from_str(item_data.name()).unwrap_or_else(|_| {
             player.send_message(Chat::new(format!("ah! {} is confusing", item_data.name())));
             block::Kind::Air
@@ -184,19 +200,28 @@ pub(crate) fn handle(wm: &Arc<WorldManager>, mut player: &Arc<Player>, p: sb::Pa
             pos += face;
           }
 
+          // Plugins may want to access the inventory, so we can't hold onto it while
+          // waiting for a reply.
+          let idx = inv.selected_index() as u32;
+          drop(inv);
+          if !player.world().events().block_place(player.clone(), pos, ty) {
+            // The client has already placed the block and used the item, so we need to
+            // roll both of those back.
+            player.sync_block_at(pos);
+            player.lock_inventory().hotbar().sync_raw(idx);
+            return;
+          }
+
           match player.world().set_block(pos, ty) {
             Ok(_) => {
               if player.game_mode() != GameMode::Creative {
-                let idx = inv.selected_index() as u32;
+                let mut inv = player.lock_inventory();
                 let stack = inv.hotbar_mut().get_raw_mut(idx).unwrap();
                 if stack.amount() >= 1 {
                   stack.set_amount(stack.amount() - 1);
                   inv.hotbar().sync_raw(idx);
                 }
               }
-              drop(inv);
-              // TODO: Handle plugins cancelling this place.
-              player.world().events().block_place(player.clone(), pos, ty);
             }
             Err(e) => {
               player.send_hotbar(Chat::new(e.to_string()));
@@ -211,21 +236,23 @@ pub(crate) fn handle(wm: &Arc<WorldManager>, mut player: &Arc<Player>, p: sb::Pa
         }
       };
     }
-    sb::Packet::PlayerPos { x, y, z, .. } => {
+    sb::Packet::PlayerPos { x, y, z, on_ground } => {
       player.set_next_pos(x, y, z);
+      player.set_on_ground(on_ground);
     }
-    sb::Packet::PlayerPosLook { x, y, z, yaw, pitch, .. } => {
+    sb::Packet::PlayerPosLook { x, y, z, yaw, pitch, on_ground } => {
       player.set_next_pos(x, y, z);
       player.set_next_look(yaw, pitch);
+      player.set_on_ground(on_ground);
     }
-    sb::Packet::PlayerLook { yaw, pitch, .. } => {
+    sb::Packet::PlayerLook { yaw, pitch, on_ground } => {
       player.set_next_look(yaw, pitch);
+      player.set_on_ground(on_ground);
     }
     sb::Packet::Flying { flying } => {
       player.set_flying_no_send(flying);
     }
-    // Just contains on_ground
-    sb::Packet::PlayerOnGround { .. } => {}
+    sb::Packet::PlayerOnGround { on_ground } => player.set_on_ground(on_ground),
     sb::Packet::PlayerCommand { command } => player.handle_command(command),
     sb::Packet::Animation { hand } => player.send_to_in_view(cb::packet::Animation {
       eid:  player.eid(),
@@ -235,11 +262,9 @@ pub(crate) fn handle(wm: &Arc<WorldManager>, mut player: &Arc<Player>, p: sb::Pa
       if let Some(crouching) = sneaking {
         player.set_crouching(crouching);
       }
-      if let Some(ent) = player.world().entities().get(eid) {
-        match action {
-          sb::UseEntityAction::Attack => player.attack(ent),
-          _ => warn!("todo: action {action:?}"),
-        }
+      match action {
+        sb::UseEntityAction::Attack => player.attack(eid),
+        _ => warn!("todo: action {action:?}"),
       }
     }
     sb::Packet::WindowClose { wid: _ } => player.lock_inventory().close_window(),
@@ -1,20 +1,38 @@
-use super::WakeEvent;
+use super::{EncodedChunk, Outgoing, WakeEvent};
 use crate::{
+  entity::EntityRef,
+  math::Vec3,
   net::{packet, ConnSender},
-  player::Player,
+  player::{DamageCause, Player},
   world::WorldManager,
 };
 use bb_common::{
-  math::FPos,
+  math::{ChunkPos, FPos},
   net::{cb, sb},
-  util::{JoinInfo, JoinMode, UUID},
+  util::{GameMode, JoinInfo, JoinMode, UUID},
   version::ProtocolVersion,
 };
+use bb_transfer::{MessageRead, MessageReader};
 use crossbeam_channel::Receiver;
 use std::sync::Arc;
 
+/// Converts a queued packet back into the packet the proxy would receive.
+fn decode(p: Outgoing) -> cb::Packet {
+  match p {
+    Outgoing::Packet(p) => p,
+    Outgoing::Chunk(pos, c) => {
+      let mut data = vec![];
+      c.write(pos, &mut data);
+      let mut m = MessageReader::new(&data);
+      let len = m.read_u32().unwrap() as usize;
+      assert_eq!(m.index() + len, data.len());
+      cb::Packet::read(&mut m).unwrap()
+    }
+  }
+}
+
 pub struct TestHandler {
-  rx:      Receiver<cb::Packet>,
+  rx:      Receiver<Outgoing>,
   wake_rx: Receiver<WakeEvent>,
   wm:      Arc<WorldManager>,
   player:  Arc<Player>,
@@ -35,16 +53,19 @@ impl TestHandler {
     let poll = mio::Poll::new().unwrap();
     let (rx, wake_rx, sender) = ConnSender::mock(&poll);
     let info = JoinInfo {
-      mode:     JoinMode::New,
-      username: "macmv".into(),
-      uuid:     UUID::from_u128(0),
-      ver:      ProtocolVersion::V1_8.id(),
+      mode:       JoinMode::New,
+      username:   "macmv".into(),
+      uuid:       UUID::from_u128(0),
+      ver:        ProtocolVersion::V1_8.id(),
+      properties: vec![],
+      ip:         None,
     };
     let player = wm.new_player(sender, info);
     TestHandler { rx, wake_rx, wm, player }
   }
   pub fn handle(&self, p: sb::Packet) { packet::handle(&self.wm, &self.player, p); }
   pub fn player(&self) -> &Arc<Player> { &self.player }
+  pub fn wm(&self) -> &Arc<WorldManager> { &self.wm }
   pub fn clear(&self) {
     while let Ok(_) = self.rx.try_recv() {}
     while let Ok(_) = self.wake_rx.try_recv() {}
@@ -52,7 +73,7 @@ impl TestHandler {
   pub fn assert_empty(&self) {
     if !self.rx.is_empty() {
       while let Ok(m) = self.rx.try_recv() {
-        info!("packet: {m:?}");
+        info!("packet: {:?}", decode(m));
       }
       panic!("got packets, but expected none");
     }
@@ -60,7 +81,7 @@ impl TestHandler {
   pub fn assert_sent(&self, expected_packets: &[cb::Packet]) {
     let mut actual_packets = vec![];
     while let Ok(p) = self.rx.try_recv() {
-      actual_packets.push(p);
+      actual_packets.push(decode(p));
     }
     let mut equal = actual_packets.len() == expected_packets.len();
     for p in expected_packets {
@@ -112,3 +133,49 @@ fn test_move_packets() {
     handler.assert_empty();
   }
 }
+
+#[test]
+fn test_unload_world() {
+  let handler = TestHandler::new();
+  let player = handler.player();
+  let world = player.world();
+  let center = player.pos().block().chunk();
+  let d = player.view_distance() as i32;
+  assert_eq!(world.view_count(center), 1);
+  player.unload_world();
+  for x in -d..=d {
+    for z in -d..=d {
+      assert_eq!(world.view_count(center + ChunkPos::new(x, z)), 0);
+    }
+  }
+}
+
+#[test]
+fn test_respawn() {
+  let handler = TestHandler::new();
+  let player = handler.player();
+  let world = player.world();
+  let center = player.pos().block().chunk();
+  player.set_game_mode(GameMode::Survival);
+  let zero = Vec3::new(0.0, 0.0, 0.0);
+  assert!(EntityRef::Player(player.clone()).damage_by(DamageCause::Generic, 100.0, false, zero));
+  assert!(!player.alive());
+  handler.clear();
+  player.respawn();
+  assert!(player.alive());
+  // Each chunk in view should only be counted once, even though the player
+  // unloaded and reloaded all of them.
+  assert_eq!(world.view_count(center), 1);
+}
+
+#[test]
+fn test_encoded_chunk() {
+  let handler = TestHandler::new();
+  let world = handler.player().world();
+  let mut packet = world.serialize_chunk(ChunkPos::new(0, 0));
+  let encoded = Arc::new(EncodedChunk::new(&packet));
+  for pos in [ChunkPos::new(0, 0), ChunkPos::new(3, -2), ChunkPos::new(-100000, 100000)] {
+    packet.pos = pos;
+    assert_eq!(decode(Outgoing::Chunk(pos, encoded.clone())), cb::Packet::Chunk(packet.clone()));
+  }
+}
@@ -0,0 +1,224 @@
+//! Saves and loads player data. Each player is stored in a separate file in
+//! the `players` directory of the default world, named after their UUID. The
+//! data is encoded with `bb_transfer`, and then gzipped, just like regions are.
+
+use super::{Player, PlayerAbilities, PlayerFood, PlayerHealth};
+use crate::item::Stack;
+use bb_common::{
+  flate2::{read::GzDecoder, write::GzEncoder, Compression},
+  math::FPos,
+  util::{GameMode, UUID},
+};
+use bb_macros::Transfer;
+use bb_transfer::{MessageReader, MessageWriter};
+use std::{
+  fs,
+  fs::File,
+  io::{Read, Write},
+  path::{Path, PathBuf},
+};
+
+/// Everything about a player that is kept between sessions.
+#[derive(Transfer, Debug, Clone)]
+pub(crate) struct PlayerData {
+  #[must_exist]
+  pos:            FPos,
+  #[must_exist]
+  yaw:            f32,
+  #[must_exist]
+  pitch:          f32,
+  #[must_exist]
+  game_mode:      GameMode,
+  #[must_exist]
+  health:         f32,
+  #[must_exist]
+  absorption:     f32,
+  #[must_exist]
+  food:           i32,
+  #[must_exist]
+  saturation:     f32,
+  #[must_exist]
+  flying_allowed: bool,
+  #[must_exist]
+  flying:         bool,
+  #[must_exist]
+  fly_speed:      f32,
+  #[must_exist]
+  walk_speed:     f32,
+  /// See [`PlayerInventory::saved_items`](super::PlayerInventory::saved_items).
+  #[must_exist]
+  items:          Vec<Stack>,
+  #[must_exist]
+  selected_index: u8,
+  /// The name of the world the player was in. This is empty for players saved
+  /// before multiple worlds were supported.
+  world:          String,
+  /// The player's food exhaustion. This is zero for players saved before
+  /// hunger was implemented.
+  exhaustion:     f32,
+}
+
+impl PlayerData {
+  /// Returns the name of the world this player was in when they were saved.
+  pub(crate) fn world(&self) -> &str { &self.world }
+
+  /// Loads the data for the given player from `dir`. Returns `None` if this
+  /// player has never joined before, or if their data could not be read.
+  pub(crate) fn load(dir: &Path, id: UUID) -> Option<PlayerData> {
+    let path = fname(dir, id);
+    if !path.exists() {
+      return None;
+    }
+    debug!("loading player from {}", path.display());
+    let mut data = vec![];
+    let res = File::open(&path).and_then(|f| GzDecoder::new(f).read_to_end(&mut data));
+    if let Err(e) = res {
+      warn!("couldn't read player data at {}: {e}", path.display());
+      return None;
+    }
+    match MessageReader::new(&data).read() {
+      Ok(v) => Some(v),
+      Err(e) => {
+        warn!("couldn't parse player data at {}: {e}", path.display());
+        None
+      }
+    }
+  }
+
+  /// Writes this data to disk, overwriting any existing data for this player.
+  fn save(&self, dir: &Path, id: UUID) {
+    let mut data = vec![];
+    MessageWriter::<&mut Vec<u8>>::new(&mut data).write(self).unwrap();
+
+    let path = fname(dir, id);
+    debug!("saving player to {}", path.display());
+    let res = fs::create_dir_all(path.parent().unwrap()).and_then(|_| {
+      let mut encoder = GzEncoder::new(File::create(&path)?, Compression::default());
+      encoder.write_all(&data)?;
+      encoder.finish().map(|_| ())
+    });
+    if let Err(e) = res {
+      warn!("couldn't save player data at {}: {e}", path.display());
+    }
+  }
+}
+
+fn fname(dir: &Path, id: UUID) -> PathBuf { dir.join(format!("{}.bbp", id.as_dashed_str())) }
+
+impl Player {
+  /// Returns all the data about this player that should be saved when they
+  /// leave.
+  pub(crate) fn data(&self) -> PlayerData {
+    let (pos, yaw, pitch) = {
+      let pos = self.pos.lock();
+      (pos.curr, pos.yaw, pos.pitch)
+    };
+    let health = self.health.lock().clone();
+    let food = self.food.lock().clone();
+    let abilities = self.abilities.lock().clone();
+    let inv = self.lock_inventory();
+    PlayerData {
+      pos,
+      yaw,
+      pitch,
+      game_mode: self.game_mode(),
+      health: health.health,
+      absorption: health.absorption,
+      food: food.food,
+      saturation: food.saturation,
+      flying_allowed: abilities.flying_allowed,
+      flying: abilities.flying,
+      fly_speed: abilities.fly_speed,
+      walk_speed: abilities.walk_speed,
+      items: inv.saved_items(),
+      selected_index: inv.selected_index(),
+      world: self.world().name().into(),
+      exhaustion: food.exhaustion,
+    }
+  }
+
+  /// Overwrites this player's state with the given data. This will not send
+  /// any packets, so it should only be called before the player has been
+  /// initialized.
+  pub(crate) fn load_data(&self, data: PlayerData) {
+    {
+      let mut pos = self.pos.lock();
+      pos.curr = data.pos;
+      pos.prev = data.pos;
+      pos.next = data.pos;
+      pos.yaw = data.yaw;
+      pos.pitch = data.pitch;
+      pos.next_yaw = data.yaw;
+      pos.next_pitch = data.pitch;
+    }
+    *self.game_mode.lock() = data.game_mode;
+    // `flying_allowed` can be changed with `/fly`, so we don't want to use the
+    // value from the game mode here.
+    let mut abilities = PlayerAbilities::new();
+    abilities.set_from_game_mode(data.game_mode);
+    abilities.flying_allowed = data.flying_allowed;
+    abilities.flying = data.flying;
+    abilities.fly_speed = data.fly_speed;
+    abilities.walk_speed = data.walk_speed;
+    *self.abilities.lock() = abilities;
+    *self.health.lock() =
+      PlayerHealth { health: data.health, absorption: data.absorption, hit_delay: 0 };
+    *self.food.lock() = PlayerFood {
+      food:       data.food,
+      saturation: data.saturation,
+      exhaustion: data.exhaustion,
+      tick_timer: 0,
+    };
+    self.lock_inventory().load_saved_items(data.items, data.selected_index);
+  }
+
+  /// Saves this player's data into `dir`. This is called when they leave,
+  /// and whenever the world is saved.
+  pub(crate) fn save(&self, dir: &Path) { self.data().save(dir, self.id()); }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::item;
+
+  #[test]
+  fn save_and_load() {
+    let dir = std::env::temp_dir().join(format!("bb-player-data-{}", std::process::id()));
+    let id = UUID::from_u128(0x1234);
+    let data = PlayerData {
+      pos:            FPos::new(1.5, 64.0, -3.25),
+      yaw:            90.0,
+      pitch:          -10.0,
+      game_mode:      GameMode::Adventure,
+      health:         12.0,
+      absorption:     2.0,
+      food:           15,
+      saturation:     3.5,
+      flying_allowed: true,
+      flying:         false,
+      fly_speed:      0.1,
+      walk_speed:     0.2,
+      items:          vec![Stack::empty(), Stack::new(item::Type::Stone).with_amount(32)],
+      selected_index: 4,
+      world:          "lobby".into(),
+      exhaustion:     1.25,
+    };
+    assert!(PlayerData::load(&dir, id).is_none());
+    data.save(&dir, id);
+    let loaded = PlayerData::load(&dir, id).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(loaded.pos, data.pos);
+    assert_eq!((loaded.yaw, loaded.pitch), (data.yaw, data.pitch));
+    assert_eq!(loaded.game_mode, data.game_mode);
+    assert_eq!((loaded.health, loaded.absorption), (data.health, data.absorption));
+    assert_eq!((loaded.food, loaded.saturation), (data.food, data.saturation));
+    assert_eq!(loaded.exhaustion, data.exhaustion);
+    assert!(loaded.flying_allowed && !loaded.flying);
+    assert_eq!((loaded.fly_speed, loaded.walk_speed), (data.fly_speed, data.walk_speed));
+    assert_eq!(loaded.items, data.items);
+    assert_eq!(loaded.selected_index, 4);
+    assert_eq!(loaded.world(), "lobby");
+  }
+}
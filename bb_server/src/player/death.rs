@@ -0,0 +1,262 @@
+//! Handles players dying, respawning, and taking damage from their
+//! surroundings (falling and the void).
+
+use super::{Player, PlayerFood, PlayerHealth};
+use crate::{block, entity, entity::EntityRef, item::Stack, math::Vec3, world::World, RNG};
+use bb_common::{
+  math::FPos,
+  metadata::Metadata,
+  net::cb,
+  util::{
+    chat::{Chat, Color},
+    Effect,
+  },
+};
+use rand::Rng;
+use std::sync::Arc;
+
+/// What caused a player to take damage. This is used to build the death
+/// message when a player dies.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum DamageCause {
+  /// Damage from a plugin, or anything else that doesn't have a more specific
+  /// cause.
+  Generic,
+  /// The player was attacked by another player. `name` is the username of
+  /// the attacker.
+  Player { eid: i32, name: String },
+  /// The player was attacked by a mob. `name` is the name of the mob, like
+  /// `Zombie`.
+  Mob { eid: i32, name: String },
+  /// The player hit the ground too hard.
+  Fall,
+  /// The player fell below the bottom of the world.
+  Void,
+  /// The player's food bar was empty.
+  Starvation,
+  /// Damage from instant damage or poison.
+  Magic,
+  /// Damage from the wither effect.
+  Wither,
+}
+
+impl DamageCause {
+  /// Returns the entity id of whoever dealt this damage, if it came from a
+  /// player or a mob.
+  pub fn attacker(&self) -> Option<i32> {
+    match self {
+      Self::Player { eid, .. } | Self::Mob { eid, .. } => Some(*eid),
+      _ => None,
+    }
+  }
+
+  /// Returns the death message for this cause. `name` is the username of the
+  /// player that died.
+  pub fn death_message(&self, name: &str) -> Chat {
+    let mut msg = Chat::empty();
+    msg.add(name).color(Color::BrightGreen);
+    match self {
+      Self::Generic => {
+        msg.add(" died").color(Color::Gray);
+      }
+      Self::Player { name: attacker, .. } => {
+        msg.add(" was slain by ").color(Color::Gray);
+        msg.add(attacker).color(Color::BrightGreen);
+      }
+      Self::Mob { name: mob, .. } => {
+        msg.add(" was slain by ").color(Color::Gray);
+        msg.add(mob).color(Color::Red);
+      }
+      Self::Fall => {
+        msg.add(" hit the ground too hard").color(Color::Gray);
+      }
+      Self::Void => {
+        msg.add(" fell out of the world").color(Color::Gray);
+      }
+      Self::Starvation => {
+        msg.add(" starved to death").color(Color::Gray);
+      }
+      Self::Magic => {
+        msg.add(" was killed by magic").color(Color::Gray);
+      }
+      Self::Wither => {
+        msg.add(" withered away").color(Color::Gray);
+      }
+    }
+    msg
+  }
+}
+
+impl Player {
+  /// Kills this player. This is called once the player's health reaches zero.
+  /// Plugins can cancel this, in which case the player is left with half a
+  /// heart.
+  ///
+  /// Unless the `keep-inventory` gamerule is set, this drops all of the
+  /// player's items where they died. If the `immediate-respawn` gamerule is
+  /// set, the player will be respawned right away.
+  pub(crate) fn die(self: &Arc<Self>, cause: DamageCause) {
+    let world = self.world();
+    let msg = cause.death_message(self.username());
+    if !world.events().player_die(self.clone(), &msg) {
+      self.lock_health().health = 1.0;
+      self.send_health();
+      return;
+    }
+    self.stop_using();
+    self.clear_effects();
+    // Plays the death animation for everyone else. The client will show the
+    // death screen on its own, as it has been sent a health of zero.
+    self.send_to_in_view(cb::packet::EntityStatus { eid: self.eid(), status: 3 });
+
+    if !world.gamerule("keep-inventory") {
+      let items = self.lock_inventory().take_all();
+      let pos = self.pos() + FPos::new(0.0, 1.0, 0.0);
+      for stack in items {
+        drop_item(&world, pos, stack);
+      }
+    }
+    if world.gamerule("show-death-messages") {
+      world.world_manager().broadcast(msg);
+    }
+    world.events().player_death(self.clone());
+
+    if world.gamerule("immediate-respawn") {
+      self.respawn();
+    }
+  }
+
+  /// Respawns this player at the world spawn point. This is called when the
+  /// client clicks respawn on the death screen. Does nothing if the player is
+  /// still alive.
+  pub fn respawn(self: &Arc<Self>) {
+    if self.alive() {
+      return;
+    }
+    let world = self.world();
+    let pos = world.spawn_point();
+
+    // Removes the dead body for everyone else. `player_spawn` will add it back.
+    self.send_to_in_view(cb::packet::RemoveEntities { eids: vec![self.eid()] });
+    self.unload_world();
+    {
+      let mut p = self.pos.lock();
+      p.curr = pos;
+      p.prev = pos;
+      p.next = pos;
+      p.vel = Vec3::new(0.0, 0.0, 0.0);
+      p.fall_distance = 0.0;
+      p.dig_progress = None;
+    }
+    *self.health.lock() = PlayerHealth { health: 20.0, absorption: 0.0, hit_delay: 0 };
+    *self.food.lock() = PlayerFood::new();
+
+    // Respawning into the same dimension will reset the client's player, and
+    // close the death screen.
+    self.send(cb::packet::Respawn {
+      game_mode:    self.game_mode(),
+      dimension:    0, // Overworld
+      world_name:   world.dimension_name(),
+      level_type:   "default".into(),
+      difficulty:   1, // Normal
+      world_height: world.height(),
+      world_min_y:  world.min_y(),
+    });
+    world.player_spawn(self);
+  }
+
+  /// Applies fall damage and void damage. This is called at the end of every
+  /// tick. `fell` is the distance the player fell, if they landed on this tick.
+  pub(super) fn environment_damage(self: &Arc<Self>, fell: Option<f64>, pos: FPos) {
+    let world = self.world();
+    if pos.y() < f64::from(world.min_y() - 64) {
+      EntityRef::Player(self.clone()).damage_by(
+        DamageCause::Void,
+        4.0,
+        false,
+        Vec3::new(0.0, 0.0, 0.0),
+      );
+      return;
+    }
+    if let Some(dist) = fell {
+      let damage = fall_damage(dist, self.effect_amplifier(Effect::JumpBoost));
+      if damage > 0.0 && world.gamerule("fall-damage") {
+        EntityRef::Player(self.clone()).damage_by(
+          DamageCause::Fall,
+          damage,
+          false,
+          Vec3::new(0.0, 0.0, 0.0),
+        );
+      }
+    } else if self.pos.lock().fall_distance > 0.0 {
+      // Falling into water or lava will stop all fall damage.
+      if matches!(world.get_kind(pos.block()), Ok(block::Kind::Water | block::Kind::Lava)) {
+        self.pos.lock().fall_distance = 0.0;
+      }
+    }
+  }
+}
+
+/// Returns the damage for falling `dist` blocks. Players can fall 3 blocks
+/// safely, and jump boost lets them fall one block further per level.
+fn fall_damage(dist: f64, jump_boost: Option<u8>) -> f32 {
+  let safe = 3.0 + jump_boost.map_or(0.0, |a| f64::from(a) + 1.0);
+  if dist > safe {
+    (dist - safe).ceil() as f32
+  } else {
+    0.0
+  }
+}
+
+/// Drops the given stack at `pos`, with a small random velocity. Empty stacks
+/// are ignored.
+fn drop_item(world: &Arc<World>, pos: FPos, stack: Stack) {
+  if stack.is_empty() {
+    return;
+  }
+  let mut meta = Metadata::new();
+  meta.set_item(8, stack.to_item());
+  let eid = world.summon_meta(entity::Type::Item, pos, meta);
+  let vel = RNG.with(|rng| {
+    let mut rng = rng.borrow_mut();
+    Vec3::new(rng.gen_range(-0.2..0.2), rng.gen_range(0.1..0.3), rng.gen_range(-0.2..0.2))
+  });
+  if let Some(e) = world.entities().get(eid) {
+    e.set_vel(vel);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn death_messages() {
+    let msg = |cause: DamageCause| cause.death_message("macmv").to_plain();
+    assert_eq!(msg(DamageCause::Generic), "macmv died");
+    let notch = DamageCause::Player { eid: 1, name: "Notch".into() };
+    let zombie = DamageCause::Mob { eid: 2, name: "Zombie".into() };
+    assert_eq!((notch.attacker(), zombie.attacker()), (Some(1), Some(2)));
+    assert_eq!(DamageCause::Fall.attacker(), None);
+    assert_eq!(msg(notch), "macmv was slain by Notch");
+    assert_eq!(msg(zombie), "macmv was slain by Zombie");
+    assert_eq!(msg(DamageCause::Fall), "macmv hit the ground too hard");
+    assert_eq!(msg(DamageCause::Void), "macmv fell out of the world");
+    assert_eq!(msg(DamageCause::Starvation), "macmv starved to death");
+    assert_eq!(msg(DamageCause::Magic), "macmv was killed by magic");
+    assert_eq!(msg(DamageCause::Wither), "macmv withered away");
+  }
+
+  #[test]
+  fn fall_distance() {
+    assert_eq!(fall_damage(3.0, None), 0.0);
+    assert_eq!(fall_damage(3.5, None), 1.0);
+    assert_eq!(fall_damage(10.0, None), 7.0);
+    // Jump boost I makes 4 blocks safe, and jump boost II makes 5 blocks safe.
+    assert_eq!(fall_damage(4.0, Some(0)), 0.0);
+    assert_eq!(fall_damage(4.5, Some(0)), 1.0);
+    assert_eq!(fall_damage(5.0, Some(1)), 0.0);
+    assert_eq!(fall_damage(10.0, Some(1)), 5.0);
+  }
+}
@@ -0,0 +1,175 @@
+//! Handles status effects on players, such as speed or regeneration.
+
+use super::{DamageCause, Player};
+use crate::{
+  entity,
+  entity::{EffectInstance, EntityRef},
+  math::Vec3,
+};
+use bb_common::{metadata::Metadata, net::cb, util::Effect};
+use std::sync::Arc;
+
+impl Player {
+  /// Returns `true` if the player has the given effect.
+  pub fn has_effect(&self, effect: Effect) -> bool { self.effects.lock().get(effect).is_some() }
+  /// Returns the amplifier of the given effect, if the player has it. An
+  /// amplifier of 0 is level I.
+  pub fn effect_amplifier(&self, effect: Effect) -> Option<u8> {
+    self.effects.lock().amplifier(effect)
+  }
+  /// Returns all of the player's active effects.
+  pub fn effects(&self) -> Vec<EffectInstance> { self.effects.lock().iter().copied().collect() }
+
+  /// Gives the player an effect. If the player already has a stronger version
+  /// of this effect, nothing happens. Instant effects, like instant health,
+  /// are applied right away, and are never stored.
+  pub fn add_effect(self: &Arc<Self>, inst: EffectInstance) {
+    if inst.effect().is_instant() {
+      self.apply_instant(inst);
+      return;
+    }
+    if !self.effects.lock().insert(inst) {
+      return;
+    }
+    match inst.effect() {
+      Effect::Absorption => {
+        self.health.lock().absorption = 4.0 * inst.level() as f32;
+      }
+      Effect::Invisibility | Effect::Glowing => self.send_status_byte(),
+      _ => {}
+    }
+    self.send_all_in_view(inst.to_packet(self.eid()));
+  }
+
+  /// Removes the given effect from the player. Returns `true` if the player
+  /// had the effect.
+  pub fn remove_effect(&self, effect: Effect) -> bool {
+    if self.effects.lock().remove(effect).is_none() {
+      return false;
+    }
+    self.effect_removed(effect);
+    true
+  }
+
+  /// Removes all effects from the player. This happens when they die, or
+  /// drink milk.
+  pub fn clear_effects(&self) {
+    let removed = self.effects.lock().clear();
+    for inst in removed {
+      self.effect_removed(inst.effect());
+    }
+  }
+
+  /// Returns the packets needed to show this player's effects to a client that
+  /// just spawned them in.
+  pub(crate) fn effect_packets(&self) -> Vec<cb::packet::EntityEffect> {
+    self.effects.lock().iter().map(|e| e.to_packet(self.eid())).collect()
+  }
+
+  /// Returns the effect bits of the status byte. See
+  /// [`status_byte`](Self::status_byte).
+  pub(super) fn effect_status_bits(&self) -> i8 {
+    let effects = self.effects.lock();
+    let mut bits = 0;
+    if effects.get(Effect::Invisibility).is_some() {
+      bits |= 0x20;
+    }
+    if effects.get(Effect::Glowing).is_some() {
+      bits |= 0x40;
+    }
+    bits
+  }
+
+  /// Counts down the player's effects, and applies regeneration, poison,
+  /// wither, and hunger. This is called every tick.
+  pub(super) fn tick_effects(self: &Arc<Self>) {
+    let (expired, active) = {
+      let mut effects = self.effects.lock();
+      if effects.is_empty() {
+        return;
+      }
+      let expired = effects.tick();
+      (expired, effects.iter().copied().collect::<Vec<_>>())
+    };
+    for effect in expired {
+      self.effect_removed(effect);
+    }
+    if !self.damageable() {
+      return;
+    }
+    for inst in active {
+      // Higher levels apply these effects more often.
+      let interval = |base: u32| (base >> inst.amplifier().min(31)).max(1);
+      match inst.effect() {
+        Effect::Regeneration if inst.duration() % interval(50) == 0 => {
+          let healed = {
+            let mut health = self.health.lock();
+            let healed = health.health < 20.0;
+            health.health = (health.health + 1.0).min(20.0);
+            healed
+          };
+          if healed {
+            self.send_health();
+          }
+        }
+        // Poison will never kill the player.
+        Effect::Poison if inst.duration() % interval(25) == 0 && self.health() > 1.0 => {
+          self.effect_damage(DamageCause::Magic, 1.0);
+        }
+        Effect::Wither if inst.duration() % interval(40) == 0 => {
+          self.effect_damage(DamageCause::Wither, 1.0);
+        }
+        Effect::Hunger => self.add_exhaustion(0.005 * inst.level() as f32),
+        _ => {}
+      }
+    }
+  }
+
+  fn apply_instant(self: &Arc<Self>, inst: EffectInstance) {
+    // Each level doubles the amount healed or damaged.
+    let scale = 2.0_f32.powi(i32::from(inst.amplifier()));
+    match inst.effect() {
+      Effect::InstantHealth => {
+        if !self.alive() {
+          return;
+        }
+        {
+          let mut health = self.health.lock();
+          health.health = (health.health + 4.0 * scale).min(20.0);
+        }
+        self.send_health();
+      }
+      Effect::InstantDamage => self.effect_damage(DamageCause::Magic, 6.0 * scale),
+      Effect::Saturation => {
+        let (food, saturation) = (self.food(), self.saturation());
+        let level = inst.level();
+        self.set_food(food + level as i32, saturation + level as f32 * 2.0);
+      }
+      _ => {}
+    }
+  }
+
+  fn effect_damage(self: &Arc<Self>, cause: DamageCause, amount: f32) {
+    EntityRef::Player(self.clone()).damage_by(cause, amount, false, Vec3::new(0.0, 0.0, 0.0));
+  }
+
+  /// Updates clients after an effect was removed.
+  fn effect_removed(&self, effect: Effect) {
+    match effect {
+      Effect::Absorption => self.health.lock().absorption = 0.0,
+      Effect::Invisibility | Effect::Glowing => self.send_status_byte(),
+      _ => {}
+    }
+    self.send_all_in_view(cb::packet::RemoveEntityEffect { eid: self.eid(), effect });
+  }
+
+  fn send_status_byte(&self) {
+    let mut meta = Metadata::new();
+    meta.set_byte(0, self.status_byte());
+    self.send_to_in_view(cb::packet::EntityMetadata {
+      eid: self.eid(),
+      ty: entity::Type::Player.id(),
+      meta,
+    });
+  }
+}
@@ -0,0 +1,308 @@
+//! Handles hunger, natural regeneration, starvation, and eating food.
+//!
+//! This follows the vanilla model: actions like sprinting and jumping add
+//! exhaustion, and every 4 points of exhaustion removes one saturation point,
+//! or one food point once saturation is empty. A full food bar regenerates
+//! health, and an empty one deals starvation damage.
+
+use super::{DamageCause, ItemUse, Player, PlayerFood, PlayerHealth};
+use crate::{block, entity::EntityRef, item, item::Stack, math::Vec3, RNG};
+use bb_common::{
+  net::cb,
+  util::{GameMode, Hand},
+};
+use rand::Rng;
+use std::sync::Arc;
+
+impl PlayerFood {
+  pub(super) fn new() -> Self {
+    PlayerFood { food: 20, saturation: 5.0, exhaustion: 0.0, tick_timer: 0 }
+  }
+
+  /// Adds the given amount of exhaustion. This does not check the `hunger`
+  /// gamerule.
+  pub(super) fn exhaust(&mut self, amount: f32) {
+    self.exhaustion = (self.exhaustion + amount).min(40.0);
+  }
+
+  /// Runs one tick of hunger and natural regeneration. `hunger` and `regen`
+  /// are the values of the `hunger` and `natural-regeneration` gamerules.
+  /// Returns `true` if the player should take starvation damage.
+  fn tick(&mut self, health: &mut PlayerHealth, hunger: bool, regen: bool) -> bool {
+    if !hunger {
+      // Minigames without hunger will keep the food bar full. Saturation is only
+      // used up by exhaustion, so we clear it, and the player only gets the slow
+      // regeneration from a full food bar.
+      self.food = 20;
+      self.saturation = 0.0;
+      self.exhaustion = 0.0;
+    } else if self.exhaustion > 4.0 {
+      self.exhaustion -= 4.0;
+      if self.saturation > 0.0 {
+        self.saturation = (self.saturation - 1.0).max(0.0);
+      } else {
+        self.food = (self.food - 1).max(0);
+      }
+    }
+
+    let damaged = health.health < 20.0;
+    if regen && damaged && self.saturation > 0.0 && self.food >= 20 {
+      // Regenerate quickly while the player has saturation.
+      self.tick_timer += 1;
+      if self.tick_timer >= 10 {
+        let amount = self.saturation.min(6.0);
+        health.health = (health.health + amount / 6.0).min(20.0);
+        self.exhaust(amount);
+        self.tick_timer = 0;
+      }
+    } else if regen && damaged && self.food >= 18 {
+      self.tick_timer += 1;
+      if self.tick_timer >= 80 {
+        health.health = (health.health + 1.0).min(20.0);
+        if hunger {
+          self.exhaust(6.0);
+        }
+        self.tick_timer = 0;
+      }
+    } else if self.food <= 0 {
+      self.tick_timer += 1;
+      if self.tick_timer >= 80 {
+        self.tick_timer = 0;
+        // We always use normal difficulty, where starving will leave the
+        // player with half a heart.
+        return health.health > 1.0;
+      }
+    } else {
+      self.tick_timer = 0;
+    }
+    false
+  }
+}
+
+impl Player {
+  /// Returns the player's food level. This is in the range 0..=20.
+  pub fn food(&self) -> i32 { self.food.lock().food }
+  /// Returns the player's saturation. This is always less than or equal to
+  /// their food level.
+  pub fn saturation(&self) -> f32 { self.food.lock().saturation }
+
+  /// Sets the player's food level and saturation, and updates the client. The
+  /// food level is clamped to 0..=20, and the saturation is clamped to the
+  /// food level.
+  pub fn set_food(&self, food: i32, saturation: f32) {
+    {
+      let mut f = self.food.lock();
+      f.food = food.clamp(0, 20);
+      f.saturation = saturation.clamp(0.0, f.food as f32);
+    }
+    self.send_health();
+  }
+
+  /// Adds exhaustion to this player. Once this adds up to 4, the player will
+  /// lose one saturation point, or one food point if they have no saturation.
+  ///
+  /// This does nothing if the player cannot be damaged, or if the `hunger`
+  /// gamerule is disabled.
+  pub fn add_exhaustion(&self, amount: f32) {
+    if !self.damageable() || !self.world().gamerule("hunger") {
+      return;
+    }
+    self.food.lock().exhaust(amount);
+  }
+
+  /// Updates the player's hunger and health. This is called at the end of
+  /// every tick. `walked` is the horizontal distance the player moved on this
+  /// tick.
+  pub(super) fn tick_food(self: &Arc<Self>, walked: f64, jumped: bool, sprinting: bool) {
+    self.tick_using();
+    if !self.damageable() {
+      return;
+    }
+    let world = self.world();
+    let hunger = world.gamerule("hunger");
+    let regen = world.gamerule("natural-regeneration");
+
+    if hunger {
+      let swimming = matches!(world.get_kind(self.block_pos()), Ok(block::Kind::Water));
+      let mut exhaustion = if swimming {
+        0.01 * walked
+      } else if sprinting {
+        0.1 * walked
+      } else {
+        0.0
+      };
+      if jumped {
+        exhaustion += if sprinting { 0.2 } else { 0.05 };
+      }
+      self.food.lock().exhaust(exhaustion as f32);
+    }
+
+    let (starve, changed) = {
+      let mut health = self.health.lock();
+      let mut food = self.food.lock();
+      let old = (health.health, food.food, food.saturation);
+      let starve = food.tick(&mut health, hunger, regen);
+      (starve, old != (health.health, food.food, food.saturation))
+    };
+
+    if changed {
+      self.send_health();
+    }
+    if starve {
+      EntityRef::Player(self.clone()).damage_by(
+        DamageCause::Starvation,
+        1.0,
+        false,
+        Vec3::new(0.0, 0.0, 0.0),
+      );
+    }
+  }
+
+  /// Counts how long the player has been using an item, and eats the item
+  /// once they have held it long enough.
+  fn tick_using(self: &Arc<Self>) {
+    let ItemUse { hand, ticks } = {
+      let mut using = self.using.lock();
+      match &mut *using {
+        Some(u) => {
+          u.ticks += 1;
+          *u
+        }
+        None => return,
+      }
+    };
+    let food = match self.lock_inventory().hand(hand).item().food() {
+      Some(f) => f,
+      None => return,
+    };
+    if ticks >= food.eat_ticks() {
+      self.eat(hand, food);
+    } else if ticks >= 7 && ticks % 4 == 0 {
+      let (volume, pitch) = RNG.with(|rng| {
+        let mut rng = rng.borrow_mut();
+        (0.5 + 0.5 * rng.gen_range(0..2) as f32, rng.gen_range(-0.2..0.2) + 1.0)
+      });
+      self.world().play_sound(
+        "entity.generic.eat".into(),
+        cb::SoundCategory::Players,
+        self.pos(),
+        volume,
+        pitch,
+      );
+    }
+  }
+
+  /// Finishes eating the food in the given hand.
+  fn eat(&self, hand: Hand, food: item::Food) {
+    self.stop_using();
+    {
+      let mut f = self.food.lock();
+      f.food = (f.food + food.hunger()).min(20);
+      f.saturation = (f.saturation + food.saturation()).min(f.food as f32);
+    }
+    if self.game_mode() != GameMode::Creative {
+      self.lock_inventory().consume_hand(hand, Stack::new(food.leftover()));
+    }
+    self.send_health();
+    // Tells the client that we accepted the item being eaten.
+    self.send(cb::packet::EntityStatus { eid: self.eid(), status: 9 });
+    let pitch = RNG.with(|rng| rng.borrow_mut().gen_range(0.9..1.0));
+    self.world().play_sound(
+      "entity.player.burp".into(),
+      cb::SoundCategory::Players,
+      self.pos(),
+      0.5,
+      pitch,
+    );
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn health(health: f32) -> PlayerHealth { PlayerHealth { health, absorption: 0.0, hit_delay: 0 } }
+
+  #[test]
+  fn exhaustion() {
+    let mut food = PlayerFood::new();
+    let mut h = health(20.0);
+    food.exhaust(4.5);
+    assert!(!food.tick(&mut h, true, true));
+    assert_eq!((food.food, food.saturation), (20, 4.0));
+    assert_eq!(food.exhaustion, 0.5);
+
+    // Once saturation is gone, exhaustion removes food instead.
+    food.saturation = 0.0;
+    food.exhaust(4.0);
+    food.tick(&mut h, true, true);
+    assert_eq!((food.food, food.saturation), (19, 0.0));
+  }
+
+  #[test]
+  fn regeneration() {
+    // A full food bar with saturation heals every 10 ticks, and uses up the
+    // saturation.
+    let mut food = PlayerFood::new();
+    let mut h = health(10.0);
+    for _ in 0..10 {
+      food.tick(&mut h, true, true);
+    }
+    assert!(h.health > 10.0);
+    assert_eq!(food.exhaustion, 5.0);
+
+    // Without saturation, 18 food heals one point every 80 ticks.
+    let mut food = PlayerFood { food: 18, saturation: 0.0, exhaustion: 0.0, tick_timer: 0 };
+    let mut h = health(10.0);
+    for _ in 0..79 {
+      food.tick(&mut h, true, true);
+    }
+    assert_eq!(h.health, 10.0);
+    food.tick(&mut h, true, true);
+    assert_eq!(h.health, 11.0);
+
+    // Below 18 food, the player doesn't heal at all.
+    let mut food = PlayerFood { food: 17, saturation: 0.0, exhaustion: 0.0, tick_timer: 0 };
+    for _ in 0..200 {
+      food.tick(&mut h, true, true);
+    }
+    assert_eq!(h.health, 11.0);
+
+    // The gamerule stops all regeneration.
+    let mut food = PlayerFood::new();
+    for _ in 0..200 {
+      food.tick(&mut h, true, false);
+    }
+    assert_eq!(h.health, 11.0);
+  }
+
+  #[test]
+  fn no_hunger() {
+    // Without hunger, the food bar stays full, but saturation can't be used
+    // for fast regeneration, as it would never run out.
+    let mut food = PlayerFood { food: 5, saturation: 5.0, exhaustion: 3.0, tick_timer: 0 };
+    let mut h = health(10.0);
+    for _ in 0..79 {
+      food.tick(&mut h, false, true);
+    }
+    assert_eq!((food.food, food.saturation, food.exhaustion), (20, 0.0, 0.0));
+    assert_eq!(h.health, 10.0);
+    food.tick(&mut h, false, true);
+    assert_eq!(h.health, 11.0);
+  }
+
+  #[test]
+  fn starvation() {
+    let mut food = PlayerFood { food: 0, saturation: 0.0, exhaustion: 0.0, tick_timer: 0 };
+    let mut h = health(10.0);
+    for _ in 0..79 {
+      assert!(!food.tick(&mut h, true, true));
+    }
+    assert!(food.tick(&mut h, true, true));
+    // Starving never kills the player on normal difficulty.
+    let mut h = health(1.0);
+    for _ in 0..80 {
+      assert!(!food.tick(&mut h, true, true));
+    }
+  }
+}
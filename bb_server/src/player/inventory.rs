@@ -10,21 +10,33 @@ use bb_common::{
   metadata::Metadata,
   net::{
     cb,
+    cb::{ArmorSlot, EquipmentSlot},
     sb::{Button, ClickWindow},
   },
   util::{GameMode, Hand},
 };
 use std::{mem, sync::Weak};
 
+const ARMOR_SLOTS: [ArmorSlot; 4] =
+  [ArmorSlot::Helmet, ArmorSlot::Chestplate, ArmorSlot::Leggings, ArmorSlot::Boots];
+
+/// Returns both hands, followed by all the armor slots.
+fn equipment_slots() -> impl Iterator<Item = EquipmentSlot> {
+  [EquipmentSlot::Hand(Hand::Main), EquipmentSlot::Hand(Hand::Off)]
+    .into_iter()
+    .chain(ARMOR_SLOTS.into_iter().map(EquipmentSlot::Armor))
+}
+
 #[derive(Debug)]
 pub struct PlayerInventory {
+  crafting:       SingleInventory<5>,
   head:           SingleInventory<1>,
   chest:          SingleInventory<1>,
   legs:           SingleInventory<1>,
   feet:           SingleInventory<1>,
-  crafting:       SingleInventory<5>,
   main:           SingleInventory<27>,
   hotbar:         SingleInventory<9>,
+  off_hand:       SingleInventory<1>,
   // An index into the hotbar (0..=8)
   selected_index: u8,
   // Open window and held item
@@ -48,13 +60,14 @@ impl PlayerInventory {
     // We always store an inventory with 46 slots, even if the client is on 1.8 (in
     // that version, there was no off-hand).
     PlayerInventory {
-      head:           SingleInventory::new(conn.clone(), 0, 0),
-      chest:          SingleInventory::new(conn.clone(), 0, 1),
-      legs:           SingleInventory::new(conn.clone(), 0, 2),
-      feet:           SingleInventory::new(conn.clone(), 0, 3),
-      crafting:       SingleInventory::new(conn.clone(), 0, 4),
+      crafting:       SingleInventory::new(conn.clone(), 0, 0),
+      head:           SingleInventory::new(conn.clone(), 0, 5),
+      chest:          SingleInventory::new(conn.clone(), 0, 6),
+      legs:           SingleInventory::new(conn.clone(), 0, 7),
+      feet:           SingleInventory::new(conn.clone(), 0, 8),
       main:           SingleInventory::new(conn.clone(), 0, 9),
-      hotbar:         SingleInventory::new(conn, 0, 36),
+      hotbar:         SingleInventory::new(conn.clone(), 0, 36),
+      off_hand:       SingleInventory::new(conn, 0, 45),
       selected_index: 0,
       window:         None,
       held:           Stack::empty(),
@@ -94,6 +107,27 @@ impl PlayerInventory {
     self.main_mut().add(&stack)
   }
 
+  /// Removes every item from the player's inventory, and returns them. This
+  /// includes armor, the crafting grid, and the item held by the cursor. This
+  /// will update the client.
+  pub(crate) fn take_all(&mut self) -> Vec<Stack> {
+    let mut items = vec![mem::replace(&mut self.held, Stack::empty())];
+    items.extend(self.crafting.inv.items_mut().iter_mut().map(mem::take));
+    for slot in ARMOR_SLOTS {
+      items.push(mem::take(&mut self.armor_inv_mut(slot).inv.items_mut()[0]));
+    }
+    items.extend(self.main.inv.items_mut().iter_mut().map(mem::take));
+    items.extend(self.hotbar.inv.items_mut().iter_mut().map(mem::take));
+    items.push(mem::take(&mut self.off_hand.inv.items_mut()[0]));
+    items.retain(|it| !it.is_empty());
+    self.sync_all();
+    self.sync(-999);
+    for slot in equipment_slots() {
+      self.send_equipment(slot);
+    }
+    items
+  }
+
   /// Returns the item in the player's main hand.
   pub fn main_hand(&self) -> &Stack { self.hotbar().get_raw(self.selected_index as u32).unwrap() }
 
@@ -118,6 +152,163 @@ impl PlayerInventory {
     self.selected_index = index;
   }
 
+  /// Returns the item in the player's off hand.
+  pub fn off_hand(&self) -> &Stack { self.off_hand.get_raw(0).unwrap() }
+  /// Returns the item in the given hand.
+  pub fn hand(&self, hand: Hand) -> &Stack {
+    match hand {
+      Hand::Main => self.main_hand(),
+      Hand::Off => self.off_hand(),
+    }
+  }
+
+  /// Swaps the items in the main hand and the off hand. This will update the
+  /// client, and show the new items to other players.
+  pub fn swap_hands(&mut self) {
+    let idx = self.selected_index as u32;
+    let main = self.hotbar.replace_raw(idx, Stack::empty());
+    let off = self.off_hand.replace_raw(0, main);
+    self.hotbar.set_raw(idx, off);
+    self.send_equipment(EquipmentSlot::Hand(Hand::Main));
+    self.send_equipment(EquipmentSlot::Hand(Hand::Off));
+  }
+
+  fn armor_inv(&self, slot: ArmorSlot) -> &SingleInventory<1> {
+    match slot {
+      ArmorSlot::Helmet => &self.head,
+      ArmorSlot::Chestplate => &self.chest,
+      ArmorSlot::Leggings => &self.legs,
+      ArmorSlot::Boots => &self.feet,
+    }
+  }
+  fn armor_inv_mut(&mut self, slot: ArmorSlot) -> &mut SingleInventory<1> {
+    match slot {
+      ArmorSlot::Helmet => &mut self.head,
+      ArmorSlot::Chestplate => &mut self.chest,
+      ArmorSlot::Leggings => &mut self.legs,
+      ArmorSlot::Boots => &mut self.feet,
+    }
+  }
+  /// Returns the armor the player is wearing in the given slot.
+  pub fn armor(&self, slot: ArmorSlot) -> &Stack { self.armor_inv(slot).get_raw(0).unwrap() }
+  /// Sets the armor in the given slot. This will update the client, and show
+  /// the new armor to other players.
+  pub fn set_armor(&mut self, slot: ArmorSlot, stack: Stack) {
+    self.armor_inv_mut(slot).set_raw(0, stack);
+    self.send_equipment(EquipmentSlot::Armor(slot));
+  }
+  /// Returns the total armor points and armor toughness of everything the
+  /// player is wearing.
+  pub fn armor_totals(&self) -> (f32, f32) {
+    ARMOR_SLOTS
+      .iter()
+      .filter_map(|slot| self.armor(*slot).item().armor())
+      .fold((0.0, 0.0), |(defense, toughness), armor| {
+        (defense + armor.defense(), toughness + armor.toughness())
+      })
+  }
+  /// Damages all the armor the player is wearing, after the player took
+  /// `amount` damage. Any armor that runs out of durability is removed. Returns
+  /// `true` if any armor broke.
+  pub(crate) fn damage_armor(&mut self, amount: f32) -> bool {
+    let damage = ((amount / 4.0) as u16).max(1);
+    let mut broke = false;
+    for slot in ARMOR_SLOTS {
+      // Things like pumpkins can be worn, but they don't lose durability.
+      if self.armor(slot).item().armor().is_none() {
+        continue;
+      }
+      if self.armor_inv_mut(slot).get_raw_mut(0).unwrap().add_damage(damage) {
+        broke = true;
+        self.send_equipment(EquipmentSlot::Armor(slot));
+      }
+      self.armor_inv(slot).sync_raw(0);
+    }
+    broke
+  }
+  /// Damages the item in the given hand. Returns `true` if the item broke.
+  pub(crate) fn damage_hand(&mut self, hand: Hand, amount: u16) -> bool {
+    let broke = match hand {
+      Hand::Main => {
+        let idx = self.selected_index as u32;
+        let broke = self.hotbar.get_raw_mut(idx).unwrap().add_damage(amount);
+        self.hotbar.sync_raw(idx);
+        broke
+      }
+      Hand::Off => {
+        let broke = self.off_hand.get_raw_mut(0).unwrap().add_damage(amount);
+        self.off_hand.sync_raw(0);
+        broke
+      }
+    };
+    if broke {
+      self.send_equipment(EquipmentSlot::Hand(hand));
+    }
+    broke
+  }
+
+  /// Removes one item from the given hand, such as when the player eats
+  /// something. If `leftover` is not air, it replaces the item once the hand
+  /// is empty, or is added to the inventory otherwise.
+  pub(crate) fn consume_hand(&mut self, hand: Hand, leftover: Stack) {
+    fn consume(stack: &mut Stack, leftover: &Stack) -> bool {
+      stack.set_amount(stack.amount().saturating_sub(1));
+      if stack.is_empty() {
+        *stack = leftover.clone();
+        true
+      } else {
+        false
+      }
+    }
+    let emptied = match hand {
+      Hand::Main => {
+        let idx = self.selected_index as u32;
+        let emptied = consume(self.hotbar.get_raw_mut(idx).unwrap(), &leftover);
+        self.hotbar.sync_raw(idx);
+        emptied
+      }
+      Hand::Off => {
+        let emptied = consume(self.off_hand.get_raw_mut(0).unwrap(), &leftover);
+        self.off_hand.sync_raw(0);
+        emptied
+      }
+    };
+    if emptied {
+      self.send_equipment(EquipmentSlot::Hand(hand));
+    } else if !leftover.is_empty() {
+      self.give(leftover);
+    }
+  }
+
+  /// Returns the equipment packets needed to show this player's held items
+  /// and armor to another player. Empty slots are skipped.
+  pub(crate) fn equipment(&self, eid: i32) -> Vec<cb::packet::EntityEquipment> {
+    let mut out = vec![];
+    for slot in equipment_slots() {
+      let stack = self.equipment_stack(slot);
+      if !stack.is_empty() {
+        out.push(cb::packet::EntityEquipment { eid, slot, item: stack.to_item() });
+      }
+    }
+    out
+  }
+  fn equipment_stack(&self, slot: EquipmentSlot) -> &Stack {
+    match slot {
+      EquipmentSlot::Hand(hand) => self.hand(hand),
+      EquipmentSlot::Armor(slot) => self.armor(slot),
+    }
+  }
+  /// Sends the item in the given slot to all other players in view.
+  fn send_equipment(&self, slot: EquipmentSlot) {
+    if let Some(p) = self.player.upgrade() {
+      p.send_to_in_view(cb::packet::EntityEquipment {
+        eid: p.eid(),
+        slot,
+        item: self.equipment_stack(slot).to_item(),
+      });
+    }
+  }
+
   pub fn main(&self) -> &SingleInventory<27> { &self.main }
   pub fn main_mut(&mut self) -> &mut SingleInventory<27> { &mut self.main }
 
@@ -148,13 +339,14 @@ impl PlayerInventory {
       }
     } else {
       match index {
-        0 => self.head.get(idx),
-        1 => self.chest.get(idx),
-        2 => self.legs.get(idx),
-        3 => self.feet.get(idx),
-        4..=8 => self.crafting.get(idx),
+        0..=4 => self.crafting.get(idx),
+        5 => self.head.get(idx),
+        6 => self.chest.get(idx),
+        7 => self.legs.get(idx),
+        8 => self.feet.get(idx),
         9..=35 => self.main.get(idx),
         36..=44 => self.hotbar.get(idx),
+        45 => self.off_hand.get(idx),
         _ => None,
       }
       .cloned()
@@ -178,13 +370,14 @@ impl PlayerInventory {
       }
     } else {
       match index {
-        0 => self.head.get_mut(idx),
-        1 => self.chest.get_mut(idx),
-        2 => self.legs.get_mut(idx),
-        3 => self.feet.get_mut(idx),
-        4..=8 => self.crafting.get_mut(idx),
+        0..=4 => self.crafting.get_mut(idx),
+        5 => self.head.get_mut(idx),
+        6 => self.chest.get_mut(idx),
+        7 => self.legs.get_mut(idx),
+        8 => self.feet.get_mut(idx),
         9..=35 => self.main.get_mut(idx),
         36..=44 => self.hotbar.get_mut(idx),
+        45 => self.off_hand.get_mut(idx),
         _ => None,
       }
       .map(f)
@@ -217,6 +410,9 @@ impl PlayerInventory {
         items.push(it.to_item());
       }
     } else {
+      for it in self.crafting.inv.items() {
+        items.push(it.to_item());
+      }
       for it in self.head.inv.items() {
         items.push(it.to_item());
       }
@@ -229,9 +425,6 @@ impl PlayerInventory {
       for it in self.feet.inv.items() {
         items.push(it.to_item());
       }
-      for it in self.crafting.inv.items() {
-        items.push(it.to_item());
-      }
     }
     for it in self.main.inv.items().iter() {
       items.push(it.to_item());
@@ -239,20 +432,68 @@ impl PlayerInventory {
     for it in self.hotbar.inv.items() {
       items.push(it.to_item());
     }
+    if self.window.is_none() {
+      // The proxy will remove this for 1.8 clients, which don't have an off hand.
+      items.push(self.off_hand().to_item());
+    }
     let held = self.held.to_item();
-    self.main.conn.send(cb::packet::WindowItems { wid: 1, items, held });
+    let wid = if self.window.is_some() { 1 } else { 0 };
+    self.main.conn.send(cb::packet::WindowItems { wid, items, held });
+  }
+
+  /// Returns all the items that should be saved when this player leaves. This
+  /// is the armor slots, the main inventory, the hotbar, and the off hand, in
+  /// that order. Crafting slots, the held item, and any open window are not
+  /// included.
+  pub(crate) fn saved_items(&self) -> Vec<Stack> {
+    let mut items = Vec::with_capacity(41);
+    items.extend(self.head.inv.items().iter().cloned());
+    items.extend(self.chest.inv.items().iter().cloned());
+    items.extend(self.legs.inv.items().iter().cloned());
+    items.extend(self.feet.inv.items().iter().cloned());
+    items.extend(self.main.inv.items().iter().cloned());
+    items.extend(self.hotbar.inv.items().iter().cloned());
+    items.extend(self.off_hand.inv.items().iter().cloned());
+    items
+  }
+  /// Replaces the items in this inventory with the ones returned from
+  /// [`saved_items`](Self::saved_items). This will not send any updates to
+  /// the client. Any missing items will be set to empty, and any extra items
+  /// will be ignored.
+  pub(crate) fn load_saved_items(&mut self, items: Vec<Stack>, selected_index: u8) {
+    let mut iter = items.into_iter().chain(std::iter::repeat(Stack::empty()));
+    self.head.inv.items_mut()[0] = iter.next().unwrap();
+    self.chest.inv.items_mut()[0] = iter.next().unwrap();
+    self.legs.inv.items_mut()[0] = iter.next().unwrap();
+    self.feet.inv.items_mut()[0] = iter.next().unwrap();
+    for it in self.main.inv.items_mut() {
+      *it = iter.next().unwrap();
+    }
+    for it in self.hotbar.inv.items_mut() {
+      *it = iter.next().unwrap();
+    }
+    self.off_hand.inv.items_mut()[0] = iter.next().unwrap();
+    self.selected_index = selected_index.min(8);
   }
   /// Sends an item update for the given slot. This shouldn't every be needed,
   /// as functions like [`set`](Self::set) and [`replace`](Self::replace) will
   /// call this for you.
   pub fn sync(&self, index: i32) {
+    if self.window.is_none() {
+      let slot = match index {
+        5 => Some(EquipmentSlot::Armor(ArmorSlot::Helmet)),
+        6 => Some(EquipmentSlot::Armor(ArmorSlot::Chestplate)),
+        7 => Some(EquipmentSlot::Armor(ArmorSlot::Leggings)),
+        8 => Some(EquipmentSlot::Armor(ArmorSlot::Boots)),
+        45 => Some(EquipmentSlot::Hand(Hand::Off)),
+        _ => None,
+      };
+      if let Some(slot) = slot {
+        self.send_equipment(slot);
+      }
+    }
     if index == self.selected_index as i32 + 36 {
-      let p = self.player.upgrade().unwrap();
-      p.send_to_in_view(cb::packet::EntityEquipment {
-        eid:  p.eid(),
-        slot: cb::EquipmentSlot::Hand(Hand::Main),
-        item: self.get(index).unwrap().to_item(),
-      });
+      self.send_equipment(EquipmentSlot::Hand(Hand::Main));
     }
     if index == -999 {
       self.main.conn.send(cb::packet::WindowItem {
@@ -275,13 +516,14 @@ impl PlayerInventory {
       }
     } else {
       match index {
-        0 => self.head.sync(idx),
-        1 => self.chest.sync(idx),
-        2 => self.legs.sync(idx),
-        3 => self.feet.sync(idx),
-        4..=8 => self.crafting.sync(idx),
+        0..=4 => self.crafting.sync(idx),
+        5 => self.head.sync(idx),
+        6 => self.chest.sync(idx),
+        7 => self.legs.sync(idx),
+        8 => self.feet.sync(idx),
         9..=35 => self.main.sync(idx),
         36..=44 => self.hotbar.sync(idx),
+        45 => self.off_hand.sync(idx),
         _ => panic!(),
       }
     }
@@ -388,8 +630,22 @@ impl PlayerInventory {
               self.sync_all();
             }
           } else {
-            // TODO: Armor slots
-            if let Some(mut stack) = self.main.get(idx).cloned() {
+            let armor = self.get(slot).and_then(|s| s.item().armor());
+            if (5..=8).contains(&slot) || slot == 45 {
+              // Moving armor or the off hand item into the main inventory.
+              let mut stack = self.get(slot).unwrap();
+              let remaining = self.main.add(&stack);
+              stack.set_amount(remaining);
+              if stack.amount() > 0 {
+                let remaining = self.hotbar.add(&stack);
+                stack.set_amount(remaining);
+              }
+              self.set(slot, stack);
+            } else if armor.map(|a| self.armor(a.slot()).is_empty()).unwrap_or(false) {
+              // Shift clicking armor will put it on, if that slot is empty.
+              let stack = self.replace(slot, Stack::empty());
+              self.set_armor(armor.unwrap().slot(), stack);
+            } else if let Some(mut stack) = self.main.get(idx).cloned() {
               let remaining = self.hotbar.add(&stack);
               stack.set_amount(remaining);
               self.main.set(idx, stack);
@@ -410,7 +666,15 @@ impl PlayerInventory {
       ClickWindow::DoubleClick => allow!(self.double_click(slot)),
       ClickWindow::DragStart(bt) => self.drag_start(bt),
       ClickWindow::DragAdd(bt) => self.drag_add(bt, slot),
-      ClickWindow::DragEnd(bt) => self.drag_end(bt),
+      ClickWindow::DragEnd(bt) => {
+        if allow {
+          self.drag_end(bt);
+        } else {
+          // The whole drag is cancelled, and the `sync_all` below will reset the client.
+          self.drag_bt = None;
+          self.drag_slots.clear();
+        }
+      }
     }
 
     self.sync_all();
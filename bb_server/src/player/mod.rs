@@ -1,8 +1,7 @@
 use crate::{
   block,
   command::{CommandSender, ErrorFormat},
-  entity,
-  entity::EntityRef,
+  entity, item,
   item::Stack,
   math::Vec3,
   net::ConnSender,
@@ -13,13 +12,24 @@ use bb_common::{
   math::{ChunkPos, FPos, Pos},
   metadata::{Metadata, Pose},
   net::{cb, sb::PlayerCommand},
-  util::{Chat, GameMode, JoinInfo, UUID},
+  util::{Chat, Effect, GameMode, Hand, JoinInfo, Property, UUID},
   version::ProtocolVersion,
 };
-use parking_lot::{Mutex, MutexGuard};
-use std::{collections::HashSet, f64::consts, fmt, net::SocketAddr, sync::Arc, time::Instant};
+use parking_lot::{Mutex, MutexGuard, RwLock};
+use std::{
+  collections::HashSet,
+  f64::consts,
+  fmt,
+  net::{IpAddr, SocketAddr},
+  sync::Arc,
+  time::Instant,
+};
 
 mod click;
+mod data;
+mod death;
+mod effect;
+mod food;
 mod inventory;
 mod scoreboard;
 mod team;
@@ -27,6 +37,8 @@ mod tick;
 pub mod window;
 
 pub use click::{AirClick, BlockClick, Click};
+pub(crate) use data::PlayerData;
+pub use death::DamageCause;
 pub use inventory::PlayerInventory;
 pub use scoreboard::Scoreboard;
 pub use team::Team;
@@ -68,9 +80,17 @@ pub(crate) struct PlayerPosition {
 
   last_set_pos: Instant,
 
-  crouching: bool,
-  sprinting: bool,
-  swimming:  bool,
+  crouching:     bool,
+  sprinting:     bool,
+  swimming:      bool,
+  on_ground:     bool,
+  /// The value of `on_ground` on the previous tick. Used to check if the player
+  /// jumped.
+  was_on_ground: bool,
+
+  /// The distance the player has fallen since they were last on the ground.
+  /// Used for fall damage.
+  fall_distance: f64,
 
   /// Stores the current progress. Mining time can change between when you start
   /// and when you end, so the most accurate way to track mining time is to
@@ -102,6 +122,20 @@ pub struct PlayerHealth {
 pub struct PlayerFood {
   food:       i32,
   saturation: f32,
+  /// Increased by things like sprinting and jumping. Once this reaches 4, it
+  /// is reset, and the player loses one saturation or food point.
+  exhaustion: f32,
+  /// Counts the ticks until the next time the player regenerates health or
+  /// takes starvation damage.
+  tick_timer: u32,
+}
+
+/// An item the player is holding right click with, such as food or a shield.
+#[derive(Debug, Clone, Copy)]
+struct ItemUse {
+  hand:  Hand,
+  /// The number of ticks the item has been used for.
+  ticks: u32,
 }
 
 pub struct Player {
@@ -110,9 +144,15 @@ pub struct Player {
   username:      String,
   tab_name:      Mutex<Option<Chat>>,
   uuid:          UUID,
+  /// The game profile properties sent from the proxy. Never changes.
+  properties:    Vec<Property>,
+  /// Overrides the `textures` property in `properties`. Set by plugins.
+  skin:          Mutex<Option<Property>>,
+  /// The client's IP, as sent by the proxy.
+  ip:            Option<IpAddr>,
   conn:          ConnSender,
   ver:           ProtocolVersion,
-  world:         Arc<World>,
+  world:         RwLock<Arc<World>>,
   view_distance: u32,
 
   game_mode: Mutex<GameMode>,
@@ -123,8 +163,16 @@ pub struct Player {
 
   abilities: Mutex<PlayerAbilities>,
 
-  health: Mutex<PlayerHealth>,
-  food:   Mutex<PlayerFood>,
+  /// The item the player is currently using, such as a shield they are
+  /// blocking with, or food they are eating. This is cleared when they let go
+  /// of right click.
+  using: Mutex<Option<ItemUse>>,
+
+  health:  Mutex<PlayerHealth>,
+  food:    Mutex<PlayerFood>,
+  /// The player's status effects. No other locks should be taken while this
+  /// is locked.
+  effects: Mutex<entity::Effects>,
 
   // Not very efficient, but required, as we generate chunks in the background. Because chunk
   // generation is slow, we need to do it over multiple ticks. If the player moves into a chunk,
@@ -198,32 +246,40 @@ impl Player {
       username: info.username,
       tab_name: Mutex::new(None),
       uuid: info.uuid,
+      properties: info.properties,
+      skin: Mutex::new(None),
+      ip: info.ip,
       inv: PlayerInventory::new(weak.clone(), conn.clone()).into(),
       scoreboard: Scoreboard::new(conn.clone()).into(),
       conn,
       ver: ProtocolVersion::from(info.ver as i32),
       view_distance: world.world_manager().config().get("view-distance"),
       game_mode: Mutex::new(game_mode),
-      world,
+      world: RwLock::new(world),
       pos: PlayerPosition {
-        curr:         pos,
-        prev:         pos,
-        next:         pos,
-        vel:          Vec3::new(0.0, 0.0, 0.0),
-        yaw:          0.0,
-        pitch:        0.0,
-        next_yaw:     0.0,
-        next_pitch:   0.0,
-        last_set_pos: Instant::now(),
-        crouching:    false,
-        sprinting:    false,
-        swimming:     false,
-        dig_progress: None,
+        curr:          pos,
+        prev:          pos,
+        next:          pos,
+        vel:           Vec3::new(0.0, 0.0, 0.0),
+        yaw:           0.0,
+        pitch:         0.0,
+        next_yaw:      0.0,
+        next_pitch:    0.0,
+        last_set_pos:  Instant::now(),
+        crouching:     false,
+        sprinting:     false,
+        swimming:      false,
+        on_ground:     true,
+        was_on_ground: true,
+        fall_distance: 0.0,
+        dig_progress:  None,
       }
       .into(),
       abilities: Mutex::new(abilities),
+      using: Mutex::new(None),
       health: PlayerHealth { health: 20.0, absorption: 0.0, hit_delay: 0 }.into(),
-      food: PlayerFood { food: 20, saturation: 5.0 }.into(),
+      food: PlayerFood::new().into(),
+      effects: Mutex::new(entity::Effects::new()),
       loaded_chunks: Mutex::new(HashSet::new()),
     })
   }
@@ -231,6 +287,11 @@ impl Player {
   /// Returns the player's username.
   pub fn username(&self) -> &String { &self.username }
 
+  /// Returns the client's IP. If the proxy is behind Velocity or BungeeCord,
+  /// this is the IP forwarded from that proxy. This is `None` if the proxy
+  /// didn't send an IP.
+  pub fn ip(&self) -> Option<IpAddr> { self.ip }
+
   /// Returns the player's entity id. Used to send packets about entities.
   pub fn eid(&self) -> i32 { self.eid }
   /// Returns the player's uuid. Used to lookup players in the world.
@@ -246,6 +307,20 @@ impl Player {
   /// Returns the player's health.
   pub fn health(&self) -> f32 { self.lock_health().health }
 
+  /// Sends the player's health and food to the client.
+  pub(crate) fn send_health(&self) {
+    let out = {
+      let health = self.health.lock();
+      let food = self.food.lock();
+      cb::packet::UpdateHealth {
+        health:     health.health,
+        food:       food.food,
+        saturation: food.saturation,
+      }
+    };
+    self.send(out);
+  }
+
   /// Returns the version that this client connected with. This will only change
   /// if the player disconnects and logs in with another client.
   pub fn ver(&self) -> ProtocolVersion { self.ver }
@@ -255,8 +330,70 @@ impl Player {
   /// Returns a locked reference to the player's scoreboard.
   pub fn lock_scoreboard(&self) -> MutexGuard<Scoreboard> { self.scoreboard.lock() }
 
-  /// Returns a reference to the world the player is in.
-  pub fn world(&self) -> &Arc<World> { &self.world }
+  /// Returns the world the player is in. This may change at any time, if the
+  /// player is moved with [`switch_world`](Self::switch_world).
+  pub fn world(&self) -> Arc<World> { self.world.read().clone() }
+
+  /// Moves this player into another world, at the given position. This will
+  /// unload all the chunks the client has, send them a respawn packet, and
+  /// then send them the chunks and players in the new world.
+  ///
+  /// If the player is already in the given world, this just teleports them.
+  pub fn switch_world(self: &Arc<Self>, world: &Arc<World>, pos: FPos) {
+    let old = self.world();
+    let (yaw, pitch) = {
+      let p = self.pos.lock();
+      (p.yaw, p.pitch)
+    };
+    if Arc::ptr_eq(&old, world) {
+      self.teleport(pos, yaw, pitch);
+      return;
+    }
+    if old.take_player(self.id()).is_none() {
+      // The player has already left.
+      return;
+    }
+    // The tab list only shows players in the same world, so we need to remove
+    // everyone in the old world.
+    let list_remove = cb::packet::PlayerList {
+      action: cb::PlayerListAction::Remove(
+        old.players().iter().map(|p| cb::PlayerListRemove { id: p.id() }).collect(),
+      ),
+    };
+    self.send(list_remove);
+    self.unload_world();
+
+    *self.world.write() = world.clone();
+    {
+      let mut p = self.pos.lock();
+      p.curr = pos;
+      p.prev = pos;
+      p.next = pos;
+      p.fall_distance = 0.0;
+      p.dig_progress = None;
+    }
+    world.world_manager().set_player_world(self.id(), world);
+    self.send(cb::packet::Respawn {
+      game_mode:    self.game_mode(),
+      dimension:    0, // Overworld
+      world_name:   world.dimension_name(),
+      level_type:   "default".into(),
+      difficulty:   1, // Normal
+      world_height: world.height(),
+      world_min_y:  world.min_y(),
+    });
+    // The respawn packet doesn't contain this, so we need to send it if the new
+    // world has a different gamerule. Older clients always show the respawn
+    // screen.
+    if self.ver() >= ProtocolVersion::V1_15_2 {
+      self.send(cb::packet::ChangeGameState {
+        action: cb::ChangeGameStateKind::EnableRespawnScreen(!world.gamerule("immediate-respawn")),
+      });
+    }
+    world.add_player(self);
+    world.player_spawn(self);
+    info!("{} moved from {} to {}", self.username(), old.name(), world.name());
+  }
 
   /// This will move the player on the next player tick. Used whenever a
   /// position packet is received.
@@ -265,6 +402,10 @@ impl Player {
     pos.next = FPos::new(x, y, z);
   }
 
+  /// Sets if the player is on the ground. Used whenever a movement packet is
+  /// received.
+  pub(crate) fn set_on_ground(&self, on_ground: bool) { self.pos.lock().on_ground = on_ground; }
+
   /// This will set the player's look direction on the next player tick. Used
   /// whenever a player look packet is received.
   pub(crate) fn set_next_look(&self, yaw: f32, pitch: f32) {
@@ -289,6 +430,7 @@ impl Player {
     p.next_yaw = yaw;
     p.next_pitch = pitch;
     p.next = pos;
+    p.fall_distance = 0.0;
   }
 
   /// Sends the player a chat message.
@@ -308,7 +450,7 @@ impl Player {
   /// Sends the particle to the player. This will always send the packet, even
   /// if the particle is too far away.
   pub fn send_particle(&self, particle: Particle) {
-    self.send(particle.to_packet(self.world.world_manager().block_converter(), self.ver));
+    self.send(particle.to_packet(self.world().world_manager().block_converter(), self.ver));
   }
   /// Sends the particle to the player, if the player can see the particle. This
   /// uses `particle.long_distance` to check if the player is in range.
@@ -372,7 +514,7 @@ impl Player {
   ///
   /// This is used when a player disconnects on their own, and they need to be
   /// removed from the players list in the world.
-  pub(crate) fn remove(&self) { self.world.world_manager().remove_player(self.uuid); }
+  pub(crate) fn remove(&self) { self.world().world_manager().remove_player(self.uuid); }
 
   /// Returns the status byte for entity metadata. The bits are as follows:
   ///
@@ -385,8 +527,11 @@ impl Player {
   /// - `0x40`: Is glowing
   /// - `0x80`: Is flying with elytra
   pub fn status_byte(&self) -> i8 {
-    let pos = self.pos.lock();
-    ((pos.crouching as i8) << 1) | ((pos.sprinting as i8) << 4) | ((pos.swimming as i8) << 5)
+    let bits = {
+      let pos = self.pos.lock();
+      ((pos.crouching as i8) << 1) | ((pos.sprinting as i8) << 4) | ((pos.swimming as i8) << 5)
+    };
+    bits | self.effect_status_bits()
   }
 
   /// Generates the player's metadata for the given version. This will include
@@ -419,6 +564,69 @@ impl Player {
   /// Returns `true` if the player is sprinting.
   pub fn is_sprinting(&self) -> bool { self.pos.lock().sprinting }
 
+  /// Returns `true` if the player is standing on the ground. This is sent from
+  /// the client, so it should not be trusted for anything important.
+  pub fn on_ground(&self) -> bool { self.pos.lock().on_ground }
+
+  /// Starts using the item in the given hand. This is called when we get a use
+  /// item packet. Nothing happens unless the item can be held down, like a
+  /// shield, or food the player is hungry enough to eat.
+  pub(crate) fn start_using(&self, hand: Hand) {
+    let ty = self.lock_inventory().hand(hand).item();
+    let usable = match ty.food() {
+      Some(food) => {
+        food.always_edible() || self.game_mode() == GameMode::Creative || self.food() < 20
+      }
+      None => {
+        ty == item::Type::Shield
+          || (self.ver() < ProtocolVersion::V1_9
+            && hand == Hand::Main
+            && ty.weapon().map(|w| w.is_sword()).unwrap_or(false))
+      }
+    };
+    if usable {
+      {
+        let mut using = self.using.lock();
+        // The client may send this again while still using the same item.
+        if using.map(|u| u.hand) == Some(hand) {
+          return;
+        }
+        *using = Some(ItemUse { hand, ticks: 0 });
+      }
+      self.send_hand_state(Some(hand));
+    }
+  }
+  /// Stops using the current item. This is called when the player lets go of
+  /// right click, or changes the item they are holding.
+  pub(crate) fn stop_using(&self) {
+    if self.using.lock().take().is_some() {
+      self.send_hand_state(None);
+    }
+  }
+  /// Returns the hand the player is currently using an item in, if any.
+  pub fn using_hand(&self) -> Option<Hand> { self.using.lock().map(|u| u.hand) }
+
+  /// Shows other players that this player is using an item in the given hand.
+  /// This will show the eating or blocking animation.
+  ///
+  /// 1.8 clients are skipped, as they use the status byte for this, which
+  /// conflicts with the sprinting flag we send.
+  fn send_hand_state(&self, hand: Option<Hand>) {
+    let state = match hand {
+      Some(Hand::Main) => 0x01,
+      Some(Hand::Off) => 0x03,
+      None => 0x00,
+    };
+    let mut meta = Metadata::new();
+    meta.set_byte(8, state);
+    let out = cb::packet::EntityMetadata { eid: self.eid(), ty: entity::Type::Player.id(), meta };
+    for other in self.world().players().iter().in_view(self.pos().chunk()).not(self.uuid) {
+      if other.ver() >= ProtocolVersion::V1_9 {
+        other.send(out.clone());
+      }
+    }
+  }
+
   /// Returns if player is currently alive
   pub fn alive(&self) -> bool { (*self.health.lock()).health > 0.0 }
 
@@ -552,6 +760,53 @@ impl Player {
   /// Returns the current tab list name.
   pub fn tab_name(&self) -> MutexGuard<'_, Option<Chat>> { self.tab_name.lock() }
 
+  /// Returns the player's game profile properties. If a skin has been set with
+  /// [`set_skin`](Self::set_skin), this will replace the `textures` property
+  /// the player logged in with.
+  pub fn properties(&self) -> Vec<Property> {
+    let skin = self.skin.lock();
+    let mut props: Vec<_> = match &*skin {
+      Some(_) => self.properties.iter().filter(|p| p.name != "textures").cloned().collect(),
+      None => self.properties.clone(),
+    };
+    props.extend(skin.clone());
+    props
+  }
+  /// Overrides this player's skin. `skin` should be a `textures` property,
+  /// usually copied from another player. If `skin` is `None`, the player's
+  /// real skin will be restored.
+  ///
+  /// Other players will see the change immediately. The player themselves
+  /// will only see their new skin after they respawn or switch worlds.
+  pub fn set_skin(self: &Arc<Self>, skin: Option<Property>) {
+    *self.skin.lock() = skin.map(|s| Property { name: "textures".into(), ..s });
+    // The client only reads skins when a player is added to the tab list, so we
+    // need to remove and re-add this player, and then spawn them again.
+    let remove = cb::packet::PlayerList {
+      action: cb::PlayerListAction::Remove(vec![cb::PlayerListRemove { id: self.id() }]),
+    };
+    let add =
+      cb::packet::PlayerList { action: cb::PlayerListAction::Add(vec![self.player_list_add()]) };
+    let world = self.world();
+    for p in world.players().iter().not(self.id()) {
+      p.send(remove.clone());
+      p.send(add.clone());
+    }
+    world.respawn_player(self);
+  }
+  /// Returns the tab list entry for this player. This is sent to other players
+  /// when this player joins, and it is how clients know which skin to use.
+  pub fn player_list_add(&self) -> cb::PlayerListAdd {
+    cb::PlayerListAdd {
+      id:           self.id(),
+      name:         self.username().clone(),
+      game_mode:    GameMode::Creative,
+      ping:         50,
+      display_name: self.tab_name().clone().map(|c| c.to_json()),
+      properties:   self.properties(),
+    }
+  }
+
   /// Sends a block update packet for the block at the given position. This
   /// ensures that the client sees what the server sees at that position.
   ///
@@ -583,7 +838,7 @@ impl Player {
   /// call [`send_all_in_view`](Self::send_all_in_view).
   pub fn send_to_in_view(&self, p: impl Into<cb::Packet>) {
     let p = p.into();
-    for other in self.world.players().iter().in_view(self.pos().chunk()).not(self.uuid) {
+    for other in self.world().players().iter().in_view(self.pos().chunk()).not(self.uuid) {
       other.send(p.clone());
     }
   }
@@ -592,7 +847,7 @@ impl Player {
   /// [`send_to_in_view`](Self::send_to_in_view).
   pub fn send_all_in_view(&self, p: impl Into<cb::Packet>) {
     let p = p.into();
-    for other in self.world.players().iter().in_view(self.pos().chunk()) {
+    for other in self.world().players().iter().in_view(self.pos().chunk()) {
       other.send(p.clone());
     }
   }
@@ -663,13 +918,19 @@ impl Player {
   ///
   /// Because this is all over the network, the player can be disconnected at
   /// any time. If the `ips` was all bad addresses, or the server refused to
-  /// accept the connection, the packet `SwitchServerFailed` will be sent back
-  /// to us.
+  /// accept the connection, the proxy will keep the player on this server, and
+  /// send them an error message.
   ///
   /// The order of `ips` matters. The first one will be tried first, then the
   /// second one, etc. This is expected to be the result of
   /// [`ToSocketAddrs`](std::net::ToSocketAddrs).
   pub fn switch_to(&self, ips: Vec<SocketAddr>) { self.send(cb::packet::SwitchServer { ips }); }
+  /// Sends a server switch packet to the proxy, for the server with the given
+  /// name in the proxy's config. If the proxy doesn't know about this server,
+  /// the player will not be moved.
+  pub fn switch_to_named(&self, name: &str) {
+    self.send(cb::packet::SwitchServerNamed { name: name.into() });
+  }
 
   pub(super) fn handle_command(&self, command: PlayerCommand) {
     let mut needs_update = false;
@@ -716,22 +977,95 @@ impl Player {
     }
   }
 
-  /// Attacks the given player. This is private to the crate, as it should only
-  /// be called when we get an attack packet.
-  pub(super) fn attack(&self, other: EntityRef) {
+  /// Attacks the entity with the given eid. This is private to the crate, as
+  /// it should only be called when we get an attack packet.
+  ///
+  /// Attacking while falling will deal a critical hit. On 1.9+, attacking with
+  /// a sword while standing still will also damage entities next to the
+  /// target.
+  pub(super) fn attack(&self, eid: i32) {
+    if !self.alive() {
+      return;
+    }
+    let world = self.world();
+    // We can't hold onto the entities lock while damaging anything, as killing a
+    // player will spawn their items.
+    let (other, nearby) = {
+      let entities = world.entities();
+      let target = match entities.get(eid) {
+        Some(ent) => ent.pos(),
+        None => return,
+      };
+      let nearby: Vec<_> = entities
+        .iter_values()
+        .filter(|(id, _)| **id != eid && **id != self.eid())
+        .filter(|(_, ent)| {
+          ent.as_entity_ref(&world).map_or(false, |ent| {
+            let pos = ent.pos();
+            (pos.y() - target.y()).abs() < 0.25 && pos.with_y(0.0).dist(target.with_y(0.0)) < 1.5
+          })
+        })
+        .map(|(_, ent)| ent.clone())
+        .collect();
+      (entities.get_owned(eid).unwrap(), nearby)
+    };
+    let other = match other.as_entity_ref(&world) {
+      Some(ent) => ent,
+      None => return,
+    };
     // Handles base damage and enchantments
-    let damage = self.lock_inventory().main_hand().attack_damage();
-    // TODO: Strength
+    let (mut damage, sword) = {
+      let inv = self.lock_inventory();
+      let stack = inv.main_hand();
+      (stack.attack_damage(), stack.item().weapon().map(|w| w.is_sword()).unwrap_or(false))
+    };
+    if let Some(amp) = self.effect_amplifier(Effect::Strength) {
+      damage += 3.0 * (f32::from(amp) + 1.0);
+    }
+    if let Some(amp) = self.effect_amplifier(Effect::Weakness) {
+      damage = (damage - 4.0 * (f32::from(amp) + 1.0)).max(0.0);
+    }
+    let (on_ground, sprinting, falling) = {
+      let pos = self.pos.lock();
+      (pos.on_ground, pos.sprinting, pos.curr.y() < pos.prev.y())
+    };
+    let crit = !on_ground && falling && !sprinting;
+    if crit {
+      damage *= 1.5;
+    }
+    let sweep = self.ver() >= ProtocolVersion::V1_9 && on_ground && !sprinting && !crit && sword;
+
     let v = self.look_as_vec() * 0.4;
-    other.damage(damage, true, Vec3::new(v.x, 0.4, v.z));
+    let cause = DamageCause::Player { eid: self.eid(), name: self.username().clone() };
+    if !other.damage_by(cause.clone(), damage, true, Vec3::new(v.x, 0.4, v.z)) {
+      return;
+    }
+    self.add_exhaustion(0.1);
+
+    let sound = if crit {
+      self.send_all_in_view(cb::packet::Animation { eid, kind: cb::AnimationKind::Crit });
+      "entity.player.attack.crit"
+    } else if sweep {
+      for ent in nearby {
+        if let Some(ent) = ent.as_entity_ref(&world) {
+          ent.damage_by(cause.clone(), 1.0, true, Vec3::new(v.x, 0.4, v.z) * 0.5);
+        }
+      }
+      "entity.player.attack.sweep"
+    } else if sprinting {
+      "entity.player.attack.knockback"
+    } else {
+      "entity.player.attack.strong"
+    };
+    world.play_sound(sound.into(), cb::SoundCategory::Players, self.pos(), 1.0, 1.0);
   }
 
   /// Returns true if the player can be damaged. This will return `false` if
   /// they are in creative, or are dead.
   ///
-  /// This will add more checks in the future.
+  /// Unlike vanilla, players in creative will not take void damage. They can
+  /// fly back up instead.
   pub fn damageable(&self) -> bool {
-    // TODO: Void damage
     matches!(self.game_mode(), GameMode::Survival | GameMode::Adventure) && self.alive()
   }
 
@@ -739,33 +1073,82 @@ impl Player {
   /// absorption will affect the amount of damage. If `blockable` is false, then
   /// this will deal exactly `damage` amount to the player.
   ///
+  /// The `knockback` is used to find the direction the damage came from, so a
+  /// shield will only block damage from the front. If `knockback` is zero, a
+  /// shield will block damage from any direction.
+  ///
   /// Returns if the entity has been successfully damaged. 0 damage will still
   /// return `true`. This will only return `false` if [`Player::damageable`] is
-  /// `false`.
+  /// `false`, or if the damage was blocked with a shield.
   pub fn damage(&self, mut amount: f32, blockable: bool, knockback: Vec3) -> bool {
     if !self.damageable() {
       return false;
     }
+    // Each level of resistance blocks 20% of all damage.
+    if let Some(amp) = self.effect_amplifier(Effect::Resistance) {
+      amount *= (1.0 - 0.2 * (f32::from(amp) + 1.0)).max(0.0);
+    }
 
     let mut health = self.health.lock();
-    let food = self.food.lock();
+    let mut food = self.food.lock();
 
     if health.hit_delay > 0 {
       return false;
     }
 
     if blockable {
-      // TODO: Blocking
-      /*
-      if self.is_blocking() {
-        damage = (1.0 + damage) * 0.5;
+      if self.world().gamerule("hunger") {
+        food.exhaust(0.1);
+      }
+      let using = self.using_hand();
+      // The position must be locked before the inventory.
+      let look = self.look_as_vec();
+      let pos = self.pos();
+      let mut inv = self.lock_inventory();
+      if let Some(hand) = using {
+        let ty = inv.hand(hand).item();
+        let facing = look.x * knockback.x + look.z * knockback.z < 0.0
+          || (knockback.x == 0.0 && knockback.z == 0.0);
+        if ty == item::Type::Shield && facing {
+          let broke = amount >= 3.0 && inv.damage_hand(hand, 1 + amount as u16);
+          drop(inv);
+          if broke {
+            self.stop_using();
+            self.world().play_sound(
+              "entity.item.break".into(),
+              cb::SoundCategory::Players,
+              pos,
+              1.0,
+              1.0,
+            );
+          }
+          self.world().play_sound(
+            "item.shield.block".into(),
+            cb::SoundCategory::Players,
+            pos,
+            1.0,
+            1.0,
+          );
+          return false;
+        } else if ty.weapon().map(|w| w.is_sword()).unwrap_or(false) {
+          // Sword blocking on 1.8
+          amount = (1.0 + amount) * 0.5;
+        }
+      }
+      // Armor loses durability based on the damage before it was reduced.
+      let (defense, toughness) = inv.armor_totals();
+      let broke = inv.damage_armor(amount);
+      amount = item::reduce_damage(amount, defense, toughness);
+      drop(inv);
+      if broke {
+        self.world().play_sound(
+          "entity.item.break".into(),
+          cb::SoundCategory::Players,
+          pos,
+          1.0,
+          1.0,
+        );
       }
-      */
-      // TODO: Armor damage and armor reducing damage
-      /*
-      let armor_damage = 25.0 - other.armor_total();
-      damage = (damage * armor_damage) / 25.0;
-       */
     }
     // Here, vanilla would send a metadata update for self's health. This
     // doesn't make any sense, as other players (with a hacked client) can see
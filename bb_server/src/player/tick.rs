@@ -1,8 +1,9 @@
 use super::{DigProgress, Player, PlayerPosition};
-use crate::{block, math::Vec3};
+use crate::{block, math::Vec3, net::Outgoing};
 use bb_common::{
   math::{ChunkPos, Pos},
   net::cb,
+  util::Effect,
   version::ProtocolVersion,
 };
 use std::{
@@ -29,6 +30,13 @@ impl Player {
     let pos_changed;
     let needs_set_pos;
     let mut invalid_move = None;
+    let mut landed = None;
+    let walked;
+    let jumped;
+    // Speed lets the player move faster, and these effects stop the player
+    // from taking fall damage.
+    let speed = self.effect_amplifier(Effect::Speed).map_or(0.0, |a| f64::from(a) + 1.0);
+    let floating = self.has_effect(Effect::Levitation) || self.has_effect(Effect::SlowFalling);
     let pos = {
       let mut pos = self.pos.lock();
       self.update_dig_progress(&mut pos);
@@ -49,7 +57,9 @@ impl Player {
       // goes above 2 every now and then.
       let flying = self.flying();
       let accel_len = accel.len();
-      if (!flying && accel_len > 3.0) || (flying && accel_len > f64::from(self.fly_speed()) * 5.0) {
+      if (!flying && accel_len > 3.0 * (1.0 + 0.2 * speed))
+        || (flying && accel_len > f64::from(self.fly_speed()) * 5.0)
+      {
         warn!(
           "{} moved too fast (pos: {} {} {})",
           self.username, pos.curr.x, pos.curr.y, pos.curr.z
@@ -68,6 +78,22 @@ impl Player {
         pos.curr = pos.next;
       }
 
+      let fell_by = pos.prev.y() - pos.curr.y();
+      if flying || floating {
+        pos.fall_distance = 0.0;
+      } else if pos.on_ground {
+        if pos.fall_distance > 0.0 {
+          landed = Some(pos.fall_distance);
+          pos.fall_distance = 0.0;
+        }
+      } else if fell_by > 0.0 {
+        pos.fall_distance += fell_by;
+      }
+      let moved = Vec3::from(pos.curr - pos.prev);
+      walked = (moved.x * moved.x + moved.z * moved.z).sqrt();
+      jumped = pos.was_on_ground && !pos.on_ground && moved.y > 0.0;
+      pos.was_on_ground = pos.on_ground;
+
       pos.yaw = pos.next_yaw;
       // We want to keep yaw within -180..=180
       pos.yaw %= 360.0;
@@ -108,7 +134,7 @@ impl Player {
     // Handle edge case for players sending dig finish too early.
     self.check_dig_wants_finish();
     if pos_changed || look_changed {
-      for other in self.world.players().iter().in_view(pos.curr.chunk()).not(self.uuid) {
+      for other in self.world().players().iter().in_view(pos.curr.chunk()).not(self.uuid) {
         // Make player move for other
         let yaw;
         let pitch;
@@ -263,6 +289,10 @@ impl Player {
     if health.hit_delay > 0 {
       health.hit_delay -= 1;
     }
+    drop(health);
+    self.tick_effects();
+    self.tick_food(walked, jumped, pos.sprinting);
+    self.environment_damage(landed, pos.curr);
   }
 
   /// Loads the chunks between min and max, inclusive.
@@ -278,24 +308,24 @@ impl Player {
     for x in min.x()..=max.x() {
       for z in min.z()..=max.z() {
         let pos = ChunkPos::new(x, z);
-        if self.world.has_loaded_chunk(pos) {
-          self.send_chunk(pos, || self.world.serialize_chunk(pos).into());
+        if self.world().has_loaded_chunk(pos) {
+          self.send_chunk(pos, || self.world().encode_chunk(pos));
         } else {
-          self.world.queue_chunk(pos, self);
+          self.world().queue_chunk(pos, self);
         }
       }
     }
   }
   /// Sends the chunk to the client, and records that the client knows about
   /// this chunk. This makes sure we avoid ever leaking memory on the client.
-  pub(crate) fn send_chunk(&self, pos: ChunkPos, f: impl FnOnce() -> cb::Packet) {
+  pub(crate) fn send_chunk(&self, pos: ChunkPos, f: impl FnOnce() -> Outgoing) {
     if self.in_view(pos) {
       let mut lock = self.loaded_chunks.lock();
       if !lock.contains(&pos) {
         lock.insert(pos);
         drop(lock);
-        self.world.inc_view(pos);
-        self.send(f());
+        self.world().inc_view(pos);
+        self.conn.send_outgoing(f());
       }
     }
   }
@@ -305,31 +335,37 @@ impl Player {
     let mut lock = self.loaded_chunks.lock();
     if lock.remove(&pos) {
       drop(lock);
-      self.world.dec_view(pos);
+      self.world().dec_view(pos);
       self.send(cb::packet::UnloadChunk { pos });
     }
   }
-  /// Unloads all the chunks that this player can see from the world. This will
-  /// call dec_view for all the chunks this player can see. This does not send
-  /// any packets! It should only be used internally when a player is being
-  /// removed.
-  ///
-  /// Note that this won't update the `loaded_chunks` table. This is again
-  /// because the player is about to be removed, so this table will never be
-  /// looked up again.
+  /// Releases every chunk this player has loaded, by calling `dec_view` on
+  /// each of them. This does not send any packets! It should only be used
+  /// internally when a player is being removed.
   pub(crate) fn unload_all(&self) {
-    let chunk = {
-      let pos = self.pos.lock();
-      pos.curr.block().chunk()
-    };
+    let loaded: Vec<ChunkPos> = self.loaded_chunks.lock().drain().collect();
+    for pos in loaded {
+      self.world().dec_view(pos);
+    }
+  }
+  /// Unloads every chunk this player knows about, and sends an unload packet
+  /// for each of them. This also cancels any chunks that are queued for this
+  /// player. This is used when moving the player into another world.
+  pub(crate) fn unload_world(&self) {
+    let world = self.world();
+    let chunk = self.pos().block().chunk();
     let v = self.view_distance as i32;
-    let max = chunk + ChunkPos::new(v, v);
-    let min = chunk - ChunkPos::new(v, v);
-    for x in min.x()..=max.x() {
-      for z in min.z()..=max.z() {
-        self.world.dec_view(ChunkPos::new(x, z));
+    for x in -v..=v {
+      for z in -v..=v {
+        world.unqueue_chunk(chunk + ChunkPos::new(x, z), self);
       }
     }
+    // `send_unload_chunk` calls `dec_view` for each chunk, so we must not call
+    // `unload_all` here as well.
+    let loaded: Vec<ChunkPos> = self.loaded_chunks.lock().iter().copied().collect();
+    for pos in loaded {
+      self.send_unload_chunk(pos);
+    }
   }
   fn unload_chunks(&self, min: ChunkPos, max: ChunkPos) {
     if min == max {
@@ -338,7 +374,7 @@ impl Player {
     for x in min.x()..=max.x() {
       for z in min.z()..=max.z() {
         let pos = ChunkPos::new(x, z);
-        self.world.unqueue_chunk(pos, self);
+        self.world().unqueue_chunk(pos, self);
         self.send_unload_chunk(pos);
       }
     }
@@ -346,7 +382,7 @@ impl Player {
 
   pub(crate) fn start_digging(&self, pos: Pos) {
     // Silently ignore dig packets outside the world.
-    if let Ok(kind) = self.world.get_kind(pos) {
+    if let Ok(kind) = self.world().get_kind(pos) {
       let mut ppos = self.pos.lock();
       ppos.dig_progress = Some(DigProgress::new(pos, kind));
       // For some reason, I end up being around a tick behind the client when they
@@ -387,7 +423,9 @@ impl Player {
         pos,
         self.world().get_block(pos).unwrap().ty(),
       ) {
-        if !self.world().break_block(pos).unwrap() {
+        if self.world().break_block(pos).unwrap() {
+          self.add_exhaustion(0.005);
+        } else {
           self.sync_block_at(pos);
         }
       } else {
@@ -406,22 +444,21 @@ impl Player {
       let mut speed = self
         .lock_inventory()
         .main_hand()
-        .mining_speed(self.world.world_manager().block_converter().get(p.kind));
-
-      // TODO: Multiply by haste:
-      // if self.has_haste() {
-      //   speed *= 1.0 + (haste_level + 1) * 0.2;
-      // }
+        .mining_speed(self.world().world_manager().block_converter().get(p.kind));
 
-      // TODO: Multiply by mining fatigue:
-      // match mining_fatigue {
-      //   0 => speed * 0.3,
-      //   1 => speed * 0.09,
-      //   2 => speed * 0.0027,
-      //   _ => speed * 0.00081,
-      // }
+      if let Some(haste) = self.effect_amplifier(Effect::Haste) {
+        speed *= 1.0 + (f64::from(haste) + 1.0) * 0.2;
+      }
+      if let Some(fatigue) = self.effect_amplifier(Effect::MiningFatigue) {
+        speed *= match fatigue {
+          0 => 0.3,
+          1 => 0.09,
+          2 => 0.0027,
+          _ => 0.00081,
+        };
+      }
 
-      if self.world.get_kind(pos.curr.block()) == Ok(block::Kind::Water) {
+      if self.world().get_kind(pos.curr.block()) == Ok(block::Kind::Water) {
         speed *= 0.2;
       }
 
@@ -456,7 +493,9 @@ impl Player {
         pos,
         self.world().get_block(pos).unwrap().ty(),
       ) {
-        if !self.world().break_block(pos).unwrap() {
+        if self.world().break_block(pos).unwrap() {
+          self.add_exhaustion(0.005);
+        } else {
           self.sync_block_at(pos);
         }
       } else {
@@ -1,150 +1,215 @@
 #[cfg(feature = "panda_plugins")]
 use super::PandaPlugin;
 
-use super::{GlobalServerEvent, Plugin, ServerEvent, ServerRequest};
+use super::{
+  CallError, GlobalServerEvent, GlobalServerRequest, Plugin, ServerEvent, ServerRequest, Storage,
+};
 use crate::{player::Player, world::WorldManager};
-use bb_common::config::Config;
+use bb_common::{config::Config, version::ProtocolVersion};
 use crossbeam_channel::Select;
 use parking_lot::Mutex;
 use std::{
-  fs,
-  sync::Arc,
+  collections::{HashMap, HashSet},
+  fmt, fs, mem,
+  path::Path,
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+  },
+  thread,
   time::{Duration, Instant},
 };
 
 /// A struct that manages all plugins. This will handle re-loading all the
-/// source files on `/reload`, and will also send events to all the plugins when
-/// needed.
+/// source files on `/plugin reload`, and will also send events to all the
+/// plugins when needed.
 pub struct PluginManager {
   start:              Instant,
   pub(super) plugins: Mutex<Vec<Plugin>>,
+  /// Plugins disabled with `/plugin disable`. These will be skipped when
+  /// loading plugins, until they are enabled again.
+  disabled:           Mutex<HashSet<String>>,
+  /// Set once the thread watching for plugin changes has been started.
+  watching:           AtomicBool,
+  /// The persistent store for each plugin. These are kept here instead of in
+  /// each plugin, so that reloading a plugin doesn't lose unsaved data.
+  storage:            Mutex<HashMap<String, Arc<Storage>>>,
+}
+
+/// The directory all plugins are stored in.
+const PLUGINS_PATH: &str = "plugins";
+
+/// Returns true if `name` can be the name of a plugin directory. Names passed
+/// to [`PluginManager::reload`] come from commands, so they must not be able to
+/// point outside of [`PLUGINS_PATH`].
+fn is_valid_plugin_name(name: &str) -> bool {
+  !name.is_empty() && !name.contains(['/', '\\']) && !name.contains("..")
 }
 
 impl PluginManager {
   /// Creates a new plugin manager. This will initialize the Ruby interpreter,
   /// and load all plugins from disk. Do not call this multiple times.
   #[allow(clippy::new_without_default)]
-  pub fn new() -> Self { PluginManager { start: Instant::now(), plugins: Mutex::new(vec![]) } }
+  pub fn new() -> Self {
+    PluginManager {
+      start:    Instant::now(),
+      plugins:  Mutex::new(vec![]),
+      disabled: Mutex::new(HashSet::new()),
+      watching: AtomicBool::new(false),
+      storage:  Mutex::new(HashMap::new()),
+    }
+  }
+
+  /// Returns the persistent store for the given plugin. The store will be
+  /// loaded from `dir` the first time this is called. Prefer
+  /// [`WorldManager::plugin_storage`], which passes the right directory.
+  pub fn storage(&self, dir: &Path, plugin: &str) -> Arc<Storage> {
+    self
+      .storage
+      .lock()
+      .entry(plugin.into())
+      .or_insert_with(|| Arc::new(Storage::load(dir, plugin)))
+      .clone()
+  }
+  /// Saves every plugin's store to disk.
+  pub fn save_storage(&self) {
+    for storage in self.storage.lock().values() {
+      storage.save();
+    }
+  }
 
   /// Returns true if plugins should print error messages with colors.
   pub fn use_color(&self) -> bool { true }
 
   /// Loads all plugins from disk. Call this to reload all plugins.
   pub fn load(&self, wm: Arc<WorldManager>) {
-    let mut plugins = self.plugins.lock();
-    plugins.clear();
-
-    #[cfg(feature = "socket_plugins")]
-    let mut sockets = super::socket::SocketManager::new(wm.clone());
+    let old = mem::take(&mut *self.plugins.lock());
+    for p in &old {
+      wm.commands().remove_plugin(p.name());
+    }
+    drop(old);
 
-    let iter = match fs::read_dir("plugins") {
+    let iter = match fs::read_dir(PLUGINS_PATH) {
       Ok(v) => v,
       Err(e) => {
-        warn!("error reading directory `plugins`: {e}");
+        warn!("error reading directory `{PLUGINS_PATH}`: {e}");
         return;
       }
     };
+    let mut loader = Loader::new(self, &wm);
+    let mut plugins = vec![];
     for f in iter {
-      let f = f.unwrap();
-      let m = fs::metadata(f.path()).unwrap();
-      if m.is_dir() {
-        let path = f.path();
-        let config = Config::new_write_default(
-          path.join("plugin.toml").to_str().unwrap(),
-          path.join("plugin-default.toml").to_str().unwrap(),
-          include_str!("plugin.toml"),
-        );
-        if !config.get::<bool>("enabled") {
+      let path = f.unwrap().path();
+      if path.is_dir() {
+        let name = path.file_stem().unwrap().to_str().unwrap().to_string();
+        if self.disabled.lock().contains(&name) {
+          info!("plugin `{name}` is disabled, skipping");
           continue;
         }
-        let ty: String = config.get("type");
-        let name = path.file_stem().unwrap().to_str().unwrap().to_string();
-        match ty.as_str() {
-          "socket" => {
-            info!("found socket plugin at {}", path.to_str().unwrap());
-            #[cfg(feature = "socket_plugins")]
-            {
-              if let Some(plugin) = sockets.add(name.clone(), f.path()) {
-                plugins.push(Plugin::new(name.clone(), config, plugin));
-              }
-            }
-            #[cfg(not(feature = "socket_plugins"))]
-            {
-              info!("socket plugins are disabling, skipping {}", path.to_str().unwrap());
-            }
-          }
-          "python" => {
-            info!("found python plugin at {}", path.to_str().unwrap());
-            #[cfg(feature = "python_plugins")]
-            {
-              plugins.push(Plugin::new(
-                name.clone(),
-                config,
-                super::python::Plugin::new(name.clone()),
-              ));
-            }
-            #[cfg(not(feature = "python_plugins"))]
-            {
-              info!("python plugins are disabling, skipping {}", path.to_str().unwrap());
-            }
-          }
-          "wasm" => {
-            info!("found wasm plugin at {}", path.to_str().unwrap());
-            #[cfg(feature = "wasm_plugins")]
-            {
-              match super::wasm::Plugin::new(
-                name.clone(),
-                &path,
-                config.get_at(["wasm", "compile"].into_iter()),
-                config.get_at(["wasm", "output"].into_iter()),
-                wm.clone(),
-              ) {
-                Ok(p) => plugins.push(Plugin::new(name.clone(), config, p)),
-                Err(e) => error!("error loading {name}: {e}"),
-              }
-            }
-            #[cfg(not(feature = "wasm_plugins"))]
-            {
-              info!("wasm plugins are disabling, skipping {}", path.to_str().unwrap());
-            }
-          }
-          "panda" => {
-            let main_path = f.path().join("main.pand");
-            info!("found panda plugin at {}", main_path.to_str().unwrap());
-            #[cfg(feature = "panda_plugins")]
-            {
-              if main_path.exists() && main_path.is_file() {
-                let name = f.path().file_stem().unwrap().to_str().unwrap().to_string();
-                let mut p = PandaPlugin::new(plugins.len(), name.clone(), wm.clone());
-
-                p.load_from_dir(&f.path(), self);
-                p.call_init();
-                plugins.push(Plugin::new(name.clone(), config, p));
-              } else {
-                error!("plugin `{name}` does not have a `main.pand` file");
-              }
-            }
-            #[cfg(not(feature = "panda_plugins"))]
-            {
-              info!("panda plugins are disabling, skipping {}", main_path.to_str().unwrap());
-            }
-          }
-          _ => error!("plugin `{name}` has invalid plugin type: `{ty}`"),
+        if let Some(p) = loader.load(name, &path) {
+          plugins.push(p);
         }
       }
     }
+    // We don't hold the lock while loading, as plugins may add commands in
+    // `init`, and command handlers lock the plugins.
+    self.plugins.lock().extend(plugins);
+    loader.finish();
+    self.plugins_changed(&wm);
+  }
 
-    #[cfg(feature = "socket_plugins")]
-    {
-      let plugins = sockets.take_plugins();
-      std::thread::spawn(|| {
-        sockets.listen();
-      });
-      for plug in plugins {
-        plug.wait_for_ready().unwrap();
-        plug.clone().spawn_listener();
+  /// Loads a single plugin from disk. If the plugin is already loaded, it will
+  /// be unloaded first. Returns `false` if the plugin couldn't be loaded, or if
+  /// it has been disabled. Any errors will be logged.
+  pub fn reload(&self, wm: &Arc<WorldManager>, name: &str) -> bool {
+    if !is_valid_plugin_name(name) {
+      warn!("invalid plugin name `{name}`");
+      return false;
+    }
+    self.unload(wm, name);
+    if self.disabled.lock().contains(name) {
+      warn!("plugin `{name}` is disabled, not loading it");
+      return false;
+    }
+    let path = Path::new(PLUGINS_PATH).join(name);
+    if !path.is_dir() {
+      warn!("there is no plugin directory at `{}`", path.display());
+      return false;
+    }
+    let mut loader = Loader::new(self, wm);
+    let plugin = loader.load(name.into(), &path);
+    let loaded = plugin.is_some();
+    self.plugins.lock().extend(plugin);
+    loader.finish();
+    self.plugins_changed(wm);
+    loaded
+  }
+
+  /// Unloads the given plugin, and removes all of its commands. Returns `false`
+  /// if the plugin was not loaded.
+  pub fn unload(&self, wm: &Arc<WorldManager>, name: &str) -> bool {
+    let plugin = {
+      let mut plugins = self.plugins.lock();
+      match plugins.iter().position(|p| p.name() == name) {
+        Some(idx) => plugins.remove(idx),
+        None => return false,
+      }
+    };
+    // Dropping the plugin will stop its thread, and close any sockets.
+    drop(plugin);
+    if let Some(storage) = self.storage.lock().get(name) {
+      storage.save();
+    }
+    wm.commands().remove_plugin(name);
+    self.plugins_changed(wm);
+    true
+  }
+
+  /// Enables the given plugin, and loads it if it isn't already loaded. Returns
+  /// `false` if the plugin couldn't be loaded.
+  pub fn enable(&self, wm: &Arc<WorldManager>, name: &str) -> bool {
+    self.disabled.lock().remove(name);
+    self.is_loaded(name) || self.reload(wm, name)
+  }
+  /// Disables the given plugin, and unloads it. It will stay disabled until
+  /// [`enable`](Self::enable) is called, even if all plugins are reloaded.
+  /// Returns `false` if the plugin was not loaded.
+  pub fn disable(&self, wm: &Arc<WorldManager>, name: &str) -> bool {
+    self.disabled.lock().insert(name.into());
+    self.unload(wm, name)
+  }
+
+  /// Returns true if a plugin with the given name is loaded.
+  pub fn is_loaded(&self, name: &str) -> bool {
+    self.plugins.lock().iter().any(|p| p.name() == name)
+  }
+  /// Returns the names of all the loaded plugins.
+  pub fn names(&self) -> Vec<String> {
+    self.plugins.lock().iter().map(|p| p.name().into()).collect()
+  }
+  /// Returns the names of all the plugins disabled with
+  /// [`disable`](Self::disable).
+  pub fn disabled_names(&self) -> Vec<String> {
+    let mut names: Vec<_> = self.disabled.lock().iter().cloned().collect();
+    names.sort();
+    names
+  }
+
+  /// Called whenever a plugin is loaded or unloaded. This sends the new command
+  /// tree to all players, and starts the file watcher if needed.
+  fn plugins_changed(&self, wm: &Arc<WorldManager>) {
+    let commands = wm.commands().serialize();
+    for p in wm.all_players() {
+      if p.ver() >= ProtocolVersion::V1_13 {
+        p.send(commands.clone());
       }
     }
+    if self.plugins.lock().iter().any(|p| p.watches())
+      && !self.watching.swap(true, Ordering::SeqCst)
+    {
+      let wm = wm.clone();
+      thread::spawn(move || watch(wm));
+    }
   }
 
   pub(crate) fn event(&self, player: Arc<Player>, event: ServerEvent) {
@@ -159,21 +224,56 @@ impl PluginManager {
       Err(e) => e.keep,
     });
   }
+  /// Sends the given request to every plugin, and waits for all of them to
+  /// reply. Each plugin has its own reply timeout (set with `reply-timeout` in
+  /// `plugin.toml`). Any plugin that doesn't reply in time is assumed to allow
+  /// the action.
+  ///
+  /// Returns `true` if every plugin allowed the action. If a single plugin
+  /// replies with `PluginReply::Cancel { allow: false }`, this will return
+  /// `false`.
   pub(crate) fn req(&self, player: Arc<Player>, request: ServerRequest) -> bool {
+    self.req_with(&request, |p, reply_id| p.req(reply_id, player.clone(), request.clone()))
+  }
+  /// Sends the given request to every plugin, and waits for all of them to
+  /// reply. This is the same as [`req`](Self::req), but for requests that
+  /// don't have a player.
+  pub(crate) fn req_global(&self, request: GlobalServerRequest) -> bool {
+    self.req_with(&request, |p, reply_id| p.req_global(reply_id, request.clone()))
+  }
+  fn req_with(
+    &self,
+    request: &dyn fmt::Debug,
+    send: impl Fn(&Plugin, u32) -> Result<(), CallError>,
+  ) -> bool {
     let reply_id = self.start.elapsed().as_micros() as u32;
     let mut plugins = self.plugins.lock();
     // Send all the events first.
-    plugins.retain(|p| match p.req(reply_id, player.clone(), request.clone()) {
+    plugins.retain(|p| match send(p, reply_id) {
       Ok(_) => true,
       Err(e) => e.keep,
     });
     // Then wait on all of them.
     let mut allow = true;
-    let mut plugins_left: Vec<_> = plugins.iter_mut().collect();
-    let deadline = Instant::now() + Duration::from_millis(50);
+    let sent = Instant::now();
+    let mut plugins_left: Vec<_> = plugins.iter().map(|p| (sent + p.reply_timeout(), p)).collect();
     while !plugins_left.is_empty() {
+      // Any plugins that have run out of time are skipped.
+      let now = Instant::now();
+      plugins_left.retain(|(deadline, p)| {
+        if *deadline <= now {
+          warn!("plugin `{}` did not reply to {:?} in time", p.name(), request);
+          false
+        } else {
+          true
+        }
+      });
+      let deadline = match plugins_left.iter().map(|(deadline, _)| *deadline).min() {
+        Some(d) => d,
+        None => break,
+      };
       let mut sel = Select::new();
-      for p in &plugins_left {
+      for (_, p) in &plugins_left {
         sel.recv(p.rx());
       }
       let index;
@@ -181,12 +281,13 @@ impl PluginManager {
       match sel.select_deadline(deadline) {
         Ok(op) => {
           index = op.index();
-          let plugin = &plugins_left[index];
+          let (_, plugin) = &plugins_left[index];
           message = op.recv(plugin.rx()).unwrap();
         }
-        Err(_) => return allow,
+        // The earliest deadline has passed, so we loop around and remove that plugin.
+        Err(_) => continue,
       }
-      let plugin = &mut plugins_left[index];
+      let (_, plugin) = &plugins_left[index];
       let now = self.start.elapsed().as_micros() as u32;
       match plugin.check_allow(message, now, reply_id) {
         Some(false) => allow = false,
@@ -199,3 +300,167 @@ impl PluginManager {
     allow
   }
 }
+
+/// Checks every watched plugin for changes once a second, and reloads any
+/// plugins that have changed. This runs forever.
+fn watch(wm: Arc<WorldManager>) {
+  loop {
+    thread::sleep(Duration::from_secs(1));
+    let changed: Vec<String> =
+      wm.plugins().plugins.lock().iter().filter(|p| p.changed()).map(|p| p.name().into()).collect();
+    for name in changed {
+      info!("plugin `{name}` changed, reloading");
+      wm.plugins().reload(&wm, &name);
+    }
+  }
+}
+
+/// Loads plugins from disk. Socket plugins are all started at once, in
+/// [`finish`](Self::finish).
+struct Loader<'a> {
+  #[allow(unused)]
+  manager: &'a PluginManager,
+  #[allow(unused)]
+  wm:      &'a Arc<WorldManager>,
+  #[cfg(feature = "socket_plugins")]
+  sockets: super::socket::SocketManager,
+}
+
+impl<'a> Loader<'a> {
+  pub fn new(manager: &'a PluginManager, wm: &'a Arc<WorldManager>) -> Self {
+    Loader {
+      manager,
+      wm,
+      #[cfg(feature = "socket_plugins")]
+      sockets: super::socket::SocketManager::new(wm.clone()),
+    }
+  }
+
+  /// Loads the plugin in the given directory. Returns `None` if the plugin is
+  /// disabled in its config, or if it failed to load.
+  pub fn load(&mut self, name: String, path: &Path) -> Option<Plugin> {
+    let config = Config::new_write_default(
+      path.join("plugin.toml").to_str().unwrap(),
+      path.join("plugin-default.toml").to_str().unwrap(),
+      include_str!("plugin.toml"),
+    );
+    if !config.get::<bool>("enabled") {
+      return None;
+    }
+    let ty: String = config.get("type");
+    match ty.as_str() {
+      "socket" => {
+        info!("found socket plugin at {}", path.to_str().unwrap());
+        #[cfg(feature = "socket_plugins")]
+        {
+          let timeout = Duration::from_millis(config.get("reply-timeout"));
+          if let Some(plugin) = self.sockets.add(name.clone(), path.to_path_buf(), timeout) {
+            return Some(Plugin::new(name, path, config, plugin));
+          }
+        }
+        #[cfg(not(feature = "socket_plugins"))]
+        {
+          info!("socket plugins are disabling, skipping {}", path.to_str().unwrap());
+        }
+      }
+      "python" => {
+        info!("found python plugin at {}", path.to_str().unwrap());
+        #[cfg(feature = "python_plugins")]
+        {
+          match super::python::Plugin::new(name.clone(), path, self.wm.clone()) {
+            Ok(p) => {
+              p.call_init();
+              return Some(Plugin::new(name, path, config, p));
+            }
+            Err(e) => error!("error loading {name}: {e}"),
+          }
+        }
+        #[cfg(not(feature = "python_plugins"))]
+        {
+          info!("python plugins are disabling, skipping {}", path.to_str().unwrap());
+        }
+      }
+      "wasm" => {
+        info!("found wasm plugin at {}", path.to_str().unwrap());
+        #[cfg(feature = "wasm_plugins")]
+        {
+          match super::wasm::Plugin::new(
+            name.clone(),
+            path,
+            config.get_at(["wasm", "compile"].into_iter()),
+            config.get_at(["wasm", "output"].into_iter()),
+            super::wasm::Limits::from_config(&config),
+            self.wm.clone(),
+          ) {
+            Ok(p) => return Some(Plugin::new(name, path, config, p)),
+            Err(e) => error!("error loading {name}: {e}"),
+          }
+        }
+        #[cfg(not(feature = "wasm_plugins"))]
+        {
+          info!("wasm plugins are disabling, skipping {}", path.to_str().unwrap());
+        }
+      }
+      "panda" => {
+        let main_path = path.join("main.pand");
+        info!("found panda plugin at {}", main_path.to_str().unwrap());
+        #[cfg(feature = "panda_plugins")]
+        {
+          if main_path.exists() && main_path.is_file() {
+            let mut p = PandaPlugin::new(name.clone(), self.wm.clone());
+
+            p.load_from_dir(path, self.manager);
+            p.call_init();
+            return Some(Plugin::new(name, path, config, p));
+          } else {
+            error!("plugin `{name}` does not have a `main.pand` file");
+          }
+        }
+        #[cfg(not(feature = "panda_plugins"))]
+        {
+          info!("panda plugins are disabling, skipping {}", main_path.to_str().unwrap());
+        }
+      }
+      _ => error!("plugin `{name}` has invalid plugin type: `{ty}`"),
+    }
+    None
+  }
+
+  /// Starts all the socket plugins that were loaded.
+  pub fn finish(self) {
+    #[cfg(feature = "socket_plugins")]
+    {
+      let mut sockets = self.sockets;
+      let socket_plugins = sockets.take_plugins();
+      if socket_plugins.is_empty() {
+        return;
+      }
+      thread::spawn(|| {
+        sockets.listen();
+      });
+      for plug in socket_plugins {
+        if plug.wait_for_ready().is_err() {
+          // The socket was closed, so the next event will remove this plugin.
+          error!("socket plugin disconnected before it was ready");
+        }
+        plug.spawn_listener();
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn plugin_names() {
+    assert!(is_valid_plugin_name("chat"));
+    assert!(is_valid_plugin_name("my-plugin_2"));
+    assert!(!is_valid_plugin_name(""));
+    assert!(!is_valid_plugin_name(".."));
+    assert!(!is_valid_plugin_name("../world"));
+    assert!(!is_valid_plugin_name("a/b"));
+    assert!(!is_valid_plugin_name("a\\b"));
+  }
+}
@@ -25,17 +25,23 @@ mod types {
       panic!("cannot call this callback in panda");
     }
     #[cfg(feature = "python_plugins")]
-    fn call_python(&self, args: Vec<pyo3::PyObject>) {
+    fn call_python(&self, _args: Vec<pyo3::PyObject>) {
       panic!("cannot call this callback in python");
     }
+    /// Returns true if this callback should be called with
+    /// [`call_python`](Self::call_python).
+    #[cfg(feature = "python_plugins")]
+    fn is_python(&self) -> bool { false }
 
     fn box_clone(&self) -> Box<dyn Callback>;
   }
 }
 
 mod manager;
+mod storage;
 
 pub use manager::PluginManager;
+pub use storage::{Scope, Storage};
 
 #[cfg(feature = "socket_plugins")]
 use socket::SocketManager;
@@ -43,7 +49,8 @@ use socket::SocketManager;
 use crate::{
   block,
   event::{
-    GlobalServerEvent, PluginMessage, PluginReply, ServerEvent, ServerMessage, ServerRequest,
+    GlobalServerEvent, GlobalServerRequest, PluginMessage, PluginReply, ServerEvent, ServerMessage,
+    ServerRequest,
   },
   player::Player,
   world::WorldManager,
@@ -52,7 +59,14 @@ use ::panda::runtime::VarSend;
 use bb_common::{config::Config, math::Pos};
 use crossbeam_channel::{Receiver, Sender};
 use parking_lot::{Mutex, MutexGuard};
-use std::{error::Error, fmt, sync::Arc, thread};
+use std::{
+  error::Error,
+  fmt, fs,
+  path::{Path, PathBuf},
+  sync::Arc,
+  thread,
+  time::{Duration, SystemTime},
+};
 
 #[derive(Debug)]
 pub enum Event {
@@ -63,21 +77,24 @@ pub enum Event {
 #[derive(Clone)]
 #[cfg_attr(feature = "python_plugins", ::pyo3::pyclass)]
 pub struct Bamboo {
-  // Index into plugins array
-  idx:  usize,
-  wm:   Arc<WorldManager>,
+  // The name of the plugin this belongs to. Used to find the plugin in the plugins array.
+  plugin: String,
+  wm:     Arc<WorldManager>,
   // Locking this removes the value. If the value is none, then this enters a wait loop until there
   // is a value present.
   //
   // This is not by any means "fast", but it will work as long as a thread doesn't lock this for
   // too long.
-  data: Arc<Mutex<Option<VarSend>>>,
+  data:   Arc<Mutex<Option<VarSend>>>,
 }
 
 impl Bamboo {
-  pub fn new(idx: usize, wm: Arc<WorldManager>) -> Self {
-    Bamboo { idx, wm, data: Arc::new(Mutex::new(Some(VarSend::None))) }
+  pub fn new(plugin: String, wm: Arc<WorldManager>) -> Self {
+    Bamboo { plugin, wm, data: Arc::new(Mutex::new(Some(VarSend::None))) }
   }
+
+  /// Returns the persistent store for this plugin.
+  pub fn storage(&self) -> Arc<Storage> { self.wm.plugin_storage(&self.plugin) }
 }
 
 impl fmt::Debug for Bamboo {
@@ -95,6 +112,9 @@ pub trait PluginImpl: std::any::Any {
   fn call_global(&self, event: GlobalServerEvent) -> Result<(), CallError>;
   /// Calls an event. This should block until it gets a reply.
   fn req(&self, player: Arc<Player>, event: ServerRequest) -> Result<PluginReply, CallError>;
+  /// Calls an event without a player. This should block until it gets a
+  /// reply.
+  fn req_global(&self, event: GlobalServerRequest) -> Result<PluginReply, CallError>;
   #[cfg(feature = "panda_plugins")]
   fn panda(&mut self) -> Option<&mut PandaPlugin> { None }
 }
@@ -102,14 +122,19 @@ pub trait PluginImpl: std::any::Any {
 pub struct Plugin {
   // This will be useful in the future. Probably.
   #[allow(unused)]
-  config:    Config,
-  #[allow(unused)]
-  name:      String,
-  imp:       Arc<Mutex<dyn PluginImpl + Send + Sync>>,
-  tx:        Sender<ServerMessage>,
-  rx:        Receiver<PluginMessage>,
+  config:        Config,
+  name:          String,
+  /// The files we check for changes, if `watch` is set in `plugin.toml`. If
+  /// any of these are modified after `loaded`, the plugin will be reloaded.
+  watch:         Vec<PathBuf>,
+  loaded:        SystemTime,
+  imp:           Arc<Mutex<dyn PluginImpl + Send + Sync>>,
+  tx:            Sender<ServerMessage>,
+  rx:            Receiver<PluginMessage>,
   /// Used to recycled events we don't care about back into the queue.
-  plugin_tx: Sender<PluginMessage>,
+  plugin_tx:     Sender<PluginMessage>,
+  /// The amount of time we will wait for a reply to a [`ServerRequest`].
+  reply_timeout: Duration,
 }
 
 #[derive(Debug)]
@@ -147,9 +172,15 @@ impl CallError {
 }
 
 impl Plugin {
-  pub fn new(name: String, config: Config, imp: impl PluginImpl + Send + Sync + 'static) -> Self {
+  pub fn new(
+    name: String,
+    path: &Path,
+    config: Config,
+    imp: impl PluginImpl + Send + Sync + 'static,
+  ) -> Self {
     let (server_tx, server_rx) = crossbeam_channel::bounded(128);
     let (plugin_tx, plugin_rx) = crossbeam_channel::bounded(128);
+    let reply_timeout = Duration::from_millis(config.get("reply-timeout"));
     let imp = Arc::new(Mutex::new(imp));
     let i = Arc::clone(&imp);
     let ptx = plugin_tx.clone();
@@ -159,7 +190,16 @@ impl Plugin {
           ServerMessage::Request { reply_id, player, request } => i
             .lock()
             .req(player, request)
-            .map(|reply| plugin_tx.send(PluginMessage::Reply { reply_id, reply }).unwrap()),
+            // If the plugin was removed while we were handling this, nobody is waiting for
+            // the reply anymore.
+            .map(|reply| {
+              let _ = plugin_tx.send(PluginMessage::Reply { reply_id, reply });
+            }),
+          ServerMessage::GlobalRequest { reply_id, request } => {
+            i.lock().req_global(request).map(|reply| {
+              let _ = plugin_tx.send(PluginMessage::Reply { reply_id, reply });
+            })
+          }
           ServerMessage::Event { player, event } => i.lock().call(player, event),
           ServerMessage::GlobalEvent { event } => i.lock().call_global(event),
           ServerMessage::Reply { .. } => Ok(()),
@@ -175,15 +215,56 @@ impl Plugin {
         }
       }
     });
-    Plugin { config, name, imp, tx: server_tx, rx: plugin_rx, plugin_tx: ptx }
+    let mut watch = vec![];
+    if config.get::<bool>("watch") {
+      watch.push(path.to_path_buf());
+      if config.get::<String>("type") == "wasm" {
+        let output: String = config.get_at(["wasm", "output"].into_iter());
+        watch.push(path.join(output));
+      }
+    }
+    Plugin {
+      config,
+      name,
+      watch,
+      loaded: SystemTime::now(),
+      imp,
+      tx: server_tx,
+      rx: plugin_rx,
+      plugin_tx: ptx,
+      reply_timeout,
+    }
+  }
+  /// Returns the name of this plugin.
+  pub fn name(&self) -> &str { &self.name }
+  /// Returns true if this plugin should be reloaded when its files change.
+  pub fn watches(&self) -> bool { !self.watch.is_empty() }
+  /// Returns true if any of the watched files have been modified since this
+  /// plugin was loaded. For directories, this checks every file directly
+  /// within that directory.
+  pub fn changed(&self) -> bool {
+    let modified_since = |path: &Path| {
+      fs::metadata(path).and_then(|m| m.modified()).map(|t| t > self.loaded).unwrap_or(false)
+    };
+    self.watch.iter().any(|path| {
+      if path.is_dir() {
+        match fs::read_dir(path) {
+          Ok(iter) => iter.flatten().any(|f| f.path().is_file() && modified_since(&f.path())),
+          Err(_) => false,
+        }
+      } else {
+        modified_since(path)
+      }
+    })
   }
+  /// Returns the amount of time the server should wait for this plugin to
+  /// reply to a request.
+  pub fn reply_timeout(&self) -> Duration { self.reply_timeout }
   pub fn call(&self, player: Arc<Player>, event: ServerEvent) -> Result<(), CallError> {
-    self.tx.send(ServerMessage::Event { player, event }).unwrap();
-    Ok(())
+    self.send(ServerMessage::Event { player, event })
   }
   pub fn call_global(&self, event: GlobalServerEvent) -> Result<(), CallError> {
-    self.tx.send(ServerMessage::GlobalEvent { event }).unwrap();
-    Ok(())
+    self.send(ServerMessage::GlobalEvent { event })
   }
   pub fn req(
     &self,
@@ -191,8 +272,19 @@ impl Plugin {
     player: Arc<Player>,
     request: ServerRequest,
   ) -> Result<(), CallError> {
-    self.tx.send(ServerMessage::Request { reply_id, player, request }).unwrap();
-    Ok(())
+    self.send(ServerMessage::Request { reply_id, player, request })
+  }
+  pub fn req_global(&self, reply_id: u32, request: GlobalServerRequest) -> Result<(), CallError> {
+    self.send(ServerMessage::GlobalRequest { reply_id, request })
+  }
+  /// Sends a message to the plugin's thread. If the plugin has stopped (for
+  /// example, it went over its resource limits), this returns an error, and
+  /// the plugin should be removed.
+  fn send(&self, msg: ServerMessage) -> Result<(), CallError> {
+    self.tx.send(msg).map_err(|e| {
+      warn!("plugin `{}` has stopped, removing it", self.name);
+      CallError::no_keep(e)
+    })
   }
   pub fn rx(&self) -> &Receiver<PluginMessage> { &self.rx }
   /// `Some(true)` means we allow.
@@ -203,7 +295,7 @@ impl Plugin {
       PluginMessage::Reply { reply_id, reply } => {
         // If it is too old, we discard this message. The listener for this reply has
         // probably already exited, so we just ignore it.
-        if reply_id + 50_000 < now {
+        if reply_id + (self.reply_timeout.as_micros() as u32) < now {
           return None;
         }
         if *reply_id == rid {
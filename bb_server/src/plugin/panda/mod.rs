@@ -1,6 +1,6 @@
 use super::{
-  types, types::Callback as BCallback, Bamboo, CallError, GlobalServerEvent, PluginImpl,
-  PluginManager, PluginReply, ServerEvent, ServerRequest,
+  types, types::Callback as BCallback, Bamboo, CallError, GlobalServerEvent, GlobalServerRequest,
+  PluginImpl, PluginManager, PluginReply, ServerEvent, ServerRequest,
 };
 use crate::{block, player::Player, world::WorldManager};
 use bb_common::{math::Pos, net::sb::ClickWindow};
@@ -33,8 +33,8 @@ impl BCallback for Callback {
 impl PandaPlugin {
   /// Creates a new plugin. The name should be the name of the plugin (for
   /// logs).
-  pub fn new(idx: usize, name: String, wm: Arc<WorldManager>) -> Self {
-    PandaPlugin { bb: Bamboo::new(idx, wm), name, sl: None }
+  pub fn new(name: String, wm: Arc<WorldManager>) -> Self {
+    PandaPlugin { bb: Bamboo::new(name.clone(), wm), name, sl: None }
   }
 
   pub fn name(&self) -> &String { &self.name }
@@ -139,6 +139,48 @@ impl PandaPlugin {
       _ => true,
     }
   }
+  pub fn call_on_interact(&self, player: Arc<Player>, block: Option<Pos>) -> bool {
+    match self.call(
+      self.path("on_interact"),
+      vec![
+        types::player::PPlayer::from(player).into(),
+        match block {
+          Some(pos) => types::util::PPos::from(pos).into(),
+          None => Var::None,
+        },
+      ],
+    ) {
+      Var::Bool(v) => v,
+      _ => true,
+    }
+  }
+  pub fn call_on_damage(&self, player: Arc<Player>, amount: f32, blockable: bool) -> bool {
+    match self.call(
+      self.path("on_damage"),
+      vec![types::player::PPlayer::from(player).into(), f64::from(amount).into(), blockable.into()],
+    ) {
+      Var::Bool(v) => v,
+      _ => true,
+    }
+  }
+  pub fn call_on_entity_damage(&self, eid: i32, amount: f32, blockable: bool) -> bool {
+    match self.call(
+      self.path("on_entity_damage"),
+      vec![eid.into(), f64::from(amount).into(), blockable.into()],
+    ) {
+      Var::Bool(v) => v,
+      _ => true,
+    }
+  }
+  pub fn call_on_death(&self, player: Arc<Player>, message: String) -> bool {
+    match self.call(
+      self.path("on_death"),
+      vec![types::player::PPlayer::from(player).into(), message.into()],
+    ) {
+      Var::Bool(v) => v,
+      _ => true,
+    }
+  }
   pub fn call_on_chat_message(&self, player: Arc<Player>, text: String) {
     self.call(
       self.path("on_chat_message"),
@@ -151,6 +193,9 @@ impl PandaPlugin {
   pub fn call_on_player_leave(&self, player: Arc<Player>) {
     self.call(self.path("on_player_leave"), vec![types::player::PPlayer::from(player).into()]);
   }
+  pub fn call_on_player_death(&self, player: Arc<Player>) {
+    self.call(self.path("on_player_death"), vec![types::player::PPlayer::from(player).into()]);
+  }
   pub fn call_on_tick(&self) { self.call(self.path("on_tick"), vec![]); }
 
   pub fn call(&self, path: TyPath, args: Vec<Var>) -> Var {
@@ -185,6 +230,9 @@ impl PluginImpl for PandaPlugin {
       ServerEvent::Chat { text } => self.call_on_chat_message(player, text),
       ServerEvent::PlayerJoin {} => self.call_on_player_join(player),
       ServerEvent::PlayerLeave {} => self.call_on_player_leave(player),
+      ServerEvent::PlayerDeath {} => self.call_on_player_death(player),
+      // Panda plugins add commands with callbacks, so this is never sent to them.
+      ServerEvent::Command { .. } => {}
     }
     Ok(())
   }
@@ -205,6 +253,20 @@ impl PluginImpl for PandaPlugin {
           self.call_on_block_break(player, pos, block.ty())
         }
         ServerRequest::ClickWindow { slot, mode } => self.call_on_click_window(player, slot, mode),
+        ServerRequest::Interact { block, .. } => self.call_on_interact(player, block),
+        ServerRequest::Damage { amount, blockable } => {
+          self.call_on_damage(player, amount, blockable)
+        }
+        ServerRequest::Death { message } => self.call_on_death(player, message),
+      },
+    })
+  }
+  fn req_global(&self, request: GlobalServerRequest) -> Result<PluginReply, CallError> {
+    Ok(PluginReply::Cancel {
+      allow: match request {
+        GlobalServerRequest::EntityDamage { eid, amount, blockable } => {
+          self.call_on_entity_damage(eid, amount, blockable)
+        }
       },
     })
   }
@@ -1,8 +1,53 @@
-use super::{types::Callback, CallError, PluginImpl, ServerMessage};
-use crossbeam_channel::{Receiver, Sender};
-use panda::runtime::RuntimeError;
-use pyo3::{exceptions, prelude::*};
-use std::{env, fs, path::Path, thread};
+//! Python plugins. Each plugin is loaded from `plugins/<name>/main.py`, and the
+//! plugin's directory is added to the python path, so it can import its own
+//! modules.
+//!
+//! All the plugin types are available in the `bamboo` module. These are the
+//! same types that Panda plugins use, without the `P` prefix. Every callback is
+//! passed a `Bamboo` instance as its first argument:
+//!
+//! ```python
+//! import bamboo
+//!
+//! def init(bb):
+//!   bb.add_command(bamboo.Command("hello", on_hello))
+//!
+//! def on_hello(bb, player, args):
+//!   player.send_message(bamboo.Chat("Hello!"))
+//!
+//! def on_block_place(bb, player, pos, kind):
+//!   return kind.to_s() != "tnt"
+//! ```
+//!
+//! Python plugins cannot generate terrain yet, so they cannot be used as a
+//! world generator.
+
+use super::{
+  types::{
+    block::{PBlockKind, PBlockType},
+    chat::{PChat, PChatSection},
+    command::PCommand,
+    item::{PClickWindow, PInventory, PStack, PUI},
+    player::{PPlayer, PTeam, PWindow},
+    storage::PStorage,
+    util::{PChunkPos, PFPos, PPos, PUUID},
+    world::{gen::PBiome, PWorld},
+    Callback,
+  },
+  Bamboo, CallError, GlobalServerEvent, GlobalServerRequest, PluginImpl, PluginReply, ServerEvent,
+  ServerRequest,
+};
+use crate::{command::Arg, player::Player, world::WorldManager};
+use panda::runtime::{RuntimeError, Var};
+use pyo3::{exceptions, prelude::*, types::PyTuple};
+use std::{
+  fs, io,
+  path::Path,
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+  },
+};
 
 pub struct PyCallback {
   callback: PyObject,
@@ -10,56 +55,275 @@ pub struct PyCallback {
 
 pub fn conv_err(err: RuntimeError) -> PyErr { exceptions::PyValueError::new_err(err.to_string()) }
 
+/// Converts a python value into a panda value. This is used for functions
+/// that take any value, like `Player.send_message`. Anything that isn't a
+/// number, boolean, or `Chat` is converted to a string.
+pub fn var_from_py(obj: &PyAny) -> Var {
+  if obj.is_none() {
+    Var::None
+  } else if let Ok(v) = obj.extract::<bool>() {
+    v.into()
+  } else if let Ok(v) = obj.extract::<i32>() {
+    v.into()
+  } else if let Ok(v) = obj.extract::<f64>() {
+    v.into()
+  } else if let Ok(v) = obj.extract::<PChat>() {
+    v.into()
+  } else {
+    obj.to_string().into()
+  }
+}
+
 impl Callback for PyObject {
+  fn call_python(&self, args: Vec<PyObject>) {
+    Python::with_gil(|py| {
+      if let Err(e) = self.call1(py, PyTuple::new(py, args)) {
+        error!("error in python callback: {e}");
+      }
+    });
+  }
+  fn is_python(&self) -> bool { true }
   fn box_clone(&self) -> Box<dyn Callback> { Box::new(self.clone()) }
 }
 
-pub struct Plugin {
-  tx: Sender<ServerMessage>,
+/// Creates a new command. This is a function in python, as commands need a
+/// python callback, and panda callbacks cannot be created from python.
+#[pyfunction]
+#[pyo3(name = "Command")]
+fn new_command(name: &str, callback: PyObject) -> PCommand {
+  PCommand::with_callback(name, Box::new(callback))
 }
 
-struct PyFuncs {
-  init: Py<PyAny>,
+/// Calls a command callback from a python plugin.
+pub fn call_command(cb: &dyn Callback, bb: &Bamboo, player: Option<&Arc<Player>>, args: &[Arg]) {
+  let args = Python::with_gil(|py| {
+    vec![
+      bb.clone().into_py(py),
+      match player {
+        Some(p) => PPlayer::from(p.clone()).into_py(py),
+        None => py.None(),
+      },
+      args.iter().map(|a| py_from_arg(py, a.clone())).collect::<Vec<_>>().into_py(py),
+    ]
+  });
+  cb.call_python(args);
+}
+
+fn py_from_arg(py: Python, arg: Arg) -> PyObject {
+  match arg {
+    Arg::Literal(text) => text.into_py(py),
+    Arg::Bool(v) => v.into_py(py),
+    Arg::Double(v) => v.into_py(py),
+    Arg::Float(v) => v.into_py(py),
+    Arg::Int(v) => v.into_py(py),
+    Arg::String(v) => v.into_py(py),
+    Arg::BlockPos(pos) => PPos::from(pos).into_py(py),
+    Arg::ColumnPos(pos) => PChunkPos::from(pos).into_py(py),
+    Arg::BlockState(kind, _props, _nbt) => PBlockKind::from(kind).into_py(py),
+    Arg::ItemStack(stack) => PStack::from(stack).into_py(py),
+    _ => {
+      warn!("command arg {arg:?} cannot be passed to python");
+      py.None()
+    }
+  }
+}
+
+/// Creates the `bamboo` module, which contains all the plugin types.
+fn bamboo_module(py: Python) -> PyResult<&PyModule> {
+  let m = PyModule::new(py, "bamboo")?;
+  m.add("Bamboo", py.get_type::<Bamboo>())?;
+  m.add("BlockKind", py.get_type::<PBlockKind>())?;
+  m.add("BlockType", py.get_type::<PBlockType>())?;
+  m.add("Chat", py.get_type::<PChat>())?;
+  m.add("ChatSection", py.get_type::<PChatSection>())?;
+  m.add_function(wrap_pyfunction!(new_command, m)?)?;
+  m.add("ClickWindow", py.get_type::<PClickWindow>())?;
+  m.add("Inventory", py.get_type::<PInventory>())?;
+  m.add("Stack", py.get_type::<PStack>())?;
+  m.add("UI", py.get_type::<PUI>())?;
+  m.add("Player", py.get_type::<PPlayer>())?;
+  m.add("Team", py.get_type::<PTeam>())?;
+  m.add("Window", py.get_type::<PWindow>())?;
+  m.add("Storage", py.get_type::<PStorage>())?;
+  m.add("Pos", py.get_type::<PPos>())?;
+  m.add("ChunkPos", py.get_type::<PChunkPos>())?;
+  m.add("FPos", py.get_type::<PFPos>())?;
+  m.add("UUID", py.get_type::<PUUID>())?;
+  m.add("World", py.get_type::<PWorld>())?;
+  m.add("Biome", py.get_type::<PBiome>())?;
+  Ok(m)
+}
+
+pub struct Plugin {
+  name:       String,
+  bb:         Bamboo,
+  module:     Py<PyModule>,
+  /// Set once we have warned that this plugin cannot generate terrain, so
+  /// that we don't log an error for every chunk.
+  warned_gen: AtomicBool,
 }
 
 impl Plugin {
-  pub fn new(name: String) -> Self {
-    let (tx, rx) = crossbeam_channel::bounded(1024);
-    thread::spawn(move || {
-      pyo3::prepare_freethreaded_python();
-      let code = fs::read_to_string(Path::new("plugins/python-test/main.py")).unwrap();
-      let funcs = match Python::with_gil::<_, PyResult<PyFuncs>>(|py| {
-        let module = PyModule::from_code(py, &code, "main.py", "main")?;
-        let funcs = PyFuncs { init: module.getattr("init")?.into_py(py) };
-        Ok(funcs)
-      }) {
+  /// Loads the python plugin in the given directory. This will run
+  /// `main.py`, but it will not call `init`.
+  pub fn new(name: String, path: &Path, wm: Arc<WorldManager>) -> io::Result<Self> {
+    pyo3::prepare_freethreaded_python();
+    let main = path.join("main.py");
+    let code = fs::read_to_string(&main)?;
+    let module = Python::with_gil::<_, PyResult<Py<PyModule>>>(|py| {
+      let sys = py.import("sys")?;
+      sys.getattr("modules")?.set_item("bamboo", bamboo_module(py)?)?;
+      sys.getattr("path")?.call_method1("insert", (0, path.to_string_lossy().to_string()))?;
+      let module = PyModule::from_code(py, &code, &main.to_string_lossy(), &name)?;
+      Ok(module.into())
+    })
+    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+    Ok(Plugin { bb: Bamboo::new(name.clone(), wm), name, module, warned_gen: false.into() })
+  }
+
+  /// Calls the given function, if it exists. The `Bamboo` instance is passed
+  /// as the first argument, and `args` are passed after that. Any errors will
+  /// be logged, and `None` will be returned.
+  fn call_func(&self, func: &str, args: impl FnOnce(Python) -> Vec<PyObject>) -> Option<PyObject> {
+    Python::with_gil(|py| {
+      let func = match self.module.as_ref(py).getattr(func) {
         Ok(f) => f,
-        Err(e) => {
-          error!("python plugin encountered error: {e}");
-          return;
-        }
+        // Every function is optional.
+        Err(_) => return None,
       };
-      while let Ok(e) = rx.recv() {
-        match match e {
-          ServerMessage::Event { .. } => Python::with_gil::<_, PyResult<()>>(|py| {
-            funcs.init.call0(py)?;
-            Ok(())
-          }),
-          _ => Ok(()),
-        } {
-          Ok(_) => {}
-          Err(e) => {
-            error!("python plugin encountered error: {e}");
-          }
+      let mut all_args = vec![self.bb.clone().into_py(py)];
+      all_args.extend(args(py));
+      match func.call1(PyTuple::new(py, all_args)) {
+        Ok(v) => Some(v.into_py(py)),
+        Err(e) => {
+          error!("error in python plugin `{}`: {e}", self.name);
+          None
         }
       }
-    });
-    Plugin { tx }
+    })
+  }
+  /// Calls the given function, and returns `true` if it returned anything
+  /// other than `False`. This is used for events that can be cancelled.
+  fn call_bool(&self, func: &str, args: impl FnOnce(Python) -> Vec<PyObject>) -> bool {
+    match self.call_func(func, args) {
+      Some(v) => Python::with_gil(|py| v.extract::<bool>(py).unwrap_or(true)),
+      None => true,
+    }
   }
+
+  pub fn call_init(&self) { self.call_func("init", |_| vec![]); }
 }
 
 impl PluginImpl for Plugin {
-  fn call(&self, event: ServerMessage) -> Result<bool, CallError> {
-    self.tx.send(event).map(|_| true).map_err(CallError::no_keep)
+  fn call(&self, player: Arc<Player>, ev: ServerEvent) -> Result<(), CallError> {
+    let player = PPlayer::from(player);
+    match ev {
+      ServerEvent::Chat { text } => {
+        self.call_func("on_chat_message", |py| vec![player.into_py(py), text.into_py(py)])
+      }
+      ServerEvent::PlayerJoin {} => self.call_func("on_player_join", |py| vec![player.into_py(py)]),
+      ServerEvent::PlayerLeave {} => {
+        self.call_func("on_player_leave", |py| vec![player.into_py(py)])
+      }
+      ServerEvent::PlayerDeath {} => {
+        self.call_func("on_player_death", |py| vec![player.into_py(py)])
+      }
+      // Commands added by python plugins use callbacks, so this is never sent here.
+      ServerEvent::Command { .. } => None,
+    };
+    Ok(())
+  }
+  fn call_global(&self, ev: GlobalServerEvent) -> Result<(), CallError> {
+    match ev {
+      GlobalServerEvent::Tick => {
+        self.call_func("on_tick", |_| vec![]);
+      }
+      // Python plugins cannot generate terrain yet. Any plugin that looks like it is
+      // trying to will never be called, so we log that once.
+      GlobalServerEvent::GenerateChunk { generator, .. } => {
+        let has_func =
+          Python::with_gil(|py| self.module.as_ref(py).hasattr("generate_chunk").unwrap_or(false));
+        if has_func && !self.warned_gen.swap(true, Ordering::Relaxed) {
+          error!(
+            "python plugin `{}` defines `generate_chunk`, but python plugins cannot generate \
+             terrain (for generator `{generator}`)",
+            self.name
+          );
+        }
+      }
+    }
+    Ok(())
+  }
+  fn req(&self, player: Arc<Player>, request: ServerRequest) -> Result<PluginReply, CallError> {
+    let player = PPlayer::from(player);
+    Ok(PluginReply::Cancel {
+      allow: match request {
+        ServerRequest::BlockPlace { pos, block } => self.call_bool("on_block_place", |py| {
+          vec![
+            player.into_py(py),
+            PPos::from(pos).into_py(py),
+            PBlockKind::from(block.kind()).into_py(py),
+          ]
+        }),
+        ServerRequest::BlockBreak { pos, block } => self.call_bool("on_block_break", |py| {
+          vec![
+            player.into_py(py),
+            PPos::from(pos).into_py(py),
+            PBlockKind::from(block.kind()).into_py(py),
+          ]
+        }),
+        ServerRequest::ClickWindow { slot, mode } => self.call_bool("on_click_window", |py| {
+          vec![player.into_py(py), slot.into_py(py), PClickWindow::from(mode).into_py(py)]
+        }),
+        ServerRequest::Interact { block, .. } => self.call_bool("on_interact", |py| {
+          vec![player.into_py(py), block.map(PPos::from).into_py(py)]
+        }),
+        ServerRequest::Damage { amount, blockable } => self.call_bool("on_damage", |py| {
+          vec![player.into_py(py), amount.into_py(py), blockable.into_py(py)]
+        }),
+        ServerRequest::Death { message } => {
+          self.call_bool("on_death", |py| vec![player.into_py(py), message.into_py(py)])
+        }
+      },
+    })
+  }
+  fn req_global(&self, request: GlobalServerRequest) -> Result<PluginReply, CallError> {
+    Ok(PluginReply::Cancel {
+      allow: match request {
+        GlobalServerRequest::EntityDamage { eid, amount, blockable } => self
+          .call_bool("on_entity_damage", |py| {
+            vec![eid.into_py(py), amount.into_py(py), blockable.into_py(py)]
+          }),
+      },
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn dispatch_event() {
+    let dir = std::env::temp_dir().join(format!("bamboo-python-test-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+      dir.join("main.py"),
+      "ticks = 0\n\
+       def on_tick(bb):\n\
+       \x20 global ticks\n\
+       \x20 ticks += 1\n",
+    )
+    .unwrap();
+    let wm = Arc::new(WorldManager::new(false));
+    let plugin = Plugin::new("test".into(), &dir, wm).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    let ticks =
+      || Python::with_gil(|py| plugin.module.as_ref(py).getattr("ticks")?.extract::<i32>());
+    assert_eq!(ticks().unwrap(), 0);
+    plugin.call_global(GlobalServerEvent::Tick).unwrap();
+    plugin.call_global(GlobalServerEvent::Tick).unwrap();
+    assert_eq!(ticks().unwrap(), 2);
   }
 }
@@ -1,21 +1,40 @@
-use super::{
-  CallError, PluginEvent, PluginImpl, PluginMessage, PluginRequest, ServerMessage, ServerReply,
-  ServerRequest,
+use super::{CallError, PluginImpl, Scope};
+use crate::{
+  block,
+  command::{Arg, Command, Parser, StringType},
+  event::{
+    GlobalServerEvent, GlobalServerRequest, JsonPlayer, JsonUUID, PluginEvent, PluginMessage,
+    PluginReply, PluginRequest, ServerEvent, ServerMessage, ServerReply, ServerRequest,
+  },
+  item,
+  item::Stack,
+  player::Player,
+  world::{World, WorldManager},
 };
-use crate::{player::Player, world::WorldManager};
-use crossbeam_channel::{Receiver, Sender};
+use bb_common::{
+  math::Pos,
+  util::{Chat, Property},
+};
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender, TryRecvError};
 use mio::{event::Event, net::UnixStream, Events, Interest, Poll, Token, Waker};
+use parking_lot::Mutex;
 use std::{
-  collections::HashMap,
+  collections::{HashMap, HashSet},
   fs, io,
   io::{BufRead, BufReader, Read, Write},
   os::unix::net::UnixListener as StdUnixListener,
   path::{Path, PathBuf},
-  process::{Command, Stdio},
-  sync::Arc,
+  process::{Command as Process, Stdio},
+  str::FromStr,
+  sync::{
+    atomic::{AtomicU32, Ordering},
+    Arc, Weak,
+  },
+  time::{Duration, Instant},
 };
 
 struct WrappedSocket {
+  name:     String,
   stream:   UnixStream,
   outgoing: Vec<u8>,
   incoming: Vec<u8>,
@@ -34,13 +53,25 @@ pub struct SocketManager {
   plugins:  Vec<Arc<SocketPlugin>>,
 }
 
+/// A plugin connected over a unix socket. Messages are JSON objects separated
+/// by null bytes. See the [`event`](crate::event) module for all the messages.
+///
+/// When this is dropped (for example, when plugins are reloaded), the socket is
+/// closed, so the plugin process knows it should exit.
 pub struct SocketPlugin {
-  wm:      Arc<WorldManager>,
-  tok:     Token,
-  waker:   Arc<Waker>,
-  tok_tx:  Sender<Token>,
-  serv_tx: Sender<ServerMessage>,
-  rx:      Receiver<PluginMessage>,
+  name:          String,
+  wm:            Arc<WorldManager>,
+  tok:           Token,
+  waker:         Arc<Waker>,
+  tok_tx:        Sender<Token>,
+  serv_tx:       Sender<ServerMessage>,
+  rx:            Receiver<PluginMessage>,
+  reply_tx:      Sender<(u32, PluginReply)>,
+  reply_rx:      Receiver<(u32, PluginReply)>,
+  next_reply:    AtomicU32,
+  reply_timeout: Duration,
+  // The `type` of every event this plugin has registered for.
+  registered:    Mutex<HashSet<String>>,
 }
 
 const LISTEN: Token = Token(0);
@@ -63,41 +94,53 @@ impl SocketManager {
     }
   }
 
-  pub fn add(&mut self, name: String, path: PathBuf) -> Option<Arc<SocketPlugin>> {
-    let mut socket = open(name, path)?;
+  pub fn add(
+    &mut self,
+    name: String,
+    path: PathBuf,
+    reply_timeout: Duration,
+  ) -> Option<Arc<SocketPlugin>> {
+    let mut socket = open(name.clone(), path)?;
 
     let tok = Token(self.next_tok);
     self.next_tok += 1;
 
     let (serv_tx, serv_rx) = crossbeam_channel::bounded(128);
     let (plug_tx, plug_rx) = crossbeam_channel::bounded(128);
+    let (reply_tx, reply_rx) = crossbeam_channel::bounded(128);
     let plugin = SocketPlugin {
+      name: name.clone(),
       wm: self.wm.clone(),
       tok,
       waker: self.waker.clone(),
       tok_tx: self.tok_tx.clone(),
       serv_tx,
       rx: plug_rx,
+      reply_tx,
+      reply_rx,
+      next_reply: 0.into(),
+      reply_timeout,
+      registered: Mutex::new(HashSet::new()),
     };
     self
       .poll
       .registry()
       .register(&mut socket, tok, Interest::READABLE | Interest::WRITABLE)
       .unwrap();
-    self.sockets.insert(tok, WrappedSocket::new(socket, plug_tx));
+    self.sockets.insert(tok, WrappedSocket::new(name, socket, plug_tx));
     self.serv_rx.insert(tok, serv_rx);
     let plugin = Arc::new(plugin);
     self.plugins.push(plugin.clone());
     Some(plugin)
   }
 
-  pub fn take_plugins(&mut self) -> Vec<Arc<SocketPlugin>> {
-    std::mem::replace(&mut self.plugins, vec![])
-  }
+  pub fn take_plugins(&mut self) -> Vec<Arc<SocketPlugin>> { std::mem::take(&mut self.plugins) }
 
+  /// Handles all socket IO. This will return once every plugin has
+  /// disconnected.
   pub fn listen(mut self) {
     let mut events = Events::with_capacity(1024);
-    loop {
+    while !self.sockets.is_empty() {
       self.poll.poll(&mut events, None).unwrap();
       for ev in &events {
         match ev.token() {
@@ -107,53 +150,80 @@ impl SocketManager {
               Ok(tok) => tok,
               Err(_) => break,
             };
-            // Don't want to block here
-            let msg = self.serv_rx[&plugin_tok].try_recv().unwrap();
+            let rx = match self.serv_rx.get(&plugin_tok) {
+              Some(rx) => rx,
+              // This plugin has already been removed.
+              None => continue,
+            };
+            // Messages are always queued before the token is sent, so an empty
+            // channel means the plugin was dropped.
+            let msg = match rx.try_recv() {
+              Ok(msg) => msg,
+              Err(TryRecvError::Empty | TryRecvError::Disconnected) => {
+                self.remove(plugin_tok);
+                continue;
+              }
+            };
 
             match self.sockets.get_mut(&plugin_tok).unwrap().send(msg) {
               Ok(_) => {}
-              Err(e) => error!("error sending to plugin: {e}"),
+              Err(e) => {
+                error!("error sending to plugin: {e}");
+                self.remove(plugin_tok);
+              }
             }
           },
           token => {
             // One of our sockets has just changed state
             match self.handle_socket_change(ev, token) {
-              Ok(()) => {}
+              Ok(true) => {}
+              Ok(false) => {
+                if let Some(s) = self.sockets.get(&token) {
+                  info!("plugin `{}` has disconnected", s.name);
+                }
+                self.remove(token);
+              }
               Err(e) => {
                 error!("could not handle socket event: {e}");
-                self.sockets.remove(&token);
+                self.remove(token);
               }
             }
           }
         }
-        /*
-
-        let mut sock = self.socket.lock();
-        let mut data = serde_json::to_vec(&ev).unwrap();
-        data.push(b'\0');
-        sock.get_mut().write_all(&data)?;
-        sock.get_mut().flush()?;
-        Ok(())
-        */
       }
     }
   }
 
-  fn handle_socket_change(&mut self, ev: &Event, tok: Token) -> io::Result<()> {
-    let socket = self.sockets.get_mut(&tok).unwrap();
-    if ev.is_readable() {
-      socket.try_read()?;
+  /// Closes the socket for the given plugin. Any future messages sent by that
+  /// plugin will fail, which will remove it from the plugin manager.
+  fn remove(&mut self, tok: Token) {
+    if let Some(mut socket) = self.sockets.remove(&tok) {
+      let _ = socket.try_flush();
+      let _ = self.poll.registry().deregister(&mut socket.stream);
+    }
+    self.serv_rx.remove(&tok);
+  }
+
+  /// Returns `false` if the socket has been closed.
+  fn handle_socket_change(&mut self, ev: &Event, tok: Token) -> io::Result<bool> {
+    let socket = match self.sockets.get_mut(&tok) {
+      Some(s) => s,
+      None => return Ok(true),
+    };
+    if ev.is_readable() && !socket.try_read()? {
+      return Ok(false);
     }
     if ev.is_writable() {
       socket.try_flush()?;
     }
-    Ok(())
+    Ok(true)
   }
 }
 
 impl WrappedSocket {
-  pub fn new(stream: UnixStream, plug_tx: Sender<PluginMessage>) -> Self {
+  pub fn new(name: String, stream: UnixStream, plug_tx: Sender<PluginMessage>) -> Self {
     WrappedSocket {
+      name,
       stream,
       outgoing: Vec::with_capacity(1024),
       incoming: Vec::with_capacity(1024),
@@ -168,41 +238,45 @@ impl WrappedSocket {
     Ok(())
   }
 
-  pub fn try_read(&mut self) -> io::Result<()> {
+  /// Reads everything available on the socket. Returns `false` if the other
+  /// end has closed the connection, or if the plugin sent a
+  /// [`PluginEvent::Disconnect`].
+  pub fn try_read(&mut self) -> io::Result<bool> {
     loop {
       let mut buf = vec![0; 1024];
       match self.stream.read(&mut buf) {
         Ok(n) => {
           if n == 0 {
-            // TODO: Close connection
-            return Ok(());
+            return Ok(false);
           }
           self.incoming.extend(&buf[..n]);
-          self.read_events()?;
+          if !self.read_events() {
+            return Ok(false);
+          }
         }
-        Err(e) if matches!(e.kind(), io::ErrorKind::WouldBlock) => return Ok(()),
+        Err(e) if matches!(e.kind(), io::ErrorKind::WouldBlock) => return Ok(true),
         Err(e) => return Err(e),
       }
     }
   }
 
-  fn read_events(&mut self) -> io::Result<()> {
+  /// Parses all the messages in the incoming buffer. Returns `false` if the
+  /// plugin wants to disconnect.
+  fn read_events(&mut self) -> bool {
     while let Some(idx) = self.incoming.iter().position(|&x| x == b'\0') {
-      let buf = &self.incoming[..idx];
-
-      let res = serde_json::from_slice(&buf);
+      let res = serde_json::from_slice(&self.incoming[..idx]);
       self.incoming.drain(..idx + 1);
       match res {
+        Ok(PluginMessage::Event { event: PluginEvent::Disconnect }) => return false,
         Ok(v) => {
-          self.plug_tx.send(v).unwrap();
-        }
-        Err(e) => {
-          error!("invalid event from plugin `{}`: {}", "", e);
-          return Ok(());
+          if self.plug_tx.send(v).is_err() {
+            return false;
+          }
         }
+        Err(e) => error!("invalid message from plugin `{}`: {e}", self.name),
       }
     }
-    Ok(())
+    true
   }
 
   pub fn try_flush(&mut self) -> io::Result<()> {
@@ -212,7 +286,7 @@ impl WrappedSocket {
         Ok(())
       }
       Err(e) if matches!(e.kind(), io::ErrorKind::WouldBlock) => Ok(()),
-      Err(e) => return Err(e),
+      Err(e) => Err(e),
     }
   }
 }
@@ -240,84 +314,240 @@ fn open(name: String, path: PathBuf) -> Option<UnixStream> {
 }
 
 impl SocketPlugin {
+  /// Spawns a thread to handle all messages from the plugin. The thread will
+  /// exit once the plugin disconnects, or once this plugin is dropped.
   pub fn spawn_listener(self: Arc<Self>) {
-    info!("spawning listener");
-    let p = self.clone();
-    std::thread::spawn(move || loop {
-      match p.read() {
-        Ok(ev) => {
-          info!("handling event {ev:?}");
-          match p.handle_message(ev) {
-            Ok(()) => {}
-            Err(e) => {
-              error!("could not handle message from plugin: {e}");
-              return;
-            }
-          }
-        }
-        Err(_) => break,
+    let rx = self.rx.clone();
+    let weak = Arc::downgrade(&self);
+    drop(self);
+    std::thread::spawn(move || {
+      while let Ok(msg) = rx.recv() {
+        let p = match weak.upgrade() {
+          Some(p) => p,
+          None => break,
+        };
+        p.handle_message(msg);
       }
     });
-    info!("spawned listener");
   }
 
-  pub fn read(&self) -> Result<PluginMessage, ()> { Ok(self.rx.recv().unwrap()) }
-  pub fn handle_message(&self, msg: PluginMessage) -> io::Result<()> {
+  pub fn read(&self) -> Result<PluginMessage, ()> { self.rx.recv().map_err(|_| ()) }
+  pub fn handle_message(self: &Arc<Self>, msg: PluginMessage) {
     match msg {
       PluginMessage::Event { event } => self.handle_event(event),
       PluginMessage::Request { reply_id, request } => self.handle_request(reply_id, request),
+      PluginMessage::Reply { reply_id, reply } => {
+        // If nobody is waiting for a reply, this will just fill up the channel, and
+        // then be dropped.
+        let _ = self.reply_tx.try_send((reply_id, reply));
+      }
+    }
+  }
+
+  fn world(&self, name: &str) -> Option<Arc<World>> {
+    if name.is_empty() {
+      Some(self.wm.default_world())
+    } else {
+      self.wm.world(name)
     }
   }
-  pub fn handle_event(&self, e: PluginEvent) -> io::Result<()> {
+  fn player(&self, id: JsonUUID) -> Option<Arc<Player>> { self.wm.get_player(id.uuid) }
+  /// Returns the storage scope for a data event. The player is used first,
+  /// then the world, and then the global scope.
+  fn scope(player: Option<JsonUUID>, world: Option<String>) -> Scope {
+    match (player, world) {
+      (Some(p), _) => Scope::Player(p.uuid),
+      (None, Some(w)) => Scope::World(w),
+      (None, None) => Scope::Global,
+    }
+  }
+
+  pub fn handle_event(self: &Arc<Self>, e: PluginEvent) {
+    if let Err(e) = self.try_handle_event(e) {
+      warn!("plugin `{}` sent an invalid event: {e}", self.name);
+    }
+  }
+  fn try_handle_event(self: &Arc<Self>, e: PluginEvent) -> Result<(), String> {
     match e {
       PluginEvent::Ready => {}
-      PluginEvent::Register { ty: _ } => todo!(),
+      PluginEvent::Disconnect => {}
+      PluginEvent::Register { ty } => {
+        self.registered.lock().insert(ty);
+      }
       PluginEvent::SendChat { text } => {
         self.wm.broadcast(text);
       }
+      PluginEvent::SendMessage { player, text } => {
+        if let Some(p) = self.player(player) {
+          p.send_message(Chat::new(text));
+        }
+      }
+      PluginEvent::SetBlock { world, pos, kind } => {
+        let world = self.world(&world).ok_or(format!("no such world `{world}`"))?;
+        let kind = block::Kind::from_str(&kind).map_err(|_| format!("no such block `{kind}`"))?;
+        world.set_kind(pos.into(), kind).map_err(|e| e.to_string())?;
+      }
+      PluginEvent::Fill { world, min, max, kind } => {
+        let world = self.world(&world).ok_or(format!("no such world `{world}`"))?;
+        let kind = block::Kind::from_str(&kind).map_err(|_| format!("no such block `{kind}`"))?;
+        let (min, max) = Pos::from(min).min_max(max.into());
+        world.fill_rect_kind(min, max, kind).map_err(|e| e.to_string())?;
+      }
+      PluginEvent::Teleport { player, pos, yaw, pitch } => {
+        if let Some(p) = self.player(player) {
+          p.teleport(pos.into(), yaw, pitch);
+        }
+      }
+      PluginEvent::GiveItem { player, item, amount } => {
+        let ty = item::Type::from_str(&item).map_err(|_| format!("no such item `{item}`"))?;
+        if let Some(p) = self.player(player) {
+          p.lock_inventory().give(Stack::new(ty).with_amount(amount));
+        }
+      }
+      PluginEvent::ShowTitle { player, title, subtitle, fade_in, stay, fade_out } => {
+        if let Some(p) = self.player(player) {
+          p.set_title(Chat::new(title));
+          p.set_subtitle(Chat::new(subtitle));
+          p.show_title(fade_in, stay, fade_out);
+        }
+      }
+      PluginEvent::ShowScoreboard { player } => {
+        if let Some(p) = self.player(player) {
+          p.lock_scoreboard().show();
+        }
+      }
+      PluginEvent::HideScoreboard { player } => {
+        if let Some(p) = self.player(player) {
+          p.lock_scoreboard().hide();
+        }
+      }
+      PluginEvent::SetScoreboardLine { player, line, text } => {
+        if line >= 15 {
+          return Err(format!("invalid scoreboard line {line}"));
+        }
+        if let Some(p) = self.player(player) {
+          p.lock_scoreboard().set_line(line, &Chat::new(text));
+        }
+      }
+      PluginEvent::ClearScoreboardLine { player, line } => {
+        if line >= 15 {
+          return Err(format!("invalid scoreboard line {line}"));
+        }
+        if let Some(p) = self.player(player) {
+          p.lock_scoreboard().clear_line(line);
+        }
+      }
+      PluginEvent::SetSkin { player, value, signature } => {
+        if let Some(p) = self.player(player) {
+          p.set_skin(Some(Property { name: "textures".into(), value, signature }));
+        }
+      }
+      PluginEvent::ResetSkin { player } => {
+        if let Some(p) = self.player(player) {
+          p.set_skin(None);
+        }
+      }
+      PluginEvent::RegisterCommand { name } => self.register_command(name),
+      PluginEvent::SetData { player, world, key, value } => {
+        self.wm.plugin_storage(&self.name).set(&Self::scope(player, world), key, value);
+      }
+      PluginEvent::RemoveData { player, world, key } => {
+        self.wm.plugin_storage(&self.name).remove(&Self::scope(player, world), &key);
+      }
     }
     Ok(())
   }
-  pub fn handle_request(&self, id: u32, r: PluginRequest) -> io::Result<()> {
-    match r {
-      PluginRequest::GetBlock { pos } => {
-        self.reply(
-          id,
-          ServerReply::Block {
-            pos:   pos.into(),
-            block: self.wm.default_world().get_block(pos.into()).unwrap().into(),
-          },
-        )?;
+
+  fn register_command(self: &Arc<Self>, name: String) {
+    let mut command = Command::new(name.as_str());
+    command.add_arg_opt("args", Parser::String(StringType::Greedy));
+    let plugin = Arc::downgrade(self);
+    self.wm.commands().add_for_plugin(&self.name, command, move |_, player, args| {
+      let player = match player {
+        Some(p) => p.clone(),
+        None => {
+          warn!("command `{name}` can only be run by players");
+          return;
+        }
+      };
+      let args = match args.get(1) {
+        Some(Arg::String(text)) => text.split_whitespace().map(String::from).collect(),
+        _ => vec![],
+      };
+      let event = ServerEvent::Command { name: name.clone(), args };
+      match Weak::upgrade(&plugin) {
+        Some(p) => {
+          let _ = p.send(ServerMessage::Event { player, event });
+        }
+        None => player.send_message(Chat::new("This command is no longer available")),
       }
-    }
-    Ok(())
+    });
   }
-  pub fn wait_for_ready(&self) -> Result<(), ()> {
-    info!("waiting for ready");
+
+  pub fn handle_request(&self, id: u32, r: PluginRequest) {
+    let reply = match r {
+      PluginRequest::GetBlock { world, pos } => match self.world(&world) {
+        Some(w) => match w.get_block(pos.into()) {
+          Ok(block) => ServerReply::Block { pos: pos.into(), block: block.into() },
+          Err(e) => ServerReply::Error { message: e.to_string() },
+        },
+        None => ServerReply::Error { message: format!("no such world `{world}`") },
+      },
+      PluginRequest::GetPlayers => {
+        let players = self.wm.all_players().into_iter().map(JsonPlayer::from).collect();
+        ServerReply::Players { players }
+      }
+      PluginRequest::GetPlayer { player } => match self.player(player) {
+        Some(p) => ServerReply::Player {
+          world:  p.world().name().into(),
+          pos:    p.pos().into(),
+          player: p.into(),
+        },
+        None => ServerReply::Error { message: "player is not online".into() },
+      },
+      PluginRequest::GetData { player, world, key } => ServerReply::Data {
+        value: self.wm.plugin_storage(&self.name).get(&Self::scope(player, world), &key),
+      },
+    };
+    let _ = self.reply(id, reply);
+  }
+  pub fn wait_for_ready(self: &Arc<Self>) -> Result<(), ()> {
     loop {
       match self.read()? {
         PluginMessage::Event { event: PluginEvent::Ready } => break,
-        e => {
-          self.handle_message(e).map_err(|_| ())?;
-        }
+        e => self.handle_message(e),
       }
     }
-    info!("plugin `{}` is ready", "");
+    info!("plugin `{}` is ready", self.name);
     Ok(())
   }
   pub fn send(&self, ev: ServerMessage) -> io::Result<()> {
-    self.tok_tx.send(self.tok).unwrap();
-    self.serv_tx.send(ev).unwrap();
-    self.waker.wake().unwrap();
+    // The message must be queued before the token is sent, so that the manager
+    // never sees a token without a message.
+    if self.serv_tx.send(ev).is_err() {
+      return Err(io::Error::new(io::ErrorKind::NotConnected, "plugin has disconnected"));
+    }
+    let _ = self.tok_tx.send(self.tok);
+    self.waker.wake()?;
     Ok(())
   }
   pub fn reply(&self, reply_id: u32, reply: ServerReply) -> io::Result<()> {
     self.send(ServerMessage::Reply { reply_id, reply })
   }
+
+  fn is_registered(&self, name: &str) -> bool { self.registered.lock().contains(name) }
+}
+
+impl Drop for SocketPlugin {
+  fn drop(&mut self) {
+    // Sending a token without a message tells the manager to close this socket.
+    let _ = self.tok_tx.send(self.tok);
+    let _ = self.waker.wake();
+  }
 }
 
 fn start_plugin(plugin: String, path: &Path) {
-  let mut child = match Command::new("./start.sh")
+  let mut child = match Process::new("./start.sh")
     .current_dir(std::env::current_dir().unwrap().join(path))
     .stdin(Stdio::piped())
     .stdout(Stdio::piped())
@@ -349,23 +579,62 @@ fn start_plugin(plugin: String, path: &Path) {
         }
       }
     }
+    // Make sure the process doesn't stick around as a zombie.
+    let _ = child.wait();
   });
 }
 
 impl PluginImpl for Arc<SocketPlugin> {
   fn call(&self, player: Arc<Player>, event: ServerEvent) -> Result<(), CallError> {
+    if !self.is_registered(event.name()) {
+      return Ok(());
+    }
     self.send(ServerMessage::Event { player, event }).map_err(CallError::no_keep)
   }
   fn call_global(&self, event: GlobalServerEvent) -> Result<(), CallError> {
+    if !self.is_registered(event.name()) {
+      return Ok(());
+    }
     self.send(ServerMessage::GlobalEvent { event }).map_err(CallError::no_keep)
   }
 
-  fn req(
-    &self,
-    player: Arc<Player>,
-    reply_id: u32,
-    request: ServerRequest,
-  ) -> Result<PluginReply, CallError> {
-    self.send(ServerMessage::Request { player, reply_id, request }).map_err(CallError::no_keep)
+  fn req(&self, player: Arc<Player>, request: ServerRequest) -> Result<PluginReply, CallError> {
+    if !self.is_registered(request.name()) {
+      return Ok(PluginReply::Cancel { allow: true });
+    }
+    let reply_id = self.next_reply.fetch_add(1, Ordering::SeqCst);
+    self.send(ServerMessage::Request { player, reply_id, request }).map_err(CallError::no_keep)?;
+    self.wait_reply(reply_id)
+  }
+  fn req_global(&self, request: GlobalServerRequest) -> Result<PluginReply, CallError> {
+    if !self.is_registered(request.name()) {
+      return Ok(PluginReply::Cancel { allow: true });
+    }
+    let reply_id = self.next_reply.fetch_add(1, Ordering::SeqCst);
+    self.send(ServerMessage::GlobalRequest { reply_id, request }).map_err(CallError::no_keep)?;
+    self.wait_reply(reply_id)
+  }
+}
+
+impl SocketPlugin {
+  /// Waits for the plugin to reply to the request with the given id. If the
+  /// plugin doesn't reply in time, the action is allowed.
+  fn wait_reply(&self, reply_id: u32) -> Result<PluginReply, CallError> {
+    let deadline = Instant::now() + self.reply_timeout;
+    loop {
+      match self.reply_rx.recv_deadline(deadline) {
+        Ok((id, reply)) if id == reply_id => return Ok(reply),
+        // An old reply, which we have already given up on.
+        Ok(_) => continue,
+        // The plugin manager will also time out, and allow the action.
+        Err(RecvTimeoutError::Timeout) => return Ok(PluginReply::Cancel { allow: true }),
+        Err(RecvTimeoutError::Disconnected) => {
+          return Err(CallError::no_keep(io::Error::new(
+            io::ErrorKind::NotConnected,
+            "plugin has disconnected",
+          )))
+        }
+      }
+    }
   }
 }
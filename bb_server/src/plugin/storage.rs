@@ -0,0 +1,224 @@
+//! Persistent key/value storage for plugins. Each plugin has its own store,
+//! which is saved in the `plugins` directory of the default world, named after
+//! the plugin. Like player data, the store is encoded with `bb_transfer`, and
+//! then gzipped.
+//!
+//! Keys and values are both strings. Plugins that need to store something more
+//! complex should serialize it themselves (for example, as JSON).
+
+use bb_common::{
+  flate2::{read::GzDecoder, write::GzEncoder, Compression},
+  util::UUID,
+};
+use bb_macros::Transfer;
+use bb_transfer::{MessageReader, MessageWriter};
+use parking_lot::Mutex;
+use std::{
+  collections::HashMap,
+  fs,
+  fs::File,
+  io::{Read, Write},
+  path::{Path, PathBuf},
+};
+
+/// Which part of a plugin's store to access. Values in one scope are never
+/// visible from another scope, so two players can both have a `kills` key.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Scope {
+  /// Data for the whole plugin.
+  Global,
+  /// Data for a single player. This is kept while the player is offline.
+  Player(UUID),
+  /// Data for a single world, stored by the world's name.
+  World(String),
+}
+
+#[derive(Transfer, Debug, Clone, Default)]
+struct StorageData {
+  #[must_exist]
+  global:  HashMap<String, String>,
+  #[must_exist]
+  players: HashMap<UUID, HashMap<String, String>>,
+  #[must_exist]
+  worlds:  HashMap<String, HashMap<String, String>>,
+}
+
+/// The store for a single plugin. This is kept by the
+/// [`PluginManager`](super::PluginManager), so that data isn't lost when a
+/// plugin is reloaded.
+#[derive(Debug)]
+pub struct Storage {
+  path:     PathBuf,
+  data:     Mutex<StorageData>,
+  /// Set whenever the data changes, and cleared once it has been saved.
+  dirty:    Mutex<bool>,
+  /// If the file on disk couldn't be read or moved out of the way, we never
+  /// write to it, so that it can still be recovered by hand.
+  readonly: bool,
+}
+
+impl StorageData {
+  fn scope(&self, scope: &Scope) -> Option<&HashMap<String, String>> {
+    match scope {
+      Scope::Global => Some(&self.global),
+      Scope::Player(id) => self.players.get(id),
+      Scope::World(name) => self.worlds.get(name),
+    }
+  }
+  fn scope_mut(&mut self, scope: &Scope) -> &mut HashMap<String, String> {
+    match scope {
+      Scope::Global => &mut self.global,
+      Scope::Player(id) => self.players.entry(*id).or_default(),
+      Scope::World(name) => self.worlds.entry(name.clone()).or_default(),
+    }
+  }
+}
+
+impl Storage {
+  /// Loads the store for the given plugin from `dir`. If the plugin has never
+  /// stored anything, this will be empty.
+  ///
+  /// If the file cannot be read, it is moved to `<plugin>.bbs.bak`, and the
+  /// store starts out empty. If that move fails, the store will never be
+  /// saved, so that the old file is not overwritten.
+  pub fn load(dir: &Path, plugin: &str) -> Self {
+    let path = dir.join(format!("{plugin}.bbs"));
+    let mut readonly = false;
+    let data = match read(&path) {
+      Ok(data) => data.unwrap_or_default(),
+      Err(e) => {
+        let backup = path.with_extension("bbs.bak");
+        match fs::rename(&path, &backup) {
+          Ok(()) => error!("{e}, moved it to {}", backup.display()),
+          Err(err) => {
+            error!("{e}, and couldn't move it to {}: {err}", backup.display());
+            error!("changes to the store for plugin `{plugin}` will not be saved");
+            readonly = true;
+          }
+        }
+        StorageData::default()
+      }
+    };
+    Storage { path, data: Mutex::new(data), dirty: Mutex::new(false), readonly }
+  }
+
+  /// Returns the value for `key`, if present.
+  pub fn get(&self, scope: &Scope, key: &str) -> Option<String> {
+    self.data.lock().scope(scope)?.get(key).cloned()
+  }
+  /// Sets `key` to `value`, and returns the old value.
+  pub fn set(&self, scope: &Scope, key: String, value: String) -> Option<String> {
+    let prev = self.data.lock().scope_mut(scope).insert(key, value);
+    *self.dirty.lock() = true;
+    prev
+  }
+  /// Removes `key`, and returns the old value.
+  pub fn remove(&self, scope: &Scope, key: &str) -> Option<String> {
+    let mut data = self.data.lock();
+    let prev = match scope {
+      Scope::Global => data.global.remove(key),
+      Scope::Player(id) => data.players.get_mut(id)?.remove(key),
+      Scope::World(name) => data.worlds.get_mut(name)?.remove(key),
+    };
+    if prev.is_some() {
+      *self.dirty.lock() = true;
+    }
+    prev
+  }
+  /// Returns all the keys in the given scope, in no particular order.
+  pub fn keys(&self, scope: &Scope) -> Vec<String> {
+    self.data.lock().scope(scope).map(|s| s.keys().cloned().collect()).unwrap_or_default()
+  }
+
+  /// Writes this store to disk, if anything has changed since it was last
+  /// saved.
+  pub fn save(&self) {
+    let mut dirty = self.dirty.lock();
+    if !*dirty || self.readonly {
+      return;
+    }
+    let mut data = vec![];
+    MessageWriter::<&mut Vec<u8>>::new(&mut data).write(&*self.data.lock()).unwrap();
+
+    let path = &self.path;
+    debug!("saving plugin store to {}", path.display());
+    let res = fs::create_dir_all(path.parent().unwrap()).and_then(|_| {
+      let mut encoder = GzEncoder::new(File::create(path)?, Compression::default());
+      encoder.write_all(&data)?;
+      encoder.finish().map(|_| ())
+    });
+    match res {
+      Ok(()) => *dirty = false,
+      Err(e) => warn!("couldn't save plugin store at {}: {e}", path.display()),
+    }
+  }
+}
+
+/// Reads the store at `path`. Returns `Ok(None)` if there is no file there,
+/// and an error message if the file exists but cannot be read.
+fn read(path: &Path) -> Result<Option<StorageData>, String> {
+  if !path.exists() {
+    return Ok(None);
+  }
+  debug!("loading plugin store from {}", path.display());
+  let mut data = vec![];
+  let res = File::open(path).and_then(|f| GzDecoder::new(f).read_to_end(&mut data));
+  if let Err(e) = res {
+    return Err(format!("couldn't read plugin store at {}: {e}", path.display()));
+  }
+  match MessageReader::new(&data).read() {
+    Ok(v) => Ok(Some(v)),
+    Err(e) => Err(format!("couldn't parse plugin store at {}: {e}", path.display())),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn scopes() {
+    let s = Storage {
+      path:     PathBuf::new(),
+      data:     Default::default(),
+      dirty:    Mutex::new(false),
+      readonly: false,
+    };
+    let id = UUID::from_u128(1);
+    s.set(&Scope::Global, "a".into(), "global".into());
+    s.set(&Scope::Player(id), "a".into(), "player".into());
+    assert_eq!(s.get(&Scope::Global, "a").as_deref(), Some("global"));
+    assert_eq!(s.get(&Scope::Player(id), "a").as_deref(), Some("player"));
+    assert_eq!(s.get(&Scope::World("world".into()), "a"), None);
+    assert_eq!(s.remove(&Scope::Player(id), "a").as_deref(), Some("player"));
+    assert_eq!(s.get(&Scope::Player(id), "a"), None);
+    assert_eq!(s.keys(&Scope::Global), vec!["a".to_string()]);
+  }
+
+  #[test]
+  fn save_and_load() {
+    let dir = std::env::temp_dir().join(format!("bb-plugin-storage-{}", std::process::id()));
+    let id = UUID::from_u128(0x1234);
+    let s = Storage::load(&dir, "test");
+    s.set(&Scope::Global, "a".into(), "global".into());
+    s.set(&Scope::Player(id), "kills".into(), "3".into());
+    s.set(&Scope::World("lobby".into()), "b".into(), "world".into());
+    s.save();
+
+    let loaded = Storage::load(&dir, "test");
+    assert_eq!(loaded.get(&Scope::Global, "a").as_deref(), Some("global"));
+    assert_eq!(loaded.get(&Scope::Player(id), "kills").as_deref(), Some("3"));
+    assert_eq!(loaded.get(&Scope::World("lobby".into()), "b").as_deref(), Some("world"));
+    assert_eq!(loaded.get(&Scope::World("other".into()), "b"), None);
+
+    // A broken store must never be overwritten.
+    fs::write(dir.join("broken.bbs"), b"not a store").unwrap();
+    let broken = Storage::load(&dir, "broken");
+    assert_eq!(broken.keys(&Scope::Global), Vec::<String>::new());
+    broken.set(&Scope::Global, "a".into(), "new".into());
+    broken.save();
+    assert_eq!(fs::read(dir.join("broken.bbs.bak")).unwrap(), b"not a store");
+
+    fs::remove_dir_all(&dir).unwrap();
+  }
+}
@@ -39,6 +39,16 @@ impl fmt::Debug for PCommand {
 }
 
 impl PCommand {
+  /// Creates a new command with the given callback. This is used by plugin
+  /// types which can't use the [`new`](Self::new) function directly.
+  pub(crate) fn with_callback(name: &str, callback: Box<dyn BCallback>) -> PCommand {
+    PCommand {
+      inner:    Arc::new(Mutex::new(Command::new(name))),
+      callback: Some(callback),
+      idx:      vec![],
+    }
+  }
+
   fn command<'a>(&self, inner: &'a mut Command) -> &'a mut Command {
     let mut c = inner;
     for idx in &self.idx {
@@ -149,12 +159,10 @@ impl PCommand {
   ///   bb.info("ran setblock!")
   /// }
   /// ```
+  // Python plugins use `bamboo.Command` instead, which takes a python callback.
+  #[panda_only]
   pub fn new(name: &str, callback: Callback) -> PCommand {
-    PCommand {
-      inner:    Arc::new(Mutex::new(Command::new(name))),
-      callback: Some(Box::new(callback)),
-      idx:      vec![],
-    }
+    PCommand::with_callback(name, Box::new(callback))
   }
   /// Adds a new block position argument to the command.
   ///
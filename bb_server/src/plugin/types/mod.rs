@@ -7,7 +7,7 @@
 //! to differentiate from the server types. In the scripting languages, the
 //! types do not start with a `P`.
 
-use super::{Bamboo, PandaPlugin};
+use super::{Bamboo, PandaPlugin, Scope};
 use bb_common::util::{chat::Color, Chat};
 use bb_server_macros::define_ty;
 use panda::{
@@ -23,12 +23,14 @@ pub mod chat;
 pub mod command;
 pub mod item;
 pub mod player;
+pub mod storage;
 pub mod util;
 pub mod world;
 
 use command::PCommand;
-use player::PTeam;
+use player::{PPlayer, PTeam};
 use std::fmt;
+use storage::PStorage;
 use world::{gen::PBiome, PWorld};
 
 pub trait Callback: fmt::Debug + Send + Sync {
@@ -42,9 +44,13 @@ pub trait Callback: fmt::Debug + Send + Sync {
     panic!("cannot call this callback in panda");
   }
   #[cfg(feature = "python_plugins")]
-  fn call_python(&self, args: Vec<pyo3::PyObject>) {
+  fn call_python(&self, _args: Vec<pyo3::PyObject>) {
     panic!("cannot call this callback in python");
   }
+  /// Returns true if this callback should be called with
+  /// [`call_python`](Self::call_python).
+  #[cfg(feature = "python_plugins")]
+  fn is_python(&self) -> bool { false }
 
   fn box_clone(&self) -> Box<dyn Callback>;
 }
@@ -150,10 +156,19 @@ impl Bamboo {
       }
     };
     let command = command.inner.lock().unwrap().clone();
-    let idx = self.idx;
-    wm.commands().add(command, move |_, player, args| {
+    let plugin_name = self.plugin.clone();
+    #[cfg(feature = "python_plugins")]
+    let bb = self.clone();
+    wm.commands().add_for_plugin(&self.plugin, command, move |_, player, args| {
       let wm = wm2.clone();
       let cb = cb.box_clone();
+      // Python callbacks don't need the plugin environment, so they can be called
+      // directly.
+      #[cfg(feature = "python_plugins")]
+      if cb.is_python() {
+        crate::plugin::python::call_command(&*cb, &bb, player, &args);
+        return;
+      }
       {
         // We need this awkward scoping setup to avoid borrowing errors, and to make
         // sure `lock` doesn't get sent between threads.
@@ -161,7 +176,11 @@ impl Bamboo {
         let mut has_err = false;
         {
           let mut lock = wm.plugins().plugins.lock();
-          let plugin = &mut lock[idx];
+          // The plugin may have been disabled since this command was added.
+          let plugin = match lock.iter_mut().find(|p| p.name() == plugin_name) {
+            Some(p) => p,
+            None => return,
+          };
           let mut imp = plugin.lock_imp();
           let panda = imp.panda().unwrap();
           if let Err(e) = cb.call_panda(
@@ -212,6 +231,8 @@ impl Bamboo {
 
   /// Locks the internal data. If the internal data is already locked, this will
   /// continue trying to lock that data.
+  // Python plugins can just use globals to store their data.
+  #[panda_only]
   pub fn lock(&self) -> Var {
     loop {
       match self.data.lock().take() {
@@ -225,6 +246,7 @@ impl Bamboo {
   /// that this should be avoided if you have multiple threads modifying
   /// `data`. This can cause a race condition if you are not careful. If you
   /// need to hold onto a lock, then call `Bamboo::lock` instead.
+  #[panda_only]
   pub fn store(&self, data: Var) -> Result<(), RuntimeError> {
     let mut lock = self.data.lock();
     if lock.is_none() {
@@ -237,6 +259,7 @@ impl Bamboo {
   /// Releases the lock on the internal data. This will move the given data back
   /// into the lock. Note that if the lock is not held, this will return an
   /// error.
+  #[panda_only]
   pub fn unlock(&self, data: Var) -> Result<(), RuntimeError> {
     let prev = self.data.lock().replace(data.into());
     if let Some(prev) = prev {
@@ -249,6 +272,20 @@ impl Bamboo {
     }
   }
 
+  /// Returns the persistent storage for this plugin. Anything stored here will
+  /// be kept after the server restarts. See `Storage` for more.
+  pub fn data(&self) -> PStorage { PStorage { inner: self.storage(), scope: Scope::Global } }
+  /// Returns the persistent storage for the given player. This is separate for
+  /// every player, and it is kept while the player is offline.
+  pub fn player_data(&self, player: &PPlayer) -> PStorage {
+    PStorage { inner: self.storage(), scope: Scope::Player(player.uuid) }
+  }
+  /// Returns the persistent storage for the given world. This is stored by the
+  /// world's name, so it will be kept if the world is re-created.
+  pub fn world_data(&self, world: &PWorld) -> PStorage {
+    PStorage { inner: self.storage(), scope: Scope::World(world.inner.name().into()) }
+  }
+
   /// Broadcasts the given chat message.
   pub fn broadcast(&self, chat: &chat::PChat) {
     self.wm.broadcast(chat.inner.lock().unwrap().clone());
@@ -256,6 +293,29 @@ impl Bamboo {
 
   /// Returns the default world.
   pub fn default_world(&self) -> PWorld { self.wm.default_world().into() }
+
+  /// Returns the world with the given name. This will return an error if
+  /// there is no world with that name.
+  pub fn world(&self, name: &str) -> Result<PWorld, RuntimeError> {
+    match self.wm.world(name) {
+      Some(w) => Ok(w.into()),
+      None => Err(RuntimeError::custom(format!("no world named `{name}`"), Span::call_site())),
+    }
+  }
+
+  /// Creates a new world with the given name. The world will be configured
+  /// by the `[worlds.<name>]` section of the server config, and any keys not
+  /// set there will be copied from `[world]`. This will return an error if
+  /// there is already a world with that name, or if the name contains
+  /// anything other than `a-z`, `0-9`, `_` and `-`.
+  pub fn add_world(&self, name: &str) -> Result<PWorld, RuntimeError> {
+    match self.wm.add_world(name) {
+      Ok(w) => Ok(w.into()),
+      Err(e) => {
+        Err(RuntimeError::custom(format!("cannot add world `{name}`: {e}"), Span::call_site()))
+      }
+    }
+  }
 }
 
 fn format(args: &[Var]) -> String {
@@ -7,10 +7,11 @@ use super::{
   wrap,
 };
 use crate::{
+  entity::EffectInstance,
   item::Stack,
   player::{Player, Team, Window},
 };
-use bb_common::util::{chat::Color, Chat, UUID};
+use bb_common::util::{chat::Color, Chat, Effect, Property, UUID};
 use bb_server_macros::define_ty;
 use panda::{
   parse::token::Span,
@@ -152,7 +153,18 @@ impl PPlayer {
   /// blocks, access other players, and modify entities.
   ///
   /// This will return an error if the player is offline.
-  pub fn world(&self) -> Result<PWorld> { Ok(self.inner()?.world().clone().into()) }
+  pub fn world(&self) -> Result<PWorld> { Ok(self.inner()?.world().into()) }
+
+  /// Moves the player into the given world, at the given position. The
+  /// player will be sent all the chunks and players in the new world. If the
+  /// player is already in that world, this will just teleport them.
+  ///
+  /// This will do nothing if the player is offline.
+  pub fn switch_world(&self, world: &PWorld, pos: &PFPos) {
+    if let Ok(i) = self.inner() {
+      i.switch_world(&world.inner, pos.inner);
+    }
+  }
 
   /// Switches the player to a new server. If the server is found, the player
   /// will be disconnected after this call. If the server is not found, an error
@@ -170,6 +182,18 @@ impl PPlayer {
     Ok(())
   }
 
+  /// Switches the player to the server with the given name. This is the name
+  /// of the server in the `[servers]` section of the proxy's config. If the
+  /// proxy doesn't know about this server, the player will stay on this
+  /// server.
+  ///
+  /// This will do nothing if the player is offline.
+  pub fn switch_server(&self, name: &str) {
+    if let Ok(i) = self.inner() {
+      i.switch_to_named(name);
+    }
+  }
+
   /// Shows an inventory to the player.
   ///
   /// This will do nothing if the player is offline.
@@ -309,6 +333,44 @@ impl PPlayer {
     }
   }
 
+  /// Sets the player's skin. `value` and `signature` are the base64 encoded
+  /// `textures` property and signature, as returned by the session server. If
+  /// `signature` is empty, the skin will be unsigned, and newer clients may
+  /// refuse to show it.
+  ///
+  /// Other players will see the new skin immediately. This player will only
+  /// see it after they respawn.
+  ///
+  /// This will do nothing if the player is offline.
+  pub fn set_skin(&self, value: &str, signature: &str) {
+    if let Ok(i) = self.inner() {
+      i.set_skin(Some(Property {
+        name:      "textures".into(),
+        value:     value.into(),
+        signature: if signature.is_empty() { None } else { Some(signature.into()) },
+      }));
+    }
+  }
+  /// Gives this player the same skin as `other`. This is useful for disguises.
+  ///
+  /// This will return an error if `other` is offline, and it will do nothing
+  /// if this player is offline.
+  pub fn copy_skin(&self, other: &PPlayer) -> Result<()> {
+    let skin = other.inner()?.properties().into_iter().find(|p| p.name == "textures");
+    if let Ok(i) = self.inner() {
+      i.set_skin(skin);
+    }
+    Ok(())
+  }
+  /// Restores the skin this player logged in with.
+  ///
+  /// This will do nothing if the player is offline.
+  pub fn reset_skin(&self) {
+    if let Ok(i) = self.inner() {
+      i.set_skin(None);
+    }
+  }
+
   /// Gives the player the passed item.
   ///
   /// This will do nothing if the player is offline.
@@ -317,6 +379,51 @@ impl PPlayer {
       i.lock_inventory().give(stack.inner.clone());
     }
   }
+
+  /// Gives the player a status effect, such as `speed` or `regeneration`.
+  /// `amplifier` is one less than the level, so `0` is level I. `duration` is
+  /// in ticks.
+  ///
+  /// This will return an error if the effect name is invalid, and it will do
+  /// nothing if the player is offline.
+  pub fn add_effect(&self, name: &str, amplifier: u8, duration: u32) -> Result<()> {
+    let effect = parse_effect(name)?;
+    if let Ok(i) = self.inner() {
+      i.add_effect(EffectInstance::new(effect, amplifier, duration));
+    }
+    Ok(())
+  }
+  /// Removes the given status effect from the player.
+  ///
+  /// This will return an error if the effect name is invalid, and it will do
+  /// nothing if the player is offline.
+  pub fn remove_effect(&self, name: &str) -> Result<()> {
+    let effect = parse_effect(name)?;
+    if let Ok(i) = self.inner() {
+      i.remove_effect(effect);
+    }
+    Ok(())
+  }
+  /// Removes all status effects from the player.
+  ///
+  /// This will do nothing if the player is offline.
+  pub fn clear_effects(&self) {
+    if let Ok(i) = self.inner() {
+      i.clear_effects();
+    }
+  }
+  /// Returns `true` if the player has the given status effect.
+  ///
+  /// This will return an error if the effect name is invalid, or if the
+  /// player is offline.
+  pub fn has_effect(&self, name: &str) -> Result<bool> {
+    let effect = parse_effect(name)?;
+    Ok(self.inner()?.has_effect(effect))
+  }
+}
+
+fn parse_effect(name: &str) -> Result<Effect> {
+  Effect::from_str(name).map_err(|e| RuntimeError::custom(e.to_string(), Span::call_site()))
 }
 
 /// A team. This is a group of players and entities, which all share a set
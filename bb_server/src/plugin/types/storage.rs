@@ -0,0 +1,50 @@
+use super::wrap;
+use crate::plugin::{Scope, Storage};
+use bb_server_macros::define_ty;
+use panda::{parse::token::Span, runtime::RuntimeError};
+use std::sync::Arc;
+
+wrap!(Arc<Storage>, PStorage, scope: Scope);
+
+/// A persistent key/value store. Anything stored here will be saved to disk,
+/// and will still be present after the server restarts.
+///
+/// Each plugin has its own storage, which is split up into global data, data
+/// for each player, and data for each world. These can be accessed through
+/// `Bamboo::data`, `Bamboo::player_data`, and `Bamboo::world_data`.
+///
+/// Keys and values are both strings.
+///
+/// # Example
+///
+/// ```
+/// fn on_player_join(bb, player) {
+///   data = bb.player_data(player)
+///   joins = 0
+///   if data.has("joins") {
+///     joins = data.get("joins").to_i()
+///   }
+///   data.set("joins", (joins + 1).to_s())
+/// }
+/// ```
+#[define_ty(panda_path = "bamboo::Storage")]
+impl PStorage {
+  /// Returns the value for the given key. This will return an error if the key
+  /// is not present. Use `has` to check if a key exists.
+  pub fn get(&self, key: &str) -> Result<String, RuntimeError> {
+    self
+      .inner
+      .get(&self.scope, key)
+      .ok_or_else(|| RuntimeError::custom(format!("no such key `{key}`"), Span::call_site()))
+  }
+  /// Returns `true` if the given key is present.
+  pub fn has(&self, key: &str) -> bool { self.inner.get(&self.scope, key).is_some() }
+  /// Sets the given key to a new value. This overwrites any existing value.
+  pub fn set(&self, key: &str, value: &str) {
+    self.inner.set(&self.scope, key.into(), value.into());
+  }
+  /// Removes the given key. Returns `true` if the key was present.
+  pub fn remove(&self, key: &str) -> bool { self.inner.remove(&self.scope, key).is_some() }
+  /// Returns all the keys that have been set, in no particular order.
+  pub fn keys(&self) -> Vec<String> { self.inner.keys(&self.scope) }
+}
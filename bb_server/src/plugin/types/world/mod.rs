@@ -4,11 +4,17 @@ use super::{
   item::PStack,
   util::{PFPos, PPos},
 };
-use crate::{entity, world::World};
+use crate::{
+  entity,
+  world::{
+    schematic::{Mirror, PasteOptions, Rotation, Schematic},
+    World,
+  },
+};
 use bb_common::{math::Pos, metadata::Metadata, net::cb::SoundCategory};
 use bb_server_macros::define_ty;
 use panda::{parse::token::Span, runtime::RuntimeError};
-use std::{fmt, sync::Arc};
+use std::{fmt, path::Path, sync::Arc};
 
 pub mod gen;
 
@@ -36,6 +42,10 @@ impl PWorld {
 /// entities, and players in this world.
 #[define_ty(panda_path = "bamboo::world::World")]
 impl PWorld {
+  /// Returns the name of this world. This can be passed to `Bamboo::world`
+  /// to look up this world again.
+  pub fn name(&self) -> String { self.inner.name().into() }
+
   /// Sets a single block in the world. This will return an error if the block
   /// is outside of the world.
   ///
@@ -85,6 +95,57 @@ impl PWorld {
     Ok(self.inner.get_block(pos.inner).unwrap().into())
   }
 
+  /// Loads the schematic at the given path, and pastes it into the world at
+  /// `pos`. This can be a Sponge schematic (`.schem`), or a legacy MCEdit
+  /// schematic (`.schematic`).
+  ///
+  /// `rotation` is the number of degrees to rotate clockwise, and must be a
+  /// multiple of 90. `mirror` can be `"none"`, `"x"` (which swaps east and
+  /// west), or `"z"` (which swaps north and south). Mirroring is applied
+  /// before rotating. If `skip_air` is set, air in the schematic will not
+  /// replace blocks in the world.
+  ///
+  /// This will return an error if the schematic could not be loaded, or if
+  /// any part of it would be outside the world.
+  pub fn paste_schematic(
+    &self,
+    path: &str,
+    pos: &PPos,
+    rotation: i32,
+    mirror: &str,
+    skip_air: bool,
+  ) -> Result<(), RuntimeError> {
+    let err = |msg: String| RuntimeError::custom(msg, Span::call_site());
+    let rotation = Rotation::from_degrees(rotation)
+      .ok_or_else(|| err(format!("invalid rotation {rotation}")))?;
+    let mirror = match mirror {
+      "none" => Mirror::None,
+      "x" => Mirror::X,
+      "z" => Mirror::Z,
+      _ => return Err(err(format!("invalid mirror {mirror}"))),
+    };
+    let schem = Schematic::load(Path::new(path), self.inner.block_converter())
+      .map_err(|e| err(format!("could not load schematic {path}: {e}")))?;
+    self
+      .inner
+      .paste_schematic(&schem, pos.inner, PasteOptions { rotation, mirror, skip_air })
+      .map_err(|e| err(format!("invalid position {}: {}", e.pos, e.msg)))
+  }
+
+  /// Saves all the blocks between `min` and `max` (inclusive) to a Sponge
+  /// schematic at the given path.
+  ///
+  /// This will return an error if `min` or `max` are outside the world, or if
+  /// the file could not be written.
+  pub fn save_schematic(&self, path: &str, min: &PPos, max: &PPos) -> Result<(), RuntimeError> {
+    self.check_pos(min.inner)?;
+    self.check_pos(max.inner)?;
+    let schem = self.inner.save_schematic(min.inner, max.inner).unwrap();
+    schem.save(Path::new(path), self.inner.block_converter()).map_err(|e| {
+      RuntimeError::custom(format!("could not save schematic {path}: {e}"), Span::call_site())
+    })
+  }
+
   /// Summons a dropped item at the given position.
   pub fn summon_item(&self, pos: &PFPos, stack: &PStack) {
     let mut meta = Metadata::new();
@@ -92,6 +153,17 @@ impl PWorld {
     self.inner.summon_meta(entity::Type::Item, pos.inner, meta);
   }
 
+  /// Summons an entity at the given position. `name` is the name of the entity,
+  /// like `zombie`. Zombies, skeletons, cows and villagers will walk around
+  /// on their own.
+  pub fn summon(&self, name: &str, pos: &PFPos) -> Result<(), RuntimeError> {
+    let ty = name
+      .parse::<entity::Type>()
+      .map_err(|e| RuntimeError::custom(e.to_string(), Span::call_site()))?;
+    self.inner.summon(ty, pos.inner);
+    Ok(())
+  }
+
   /// Plays the given sound at the given positions. All nearby players will be
   /// able to hear it.
   pub fn play_sound(
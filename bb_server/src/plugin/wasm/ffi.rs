@@ -2,9 +2,9 @@ use super::Env;
 
 use bb_common::{
   math::{FPos, Pos},
-  util::UUID,
+  util::{Chat, UUID},
 };
-use bb_ffi::{CBool, CFPos, CList, COpt, CPos, CStr, CUUID};
+use bb_ffi::{CBool, CChat, CFPos, CList, COpt, CPos, CStr, CUUID};
 use std::mem;
 
 pub trait FromFfi {
@@ -132,15 +132,24 @@ where
 impl ToFfi for UUID {
   type Ffi = CUUID;
 
-  fn to_ffi(&self, _env: &Env) -> CUUID {
-    CUUID {
-      bytes: [
-        self.as_u128() as u32,
-        (self.as_u128() >> 32) as u32,
-        (self.as_u128() >> (2 * 32)) as u32,
-        (self.as_u128() >> (3 * 32)) as u32,
-      ],
-    }
+  fn to_ffi(&self, _env: &Env) -> CUUID { uuid_to_ffi(*self) }
+}
+/// Converts a UUID into a `CUUID`. This is the same as [`ToFfi::to_ffi`], but
+/// it doesn't need an [`Env`], so it can be used when calling into a plugin.
+pub fn uuid_to_ffi(id: UUID) -> CUUID {
+  let id = id.as_u128();
+  CUUID { bytes: [id as u32, (id >> 32) as u32, (id >> (2 * 32)) as u32, (id >> (3 * 32)) as u32] }
+}
+impl FromFfi for UUID {
+  type Ffi = CUUID;
+
+  fn from_ffi(_env: &Env, ffi: CUUID) -> UUID {
+    UUID::from_u128(
+      (ffi.bytes[3] as u128) << (3 * 32)
+        | (ffi.bytes[2] as u128) << (2 * 32)
+        | (ffi.bytes[1] as u128) << 32
+        | ffi.bytes[0] as u128,
+    )
   }
 }
 impl ToFfi for &'_ str {
@@ -161,3 +170,8 @@ impl FromFfi for String {
     cstr.ptr.get_utf8_string(env.mem(), cstr.len).unwrap()
   }
 }
+impl FromFfi for Chat {
+  type Ffi = CChat;
+
+  fn from_ffi(env: &Env, cchat: CChat) -> Chat { Chat::new(String::from_ffi(env, cchat.message)) }
+}
@@ -2,17 +2,26 @@ use super::{FromFfi, ToFfi};
 use crate::{
   block,
   command::{Command, NodeType, Parser},
+  entity::EffectInstance,
+  item,
+  item::{SharedInventory, Stack},
   particle::Particle,
-  world::WorldManager,
+  player::{window::GenericWindow, Player, Window},
+  plugin::Scope,
+  world::{World, WorldManager},
 };
 use bb_common::{
   math::{FPos, Pos},
-  util::Chat,
+  net::cb::SoundCategory,
+  util::{chat::Color, Chat, Effect, Property, UUID},
   version::BlockVersion,
 };
-use bb_ffi::{CBlockPropValue, CChat, CCommand, CCommandArg, CFPos, CList, CParticle, CPos, CUUID};
+use bb_ffi::{
+  CBlockPropValue, CChat, CCommand, CCommandArg, CFPos, CInventory, CItemStack, CList, CParticle,
+  CPos, CSound, CStoreScope, CUUID,
+};
 use log::Level;
-use std::{mem, sync::Arc};
+use std::{mem, net::ToSocketAddrs, str::FromStr, sync::Arc};
 use wasmer::{
   imports, Array, Function, ImportObject, LazyInit, Memory, NativeFunc, Store, WasmPtr, WasmerEnv,
 };
@@ -87,6 +96,49 @@ impl Env {
   }
 }
 
+impl Env {
+  /// Looks up the player with the given id. Returns `None` if the pointer is
+  /// invalid, or if the player is offline.
+  pub fn player(&self, id: WasmPtr<CUUID>) -> Option<Arc<Player>> {
+    let id = id.deref(self.mem())?.get();
+    self.wm.get_player(UUID::from_ffi(self, id))
+  }
+  /// Returns the world with the given id. World ids are indices into
+  /// [`WorldManager::worlds`], so the default world is always `0`.
+  pub fn world(&self, wid: u32) -> Option<Arc<World>> {
+    self.wm.worlds().get(wid as usize).cloned()
+  }
+  /// Reads the chat message at the given pointer. Returns `None` if the pointer
+  /// is invalid.
+  pub fn chat(&self, chat: WasmPtr<CChat>) -> Option<Chat> {
+    let chat = chat.deref(self.mem())?.get();
+    Some(Chat::from_ffi(self, chat))
+  }
+  /// Reads a storage scope from a plugin. Returns `None` if the pointer is
+  /// invalid, or if the scope refers to a world or player that doesn't exist.
+  pub fn scope(&self, scope: WasmPtr<CStoreScope>) -> Option<Scope> {
+    let scope = scope.deref(self.mem())?.get();
+    match scope.kind {
+      0 => Some(Scope::Global),
+      1 => Some(Scope::Player(UUID::from_ffi(self, scope.player))),
+      2 => Some(Scope::World(self.world(scope.wid)?.name().into())),
+      _ => None,
+    }
+  }
+  /// Converts an item stack from a plugin. Returns `None` if the item name is
+  /// invalid.
+  pub fn stack(&self, stack: CItemStack) -> Option<Stack> {
+    let name = String::from_ffi(self, stack.item);
+    match item::Type::from_str(&name) {
+      Ok(ty) => Some(Stack::new(ty).with_amount(stack.amount)),
+      Err(e) => {
+        warn!("plugin used invalid item: {e}");
+        None
+      }
+    }
+  }
+}
+
 fn log_from_level(level: u32) -> Option<Level> {
   Some(match level {
     1 => Level::Error,
@@ -142,23 +194,13 @@ fn log(
 }
 
 fn broadcast(env: &Env, message: WasmPtr<CChat>) {
-  let chat = message.deref(env.mem()).unwrap().get();
-  let s = chat.message.ptr.get_utf8_string_with_nul(env.mem()).unwrap();
-  env.wm.broadcast(Chat::new(s));
+  if let Some(chat) = env.chat(message) {
+    env.wm.broadcast(chat);
+  }
 }
 
 fn player_username(env: &Env, id: WasmPtr<CUUID>) -> u32 {
-  let mem = env.mem();
-  let uuid = match id.deref(mem) {
-    Some(id) => id.get(),
-    None => return 1,
-  };
-  let player = match env.wm.get_player(bb_common::util::UUID::from_u128(
-    (uuid.bytes[3] as u128) << (3 * 32)
-      | (uuid.bytes[2] as u128) << (2 * 32)
-      | (uuid.bytes[1] as u128) << 32
-      | uuid.bytes[0] as u128,
-  )) {
+  let player = match env.player(id) {
     Some(p) => p,
     None => return 1,
   };
@@ -167,17 +209,7 @@ fn player_username(env: &Env, id: WasmPtr<CUUID>) -> u32 {
   ptr.offset()
 }
 fn player_pos(env: &Env, id: WasmPtr<CUUID>) -> u32 {
-  let mem = env.mem();
-  let uuid = match id.deref(mem) {
-    Some(id) => id.get(),
-    None => return 0,
-  };
-  let player = match env.wm.get_player(bb_common::util::UUID::from_u128(
-    (uuid.bytes[3] as u128) << (3 * 32)
-      | (uuid.bytes[2] as u128) << (2 * 32)
-      | (uuid.bytes[1] as u128) << 32
-      | uuid.bytes[0] as u128,
-  )) {
+  let player = match env.player(id) {
     Some(p) => p,
     None => return 0,
   };
@@ -186,17 +218,7 @@ fn player_pos(env: &Env, id: WasmPtr<CUUID>) -> u32 {
   ptr.offset()
 }
 fn player_look_as_vec(env: &Env, id: WasmPtr<CUUID>) -> u32 {
-  let mem = env.mem();
-  let uuid = match id.deref(mem) {
-    Some(id) => id.get(),
-    None => return 0,
-  };
-  let player = match env.wm.get_player(bb_common::util::UUID::from_u128(
-    (uuid.bytes[3] as u128) << (3 * 32)
-      | (uuid.bytes[2] as u128) << (2 * 32)
-      | (uuid.bytes[1] as u128) << 32
-      | uuid.bytes[0] as u128,
-  )) {
+  let player = match env.player(id) {
     Some(p) => p,
     None => return 0,
   };
@@ -204,22 +226,17 @@ fn player_look_as_vec(env: &Env, id: WasmPtr<CUUID>) -> u32 {
   let ptr = env.malloc_store(cpos);
   ptr.offset()
 }
+fn player_send_message(env: &Env, id: WasmPtr<CUUID>, message: WasmPtr<CChat>) {
+  if let (Some(player), Some(chat)) = (env.player(id), env.chat(message)) {
+    player.send_message(chat);
+  }
+}
 fn player_send_particle(env: &Env, id: WasmPtr<CUUID>, particle: WasmPtr<CParticle>) {
-  let mem = env.mem();
-  let uuid = match id.deref(mem) {
-    Some(id) => id.get(),
-    None => return,
-  };
-  let player = match env.wm.get_player(bb_common::util::UUID::from_u128(
-    (uuid.bytes[3] as u128) << (3 * 32)
-      | (uuid.bytes[2] as u128) << (2 * 32)
-      | (uuid.bytes[1] as u128) << 32
-      | uuid.bytes[0] as u128,
-  )) {
+  let player = match env.player(id) {
     Some(p) => p,
     None => return,
   };
-  let cparticle = match particle.deref(mem) {
+  let cparticle = match particle.deref(env.mem()) {
     Some(p) => p.get(),
     None => return,
   };
@@ -227,93 +244,346 @@ fn player_send_particle(env: &Env, id: WasmPtr<CUUID>, particle: WasmPtr<CPartic
   player.send_particle(particle);
 }
 
-fn player_world(env: &Env, player: WasmPtr<CUUID>) -> i32 {
-  let mem = env.mem();
-  let uuid = match player.deref(mem) {
-    Some(p) => p.get(),
+fn player_world(env: &Env, id: WasmPtr<CUUID>) -> i32 {
+  let player = match env.player(id) {
+    Some(p) => p,
+    None => return -1,
+  };
+  let world = player.world();
+  match env.wm.worlds().iter().position(|w| Arc::ptr_eq(w, &world)) {
+    Some(wid) => wid as i32,
+    None => -1,
+  }
+}
+fn player_teleport(env: &Env, id: WasmPtr<CUUID>, pos: WasmPtr<CFPos>, yaw: f32, pitch: f32) {
+  let player = match env.player(id) {
+    Some(p) => p,
+    None => return,
+  };
+  if let Some(pos) = pos.deref(env.mem()) {
+    player.teleport(FPos::from_ffi(env, pos.get()), yaw, pitch);
+  }
+}
+fn player_switch_world(env: &Env, id: WasmPtr<CUUID>, wid: u32, pos: WasmPtr<CFPos>) -> i32 {
+  let (player, world) = match (env.player(id), env.world(wid)) {
+    (Some(p), Some(w)) => (p, w),
+    _ => return -1,
+  };
+  match pos.deref(env.mem()) {
+    Some(pos) => {
+      player.switch_world(&world, FPos::from_ffi(env, pos.get()));
+      0
+    }
+    None => -1,
+  }
+}
+fn player_switch_to(env: &Env, id: WasmPtr<CUUID>, ip_ptr: WasmPtr<u8, Array>, ip_len: u32) -> i32 {
+  let player = match env.player(id) {
+    Some(p) => p,
+    None => return -1,
+  };
+  let ip = match ip_ptr.get_utf8_string(env.mem(), ip_len) {
+    Some(ip) => ip,
     None => return -1,
   };
-  let _player = match env.wm.get_player(bb_common::util::UUID::from_u128(
-    (uuid.bytes[3] as u128) << (3 * 32)
-      | (uuid.bytes[2] as u128) << (2 * 32)
-      | (uuid.bytes[1] as u128) << 32
-      | uuid.bytes[0] as u128,
-  )) {
+  match ip.to_socket_addrs() {
+    Ok(ips) => {
+      player.switch_to(ips.collect());
+      0
+    }
+    Err(e) => {
+      warn!("plugin tried to switch to invalid ip '{ip}': {e}");
+      -1
+    }
+  }
+}
+fn player_set_title(env: &Env, id: WasmPtr<CUUID>, title: WasmPtr<CChat>) {
+  if let (Some(player), Some(chat)) = (env.player(id), env.chat(title)) {
+    player.set_title(chat);
+  }
+}
+fn player_set_subtitle(env: &Env, id: WasmPtr<CUUID>, subtitle: WasmPtr<CChat>) {
+  if let (Some(player), Some(chat)) = (env.player(id), env.chat(subtitle)) {
+    player.set_subtitle(chat);
+  }
+}
+fn player_show_title(env: &Env, id: WasmPtr<CUUID>, fade_in: u32, stay: u32, fade_out: u32) {
+  if let Some(player) = env.player(id) {
+    player.show_title(fade_in, stay, fade_out);
+  }
+}
+fn player_show_scoreboard(env: &Env, id: WasmPtr<CUUID>) {
+  if let Some(player) = env.player(id) {
+    player.lock_scoreboard().show();
+  }
+}
+fn player_hide_scoreboard(env: &Env, id: WasmPtr<CUUID>) {
+  if let Some(player) = env.player(id) {
+    player.lock_scoreboard().hide();
+  }
+}
+fn player_set_scoreboard_line(
+  env: &Env,
+  id: WasmPtr<CUUID>,
+  line: u8,
+  message: WasmPtr<CChat>,
+) -> i32 {
+  if line >= 15 {
+    return -1;
+  }
+  if let (Some(player), Some(chat)) = (env.player(id), env.chat(message)) {
+    player.lock_scoreboard().set_line(line, &chat);
+  }
+  0
+}
+fn player_clear_scoreboard_line(env: &Env, id: WasmPtr<CUUID>, line: u8) -> i32 {
+  if line >= 15 {
+    return -1;
+  }
+  if let Some(player) = env.player(id) {
+    player.lock_scoreboard().clear_line(line);
+  }
+  0
+}
+fn player_give(env: &Env, id: WasmPtr<CUUID>, stack: WasmPtr<CItemStack>) -> i32 {
+  let player = match env.player(id) {
     Some(p) => p,
     None => return -1,
   };
+  let stack = match stack.deref(env.mem()).and_then(|s| env.stack(s.get())) {
+    Some(s) => s,
+    None => return -1,
+  };
+  player.lock_inventory().give(stack);
   0
 }
-
-fn world_set_block(env: &Env, _wid: u32, pos: WasmPtr<CPos>, id: u32) -> i32 {
+fn player_show_inventory(
+  env: &Env,
+  id: WasmPtr<CUUID>,
+  inv: WasmPtr<CInventory>,
+  title: WasmPtr<CChat>,
+) -> i32 {
+  let player = match env.player(id) {
+    Some(p) => p,
+    None => return -1,
+  };
+  let (inv, title) = match (inv.deref(env.mem()), env.chat(title)) {
+    (Some(inv), Some(title)) => (inv.get(), title),
+    _ => return -1,
+  };
+  let mut items = Vec::with_capacity(inv.items.len as usize);
+  for stack in Vec::<CItemStack>::from_ffi(env, inv.items) {
+    match env.stack(stack) {
+      Some(s) => items.push(s),
+      None => return -1,
+    }
+  }
+  match window_from_items(inv.rows, items) {
+    Some(win) => {
+      player.show_inventory(win, &title);
+      0
+    }
+    None => -1,
+  }
+}
+fn player_set_skin(
+  env: &Env,
+  id: WasmPtr<CUUID>,
+  value_ptr: WasmPtr<u8, Array>,
+  value_len: u32,
+  sig_ptr: WasmPtr<u8, Array>,
+  sig_len: u32,
+) -> i32 {
+  let player = match env.player(id) {
+    Some(p) => p,
+    None => return -1,
+  };
   let mem = env.mem();
-  let pos = match pos.deref(mem) {
-    Some(p) => p.get(),
+  let value = match value_ptr.get_utf8_string(mem, value_len) {
+    Some(v) => v,
+    None => return -1,
+  };
+  let signature = if sig_len == 0 {
+    None
+  } else {
+    match sig_ptr.get_utf8_string(mem, sig_len) {
+      Some(s) => Some(s),
+      None => return -1,
+    }
+  };
+  player.set_skin(Some(Property { name: "textures".into(), value, signature }));
+  0
+}
+fn player_reset_skin(env: &Env, id: WasmPtr<CUUID>) {
+  if let Some(player) = env.player(id) {
+    player.set_skin(None);
+  }
+}
+fn player_add_effect(
+  env: &Env,
+  id: WasmPtr<CUUID>,
+  effect: u32,
+  amplifier: u32,
+  duration: u32,
+) -> i32 {
+  let player = match env.player(id) {
+    Some(p) => p,
     None => return -1,
   };
-  let world = env.wm.default_world();
+  let effect = match u8::try_from(effect).ok().and_then(Effect::from_id) {
+    Some(e) => e,
+    None => return -1,
+  };
+  player.add_effect(EffectInstance::new(effect, amplifier.min(255) as u8, duration));
+  0
+}
+fn player_remove_effect(env: &Env, id: WasmPtr<CUUID>, effect: u32) -> i32 {
+  let player = match env.player(id) {
+    Some(p) => p,
+    None => return -1,
+  };
+  match u8::try_from(effect).ok().and_then(Effect::from_id) {
+    Some(e) => player.remove_effect(e) as i32,
+    None => -1,
+  }
+}
+fn player_clear_effects(env: &Env, id: WasmPtr<CUUID>) {
+  if let Some(player) = env.player(id) {
+    player.clear_effects();
+  }
+}
+
+/// Creates a generic chest-like window with the given number of rows. Returns
+/// `None` if `rows` is not within 1 and 6.
+fn window_from_items(rows: u32, items: Vec<Stack>) -> Option<Window> {
+  macro_rules! generic {
+    ( $variant:ident, $size:expr ) => {{
+      let inv = SharedInventory::<$size>::new();
+      {
+        let mut lock = inv.lock();
+        for (i, stack) in items.into_iter().take($size).enumerate() {
+          lock.set(i as u32, stack);
+        }
+      }
+      Window::$variant(GenericWindow { inv })
+    }};
+  }
+  Some(match rows {
+    1 => generic!(Generic9x1, 9),
+    2 => generic!(Generic9x2, 18),
+    3 => generic!(Generic9x3, 27),
+    4 => generic!(Generic9x4, 36),
+    5 => generic!(Generic9x5, 45),
+    6 => generic!(Generic9x6, 54),
+    _ => return None,
+  })
+}
+
+fn world_set_block(env: &Env, wid: u32, pos: WasmPtr<CPos>, id: u32) -> i32 {
+  let (world, pos) = match (env.world(wid), pos.deref(env.mem())) {
+    (Some(w), Some(p)) => (w, p.get()),
+    _ => return -1,
+  };
   let ty = env.wm.block_converter().type_from_id(id, env.ver);
-  match world.set_block(Pos::new(pos.x, pos.y, pos.z), ty) {
+  match world.set_block(Pos::from_ffi(env, pos), ty) {
     Ok(_) => 0,
     Err(_) => -1,
   }
 }
-fn world_set_block_kind(env: &Env, _wid: u32, pos: WasmPtr<CPos>, kind: u32) -> i32 {
-  let mem = env.mem();
-  let pos = match pos.deref(mem) {
-    Some(p) => p.get(),
-    None => return -1,
+fn world_set_block_kind(env: &Env, wid: u32, pos: WasmPtr<CPos>, kind: u32) -> i32 {
+  let (world, pos) = match (env.world(wid), pos.deref(env.mem())) {
+    (Some(w), Some(p)) => (w, p.get()),
+    _ => return -1,
   };
-  let world = env.wm.default_world();
   let kind = block::Kind::from_id(kind).unwrap_or(block::Kind::Air);
-  match world.set_kind(Pos::new(pos.x, pos.y, pos.z), kind) {
+  match world.set_kind(Pos::from_ffi(env, pos), kind) {
     Ok(_) => 0,
     Err(_) => -1,
   }
 }
-fn world_get_block(env: &Env, _wid: u32, pos: WasmPtr<CPos>) -> u32 {
-  let mem = env.mem();
-  let pos = match pos.deref(mem) {
-    Some(p) => p.get(),
-    None => return u32::MAX,
+fn world_get_block(env: &Env, wid: u32, pos: WasmPtr<CPos>) -> u32 {
+  let (world, pos) = match (env.world(wid), pos.deref(env.mem())) {
+    (Some(w), Some(p)) => (w, p.get()),
+    _ => return u32::MAX,
   };
-  let world = env.wm.default_world();
-  match world.get_block(Pos::new(pos.x, pos.y, pos.z)) {
+  match world.get_block(Pos::from_ffi(env, pos)) {
     Ok(ty) => ty.id(),
     Err(_) => u32::MAX,
   }
 }
-fn world_players(env: &Env, _wid: u32) -> u32 {
-  // TODO: Use the world id.
-  let world = env.wm.default_world();
-  let players: Vec<_> = world.players().iter().map(|p| p.id()).collect();
+fn world_players(env: &Env, wid: u32) -> u32 {
+  let players: Vec<_> = match env.world(wid) {
+    Some(world) => world.players().iter().map(|p| p.id()).collect(),
+    None => vec![],
+  };
   let cplayers = players.as_slice().to_ffi(env);
   let ptr = env.malloc_store(cplayers);
   ptr.offset()
 }
-fn world_spawn_particle(env: &Env, _wid: u32, particle: WasmPtr<CParticle>) {
-  // TODO: Use the world id.
-  let world = env.wm.default_world();
-  let mem = env.mem();
-  let cparticle = match particle.deref(mem) {
-    Some(p) => p.get(),
-    None => return,
+fn world_spawn_particle(env: &Env, wid: u32, particle: WasmPtr<CParticle>) {
+  let (world, cparticle) = match (env.world(wid), particle.deref(env.mem())) {
+    (Some(w), Some(p)) => (w, p.get()),
+    _ => return,
   };
   let particle = Particle::from_ffi(env, cparticle);
   world.spawn_particle(particle);
 }
-fn world_raycast(env: &Env, from: WasmPtr<CFPos>, to: WasmPtr<CFPos>, water: u8) -> u32 {
+fn world_fill_rect_kind(
+  env: &Env,
+  wid: u32,
+  min: WasmPtr<CPos>,
+  max: WasmPtr<CPos>,
+  kind: u32,
+) -> i32 {
   let mem = env.mem();
-  let from = match from.deref(mem) {
-    Some(p) => FPos::from_ffi(env, p.get()),
-    None => return 0,
+  let (world, min, max) = match (env.world(wid), min.deref(mem), max.deref(mem)) {
+    (Some(w), Some(min), Some(max)) => (w, min.get(), max.get()),
+    _ => return -1,
   };
-  let to = match to.deref(mem) {
-    Some(p) => FPos::from_ffi(env, p.get()),
-    None => return 0,
+  let kind = block::Kind::from_id(kind).unwrap_or(block::Kind::Air);
+  match world.fill_rect_kind(Pos::from_ffi(env, min), Pos::from_ffi(env, max), kind) {
+    Ok(_) => 0,
+    Err(_) => -1,
+  }
+}
+fn world_play_sound(env: &Env, wid: u32, sound: WasmPtr<CSound>) {
+  let (world, sound) = match (env.world(wid), sound.deref(env.mem())) {
+    (Some(w), Some(s)) => (w, s.get()),
+    _ => return,
+  };
+  let category = match sound.category {
+    0 => SoundCategory::Master,
+    1 => SoundCategory::Music,
+    2 => SoundCategory::Records,
+    3 => SoundCategory::Weather,
+    4 => SoundCategory::Blocks,
+    5 => SoundCategory::Hostile,
+    6 => SoundCategory::Neutral,
+    7 => SoundCategory::Players,
+    8 => SoundCategory::Ambient,
+    9 => SoundCategory::Voice,
+    _ => {
+      warn!("plugin tried to play sound with invalid category {}", sound.category);
+      return;
+    }
+  };
+  world.play_sound(
+    String::from_ffi(env, sound.name),
+    category,
+    FPos::from_ffi(env, sound.pos),
+    sound.volume,
+    sound.pitch,
+  );
+}
+fn world_raycast(env: &Env, wid: u32, from: WasmPtr<CFPos>, to: WasmPtr<CFPos>, water: u8) -> u32 {
+  let mem = env.mem();
+  let (world, from, to) = match (env.world(wid), from.deref(mem), to.deref(mem)) {
+    (Some(w), Some(from), Some(to)) => {
+      (w, FPos::from_ffi(env, from.get()), FPos::from_ffi(env, to.get()))
+    }
+    _ => return 0,
   };
   let water = water == 1;
-  let world = env.wm.default_world();
   match world.raycast(from, to, water) {
     Some((pos, _res)) => {
       let cpos = pos.to_ffi(env);
@@ -323,6 +593,119 @@ fn world_raycast(env: &Env, from: WasmPtr<CFPos>, to: WasmPtr<CFPos>, water: u8)
     None => 0,
   }
 }
+
+fn team_create(env: &Env, name_ptr: WasmPtr<u8, Array>, name_len: u32) -> i32 {
+  let name = match name_ptr.get_utf8_string(env.mem(), name_len) {
+    Some(n) => n,
+    None => return -1,
+  };
+  match env.wm.create_team(name) {
+    Some(_) => 0,
+    None => -1,
+  }
+}
+fn team_set_color(
+  env: &Env,
+  name_ptr: WasmPtr<u8, Array>,
+  name_len: u32,
+  color_ptr: WasmPtr<u8, Array>,
+  color_len: u32,
+) -> i32 {
+  let mem = env.mem();
+  let (name, color) =
+    match (name_ptr.get_utf8_string(mem, name_len), color_ptr.get_utf8_string(mem, color_len)) {
+      (Some(n), Some(c)) => (n, c),
+      _ => return -1,
+    };
+  let team = match env.wm.team(&name) {
+    Some(t) => t,
+    None => return -1,
+  };
+  match Color::from_str(&color) {
+    Ok(color) => {
+      team.lock().set_color(color);
+      0
+    }
+    Err(e) => {
+      warn!("plugin tried to set invalid team color: {e}");
+      -1
+    }
+  }
+}
+fn team_add_player(
+  env: &Env,
+  name_ptr: WasmPtr<u8, Array>,
+  name_len: u32,
+  id: WasmPtr<CUUID>,
+) -> i32 {
+  let name = match name_ptr.get_utf8_string(env.mem(), name_len) {
+    Some(n) => n,
+    None => return -1,
+  };
+  match (env.wm.team(&name), env.player(id)) {
+    (Some(team), Some(player)) => {
+      team.lock().add(&player);
+      0
+    }
+    _ => -1,
+  }
+}
+
+fn store_get(
+  env: &Env,
+  scope: WasmPtr<CStoreScope>,
+  key_ptr: WasmPtr<u8, Array>,
+  key_len: u32,
+) -> u32 {
+  let (scope, key) = match (env.scope(scope), key_ptr.get_utf8_string(env.mem(), key_len)) {
+    (Some(s), Some(k)) => (s, k),
+    _ => return 0,
+  };
+  match env.wm.plugin_storage(&env.name).get(&scope, &key) {
+    Some(value) => {
+      let cvalue = value.as_str().to_ffi(env);
+      let ptr = env.malloc_store(cvalue);
+      ptr.offset()
+    }
+    None => 0,
+  }
+}
+fn store_set(
+  env: &Env,
+  scope: WasmPtr<CStoreScope>,
+  key_ptr: WasmPtr<u8, Array>,
+  key_len: u32,
+  value_ptr: WasmPtr<u8, Array>,
+  value_len: u32,
+) -> i32 {
+  let mem = env.mem();
+  let (scope, key, value) = match (
+    env.scope(scope),
+    key_ptr.get_utf8_string(mem, key_len),
+    value_ptr.get_utf8_string(mem, value_len),
+  ) {
+    (Some(s), Some(k), Some(v)) => (s, k, v),
+    _ => return -1,
+  };
+  env.wm.plugin_storage(&env.name).set(&scope, key, value);
+  0
+}
+fn store_remove(
+  env: &Env,
+  scope: WasmPtr<CStoreScope>,
+  key_ptr: WasmPtr<u8, Array>,
+  key_len: u32,
+) -> i32 {
+  let (scope, key) = match (env.scope(scope), key_ptr.get_utf8_string(env.mem(), key_len)) {
+    (Some(s), Some(k)) => (s, k),
+    _ => return -1,
+  };
+  match env.wm.plugin_storage(&env.name).remove(&scope, &key) {
+    Some(_) => 1,
+    None => 0,
+  }
+}
+
 fn block_data_for_kind(env: &Env, kind: u32) -> u32 {
   // TODO: Convert kind to server version
   let data = env.wm.block_converter().get(match block::Kind::from_id(kind) {
@@ -402,7 +785,7 @@ fn add_command(env: &Env, cmd: WasmPtr<CCommand>) {
   if let Some(cmd) = command_from_env(env, cmd) {
     let e = env;
     let env = env.clone();
-    e.wm.commands().add(cmd, move |_, player, args| {
+    e.wm.commands().add_for_plugin(&e.name, cmd, move |_, player, args| {
       let id = match player {
         Some(p) => env.malloc_store(p.id().to_ffi(&env)),
         None => WasmPtr::new(0),
@@ -455,14 +838,69 @@ pub fn imports(store: &Store, wm: Arc<WorldManager>, name: String) -> ImportObje
       "bb_player_pos" => Function::new_native_with_env(store, env.clone(), player_pos),
       "bb_player_look_as_vec" => Function::new_native_with_env(store, env.clone(), player_look_as_vec),
       "bb_player_world" => Function::new_native_with_env(store, env.clone(), player_world),
+      "bb_player_send_message" => Function::new_native_with_env(store, env.clone(), player_send_message),
       "bb_player_send_particle" => Function::new_native_with_env(store, env.clone(), player_send_particle),
+      "bb_player_teleport" => Function::new_native_with_env(store, env.clone(), player_teleport),
+      "bb_player_switch_world" => Function::new_native_with_env(store, env.clone(), player_switch_world),
+      "bb_player_switch_to" => Function::new_native_with_env(store, env.clone(), player_switch_to),
+      "bb_player_set_title" => Function::new_native_with_env(store, env.clone(), player_set_title),
+      "bb_player_set_subtitle" => Function::new_native_with_env(store, env.clone(), player_set_subtitle),
+      "bb_player_show_title" => Function::new_native_with_env(store, env.clone(), player_show_title),
+      "bb_player_show_scoreboard" => Function::new_native_with_env(store, env.clone(), player_show_scoreboard),
+      "bb_player_hide_scoreboard" => Function::new_native_with_env(store, env.clone(), player_hide_scoreboard),
+      "bb_player_set_scoreboard_line" => Function::new_native_with_env(store, env.clone(), player_set_scoreboard_line),
+      "bb_player_clear_scoreboard_line" => Function::new_native_with_env(store, env.clone(), player_clear_scoreboard_line),
+      "bb_player_give" => Function::new_native_with_env(store, env.clone(), player_give),
+      "bb_player_show_inventory" => Function::new_native_with_env(store, env.clone(), player_show_inventory),
+      "bb_player_set_skin" => Function::new_native_with_env(store, env.clone(), player_set_skin),
+      "bb_player_reset_skin" => Function::new_native_with_env(store, env.clone(), player_reset_skin),
+      "bb_player_add_effect" => Function::new_native_with_env(store, env.clone(), player_add_effect),
+      "bb_player_remove_effect" => Function::new_native_with_env(store, env.clone(), player_remove_effect),
+      "bb_player_clear_effects" => Function::new_native_with_env(store, env.clone(), player_clear_effects),
       "bb_world_set_block" => Function::new_native_with_env(store, env.clone(), world_set_block),
       "bb_world_set_block_kind" => Function::new_native_with_env(store, env.clone(), world_set_block_kind),
       "bb_world_get_block" => Function::new_native_with_env(store, env.clone(), world_get_block),
       "bb_world_players" => Function::new_native_with_env(store, env.clone(), world_players),
       "bb_world_spawn_particle" => Function::new_native_with_env(store, env.clone(), world_spawn_particle),
+      "bb_world_fill_rect_kind" => Function::new_native_with_env(store, env.clone(), world_fill_rect_kind),
+      "bb_world_play_sound" => Function::new_native_with_env(store, env.clone(), world_play_sound),
       "bb_world_raycast" => Function::new_native_with_env(store, env.clone(), world_raycast),
+      "bb_team_create" => Function::new_native_with_env(store, env.clone(), team_create),
+      "bb_team_set_color" => Function::new_native_with_env(store, env.clone(), team_set_color),
+      "bb_team_add_player" => Function::new_native_with_env(store, env.clone(), team_add_player),
+      "bb_store_get" => Function::new_native_with_env(store, env.clone(), store_get),
+      "bb_store_set" => Function::new_native_with_env(store, env.clone(), store_set),
+      "bb_store_remove" => Function::new_native_with_env(store, env.clone(), store_remove),
       "bb_time_since_start" => Function::new_native_with_env(store, env, time_since_start),
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::net::tests::TestHandler;
+  use wasmer::MemoryType;
+
+  #[test]
+  fn scoreboard_line() {
+    let handler = TestHandler::new();
+    let store = Store::default();
+    let mut env = Env {
+      memory:      LazyInit::new(),
+      wasm_malloc: LazyInit::new(),
+      on_command:  LazyInit::new(),
+      wm:          handler.wm().clone(),
+      ver:         BlockVersion::latest(),
+      name:        Arc::new("test".into()),
+    };
+    env.memory.initialize(Memory::new(&store, MemoryType::new(1, None, false)).unwrap());
+    let id = WasmPtr::<CUUID>::new(0);
+    id.deref(env.mem()).unwrap().set(super::super::ffi::uuid_to_ffi(handler.player().id()));
+
+    assert_eq!(player_clear_scoreboard_line(&env, id, 14), 0);
+    assert_eq!(player_clear_scoreboard_line(&env, id, 15), -1);
+    assert_eq!(player_clear_scoreboard_line(&env, id, 255), -1);
+    assert_eq!(player_set_scoreboard_line(&env, id, 15, WasmPtr::new(64)), -1);
+  }
+}
@@ -59,29 +59,37 @@ where
   }
 }
 
-impl<B, C, D> Input for (CUUID, B, C, D)
-where
-  B: Input + FromToNativeWasmType + Copy,
-  C: Input + FromToNativeWasmType + Copy,
-  D: Input + FromToNativeWasmType + Copy,
-{
-  type WasmArgs = (u32, u32, u32, u32, B, C, D);
-  fn call_native<Rets: WasmTypeList>(
-    &self,
-    native: &NativeFunc<Self::WasmArgs, Rets>,
-  ) -> Result<Rets> {
-    native.call(
-      self.0.bytes[0],
-      self.0.bytes[1],
-      self.0.bytes[2],
-      self.0.bytes[3],
-      self.1,
-      self.2,
-      self.3,
-    )
-  }
+/// Implements `Input` for tuples starting with a `CUUID`. The UUID is passed to
+/// wasm as 4 separate `u32`s.
+macro_rules! uuid_input {
+  ( $($name:ident: $idx:tt),* ) => {
+    impl<$($name),*> Input for (CUUID, $($name,)*)
+    where
+      $($name: Input + FromToNativeWasmType + Copy,)*
+    {
+      type WasmArgs = (u32, u32, u32, u32, $($name,)*);
+      fn call_native<Rets: WasmTypeList>(
+        &self,
+        native: &NativeFunc<Self::WasmArgs, Rets>,
+      ) -> Result<Rets> {
+        native.call(
+          self.0.bytes[0],
+          self.0.bytes[1],
+          self.0.bytes[2],
+          self.0.bytes[3],
+          $(self.$idx,)*
+        )
+      }
+    }
+  };
 }
 
+uuid_input!();
+uuid_input!(B: 1);
+uuid_input!(B: 1, C: 2);
+uuid_input!(B: 1, C: 2, D: 3);
+uuid_input!(B: 1, C: 2, D: 3, E: 4, F: 5);
+
 impl Input for i32 {
   type WasmArgs = i32;
   fn call_native<Rets: WasmTypeList>(
@@ -100,3 +108,12 @@ impl<T: Copy> Input for WasmPtr<T> {
     native.call(*self)
   }
 }
+impl Input for f32 {
+  type WasmArgs = f32;
+  fn call_native<Rets: WasmTypeList>(
+    &self,
+    native: &NativeFunc<Self::WasmArgs, Rets>,
+  ) -> Result<Rets> {
+    native.call(*self)
+  }
+}
@@ -0,0 +1,276 @@
+use bb_common::config::Config;
+use parking_lot::{Condvar, Mutex};
+use std::{
+  ptr::NonNull,
+  sync::Arc,
+  thread,
+  time::{Duration, Instant},
+};
+use wasmer::{
+  vm::{self, MemoryError, MemoryStyle, TableStyle, VMMemoryDefinition, VMTableDefinition},
+  BaseTunables, CompilerConfig, Cranelift, Instance, MemoryType, Pages, Store, TableType, Target,
+  Tunables, Universal,
+};
+use wasmer_middlewares::{metering, Metering};
+
+/// The resource limits for a single wasm plugin. These are read from the
+/// `[wasm]` section of `plugin.toml`.
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+  /// The maximum number of pages the plugin's memory can grow to.
+  pub max_memory:       Pages,
+  /// The number of instructions a single call can execute. If `None`, there is
+  /// no limit.
+  pub max_instructions: Option<u64>,
+  /// The amount of time a single call can take. If `None`, there is no limit.
+  pub timeout:          Option<Duration>,
+}
+
+impl Limits {
+  pub fn from_config(config: &Config) -> Self {
+    let max_instructions: u64 = config.get_at(["wasm", "max-instructions"].into_iter());
+    let timeout: u64 = config.get_at(["wasm", "timeout"].into_iter());
+    Limits {
+      max_memory:       Pages(config.get_at(["wasm", "max-memory-pages"].into_iter())),
+      max_instructions: if max_instructions == 0 { None } else { Some(max_instructions) },
+      timeout:          if timeout == 0 { None } else { Some(Duration::from_millis(timeout)) },
+    }
+  }
+
+  /// Returns true if modules need instruction metering. The timeout also
+  /// needs metering, as that is how the [`Watchdog`] stops a call.
+  pub fn metered(&self) -> bool { self.max_instructions.is_some() || self.timeout.is_some() }
+
+  /// Creates a store that will enforce these limits. Any module compiled with
+  /// this store will have instruction metering added (if needed), and any
+  /// memory created will be capped at `max_memory`.
+  pub fn store(&self) -> Store {
+    let mut compiler = Cranelift::default();
+    if self.metered() {
+      let max = self.max_instructions.unwrap_or(u64::MAX);
+      compiler.push_middleware(Arc::new(Metering::new(max, |_| 1)));
+    }
+    let tunables = LimitingTunables {
+      limit: self.max_memory,
+      base:  BaseTunables::for_target(&Target::default()),
+    };
+    Store::new_with_tunables(&Universal::new(compiler).engine(), tunables)
+  }
+}
+
+/// Stops calls into a plugin that run past their deadline. This runs on its
+/// own thread, and once the deadline passes, it sets the plugin's remaining
+/// instructions to zero. The next metering check in the plugin will then trap,
+/// so even an infinite loop will be stopped.
+///
+/// Host functions are not interrupted, so a plugin stuck inside a call to the
+/// server will only be stopped once that call returns.
+pub struct Watchdog {
+  state: Arc<(Mutex<WatchdogState>, Condvar)>,
+}
+
+#[derive(Default)]
+struct WatchdogState {
+  deadline: Option<Instant>,
+  fired:    bool,
+  stop:     bool,
+}
+
+impl Watchdog {
+  /// Starts the watchdog thread for the given instance. The instance must
+  /// have been compiled with metering (see [`Limits::metered`]).
+  pub fn new(inst: Instance) -> Self {
+    let state = Arc::new((Mutex::new(WatchdogState::default()), Condvar::new()));
+    let s = state.clone();
+    thread::spawn(move || {
+      let (lock, cvar) = &*s;
+      let mut state = lock.lock();
+      while !state.stop {
+        match state.deadline {
+          Some(deadline) if Instant::now() >= deadline => {
+            state.fired = true;
+            metering::set_remaining_points(&inst, 0);
+            // The plugin may write back its remaining points after we set them, so we
+            // keep setting them until the call returns.
+            cvar.wait_for(&mut state, Duration::from_millis(1));
+          }
+          Some(deadline) => {
+            cvar.wait_until(&mut state, deadline);
+          }
+          None => cvar.wait(&mut state),
+        }
+      }
+    });
+    Watchdog { state }
+  }
+
+  /// Starts watching a call. If [`finish`](Self::finish) isn't called within
+  /// `timeout`, the call will be stopped.
+  pub fn start(&self, timeout: Duration) {
+    let (lock, cvar) = &*self.state;
+    let mut state = lock.lock();
+    state.deadline = Some(Instant::now() + timeout);
+    state.fired = false;
+    cvar.notify_one();
+  }
+
+  /// Stops watching the current call. Returns `true` if the call was stopped
+  /// because it ran past the deadline.
+  pub fn finish(&self) -> bool {
+    let (lock, _) = &*self.state;
+    let mut state = lock.lock();
+    state.deadline = None;
+    state.fired
+  }
+}
+
+impl Drop for Watchdog {
+  fn drop(&mut self) {
+    let (lock, cvar) = &*self.state;
+    lock.lock().stop = true;
+    cvar.notify_one();
+  }
+}
+
+/// Wraps some tunables, and caps the maximum size of every memory. This is
+/// how we stop a plugin from using all of the server's memory: once the limit
+/// is reached, `memory.grow` will fail inside the plugin.
+struct LimitingTunables<T> {
+  limit: Pages,
+  base:  T,
+}
+
+impl<T: Tunables> LimitingTunables<T> {
+  /// Memories without a maximum size will be given our limit as their maximum.
+  fn adjust_memory(&self, requested: &MemoryType) -> MemoryType {
+    let mut adjusted = *requested;
+    if requested.maximum.map_or(true, |max| max > self.limit) {
+      adjusted.maximum = Some(self.limit);
+    }
+    adjusted
+  }
+  fn validate_memory(&self, ty: &MemoryType) -> Result<(), MemoryError> {
+    if ty.minimum > self.limit {
+      return Err(MemoryError::Generic(format!(
+        "plugin requires {} pages of memory, which is more than the limit of {} pages",
+        ty.minimum.0, self.limit.0
+      )));
+    }
+    Ok(())
+  }
+}
+
+impl<T: Tunables> Tunables for LimitingTunables<T> {
+  fn memory_style(&self, memory: &MemoryType) -> MemoryStyle {
+    self.base.memory_style(&self.adjust_memory(memory))
+  }
+  fn table_style(&self, table: &TableType) -> TableStyle { self.base.table_style(table) }
+
+  fn create_host_memory(
+    &self,
+    ty: &MemoryType,
+    style: &MemoryStyle,
+  ) -> Result<Arc<dyn vm::Memory>, MemoryError> {
+    let adjusted = self.adjust_memory(ty);
+    self.validate_memory(&adjusted)?;
+    self.base.create_host_memory(&adjusted, style)
+  }
+  unsafe fn create_vm_memory(
+    &self,
+    ty: &MemoryType,
+    style: &MemoryStyle,
+    vm_definition_location: NonNull<VMMemoryDefinition>,
+  ) -> Result<Arc<dyn vm::Memory>, MemoryError> {
+    let adjusted = self.adjust_memory(ty);
+    self.validate_memory(&adjusted)?;
+    self.base.create_vm_memory(&adjusted, style, vm_definition_location)
+  }
+
+  fn create_host_table(
+    &self,
+    ty: &TableType,
+    style: &TableStyle,
+  ) -> Result<Arc<dyn vm::Table>, String> {
+    self.base.create_host_table(ty, style)
+  }
+  unsafe fn create_vm_table(
+    &self,
+    ty: &TableType,
+    style: &TableStyle,
+    vm_definition_location: NonNull<VMTableDefinition>,
+  ) -> Result<Arc<dyn vm::Table>, String> {
+    self.base.create_vm_table(ty, style, vm_definition_location)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use wasmer::{imports, Memory, Module};
+
+  fn config(src: &str) -> Config {
+    let path =
+      std::env::temp_dir().join(format!("bamboo-limits-{}-{}.toml", std::process::id(), src.len()));
+    std::fs::write(&path, src).unwrap();
+    let config = Config::new(path.to_str().unwrap(), include_str!("../plugin.toml"));
+    std::fs::remove_file(&path).unwrap();
+    config
+  }
+
+  #[test]
+  fn parse_limits() {
+    let limits = Limits::from_config(&config(""));
+    assert_eq!(limits.max_memory, Pages(256));
+    assert_eq!(limits.max_instructions, Some(100_000_000));
+    assert_eq!(limits.timeout, Some(Duration::from_millis(100)));
+
+    let limits = Limits::from_config(&config(
+      "[wasm]\nmax-memory-pages = 16\nmax-instructions = 0\ntimeout = 0\n",
+    ));
+    assert_eq!(limits.max_memory, Pages(16));
+    assert_eq!(limits.max_instructions, None);
+    assert_eq!(limits.timeout, None);
+    assert!(!limits.metered());
+  }
+
+  #[test]
+  fn memory_limit() {
+    let limits =
+      Limits { max_memory: Pages(4), max_instructions: None, timeout: None };
+    let store = limits.store();
+    let mem = Memory::new(&store, MemoryType::new(1, None, false)).unwrap();
+    assert_eq!(mem.ty().maximum, Some(Pages(4)));
+    mem.grow(3).unwrap();
+    assert!(mem.grow(1).is_err());
+    assert_eq!(mem.size(), Pages(4));
+
+    assert!(Memory::new(&store, MemoryType::new(5, None, false)).is_err());
+  }
+
+  #[test]
+  fn watchdog() {
+    // (module (func (export "spin") (loop (br 0))))
+    let wasm = [
+      0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, // header
+      0x01, 0x04, 0x01, 0x60, 0x00, 0x00, // types
+      0x03, 0x02, 0x01, 0x00, // functions
+      0x07, 0x08, 0x01, 0x04, b's', b'p', b'i', b'n', 0x00, 0x00, // exports
+      0x0a, 0x09, 0x01, 0x07, 0x00, 0x03, 0x40, 0x0c, 0x00, 0x0b, 0x0b, // code
+    ];
+    let limits = Limits {
+      max_memory:       Pages(4),
+      max_instructions: None,
+      timeout:          Some(Duration::from_millis(50)),
+    };
+    let store = limits.store();
+    let module = Module::new(&store, wasm).unwrap();
+    let inst = Instance::new(&module, &imports! {}).unwrap();
+    let spin = inst.exports.get_native_function::<(), ()>("spin").unwrap();
+    let watchdog = Watchdog::new(inst.clone());
+
+    metering::set_remaining_points(&inst, u64::MAX);
+    watchdog.start(limits.timeout.unwrap());
+    assert!(spin.call().is_err());
+    assert!(watchdog.finish());
+  }
+}
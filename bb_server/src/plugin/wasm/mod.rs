@@ -1,23 +1,39 @@
 mod ffi;
 mod funcs;
 mod input;
+mod limits;
 mod output;
 
 pub use ffi::{FromFfi, ToFfi};
 pub use funcs::Env;
+pub use limits::{Limits, Watchdog};
 
-use super::{CallError, GlobalServerEvent, PluginImpl, PluginReply, ServerEvent, ServerRequest};
+use super::{
+  CallError, GlobalServerEvent, GlobalServerRequest, PluginImpl, PluginReply, ServerEvent,
+  ServerRequest,
+};
 use crate::{
   player::Player,
   world::{MultiChunk, WorldManager},
 };
-use bb_ffi::CUUID;
+use bb_common::util::Hand;
 use parking_lot::Mutex;
-use std::{fs, io, path::Path, process::Command, sync::Arc};
+use std::{
+  fs, io,
+  path::Path,
+  process::Command,
+  sync::Arc,
+  time::{Duration, Instant},
+};
 use thiserror::Error;
-use wasmer::{Instance, Memory, Module, NativeFunc, Store, WasmPtr, WasmTypeList};
+use wasmer::{Instance, Memory, Module, NativeFunc, WasmPtr, WasmTypeList};
+use wasmer_middlewares::metering::{self, MeteringPoints};
 
 pub struct Plugin {
+  name:          String,
+  limits:        Limits,
+  /// Only present if `limits.timeout` is set.
+  watchdog:      Option<Watchdog>,
   inst_mem_lock: Mutex<()>,
   inst:          Instance,
 }
@@ -55,6 +71,20 @@ pub enum PluginCreateError {
   InstantiationError(#[from] wasmer::InstantiationError),
   #[error("could not compile plugin: {0}")]
   CompileError(#[from] wasmer::CompileError),
+  #[error("failed to initialize plugin: {0}")]
+  InitFailed(CallError),
+}
+
+/// An error from a plugin going over one of its [`Limits`]. These will always
+/// disable the plugin.
+#[derive(Error, Debug)]
+pub enum LimitError {
+  #[error("plugin `{plugin}` ran out of instructions while calling `{func}`")]
+  OutOfInstructions { plugin: String, func: String },
+  #[error(
+    "plugin `{plugin}` took {took:?} to call `{func}`, which is over the limit of {limit:?}"
+  )]
+  Timeout { plugin: String, func: String, took: Duration, limit: Duration },
 }
 
 impl Plugin {
@@ -63,6 +93,7 @@ impl Plugin {
     path: &Path,
     compile: String,
     output: String,
+    limits: Limits,
     wm: Arc<WorldManager>,
   ) -> Result<Self, PluginCreateError> {
     if !compile.is_empty() {
@@ -79,13 +110,14 @@ impl Plugin {
         return Err(PluginCreateError::CompileFailed(String::from_utf8_lossy(&out.stderr).into()));
       }
     }
-    let store = Store::default();
+    let store = limits.store();
     let module =
       Module::new(&store, fs::read(path.join(output)).map_err(PluginCreateError::Missing)?)?;
-    let import_object = funcs::imports(&store, wm, name);
+    let import_object = funcs::imports(&store, wm, name.clone());
     let inst = Instance::new(&module, &import_object)?;
-    let plug = Plugin { inst_mem_lock: Mutex::new(()), inst };
-    plug.call("init", ()).unwrap();
+    let watchdog = limits.timeout.map(|_| Watchdog::new(inst.clone()));
+    let plug = Plugin { name, limits, watchdog, inst_mem_lock: Mutex::new(()), inst };
+    plug.call("init", ()).map_err(PluginCreateError::InitFailed)?;
     Ok(plug)
   }
 
@@ -93,7 +125,7 @@ impl Plugin {
     // Try to get function with int. If this fails, error.
     // If the function doesn't exist, we error.
     match self.inst.exports.get_native_function::<I::WasmArgs, u8>(name) {
-      Ok(func) => input.call_native(&func).map(|v| v != 0).map_err(CallError::keep),
+      Ok(func) => self.limit(name, || input.call_native(&func)).map(|v| v != 0),
       Err(e) => Err(CallError::no_keep(e)),
     }
   }
@@ -101,7 +133,7 @@ impl Plugin {
     // Try to get function with int. If this fails, error.
     // If the function doesn't exist, we error.
     match self.inst.exports.get_native_function::<I::WasmArgs, i32>(name) {
-      Ok(func) => input.call_native(&func).map_err(CallError::keep),
+      Ok(func) => self.limit(name, || input.call_native(&func)),
       Err(e) => Err(CallError::no_keep(e)),
     }
   }
@@ -109,11 +141,71 @@ impl Plugin {
     // Try to get function with int. If this fails, error.
     // If the function doesn't exist, we error.
     match self.inst.exports.get_native_function::<I::WasmArgs, ()>(name) {
-      Ok(func) => input.call_native(&func).map_err(CallError::keep),
+      Ok(func) => self.limit(name, || input.call_native(&func)),
       Err(e) => Err(CallError::no_keep(e)),
     }
   }
 
+  /// Returns `true` if the plugin exports a function with the given name.
+  fn exports(&self, name: &str) -> bool { self.inst.exports.get_function(name).is_ok() }
+  /// Calls an event handler. All event handlers are optional, so this does
+  /// nothing if the plugin doesn't export `name`.
+  fn call_event<I: Input>(&self, name: &str, input: I) -> Result<(), CallError> {
+    if self.exports(name) {
+      self.call(name, input)
+    } else {
+      Ok(())
+    }
+  }
+  /// Calls an event handler that can cancel the event. If the plugin doesn't
+  /// export `name`, the event is allowed.
+  fn call_cancel<I: Input>(&self, name: &str, input: I) -> Result<bool, CallError> {
+    if self.exports(name) {
+      self.call_bool(name, input)
+    } else {
+      Ok(true)
+    }
+  }
+
+  /// Runs a single call into the plugin, enforcing our [`Limits`]. The
+  /// instruction counter is reset before every call. If the plugin runs out of
+  /// instructions or runs past the timeout, the call is stopped, and this
+  /// returns an error that will disable the plugin. Any other trap will be
+  /// logged, and the plugin will be kept.
+  fn limit<R>(
+    &self,
+    func: &str,
+    call: impl FnOnce() -> Result<R, wasmer::RuntimeError>,
+  ) -> Result<R, CallError> {
+    if self.limits.metered() {
+      metering::set_remaining_points(&self.inst, self.limits.max_instructions.unwrap_or(u64::MAX));
+    }
+    let start = Instant::now();
+    if let (Some(watchdog), Some(limit)) = (&self.watchdog, self.limits.timeout) {
+      watchdog.start(limit);
+    }
+    let res = call();
+    let timed_out = self.watchdog.as_ref().map_or(false, |w| w.finish());
+    let took = start.elapsed();
+    if let (true, Some(limit)) = (timed_out, self.limits.timeout) {
+      return Err(CallError::no_keep(LimitError::Timeout {
+        plugin: self.name.clone(),
+        func: func.into(),
+        took,
+        limit,
+      }));
+    }
+    if self.limits.max_instructions.is_some()
+      && matches!(metering::get_remaining_points(&self.inst), MeteringPoints::Exhausted)
+    {
+      return Err(CallError::no_keep(LimitError::OutOfInstructions {
+        plugin: self.name.clone(),
+        func:   func.into(),
+      }));
+    }
+    res.map_err(CallError::keep)
+  }
+
   fn malloc_str(&self, text: &str) -> Result<WasmPtr<u8>, CallError> {
     let ptr = self.call_int("wasm_malloc", (text.len() as i32 + 1, 1))? as u32;
     let mem = self.inst.exports.get_memory("memory").unwrap();
@@ -169,18 +261,29 @@ impl Plugin {
 }
 
 impl PluginImpl for Plugin {
-  fn call(&self, _player: Arc<Player>, ev: ServerEvent) -> Result<(), CallError> {
-    warn!("todo: server event {ev:?}");
-    /*
+  fn call(&self, player: Arc<Player>, ev: ServerEvent) -> Result<(), CallError> {
+    let id = ffi::uuid_to_ffi(player.id());
     match ev {
-      _ => warn!("todo: ev {ev:?}"),
+      ServerEvent::Chat { text } => {
+        if self.exports("on_chat") {
+          let ptr = self.malloc_str(&text)?;
+          let res = self.call("on_chat", (id, ptr, text.len() as i32));
+          self.free_str(ptr, &text)?;
+          res?;
+        }
+      }
+      ServerEvent::PlayerJoin {} => self.call_event("on_player_join", (id,))?,
+      ServerEvent::PlayerLeave {} => self.call_event("on_player_leave", (id,))?,
+      ServerEvent::PlayerDeath {} => self.call_event("on_player_death", (id,))?,
+      // Commands added by wasm plugins call `on_command` directly, so this is never
+      // sent here.
+      ServerEvent::Command { .. } => {}
     }
-    */
     Ok(())
   }
   fn call_global(&self, ev: GlobalServerEvent) -> Result<(), CallError> {
     match ev {
-      GlobalServerEvent::Tick => self.call("on_tick", ())?,
+      GlobalServerEvent::Tick => self.call_event("on_tick", ())?,
       GlobalServerEvent::GenerateChunk { generator, chunk, pos } => {
         self.generate_chunk(&generator, chunk, pos)?
       }
@@ -189,25 +292,38 @@ impl PluginImpl for Plugin {
   }
 
   fn req(&self, player: Arc<Player>, request: ServerRequest) -> Result<PluginReply, CallError> {
+    let id = ffi::uuid_to_ffi(player.id());
     Ok(PluginReply::Cancel {
       allow: match request {
-        ServerRequest::BlockPlace { pos, .. } => self.call_bool(
-          "on_block_place",
-          (
-            CUUID {
-              bytes: [
-                u32::from_ne_bytes(player.id().as_le_bytes()[0..4].try_into().unwrap()),
-                u32::from_ne_bytes(player.id().as_le_bytes()[4..8].try_into().unwrap()),
-                u32::from_ne_bytes(player.id().as_le_bytes()[8..12].try_into().unwrap()),
-                u32::from_ne_bytes(player.id().as_le_bytes()[12..16].try_into().unwrap()),
-              ],
-            },
-            pos.x(),
-            pos.y(),
-            pos.z(),
-          ),
-        )?,
-        _ => true,
+        ServerRequest::BlockPlace { pos, .. } => {
+          self.call_cancel("on_block_place", (id, pos.x(), pos.y(), pos.z()))?
+        }
+        ServerRequest::BlockBreak { pos, .. } => {
+          self.call_cancel("on_block_break", (id, pos.x(), pos.y(), pos.z()))?
+        }
+        ServerRequest::ClickWindow { slot, .. } => {
+          self.call_cancel("on_click_window", (id, slot))?
+        }
+        ServerRequest::Interact { hand, block } => {
+          let pos = block.unwrap_or_default();
+          self.call_cancel(
+            "on_interact",
+            (id, (hand == Hand::Main) as i32, block.is_some() as i32, pos.x(), pos.y(), pos.z()),
+          )?
+        }
+        ServerRequest::Damage { amount, blockable } => {
+          self.call_cancel("on_damage", (id, amount, blockable as i32))?
+        }
+        ServerRequest::Death { .. } => self.call_cancel("on_death", (id,))?,
+      },
+    })
+  }
+  fn req_global(&self, request: GlobalServerRequest) -> Result<PluginReply, CallError> {
+    Ok(PluginReply::Cancel {
+      allow: match request {
+        GlobalServerRequest::EntityDamage { eid, amount, blockable } => {
+          self.call_cancel("on_entity_damage", (eid, amount, blockable as i32))?
+        }
       },
     })
   }
@@ -1,8 +1,8 @@
 //! Implements `MessageWrite` and `MessageRead` for `Region`, `Region::save`,
 //! and `Region::load`.
 
-use super::Region;
-use crate::world::{CountedChunk, MultiChunk};
+use super::{Region, RegionPos};
+use crate::world::{CountedChunk, MultiChunk, World};
 use bb_common::{
   chunk::{paletted, Section},
   flate2::{read::GzDecoder, write::GzEncoder, Compression},
@@ -18,6 +18,14 @@ use std::{
   path::PathBuf,
 };
 
+/// The version of the chunk format. This is stored in every chunk, so that
+/// chunks saved by older versions can still be loaded.
+///
+/// - Version 0 did not store any light data.
+/// - Version 1 stores sky light and block light.
+/// - Version 2 stores biomes.
+const FORMAT_VERSION: u32 = 2;
+
 thread_local! {
   static CACHE: (RefCell<Vec<u8>>, RefCell<Vec<u8>>) = (RefCell::new(vec![]), RefCell::new(vec![]));
 }
@@ -153,15 +161,41 @@ impl Region {
   }
 
   fn fname(&self) -> PathBuf {
-    PathBuf::new().join("world").join("chunks").join(&format!("{}.{}.bbr", self.pos.x, self.pos.z))
+    chunks_dir(&self.world).join(&format!("{}.{}.bbr", self.pos.x, self.pos.z))
   }
 }
 
+fn chunks_dir(world: &World) -> PathBuf { world.storage_path().join("chunks") }
+
+/// Returns the positions of all the regions in the given world that have been
+/// saved to disk.
+pub(super) fn saved_regions(world: &World) -> Vec<RegionPos> {
+  let dir = match fs::read_dir(chunks_dir(world)) {
+    Ok(dir) => dir,
+    Err(_) => return vec![],
+  };
+  dir
+    .filter_map(|f| {
+      let name = f.ok()?.file_name();
+      let mut sections = name.to_str()?.split('.');
+      let x = sections.next()?.parse().ok()?;
+      let z = sections.next()?.parse().ok()?;
+      if sections.next()? != "bbr" || sections.next().is_some() {
+        return None;
+      }
+      Some(RegionPos { x, z })
+    })
+    .collect()
+}
+
 impl Region {
   fn write(&self, w: &mut MessageWriter<&mut Vec<u8>>) -> Result<(), WriteError> {
     w.write_struct(1024, |w| {
       for chunk in &self.chunks {
-        let c = chunk.as_ref().map(WriteableChunk);
+        // Default chunks are regenerated when they are loaded, so there is no need
+        // to save them.
+        let c =
+          chunk.as_ref().filter(|c| !self.world.is_default_chunk(&c.lock())).map(WriteableChunk);
         w.write_enum(if c.is_some() { 1 } else { 0 }, if c.is_some() { 1 } else { 0 }, |w| {
           if let Some(c) = c {
             c.write(w)
@@ -260,7 +294,9 @@ impl ReadableChunk<'_> {
         r.read_struct_with(|mut s| {
           let pos: Pos = s.read(0)?;
           let pos = RelPos::new(pos.x.try_into().unwrap(), pos.y, pos.z.try_into().unwrap());
-          let kind = lock.get_kind(pos).unwrap();
+          // `pos` has already been transformed, so we can't use `get_kind` here.
+          let id = lock.inner().get_block(pos).unwrap();
+          let kind = lock.type_converter().kind_from_id(id, BlockVersion::latest());
           let behaviors = lock.wm().block_behaviors();
           match behaviors.call(kind, |b| s.read_with(1, |r| Ok(b.load_te(r)))) {
             // No behavior for this block
@@ -280,6 +316,20 @@ impl ReadableChunk<'_> {
         })
       })?;
 
+      // Version 0 didn't store light, so we need to recompute it.
+      let format: u32 = s.read(3)?;
+      if format >= 1 {
+        let sky = s.read(4)?;
+        let block = s.must_read(5)?;
+        lock.set_light(sky, block);
+      } else {
+        lock.update_all_light();
+      }
+      // Versions before 2 didn't store biomes, so those chunks are left empty.
+      if format >= 2 {
+        *lock.biomes_mut() = s.must_read(6)?;
+      }
+
       Ok(())
     })
   }
@@ -288,8 +338,7 @@ impl ReadableChunk<'_> {
 struct WriteableChunk<'a>(&'a CountedChunk);
 impl WriteableChunk<'_> {
   fn write(&self, w: &mut MessageWriter<&mut Vec<u8>>) -> Result<(), WriteError> {
-    // TODO: Write light
-    w.write_struct(3, |w| {
+    w.write_struct(7, |w| {
       let lock = self.0.chunk.lock();
       w.write_list(lock.inner().sections())?;
       w.write_u32(BlockVersion::latest().to_index())?;
@@ -300,6 +349,10 @@ impl WriteableChunk<'_> {
           Ok(())
         })
       })?;
+      w.write_u32(FORMAT_VERSION)?;
+      w.write(lock.sky_light())?;
+      w.write(lock.block_light())?;
+      w.write(lock.biomes())?;
       Ok(())
     })
   }
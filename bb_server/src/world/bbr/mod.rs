@@ -6,7 +6,7 @@ use super::{CountedChunk, World};
 use bb_common::math::ChunkPos;
 use parking_lot::{Mutex, MutexGuard, RwLock, RwLockWriteGuard};
 use std::{
-  collections::HashMap,
+  collections::{HashMap, HashSet},
   sync::{Arc, Weak},
 };
 
@@ -89,6 +89,17 @@ impl RegionMap {
     }
   }
 
+  /// Calls `f` for every region in this world. This includes regions that are
+  /// only stored on disk, which will be loaded. Any regions loaded this way
+  /// will be unloaded again the next time chunks are unloaded.
+  pub fn for_each_region(&self, mut f: impl FnMut(&Region)) {
+    let mut positions: HashSet<RegionPos> = self.regions.read().keys().copied().collect();
+    positions.extend(fs::saved_regions(&self.world.upgrade().unwrap()));
+    for pos in positions {
+      self.region(pos.min_chunk(), |region| f(&region));
+    }
+  }
+
   pub fn save(&self) {
     let lock = self.regions.read();
     for region in lock.values() {
@@ -108,6 +119,17 @@ impl Region {
     region
   }
 
+  pub fn pos(&self) -> RegionPos { self.pos }
+
+  /// Iterates through all the chunks that are stored in this region, along
+  /// with their absolute positions.
+  pub fn chunks(&self) -> impl Iterator<Item = (ChunkPos, &CountedChunk)> {
+    let min = self.pos.min_chunk();
+    self.chunks.iter().enumerate().filter_map(move |(i, c)| {
+      c.as_ref().map(|c| (min + ChunkPos::new(i as i32 % 32, i as i32 / 32), c))
+    })
+  }
+
   pub fn get(&self, pos: RegionRelPos) -> &Option<CountedChunk> {
     &self.chunks[pos.x as usize + pos.z as usize * 32]
   }
@@ -153,6 +175,9 @@ impl RegionPos {
       z: if chunk.z() < 0 { (chunk.z() + 1) / 32 - 1 } else { chunk.z() / 32 },
     }
   }
+
+  /// Returns the chunk with the lowest X and Z within this region.
+  pub fn min_chunk(&self) -> ChunkPos { ChunkPos::new(self.x * 32, self.z * 32) }
 }
 
 impl RegionRelPos {
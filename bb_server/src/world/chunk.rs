@@ -1,7 +1,10 @@
 use super::WorldManager;
 use crate::{block, block::TileEntity};
 use bb_common::{
-  chunk::{paletted::Section as PalettedSection, BlockLight, Chunk, LightChunk, SkyLight},
+  chunk::{
+    biome::BiomeChunk, paletted::Section as PalettedSection, BlockLight, Chunk, LightChunk,
+    SkyLight,
+  },
   math::{PosError, RelPos},
   version::BlockVersion,
 };
@@ -34,8 +37,13 @@ impl fmt::Debug for CountedChunk {
 /// using far less memory, and was nearly as fast. The name starts with Multi
 /// because it used to store all of the other versioning data. It would be a
 /// pain to change it, and I don't really want to bother.
+///
+/// The chunk data is copy-on-write. [`share`](Self::share) will create a new
+/// chunk which points to the same data, and the first modification to either
+/// chunk will give that chunk its own copy. This is how the default chunk in
+/// void and superflat worlds works.
 pub struct MultiChunk {
-  inner: ChunkData,
+  inner: Arc<ChunkData>,
   wm:    Arc<WorldManager>,
 }
 
@@ -44,6 +52,7 @@ struct ChunkData {
   tes:          HashMap<RelPos, Arc<dyn TileEntity>>,
   sky:          Option<LightChunk<SkyLight>>,
   block:        LightChunk<BlockLight>,
+  biomes:       BiomeChunk,
   /// Set to false when the world is generating, which makes things much faster.
   update_light: bool,
 
@@ -70,6 +79,7 @@ impl ChunkData {
       tes: HashMap::new(),
       sky: if sky { Some(LightChunk::new()) } else { None },
       block: LightChunk::new(),
+      biomes: BiomeChunk::new(),
       update_light: true,
       height,
       min_y,
@@ -94,6 +104,21 @@ impl ChunkData {
     Ok(())
   }
 
+  /// Copies all the data in this chunk. See [`Chunk::duplicate`]. Tile
+  /// entities are reference counted, so they are not copied.
+  fn duplicate(&self) -> Self {
+    ChunkData {
+      inner:        self.inner.duplicate(),
+      tes:          self.tes.clone(),
+      sky:          self.sky.clone(),
+      block:        self.block.clone(),
+      biomes:       self.biomes.clone(),
+      update_light: self.update_light,
+      height:       self.height,
+      min_y:        self.min_y,
+    }
+  }
+
   fn enable_lighting(&mut self, enabled: bool) {
     if !self.update_light && enabled {
       self.update_all_light();
@@ -123,7 +148,28 @@ impl MultiChunk {
   /// The second argument is for sky light data. Places like the nether do not
   /// contain sky light information, so the sky light data is not present.
   pub fn new(wm: Arc<WorldManager>, sky: bool, height: u32, min_y: i32) -> MultiChunk {
-    MultiChunk { inner: ChunkData::new(sky, height, min_y), wm }
+    MultiChunk { inner: Arc::new(ChunkData::new(sky, height, min_y)), wm }
+  }
+
+  /// Creates a new chunk that shares all of its data with this chunk. This is
+  /// very cheap. The first time either chunk is modified, that chunk will copy
+  /// the data, so changes will never be visible in the other chunk.
+  pub fn share(&self) -> MultiChunk {
+    MultiChunk { inner: self.inner.clone(), wm: self.wm.clone() }
+  }
+
+  /// Returns true if this chunk and `other` share the same data. This will be
+  /// true for chunks created with [`share`](Self::share), until one of them is
+  /// modified.
+  pub fn shares_data(&self, other: &MultiChunk) -> bool { Arc::ptr_eq(&self.inner, &other.inner) }
+
+  /// Returns a mutable reference to the chunk data, copying it first if it is
+  /// shared with another chunk.
+  fn data_mut(inner: &mut Arc<ChunkData>) -> &mut ChunkData {
+    if Arc::get_mut(inner).is_none() {
+      *inner = Arc::new(inner.duplicate());
+    }
+    Arc::get_mut(inner).unwrap()
   }
 
   /// Returns a reference to the global world manager.
@@ -140,7 +186,9 @@ impl MultiChunk {
   /// distance will see any of these changes!
   pub fn set_type(&mut self, p: RelPos, ty: block::Type) -> Result<(), PosError> {
     let p = self.transform_pos(p)?;
-    self.inner.set_type_id(p, ty.id(), ty.kind(), &self.wm.block_behaviors()).unwrap();
+    Self::data_mut(&mut self.inner)
+      .set_type_id(p, ty.id(), ty.kind(), &self.wm.block_behaviors())
+      .unwrap();
     Ok(())
   }
 
@@ -151,7 +199,9 @@ impl MultiChunk {
   ) -> Result<(), PosError> {
     let p = self.transform_pos(p)?;
     let ty = f(self.wm.block_converter());
-    self.inner.set_type_id(p, ty.id(), ty.kind(), &self.wm.block_behaviors()).unwrap();
+    Self::data_mut(&mut self.inner)
+      .set_type_id(p, ty.id(), ty.kind(), &self.wm.block_behaviors())
+      .unwrap();
     Ok(())
   }
 
@@ -168,7 +218,9 @@ impl MultiChunk {
   pub fn set_kind(&mut self, p: RelPos, kind: block::Kind) -> Result<(), PosError> {
     let p = self.transform_pos(p)?;
     let ty = self.wm.block_converter().get(kind).default_type();
-    self.inner.set_type_id(p, ty.id(), ty.kind(), &self.wm.block_behaviors()).unwrap();
+    Self::data_mut(&mut self.inner)
+      .set_type_id(p, ty.id(), ty.kind(), &self.wm.block_behaviors())
+      .unwrap();
     Ok(())
   }
 
@@ -186,10 +238,11 @@ impl MultiChunk {
   pub fn fill(&mut self, min: RelPos, max: RelPos, ty: block::Type) -> Result<(), PosError> {
     let min = self.transform_pos(min)?;
     let max = self.transform_pos(max)?;
-    self.inner.inner.fill(min, max, ty.id()).unwrap();
+    let data = Self::data_mut(&mut self.inner);
+    data.inner.fill(min, max, ty.id()).unwrap();
     // TODO: Update light correctly.
-    self.inner.update_light(min);
-    self.inner.update_light(max);
+    data.update_light(min);
+    data.update_light(max);
     Ok(())
   }
 
@@ -203,14 +256,12 @@ impl MultiChunk {
   pub fn fill_kind(&mut self, min: RelPos, max: RelPos, kind: block::Kind) -> Result<(), PosError> {
     let min = self.transform_pos(min)?;
     let max = self.transform_pos(max)?;
-    self
-      .inner
-      .inner
-      .fill(min, max, self.wm.block_converter().get(kind).default_type().id())
-      .unwrap();
+    let id = self.wm.block_converter().get(kind).default_type().id();
+    let data = Self::data_mut(&mut self.inner);
+    data.inner.fill(min, max, id).unwrap();
     // TODO: Update light correctly.
-    self.inner.update_light(min);
-    self.inner.update_light(max);
+    data.update_light(min);
+    data.update_light(max);
     Ok(())
   }
 
@@ -243,7 +294,7 @@ impl MultiChunk {
 
   pub fn tes(&self) -> &HashMap<RelPos, Arc<dyn TileEntity>> { &self.inner.tes }
   pub(crate) fn tes_mut(&mut self) -> &mut HashMap<RelPos, Arc<dyn TileEntity>> {
-    &mut self.inner.tes
+    &mut Self::data_mut(&mut self.inner).tes
   }
 
   pub fn get_te(&self, p: RelPos) -> Result<Option<Arc<dyn TileEntity>>, PosError> {
@@ -255,10 +306,10 @@ impl MultiChunk {
   /// because a `Chunk` cannot accept positions with a negative Y value, but
   /// worlds can have negative block positions.
   pub fn transform_pos(&self, mut p: RelPos) -> Result<RelPos, PosError> {
-    if p.y() < self.inner.min_y || p.y() >= self.inner.height as i32 - self.inner.min_y {
+    if p.y() < self.inner.min_y || p.y() >= self.inner.height as i32 + self.inner.min_y {
       Err(p.err("is outside the world".into()))
     } else {
-      p = p.add_y(self.inner.min_y);
+      p = p.add_y(-self.inner.min_y);
       Ok(p)
     }
   }
@@ -275,7 +326,9 @@ impl MultiChunk {
   /// value you specify.
   pub fn inner(&self) -> &Chunk<PalettedSection> { &self.inner.inner }
   /// Same as [`inner`](Self::inner), but returns a mutable reference.
-  pub fn inner_mut(&mut self) -> &mut Chunk<PalettedSection> { &mut self.inner.inner }
+  pub fn inner_mut(&mut self) -> &mut Chunk<PalettedSection> {
+    &mut Self::data_mut(&mut self.inner).inner
+  }
 
   /// Returns a reference to the global type converter. Used to convert a block
   /// id to/from any version.
@@ -288,8 +341,39 @@ impl MultiChunk {
   /// data to clients.
   pub fn block_light(&self) -> &LightChunk<BlockLight> { &self.inner.block }
 
+  /// Returns the biomes in this chunk. The Y axis of the biome cells starts at
+  /// the bottom of the world, so the same
+  /// [`transform_pos`](Self::transform_pos) rules apply here.
+  pub fn biomes(&self) -> &BiomeChunk { &self.inner.biomes }
+  /// Same as [`biomes`](Self::biomes), but returns a mutable reference.
+  pub fn biomes_mut(&mut self) -> &mut BiomeChunk { &mut Self::data_mut(&mut self.inner).biomes }
+
+  /// Replaces the light data in this chunk. This is used when loading chunks
+  /// from disk, so that we don't need to recompute the lighting. If this chunk
+  /// has sky light, and `sky` is `None`, the sky light will be recomputed.
+  pub(crate) fn set_light(
+    &mut self,
+    sky: Option<LightChunk<SkyLight>>,
+    block: LightChunk<BlockLight>,
+  ) {
+    let data = Self::data_mut(&mut self.inner);
+    data.block = block;
+    if let Some(old) = &mut data.sky {
+      match sky {
+        Some(sky) => *old = sky,
+        None => old.update_all(&data.inner),
+      }
+    }
+  }
+
+  /// Recomputes all the lighting data in this chunk. This is very slow, and
+  /// should only be used when the light data is missing.
+  pub(crate) fn update_all_light(&mut self) { Self::data_mut(&mut self.inner).update_all_light(); }
+
   /// Will enable/disable lighting. Chunks have lighting enabled by default. If
   /// enabled, and if it was previously disabled, all the lighting information
   /// will be recalculated (which is very slow).
-  pub fn enable_lighting(&mut self, enabled: bool) { self.inner.enable_lighting(enabled) }
+  pub fn enable_lighting(&mut self, enabled: bool) {
+    Self::data_mut(&mut self.inner).enable_lighting(enabled)
+  }
 }
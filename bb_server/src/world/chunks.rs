@@ -108,7 +108,7 @@ impl World {
   }
   pub fn queue_chunk(&self, pos: ChunkPos, player: &Arc<Player>) {
     if self.regions.region(pos, |region| region.has_chunk(pos)) {
-      player.send_chunk(pos, || self.serialize_chunk(pos).into());
+      player.send_chunk(pos, || self.encode_chunk(pos));
       return;
     }
     self.chunks_to_load.lock().add(pos, player);
@@ -131,10 +131,10 @@ impl World {
           let mut queue_lock = s.world.chunks_to_load.lock();
           if let Some(chunk) = queue_lock.remove_pos(pos) {
             if !chunk.players.is_empty() {
-              let out = s.world.serialize_chunk(pos);
+              let out = s.world.encode_chunk(pos);
               for weak in chunk.players.values() {
                 if let Some(p) = weak.upgrade() {
-                  p.send_chunk(pos, || out.clone().into());
+                  p.send_chunk(pos, || out.clone());
                 }
               }
             }
@@ -61,6 +61,10 @@ impl EntitiesMapRef<'_> {
   pub fn get(&self, eid: i32) -> Option<EntityRef> {
     self.inner.get(&eid)?.as_entity_ref(self.world)
   }
+  /// Returns a clone of the entity for the given id. Unlike
+  /// [`get`](Self::get), this doesn't borrow the map, so it can still be used
+  /// after the lock is released. Returns `None` if the id is invalid.
+  pub fn get_owned(&self, eid: i32) -> Option<Entity> { self.inner.get(&eid).cloned() }
   /// Returns the entity for the given id. Returns `None` if the id is invalid,
   /// or if the entity is a player.
   pub fn get_ent(&self, eid: i32) -> Option<&Arc<EntityData>> { self.inner.get(&eid)?.as_entity() }
@@ -155,6 +159,17 @@ impl World {
     }
   }
 
+  /// Sends entity look packets to everyone in view of `pos`. `yaw` and `pitch`
+  /// are in degrees.
+  pub(crate) fn send_entity_look(&self, pos: ChunkPos, eid: i32, yaw: f32, pitch: f32) {
+    let yaw = (yaw / 360.0 * 256.0).round() as i8;
+    let pitch = (pitch / 360.0 * 256.0).round() as i8;
+    for p in self.players().iter().in_view(pos) {
+      p.send(cb::packet::EntityLook { eid, yaw, pitch, on_ground: true });
+      p.send(cb::packet::EntityHeadLook { eid, yaw });
+    }
+  }
+
   /// Sends entity position packets to everyone in view of `old`.
   pub(crate) fn send_entity_pos(&self, eid: i32, old: FPos, new: FPos, on_ground: bool) {
     for p in self.players().iter().in_view(old.chunk()) {
@@ -216,9 +231,13 @@ impl World {
       pitch: pitch as i8,
       meta: player.metadata(),
     };
+    let equipment = player.lock_inventory().equipment(player.eid());
     for p in self.players().iter().in_view(chunk).not(player.id()) {
       p.send(remove.clone());
       p.send(add.clone());
+      for eq in &equipment {
+        p.send(eq.clone());
+      }
     }
   }
 
@@ -284,6 +303,11 @@ impl World {
           data
         },
       });
+      if let EntityRef::Entity(e) = ent {
+        for p in e.effect_packets() {
+          player.send(p);
+        }
+      }
     }
   }
 }
@@ -209,6 +209,7 @@ pub struct WorldGen {
   #[allow(unused)]
   underground: Underground,
   debug:       bool,
+  superflat:   bool,
 }
 
 impl WorldGen {
@@ -226,6 +227,7 @@ impl WorldGen {
       max_height,
       underground: Underground::new(seed),
       debug: false,
+      superflat: false,
     }
   }
   pub fn from_config(config: &ConfigSection) -> Self {
@@ -235,6 +237,10 @@ impl WorldGen {
       gen
     } else if config.get("void") {
       WorldGen::new()
+    } else if config.get("superflat") {
+      let mut gen = WorldGen::new();
+      gen.superflat = true;
+      gen
     } else {
       let mut gen = WorldGen::new();
       /*
@@ -253,11 +259,23 @@ impl WorldGen {
     self.biomes.push(Box::new(B::new(id)));
   }
 
+  /// Returns true if every chunk generated is the same, no matter the
+  /// position. This is the case for void and superflat worlds, which means the
+  /// world can use a single default chunk for everything it generates.
+  pub fn is_uniform(&self) -> bool { !self.debug && self.biomes.is_empty() }
+
   pub fn generate(&self, pos: ChunkPos, c: &mut MultiChunk) {
     if self.debug {
       self.debug_world(pos, c);
       return;
     }
+    if self.superflat {
+      c.fill_kind(RelPos::new(0, 0, 0), RelPos::new(15, 0, 15), block::Kind::Bedrock).unwrap();
+      c.fill_kind(RelPos::new(0, 1, 0), RelPos::new(15, 2, 15), block::Kind::Dirt).unwrap();
+      c.fill_kind(RelPos::new(0, 3, 0), RelPos::new(15, 3, 15), block::Kind::GrassBlock).unwrap();
+      c.update_all_light();
+      return;
+    }
     // Fast path for void worlds
     if self.biomes.is_empty() {
       return;
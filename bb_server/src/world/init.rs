@@ -1,4 +1,4 @@
-use super::{World, WorldManager};
+use super::{parse_export_version, World, WorldManager};
 use crate::{
   command::{Arg, Command, Parser, StringType},
   entity,
@@ -10,7 +10,10 @@ use bb_common::{
   util::{Buffer, Chat, GameMode, JoinInfo},
   version::ProtocolVersion,
 };
-use std::sync::Arc;
+use std::{path::Path, sync::Arc, thread};
+
+/// The folder that `/export` writes vanilla worlds to.
+const EXPORT_PATH: &str = "export";
 
 impl World {
   pub fn init(&self) {
@@ -33,29 +36,27 @@ impl World {
       .add_arg("center", Parser::BlockPos)
       .add_arg("radius", Parser::Float { min: Some(0.0), max: None })
       .add_arg("block", Parser::BlockState);
-    self.commands().add(c, |world, _, args| {
+    self.commands().add(c, |world, player, args| {
       // args[0] is `fill`
+      let w = player.map(|p| p.world()).unwrap_or_else(|| world.default_world());
       match args[1].lit() {
         "rect" => {
           let min = args[2].pos();
           let max = args[3].pos();
           let block = args[4].block();
           let (min, max) = min.min_max(max);
-          let w = world.default_world();
           w.fill_rect_kind(min, max, block).unwrap();
         }
         "circle" => {
           let pos = args[2].pos();
           let radius = args[3].float();
           let block = args[4].block();
-          let w = world.default_world();
           w.fill_circle_kind(pos, radius, block).unwrap();
         }
         "sphere" => {
           let pos = args[2].pos();
           let radius = args[3].float();
           let block = args[4].block();
-          let w = world.default_world();
           w.fill_sphere_kind(pos, radius, block).unwrap();
         }
         _ => unreachable!(),
@@ -157,6 +158,143 @@ impl World {
       }
     });
 
+    let mut c = Command::new("export");
+    c.add_arg("version", Parser::String(StringType::Word));
+    self.commands().add(c, |wm, player, args| {
+      // args[0] is `export`
+      let reply = |player: Option<&Arc<Player>>, msg: String| match player {
+        Some(p) => p.send_message(Chat::new(msg)),
+        None => info!("{msg}"),
+      };
+      let ver = match parse_export_version(args[1].str()) {
+        Some(v) => v,
+        None => {
+          reply(player, format!("invalid version {}", args[1].str()));
+          return;
+        }
+      };
+      reply(player, format!("exporting world to `{EXPORT_PATH}`..."));
+      // Exporting can take a while, so we don't want to block the player's
+      // connection.
+      let world = player.map(|p| p.world()).unwrap_or_else(|| wm.default_world());
+      let player = player.cloned();
+      thread::spawn(move || {
+        let msg = match world.export_to_disk(Path::new(EXPORT_PATH), ver) {
+          Ok(n) => format!("exported {n} chunks to `{EXPORT_PATH}`"),
+          Err(e) => format!("could not export world: {e}"),
+        };
+        reply(player.as_ref(), msg);
+      });
+    });
+
+    let mut c = Command::new("world");
+    c.add_lit("list");
+    c.add_lit("tp").add_arg("name", Parser::String(StringType::Word));
+    c.add_lit("create").add_arg("name", Parser::String(StringType::Word));
+    self.commands().add(c, |wm, player, args| {
+      // args[0] is `world`
+      let reply = |msg: String| match player {
+        Some(p) => p.send_message(Chat::new(msg)),
+        None => info!("{msg}"),
+      };
+      match args[1].lit() {
+        "list" => {
+          let names: Vec<String> = wm
+            .worlds()
+            .iter()
+            .map(|w| format!("{} ({} players)", w.name(), w.players().len()))
+            .collect();
+          reply(format!("worlds: {}", names.join(", ")));
+        }
+        "tp" => {
+          let name = args[2].str();
+          let p = match player {
+            Some(p) => p,
+            None => {
+              reply("only players can switch worlds".into());
+              return;
+            }
+          };
+          match wm.world(name) {
+            Some(w) => {
+              let spawn = w.spawn_point();
+              p.switch_world(&w, spawn);
+            }
+            None => reply(format!("no world named `{name}`")),
+          }
+        }
+        "create" => {
+          let name = args[2].str();
+          match wm.add_world(name) {
+            Ok(_) => reply(format!("created world `{name}`")),
+            Err(super::AddWorldError::Exists) => {
+              reply(format!("a world named `{name}` already exists"))
+            }
+            Err(super::AddWorldError::InvalidName) => reply(format!(
+              "invalid world name `{name}`, only `a-z`, `0-9`, `_` and `-` are allowed"
+            )),
+          }
+        }
+        _ => unreachable!(),
+      }
+    });
+
+    let mut c = Command::new("plugin");
+    c.add_lit("list");
+    c.add_lit("reload").add_arg("name", Parser::String(StringType::Word));
+    c.add_lit("enable").add_arg("name", Parser::String(StringType::Word));
+    c.add_lit("disable").add_arg("name", Parser::String(StringType::Word));
+    self.commands().add(c, |wm, player, args| {
+      // args[0] is `plugin`
+      let wm = wm.clone();
+      let player = player.cloned();
+      // Loading plugins changes the command tree, which is locked while this
+      // command runs, so we do all the work on another thread.
+      thread::spawn(move || {
+        let reply = |player: Option<&Arc<Player>>, msg: String| match player {
+          Some(p) => p.send_message(Chat::new(msg)),
+          None => info!("{msg}"),
+        };
+        let plugins = wm.plugins();
+        let msg = match args[1].lit() {
+          "list" => {
+            let mut msg = format!("plugins: {}", plugins.names().join(", "));
+            let disabled = plugins.disabled_names();
+            if !disabled.is_empty() {
+              msg += &format!(" (disabled: {})", disabled.join(", "));
+            }
+            msg
+          }
+          "reload" => {
+            let name = args[2].str();
+            if plugins.reload(&wm, name) {
+              format!("reloaded plugin `{name}`")
+            } else {
+              format!("could not load plugin `{name}`, see the server logs for details")
+            }
+          }
+          "enable" => {
+            let name = args[2].str();
+            if plugins.enable(&wm, name) {
+              format!("enabled plugin `{name}`")
+            } else {
+              format!("could not load plugin `{name}`, see the server logs for details")
+            }
+          }
+          "disable" => {
+            let name = args[2].str();
+            if plugins.disable(&wm, name) {
+              format!("disabled plugin `{name}`")
+            } else {
+              format!("plugin `{name}` is not loaded, but it will stay disabled")
+            }
+          }
+          _ => unreachable!(),
+        };
+        reply(player.as_ref(), msg);
+      });
+    });
+
     info!("generating terrain...");
     /*
     let chunks = Mutex::new(vec![]);
@@ -230,9 +368,10 @@ impl World {
       difficulty:            1, // Normal
       view_distance:         player.view_distance() as u16,
       reduced_debug_info:    false,
-      enable_respawn_screen: true,
+      enable_respawn_screen: !self.gamerule("immediate-respawn"),
       world_height:          self.height,
       world_min_y:           self.min_y,
+      world_name:            self.dimension_name(),
     };
 
     player.send(out);
@@ -244,6 +383,23 @@ impl World {
       player.send(self.commands().serialize());
     }
 
+    let mut data = vec![];
+    let mut buf = Buffer::new(&mut data);
+    buf.write_str("Bamboo");
+    player.send(cb::packet::PluginMessage { channel: "minecraft:brand".into(), data });
+
+    self.player_spawn(player);
+
+    for (_, team) in self.wm.teams().iter() {
+      team.lock().send_join(player);
+    }
+  }
+
+  /// Sends everything about this world to the player. This includes chunks,
+  /// their position, inventory, and all the players and entities around them.
+  /// This is used when a player joins, and after a player is moved into this
+  /// world with a respawn packet.
+  pub(crate) fn player_spawn(&self, player: &Player) {
     player.send(cb::packet::EntityStatus {
       eid:    player.eid(),
       // Set op permission to level 4
@@ -251,37 +407,38 @@ impl World {
       status: 28,
     });
 
+    // The player may have been loaded from disk, so they might not be at spawn.
+    let (pos, pitch, yaw) = player.pos_look();
+    let center = pos.block().chunk();
     let d = player.view_distance() as i32;
     for x in -d..=d {
       for z in -d..=d {
-        let pos = ChunkPos::new(x, z);
-        self.inc_view(pos);
-        player.send_chunk(pos, || self.serialize_chunk(pos).into());
+        let pos = center + ChunkPos::new(x, z);
+        player.send_chunk(pos, || self.encode_chunk(pos));
       }
     }
 
-    let mut data = vec![];
-    let mut buf = Buffer::new(&mut data);
-    buf.write_str("Bamboo");
-    player.send(cb::packet::PluginMessage { channel: "minecraft:brand".into(), data });
-
-    let pos = player.pos();
     player.send(cb::packet::SetPosLook {
       pos,
-      yaw: 0.0,
-      pitch: 0.0,
+      yaw,
+      pitch,
       flags: 0,
       teleport_id: 1234,
       should_dismount: true,
     });
+    player.send_abilities();
+    player.send_health();
+    player.lock_inventory().sync_all();
+    // The client always starts on the first slot, but the selected slot may
+    // have been loaded from disk.
+    let slot = player.lock_inventory().selected_index();
+    player.send(cb::packet::ChangeHeldItem { slot });
+    // The client clears all effects when it respawns.
+    for p in player.effect_packets() {
+      player.send(p);
+    }
 
-    let my_info = cb::PlayerListAdd {
-      id:           player.id(),
-      name:         player.username().clone(),
-      game_mode:    GameMode::Creative,
-      ping:         50,
-      display_name: player.tab_name().clone().map(|c| c.to_json()),
-    };
+    let my_info = player.player_list_add();
     let my_info_packet =
       cb::packet::PlayerList { action: cb::PlayerListAction::Add(vec![my_info.clone()]) };
 
@@ -293,13 +450,7 @@ impl World {
       other.send(my_info_packet.clone());
 
       // Add `other` to the list of players that I know about
-      info.push(cb::PlayerListAdd {
-        id:           other.id(),
-        name:         other.username().clone(),
-        game_mode:    GameMode::Creative,
-        ping:         50,
-        display_name: other.tab_name().clone().map(|c| c.to_json()),
-      });
+      info.push(other.player_list_add());
     }
     player.send(cb::packet::PlayerList { action: cb::PlayerListAction::Add(info) });
 
@@ -323,6 +474,12 @@ impl World {
           pitch: pitch as i8,
           meta: player.metadata(),
         });
+        for p in player.lock_inventory().equipment(player.eid()) {
+          other.send(p);
+        }
+        for p in player.effect_packets() {
+          other.send(p);
+        }
 
         // Create a packet that will spawn `other` for me
         let (pos, pitch, yaw) = other.pos_look();
@@ -335,15 +492,17 @@ impl World {
           pitch: pitch as i8,
           meta: other.metadata(),
         });
+        for p in other.lock_inventory().equipment(other.eid()) {
+          player.send(p);
+        }
+        for p in other.effect_packets() {
+          player.send(p);
+        }
       }
       if other.as_entity().is_some() {
         // Create a packet that will spawn `other` for me
         self.send_entity_spawn(player, &other);
       }
     }
-
-    for (_, team) in self.wm.teams().iter() {
-      team.lock().send_join(player);
-    }
   }
 }
@@ -28,7 +28,7 @@ use bb_common::{
   net::cb,
   util::{
     chat::{Chat, Color},
-    GameMode, JoinInfo, ThreadPool, UUID,
+    GameMode, JoinInfo, ServerStatus, StatusPlayer, ThreadPool, UUID,
   },
 };
 use parking_lot::{Mutex, MutexGuard, RwLock, RwLockReadGuard};
@@ -36,6 +36,7 @@ use std::{
   collections::HashMap,
   convert::TryInto,
   fmt,
+  path::{Path, PathBuf},
   sync::{
     atomic::{AtomicBool, AtomicI32, AtomicU32, Ordering},
     Arc,
@@ -51,9 +52,9 @@ use crate::{
   entity,
   entity::Entity,
   item,
-  net::ConnSender,
+  net::{ConnSender, EncodedChunk, Outgoing},
   particle::Particle,
-  player::{Player, Team},
+  player::{Player, PlayerData, Team},
   plugin,
   tags::Tags,
 };
@@ -61,10 +62,12 @@ use crate::{
 pub use chunk::{CountedChunk, MultiChunk};
 pub use entities::{EntitiesIter, EntitiesMap, EntitiesMapRef};
 pub use players::{PlayersIter, PlayersMap};
+pub use region::parse_export_version;
 
 use bbr::{RegionMap, RegionRelPos};
 use chunks::ChunksToLoad;
 use gen::WorldGen;
+use schematic::{PasteOptions, Schematic};
 
 // pub struct ChunkRef<'a> {
 //   pos:    ChunkPos,
@@ -89,7 +92,6 @@ pub struct World {
   gen:              WorldGen,
   players:          RwLock<PlayersMap>,
   entities:         RwLock<EntitiesMap>,
-  eid:              AtomicI32,
   block_converter:  Arc<block::TypeConverter>,
   item_converter:   Arc<item::TypeConverter>,
   entity_converter: Arc<entity::TypeConverter>,
@@ -100,6 +102,14 @@ pub struct World {
   config:           ConfigSection,
   // If set, then the world cannot be modified.
   locked:           AtomicBool,
+  // The name of this world. This is unique across all worlds.
+  name:             String,
+  // The directory this world is saved in.
+  storage:          PathBuf,
+  // If the generator produces the same chunk everywhere, then every generated
+  // chunk shares its data with this chunk, until that chunk is modified. The
+  // encoded packet is cached, so that these chunks are only serialized once.
+  default_chunk:    Option<(MultiChunk, Arc<EncodedChunk>)>,
 
   chunks_to_load: Mutex<ChunksToLoad>,
 
@@ -120,6 +130,9 @@ pub struct WorldManager {
   worlds:           RwLock<Vec<Arc<World>>>,
   // Player id to world index and player
   players:          RwLock<HashMap<UUID, (usize, Arc<Player>)>>,
+  // Entity ids are shared between all worlds, so that players can move between
+  // worlds without needing a new eid.
+  eid:              AtomicI32,
   // Team name to team
   teams:            RwLock<HashMap<String, Arc<Mutex<Team>>>>,
   block_converter:  Arc<block::TypeConverter>,
@@ -144,8 +157,51 @@ struct State {
 
 const TICK_TIME: Duration = Duration::from_millis(50);
 
+/// Returns true if `name` can be used as the name of a new world. World names
+/// are used as directory names, so only `a-z`, `0-9`, `_` and `-` are allowed.
+pub(crate) fn is_valid_world_name(name: &str) -> bool {
+  !name.is_empty() && name.bytes().all(|c| matches!(c, b'a'..=b'z' | b'0'..=b'9' | b'_' | b'-'))
+}
+
+/// An error returned from [`WorldManager::add_world`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddWorldError {
+  /// There is already a world with this name.
+  Exists,
+  /// The name contains something other than `a-z`, `0-9`, `_` and `-`.
+  InvalidName,
+}
+
+impl fmt::Display for AddWorldError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Self::Exists => write!(f, "a world with that name already exists"),
+      Self::InvalidName => {
+        write!(f, "invalid world name, only `a-z`, `0-9`, `_` and `-` are allowed")
+      }
+    }
+  }
+}
+
+impl std::error::Error for AddWorldError {}
+
+/// Serializes an entire chunk. See [`World::serialize_chunk`].
+fn chunk_packet(c: &MultiChunk, pos: ChunkPos, height: u32) -> cb::packet::Chunk {
+  let mut sections: Vec<_> = c.inner().sections().cloned().collect();
+  sections.resize((height as usize + 15) / 16, None);
+  cb::packet::Chunk {
+    pos,
+    full: true,
+    sections,
+    sky_light: c.sky_light().clone(),
+    block_light: c.block_light().clone(),
+    biomes: c.biomes().clone(),
+  }
+}
+
 impl World {
   /// Creates a new world. See also [`WorldManager::add_world`].
+  #[allow(clippy::too_many_arguments)]
   pub(crate) fn new(
     block_converter: Arc<block::TypeConverter>,
     item_converter: Arc<item::TypeConverter>,
@@ -153,26 +209,38 @@ impl World {
     plugins: Arc<plugin::PluginManager>,
     commands: Arc<CommandTree>,
     wm: Arc<WorldManager>,
+    name: String,
+    config: ConfigSection,
   ) -> Arc<Self> {
-    let config = wm.config().section("world");
     let gen = WorldGen::from_config(&config);
-    /*
-    for schematic in config.get::<_, Vec<String>>("schematics") {
-      let path = schematic.get("path");
-      let pos = Pos::new(schematic.get("x"), schematic.get("y"), schematic.get("z"));
-      schematic::load_from_file(&mut chunks, path, &block_converter, || {
-        CountedChunk::new(MultiChunk::new(block_converter.clone(), true))
-      })
-      .unwrap_or_else(|err| error!("could not load schematic file {}: {}", path, err));
-    }
-    */
+    // `storage` is never copied from `[world]`, as two worlds should never be
+    // saved in the same place.
+    let is_default = name == wm.default_world_name();
+    let storage =
+      if is_default || config.is_set("storage") { config.get::<&str>("storage") } else { "" };
+    let storage = if !storage.is_empty() {
+      PathBuf::from(storage)
+    } else if is_default {
+      PathBuf::from("world")
+    } else {
+      PathBuf::new().join("worlds").join(&name)
+    };
+    let height = config.get("height");
+    let min_y = config.get("min_y");
+    let default_chunk = if gen.is_uniform() {
+      let mut c = MultiChunk::new(wm.clone(), true, height, min_y);
+      gen.generate(ChunkPos::new(0, 0), &mut c);
+      let encoded = EncodedChunk::new(&chunk_packet(&c, ChunkPos::new(0, 0), height));
+      Some((c, Arc::new(encoded)))
+    } else {
+      None
+    };
     let world = Arc::new_cyclic(|weak| World {
       regions: RegionMap::new(weak.clone()),
       // generator: config.get("generator"),
       gen,
       players: RwLock::new(PlayersMap::new()),
       entities: RwLock::new(EntitiesMap::new()),
-      eid: 1.into(),
       block_converter,
       item_converter,
       entity_converter,
@@ -180,8 +248,11 @@ impl World {
       commands,
       uspt: Arc::new(0.into()),
       locked: config.get::<bool>("locked").into(),
-      height: config.get("height"),
-      min_y: config.get("min_y"),
+      height,
+      min_y,
+      name,
+      storage,
+      default_chunk,
       config,
       wm,
       chunks_to_load: Mutex::new(ChunksToLoad::new()),
@@ -190,14 +261,66 @@ impl World {
     if vanilla.get("enabled") {
       world.load_from_disk(&std::path::PathBuf::new().join(vanilla.get::<&str>("path"))).unwrap();
     }
+    let schem = world.config().section("schematic");
+    if schem.get("enabled") {
+      let path = schem.get::<&str>("path");
+      let pos = Pos::new(schem.get("x"), schem.get("y"), schem.get("z"));
+      let res = Schematic::load(std::path::Path::new(path), &world.block_converter)
+        .map_err(|e| e.to_string())
+        .and_then(|s| {
+          world.paste_schematic(&s, pos, PasteOptions::default()).map_err(|e| e.to_string())
+        });
+      if let Err(e) = res {
+        error!("could not load schematic file {path}: {e}");
+      }
+    }
     // Note that the world is not initialized yet, as we want to load plugins before
     // initializing.
     world
   }
 
-  /// Returns the config used in the whole server.
+  /// Returns the config for this world. For the default world, this is the
+  /// `[world]` section. For any other world, this is the `[worlds.<name>]`
+  /// section, which will fall back to `[world]` for missing keys.
   pub fn config(&self) -> &ConfigSection { &self.config }
 
+  /// Returns the value of a gamerule in this world. These are set in the
+  /// `gamerules` section of the world's config.
+  pub fn gamerule(&self, name: &str) -> bool { self.config.section("gamerules").get(name) }
+
+  /// Returns the name of this world. No two worlds will have the same name.
+  pub fn name(&self) -> &str { &self.name }
+
+  /// Returns the directory this world is saved in.
+  pub fn storage_path(&self) -> &Path { &self.storage }
+
+  /// Returns the name of this world as a resource location. This is sent to
+  /// 1.16+ clients, which will only reset their world when it changes.
+  pub(crate) fn dimension_name(&self) -> String {
+    let name: String = self
+      .name
+      .chars()
+      .map(|c| match c {
+        'a'..='z' | '0'..='9' | '_' | '-' | '.' => c,
+        'A'..='Z' => c.to_ascii_lowercase(),
+        _ => '_',
+      })
+      .collect();
+    format!("bamboo:{name}")
+  }
+
+  /// Returns the position new players should spawn at in this world. If
+  /// `find-spawn` is set, this will search upwards from the configured spawn
+  /// point.
+  pub fn spawn_point(&self) -> FPos {
+    let wm = self.world_manager();
+    if wm.config().get("find-spawn") {
+      self.find_spawn_point(wm.spawn_point.block()).into()
+    } else {
+      wm.spawn_point
+    }
+  }
+
   fn global_tick_loop(self: Arc<Self>) {
     let pool = ThreadPool::auto("global tick loop", || State {
       uspt:  self.uspt.clone(),
@@ -273,8 +396,13 @@ impl World {
         });
       }
       */
-      for (&eid, ent) in self.entities().iter_values() {
-        let ent = ent.clone();
+      // We can't hold onto the entities lock while sending jobs to the pool.
+      // Entities lock the map while they tick (mobs look for targets, and
+      // killing something will summon items), and `execute` blocks once the
+      // queue is full, so that would deadlock.
+      let entities: Vec<_> =
+        self.entities().iter_values().map(|(&eid, ent)| (eid, ent.clone())).collect();
+      for (eid, ent) in entities {
         let w = self.clone();
         pool.execute(move |s| {
           if let Some(ent) = ent.as_entity_ref(w.as_ref()) {
@@ -322,10 +450,7 @@ impl World {
         return;
       }
       drop(players);
-      let mut players = self.players.write();
-      players.insert(player.id(), player.clone());
-      let mut entities = self.entities.write();
-      entities.insert(player.eid(), Entity::Player(player.id()));
+      self.add_player(&player);
     }
     info!("{} has joined the game", player.username());
 
@@ -344,8 +469,15 @@ impl World {
     self.events().player_join(player);
   }
 
-  /// Returns a new, unique EID.
-  pub fn new_eid(&self) -> i32 { self.eid.fetch_add(1, Ordering::SeqCst) }
+  /// Adds the player to the players and entities tables. This does not send
+  /// any packets.
+  pub(crate) fn add_player(&self, player: &Arc<Player>) {
+    self.players.write().insert(player.id(), player.clone());
+    self.entities.write().insert(player.eid(), Entity::Player(player.id()));
+  }
+
+  /// Returns a new, unique EID. EIDs are unique across all worlds.
+  pub fn new_eid(&self) -> i32 { self.wm.new_eid() }
 
   /// Returns the current block converter. This can be used to convert old block
   /// ids to new ones, and vice versa. This can also be used to convert block
@@ -373,7 +505,13 @@ impl World {
   /// chunk, or even look in the chunks table at all. It should be used if you
   /// have a list of chunks to generate, and you would like to generate them in
   /// parallel.
+  ///
+  /// If this world has a default chunk, this will return a chunk sharing its
+  /// data, without generating anything.
   pub fn pre_generate_chunk(&self, pos: ChunkPos) -> MultiChunk {
+    if let Some((c, _)) = &self.default_chunk {
+      return c.share();
+    }
     let mut c = MultiChunk::new(self.world_manager().clone(), true, self.height, self.min_y);
     self.gen.generate(pos, &mut c);
     c
@@ -397,6 +535,16 @@ impl World {
   /// data saved on disk, it only checks if the given chunk is in memory.
   pub fn has_loaded_chunk(&self, pos: ChunkPos) -> bool { self.regions.has_chunk(pos) }
 
+  /// Returns true if the given chunk still shares its data with this world's
+  /// default chunk. These chunks have not been modified since they were
+  /// generated, so they don't need to be saved.
+  pub fn is_default_chunk(&self, chunk: &MultiChunk) -> bool {
+    match &self.default_chunk {
+      Some((c, _)) => c.shares_data(chunk),
+      None => false,
+    }
+  }
+
   /// Stores a list of chunks in the internal map. This should be used after
   /// calling [`pre_generate_chunk`](Self::pre_generate_chunk) a number of
   /// times.
@@ -443,18 +591,16 @@ impl World {
   /// If you are trying to produce a large block change packet, use
   /// [`serialize_partial_chunk`](Self::serialize_partial_chunk).
   pub fn serialize_chunk(&self, pos: ChunkPos) -> cb::packet::Chunk {
-    self.chunk(pos, |c| {
-      let inner = c.inner();
+    self.chunk(pos, |c| chunk_packet(&c, pos, self.height))
+  }
 
-      let mut sections: Vec<_> = inner.sections().cloned().collect();
-      sections.resize((self.height as usize + 15) / 16, None);
-      cb::packet::Chunk {
-        pos,
-        full: true,
-        sections,
-        sky_light: c.sky_light().clone(),
-        block_light: c.block_light().clone(),
-      }
+  /// Same as [`serialize_chunk`](Self::serialize_chunk), but chunks that still
+  /// share their data with the default chunk all reuse the same encoded
+  /// packet, so they are never serialized or copied again.
+  pub(crate) fn encode_chunk(&self, pos: ChunkPos) -> Outgoing {
+    self.chunk(pos, |c| match &self.default_chunk {
+      Some((default, encoded)) if default.shares_data(&c) => Outgoing::Chunk(pos, encoded.clone()),
+      _ => Outgoing::Packet(chunk_packet(&c, pos, self.height).into()),
     })
   }
 
@@ -486,6 +632,8 @@ impl World {
         // TODO: Only clone the sections we care about
         sky_light: c.sky_light().clone(),
         block_light: c.block_light().clone(),
+        // 1.17+ clients don't have partial chunks, so they need the biomes as well.
+        biomes: c.biomes().clone(),
       }
     })
   }
@@ -537,6 +685,14 @@ impl World {
     })
   }
 
+  /// Returns how many people are viewing the given chunk.
+  #[cfg(test)]
+  pub(crate) fn view_count(&self, pos: ChunkPos) -> u32 {
+    self.regions.region(pos, |region| {
+      region.get(RegionRelPos::new(pos)).as_ref().map_or(0, |c| c.count.load(Ordering::SeqCst))
+    })
+  }
+
   /// This broadcasts a chat message to everybody in the world. Note that this
   /// does not lock the players map exclusively. So, if this is called twice,
   /// both operations will execute in parallel. This might cause some packets to
@@ -557,13 +713,8 @@ impl World {
   /// WorldManagger, so that the world managger's table of players to worlds
   /// stays synced.
   fn remove_player(&self, id: UUID) {
-    let mut lock = self.players.write();
     // If the player is not present, this player has already been removed.
-    if let Some(p) = lock.remove(&id) {
-      let players_is_empty = lock.is_empty();
-      drop(lock);
-
-      self.entities.write().remove(&p.eid());
+    if let Some(p) = self.take_player(id) {
       self.events().player_leave(p.clone());
       info!("{} left the game", p.username());
 
@@ -575,17 +726,9 @@ impl World {
         self.world_manager().broadcast(msg);
       }
 
-      let entity_remove = cb::packet::RemoveEntities { eids: vec![p.eid()] };
-      let list_remove = cb::packet::PlayerList {
-        action: cb::PlayerListAction::Remove(vec![cb::PlayerListRemove { id: p.id() }]),
-      };
-      for other in p.world().players().iter().in_view(p.pos().block().chunk()).not(p.id()) {
-        other.send(entity_remove.clone());
-        other.send(list_remove.clone());
-      }
       p.unload_all();
 
-      if players_is_empty {
+      if self.players.read().is_empty() {
         self.unload_chunks();
         /*
         let len = self.chunks.read().len();
@@ -597,9 +740,35 @@ impl World {
     }
   }
 
+  /// Removes the player from the players and entities tables, and despawns
+  /// them for everyone else in this world. This does not send any packets to
+  /// the player being removed. Returns `None` if the player is not in this
+  /// world.
+  pub(crate) fn take_player(&self, id: UUID) -> Option<Arc<Player>> {
+    let p = self.players.write().remove(&id)?;
+    self.entities.write().remove(&p.eid());
+
+    let entity_remove = cb::packet::RemoveEntities { eids: vec![p.eid()] };
+    let list_remove = cb::packet::PlayerList {
+      action: cb::PlayerListAction::Remove(vec![cb::PlayerListRemove { id: p.id() }]),
+    };
+    for other in self.players().iter().not(p.id()) {
+      if other.in_view(p.pos().block().chunk()) {
+        other.send(entity_remove.clone());
+      }
+      other.send(list_remove.clone());
+    }
+    Some(p)
+  }
+
   // Unloads all the chunks that are cached for unloading.
   pub fn unload_chunks(&self) { self.regions.unload_chunks(); }
 
+  /// Returns the height of this world, in blocks.
+  pub fn height(&self) -> u32 { self.height }
+  /// Returns the minimum Y level of this world.
+  pub fn min_y(&self) -> i32 { self.min_y }
+
   /// Returns true if the world is locked. This is an atomic load, so it will
   /// always be a race condition. However, whenever you modify the world, this
   /// is also checked, so it won't end up being a problem.
@@ -680,6 +849,7 @@ impl WorldManager {
       worlds:            RwLock::new(vec![]),
       players:           RwLock::new(HashMap::new()),
       teams:             RwLock::new(HashMap::new()),
+      eid:               1.into(),
       default_game_mode: config.get("default-gamemode"),
       spawn_point:       config.get("spawn-point"),
       config:            Arc::new(config),
@@ -708,33 +878,77 @@ impl WorldManager {
   /// blocking call.
   pub fn run(self: Arc<Self>) { self.global_tick_loop(); }
 
-  /// Adds a new world.
-  pub fn add_world(self: &Arc<Self>) {
-    let world = World::new(
-      self.block_converter.clone(),
-      self.item_converter.clone(),
-      self.entity_converter.clone(),
-      self.plugins.clone(),
-      self.commands.clone(),
-      self.clone(),
-    );
+  /// Adds the default world, and then every world listed in the `[worlds]`
+  /// section of the config.
+  pub fn load_worlds(self: &Arc<Self>) {
+    if let Err(e) = self.add_world(&self.default_world_name()) {
+      error!("could not add the default world: {e}");
+    }
+    for name in self.config().section("worlds").keys() {
+      if let Err(e) = self.add_world(&name) {
+        warn!("skipping world `{name}`: {e}");
+      }
+    }
+  }
+
+  /// Adds a new world with the given name. The world will be configured by
+  /// the `[worlds.<name>]` section of the config, falling back to `[world]`
+  /// for any keys that aren't set. The first world added will become the
+  /// default world.
+  ///
+  /// Returns an error, and does nothing, if there is already a world with the
+  /// given name, or if the name is invalid (see [`AddWorldError`]).
+  pub fn add_world(self: &Arc<Self>, name: &str) -> Result<Arc<World>, AddWorldError> {
+    let world = self.create_world(name)?;
     let w = Arc::clone(&world);
     thread::spawn(move || {
       w.global_tick_loop();
     });
-    self.worlds.write().push(world);
+    self.worlds.write().push(world.clone());
+    Ok(world)
   }
   #[cfg(test)]
   pub(crate) fn add_world_no_tick(self: &Arc<Self>) {
-    self.worlds.write().push(World::new(
+    let world = self.create_world(&self.default_world_name()).unwrap();
+    self.worlds.write().push(world);
+  }
+  fn create_world(self: &Arc<Self>, name: &str) -> Result<Arc<World>, AddWorldError> {
+    // The name is used as a directory name, so we can't allow things like `../`.
+    if !is_valid_world_name(name) {
+      return Err(AddWorldError::InvalidName);
+    }
+    if self.world(name).is_some() {
+      return Err(AddWorldError::Exists);
+    }
+    let config = if name == self.default_world_name() {
+      self.config.section("world")
+    } else {
+      self.config.section(&["worlds", name][..]).with_fallback(self.config.section("world"))
+    };
+    Ok(World::new(
       self.block_converter.clone(),
       self.item_converter.clone(),
       self.entity_converter.clone(),
       self.plugins.clone(),
       self.commands.clone(),
       self.clone(),
-    ));
+      name.into(),
+      config,
+    ))
+  }
+
+  /// Returns the world with the given name, if present.
+  pub fn world(&self, name: &str) -> Option<Arc<World>> {
+    self.worlds.read().iter().find(|w| w.name() == name).cloned()
   }
+
+  /// Returns the name of the default world. This is set by `world.name` in the
+  /// config.
+  pub fn default_world_name(&self) -> String { self.config.section("world").get("name") }
+
+  /// Returns a new, unique EID. This is shared between all worlds.
+  pub fn new_eid(&self) -> i32 { self.eid.fetch_add(1, Ordering::SeqCst) }
+
   /// Creates a team. Returns `None`, and does nothing, if there is already a
   /// team with the given name.
   pub fn create_team(self: &Arc<Self>, name: String) -> Option<Arc<Mutex<Team>>> {
@@ -812,6 +1026,18 @@ impl WorldManager {
   /// any other context.
   pub fn default_world(&self) -> Arc<World> { self.worlds.read()[0].clone() }
 
+  /// Returns the directory player data is saved in. This is the `players`
+  /// directory inside the default world's storage path.
+  pub(crate) fn players_dir(&self) -> PathBuf {
+    self.default_world().storage_path().join("players")
+  }
+
+  /// Returns the persistent store for the given plugin. Plugin stores are
+  /// saved in the `plugins` directory inside the default world's storage path.
+  pub fn plugin_storage(&self, plugin: &str) -> Arc<plugin::Storage> {
+    self.plugins.storage(&self.default_world().storage_path().join("plugins"), plugin)
+  }
+
   // /// Adds a new player into the game. This should be called when a new grpc
   // /// proxy connects.
   // pub async fn new_player(&self, req: Streaming<Packet>, tx:
@@ -832,18 +1058,42 @@ impl WorldManager {
   /// Adds a new player into the game. This should be called when a new grpc
   /// proxy connects.
   pub fn new_player(&self, conn: ConnSender, info: JoinInfo) -> Arc<Player> {
-    let w = self.worlds.read()[0].clone();
-    let spawn = if self.config().get("find-spawn") {
-      w.find_spawn_point(self.spawn_point.block()).into()
+    let data = if self.config().get("save-players") {
+      PlayerData::load(&self.players_dir(), info.uuid)
     } else {
-      self.spawn_point
+      None
     };
-    let player = Player::new(w.new_eid(), conn, info.clone(), w.clone(), spawn);
-    self.players.write().insert(info.uuid, (0, player.clone()));
+    // Players are placed back into the world they left from. If that world is
+    // gone, they go into the default world.
+    let (idx, w) = {
+      let worlds = self.worlds.read();
+      let idx = data
+        .as_ref()
+        .and_then(|data| worlds.iter().position(|w| w.name() == data.world()))
+        .unwrap_or(0);
+      (idx, worlds[idx].clone())
+    };
+    let player = Player::new(self.new_eid(), conn, info.clone(), w.clone(), w.spawn_point());
+    if let Some(data) = data {
+      player.load_data(data);
+    }
+    self.players.write().insert(info.uuid, (idx, player.clone()));
     w.new_player(player.clone(), info);
     player
   }
 
+  /// Updates the world that the given player is in. This should only be called
+  /// by [`Player::switch_world`].
+  pub(crate) fn set_player_world(&self, id: UUID, world: &Arc<World>) {
+    let idx = match self.worlds.read().iter().position(|w| Arc::ptr_eq(w, world)) {
+      Some(idx) => idx,
+      None => return,
+    };
+    if let Some((i, _)) = self.players.write().get_mut(&id) {
+      *i = idx;
+    }
+  }
+
   /// Removes the player. This is not part of the public API because it does not
   /// terminate their connection. This is called after their connection is
   /// terminated.
@@ -851,7 +1101,12 @@ impl WorldManager {
   /// If the player is not present, this will do nothing.
   pub(crate) fn remove_player(&self, id: UUID) {
     let idx = match self.players.read().get(&id) {
-      Some(v) => v.0,
+      Some((idx, p)) => {
+        if self.config().get("save-players") {
+          p.save(&self.players_dir());
+        }
+        *idx
+      }
       None => return,
     };
     // This must be a read lock, or else this deadlocks (because of the leave
@@ -867,9 +1122,16 @@ impl WorldManager {
   }
 
   fn global_tick_loop(self: Arc<Self>) {
+    let mut tick = 0_u32;
     let mut start = Instant::now();
     loop {
       self.events().tick();
+      // Every minute, save any plugin stores that have changed, so that a crash
+      // doesn't lose everything since the last full save.
+      tick = tick.wrapping_add(1);
+      if tick % (20 * 60) == 0 {
+        self.plugins.save_storage();
+      }
       let passed = Instant::now().duration_since(start);
       start += TICK_TIME;
       match TICK_TIME.checked_sub(passed) {
@@ -892,6 +1154,11 @@ impl WorldManager {
     self.players.read().get(&id).map(|v| v.1.clone())
   }
 
+  /// Returns every player on the server, in all worlds.
+  pub fn all_players(&self) -> Vec<Arc<Player>> {
+    self.players.read().values().map(|(_, p)| p.clone()).collect()
+  }
+
   pub fn get_player_username(&self, name: &String) -> Option<Arc<Player>> {
     for (_, (_, p)) in self.players.read().iter() {
       if p.username() == name {
@@ -901,10 +1168,35 @@ impl WorldManager {
     None
   }
 
+  /// Returns the status of this server. This is sent to the proxy whenever a
+  /// client pings the server, and is shown in their server list.
+  pub fn status(&self) -> ServerStatus {
+    let players = self.players.read();
+    ServerStatus {
+      online: players.len() as u32,
+      max:    self.config().get("max-players"),
+      // Vanilla shows up to 12 players when hovering over the player count.
+      sample: players
+        .values()
+        .take(12)
+        .map(|(_, p)| StatusPlayer { username: p.username().clone(), uuid: p.id() })
+        .collect(),
+    }
+  }
+
+  /// Saves all the worlds, all online players (if `save-players` is
+  /// enabled), and all plugin data.
   pub fn save_all(&self) {
     for world in self.worlds.read().iter() {
       world.save();
     }
+    if self.config().get("save-players") {
+      let dir = self.players_dir();
+      for (_, p) in self.players.read().values() {
+        p.save(&dir);
+      }
+    }
+    self.plugins.save_storage();
   }
 
   #[cfg(not(target_family = "unix"))]
@@ -944,3 +1236,17 @@ impl WorldManager {
     unsafe { signal(Signal::SIGINT, handler) }.unwrap();
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn world_names() {
+    let wm = Arc::new(WorldManager::new(false));
+    assert_eq!(wm.add_world("../x").err(), Some(AddWorldError::InvalidName));
+    assert_eq!(wm.add_world("a/b").err(), Some(AddWorldError::InvalidName));
+    assert_eq!(wm.add_world("").err(), Some(AddWorldError::InvalidName));
+    assert!(wm.world("../x").is_none());
+  }
+}
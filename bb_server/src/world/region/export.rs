@@ -0,0 +1,509 @@
+//! Exports Bamboo worlds as vanilla Anvil worlds. This is the inverse of
+//! [`World::load_from_disk`], and is used to open worlds built on Bamboo in
+//! vanilla tools, like MCEdit or Amulet.
+//!
+//! Only blocks, biomes and block entities are exported. Lighting is left for
+//! vanilla to recompute, and entities are not exported at all. Blocks are
+//! written with their latest names, so blocks that have been renamed since the
+//! chosen version may not be recognized.
+
+use super::super::{bbr::Region, MultiChunk, World};
+use crate::block;
+use bb_common::{
+  chunk::{biome, Section},
+  flate2::{
+    write::{GzEncoder, ZlibEncoder},
+    Compression,
+  },
+  math::{ChunkPos, SectionRelPos},
+  nbt::{Tag, NBT},
+  version::{BlockVersion, ProtocolVersion},
+};
+use std::{
+  collections::HashMap,
+  fs,
+  fs::File,
+  io,
+  io::Write,
+  path::Path,
+  time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Parses a version name, like `1.12.2`, into the block version that should be
+/// exported. Returns `None` if the version is invalid. 1.13 is also invalid, as
+/// Bamboo doesn't support it.
+pub fn parse_export_version(name: &str) -> Option<BlockVersion> {
+  match ProtocolVersion::parse_name(name) {
+    ProtocolVersion::Invalid
+    | ProtocolVersion::V1_13
+    | ProtocolVersion::V1_13_1
+    | ProtocolVersion::V1_13_2 => None,
+    v => Some(v.block()),
+  }
+}
+
+/// Returns the `DataVersion` of the last release with the given block version.
+/// 1.8 doesn't store a data version, so this returns `None` for 1.8.
+fn data_version(ver: BlockVersion) -> Option<i32> {
+  Some(match ver {
+    BlockVersion::V1_8 => return None,
+    BlockVersion::V1_9 => 184,
+    BlockVersion::V1_10 => 512,
+    BlockVersion::V1_11 => 922,
+    BlockVersion::V1_12 => 1343,
+    BlockVersion::V1_14 => 1976,
+    BlockVersion::V1_15 => 2230,
+    BlockVersion::V1_16 => 2586,
+    BlockVersion::V1_17 => 2730,
+    BlockVersion::V1_18 => 2975,
+    _ => 3105,
+  })
+}
+
+impl World {
+  /// Exports this world as a vanilla world for the given version. `path` is
+  /// the world folder, which will contain `level.dat` and the `region` folder.
+  /// Any existing region files in that folder will be overwritten.
+  ///
+  /// This includes every chunk that has been generated, not just the chunks
+  /// that are loaded. Before 1.18, the world could only go from Y 0 to 255, so
+  /// anything outside of that range is not exported for older versions.
+  ///
+  /// Returns the number of chunks that were exported.
+  pub fn export_to_disk(&self, path: &Path, ver: BlockVersion) -> io::Result<u32> {
+    fs::create_dir_all(path.join("region"))?;
+    self.write_level_dat(path, ver)?;
+
+    let mut total = 0;
+    let mut res = Ok(());
+    self.regions.for_each_region(|region| {
+      if res.is_ok() {
+        match self.export_region(region, path, ver) {
+          Ok(n) => total += n,
+          Err(e) => res = Err(e),
+        }
+      }
+    });
+    res?;
+    info!("exported {total} chunks to {}", path.display());
+    Ok(total)
+  }
+
+  /// Writes a single region file. If there are no chunks in this region, no
+  /// file is written.
+  fn export_region(&self, region: &Region, path: &Path, ver: BlockVersion) -> io::Result<u32> {
+    // The first 4096 bytes are the location of each chunk, and the next 4096
+    // bytes are the timestamps of each chunk.
+    let mut header = vec![0; 8192];
+    let mut chunks = vec![];
+    let mut count = 0;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as u32;
+    for (pos, chunk) in region.chunks() {
+      let nbt = self.export_chunk(pos, &chunk.lock(), ver);
+      let mut encoder = ZlibEncoder::new(vec![], Compression::default());
+      encoder.write_all(&nbt.serialize())?;
+      let data = encoder.finish()?;
+
+      // Each chunk starts with a 5 byte header, and is padded to a multiple of
+      // 4096 bytes. The region header can only store 255 sectors for a chunk.
+      let sectors = (data.len() + 5 + 4095) / 4096;
+      if sectors > 255 {
+        warn!("chunk {pos:?} is too large to export, skipping");
+        continue;
+      }
+      let offset = 2 + chunks.len() / 4096;
+      let idx = (pos.x() & 31) as usize + (pos.z() & 31) as usize * 32;
+      header[idx * 4..idx * 4 + 4]
+        .copy_from_slice(&((offset as u32) << 8 | sectors as u32).to_be_bytes());
+      header[4096 + idx * 4..4096 + idx * 4 + 4].copy_from_slice(&now.to_be_bytes());
+
+      let start = chunks.len();
+      // The length includes the compression byte.
+      chunks.extend_from_slice(&(data.len() as u32 + 1).to_be_bytes());
+      // 2 means zlib compression.
+      chunks.push(2);
+      chunks.extend_from_slice(&data);
+      chunks.resize(start + sectors * 4096, 0);
+      count += 1;
+    }
+    if count == 0 {
+      return Ok(0);
+    }
+
+    let pos = region.pos();
+    let path = path.join("region").join(format!("r.{}.{}.mca", pos.x, pos.z));
+    debug!("exporting region to {}", path.display());
+    let mut f = File::create(path)?;
+    f.write_all(&header)?;
+    f.write_all(&chunks)?;
+    Ok(count)
+  }
+
+  /// Converts a single chunk to vanilla NBT.
+  fn export_chunk(&self, pos: ChunkPos, chunk: &MultiChunk, ver: BlockVersion) -> NBT {
+    let conv = chunk.type_converter();
+    let mut sections = vec![];
+    // Only used before 1.13. Vanilla doesn't read this until 1.13, and
+    // recomputes missing heightmaps after that.
+    let mut heightmap = vec![0; 256];
+    for (i, section) in chunk.inner().sections().enumerate() {
+      let section = match section {
+        Some(s) if s.non_air_blocks() > 0 => s,
+        _ => continue,
+      };
+      let y = i as i32 + (self.min_y >> 4);
+      if ver < BlockVersion::V1_18 && !(0..16).contains(&y) {
+        continue;
+      }
+      let ids: Vec<u32> = (0..4096)
+        .map(|i| {
+          let pos = SectionRelPos::new((i % 16) as u8, (i / 256) as u8, ((i / 16) % 16) as u8);
+          conv.custom_to_vanilla(section.get_block(pos))
+        })
+        .collect();
+      sections.push(if ver <= BlockVersion::V1_12 {
+        export_section_old(conv, y, &ids, ver, &mut heightmap)
+      } else {
+        export_section(conv, y, &ids, ver, &chunk.biomes().section(i as u32))
+      });
+    }
+
+    let mut tes = vec![];
+    for (rel, te) in chunk.tes() {
+      // `rel` has already been transformed, so we need to undo that here.
+      let y = rel.y() + self.min_y;
+      if ver < BlockVersion::V1_18 && !(0..256).contains(&y) {
+        continue;
+      }
+      let id = chunk.inner().get_block(*rel).map(|id| conv.custom_to_vanilla(id)).unwrap_or(0);
+      let kind = conv.kind_from_id(id, BlockVersion::latest());
+      let nbt =
+        self.world_manager().block_behaviors().call(kind, |b| b.save_te_nbt(te.as_ref())).flatten();
+      if let Some(mut nbt) = nbt {
+        nbt.insert("x".into(), Tag::Int(pos.block_x() + rel.x() as i32));
+        nbt.insert("y".into(), Tag::Int(y));
+        nbt.insert("z".into(), Tag::Int(pos.block_z() + rel.z() as i32));
+        if ver < BlockVersion::V1_11 {
+          if let Some(Tag::String(id)) = nbt.get_mut("id") {
+            *id = old_te_id(id);
+          }
+        }
+        tes.push(Tag::Compound(nbt));
+      }
+    }
+
+    let last_update = ("LastUpdate", Tag::Long(0));
+    let mut root = vec![];
+    if let Some(v) = data_version(ver) {
+      root.push(("DataVersion", Tag::Int(v)));
+    }
+    if ver >= BlockVersion::V1_18 {
+      root.extend([
+        ("xPos", Tag::Int(pos.x())),
+        ("yPos", Tag::Int(self.min_y >> 4)),
+        ("zPos", Tag::Int(pos.z())),
+        ("Status", Tag::String("full".into())),
+        last_update,
+        ("sections", Tag::List(sections)),
+        ("block_entities", Tag::List(tes)),
+      ]);
+    } else {
+      let mut level = vec![
+        ("xPos", Tag::Int(pos.x())),
+        ("zPos", Tag::Int(pos.z())),
+        last_update,
+        ("Sections", Tag::List(sections)),
+        ("TileEntities", Tag::List(tes)),
+        ("Entities", Tag::List(vec![])),
+      ];
+      // Before 1.15, biomes are stored for each column, and from 1.15 to 1.17, they
+      // are stored for each 4x4x4 cell, starting at Y 0.
+      let biomes = chunk.biomes();
+      let legacy = |b: u8| biome::info(b).legacy_id as i32;
+      if ver <= BlockVersion::V1_12 {
+        let ids = (0..256_u32)
+          .map(|i| legacy(biomes.column((i % 16) as u8, (i / 16) as u8)) as u8)
+          .collect();
+        level.push(("Biomes", Tag::ByteArr(ids)));
+      } else if ver <= BlockVersion::V1_14 {
+        let ids =
+          (0..256_u32).map(|i| legacy(biomes.column((i % 16) as u8, (i / 16) as u8))).collect();
+        level.push(("Biomes", Tag::IntArray(ids)));
+      } else {
+        let ids = (0..1024)
+          .map(|i| match u32::try_from(i / 16 - self.min_y / 4) {
+            Ok(y) => legacy(biomes.get((i % 4) as u8, y, ((i / 4) % 4) as u8)),
+            Err(_) => legacy(biome::VOID),
+          })
+          .collect();
+        level.push(("Biomes", Tag::IntArray(ids)));
+      }
+      if ver <= BlockVersion::V1_12 {
+        // Setting `LightPopulated` to 0 makes vanilla recompute the light.
+        level.extend([
+          ("TerrainPopulated", Tag::Byte(1)),
+          ("LightPopulated", Tag::Byte(0)),
+          ("HeightMap", Tag::IntArray(heightmap)),
+        ]);
+      } else {
+        level.push(("Status", Tag::String("full".into())));
+      }
+      root.push(("Level", Tag::compound(&level)));
+    }
+    NBT::new("", Tag::compound(&root))
+  }
+
+  /// Writes a `level.dat` file, which is needed for tools to recognize the
+  /// exported world.
+  fn write_level_dat(&self, path: &Path, ver: BlockVersion) -> io::Result<()> {
+    let spawn = self.world_manager().spawn_point.block();
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as i64;
+    let mut data = vec![
+      ("LevelName", Tag::String("Bamboo".into())),
+      // This is the version of the Anvil format, not the game version.
+      ("version", Tag::Int(19133)),
+      ("GameType", Tag::Int(1)),
+      ("allowCommands", Tag::Byte(1)),
+      ("SpawnX", Tag::Int(spawn.x)),
+      ("SpawnY", Tag::Int(spawn.y)),
+      ("SpawnZ", Tag::Int(spawn.z)),
+      ("LastPlayed", Tag::Long(now)),
+    ];
+    if let Some(v) = data_version(ver) {
+      data.push(("DataVersion", Tag::Int(v)));
+      data.push((
+        "Version",
+        Tag::compound(&[
+          ("Id", Tag::Int(v)),
+          ("Name", Tag::String(ver.protocol().to_string())),
+          ("Snapshot", Tag::Byte(0)),
+        ]),
+      ));
+    }
+    let nbt = NBT::new("", Tag::compound(&[("Data", Tag::compound(&data))]));
+    let mut encoder = GzEncoder::new(File::create(path.join("level.dat"))?, Compression::default());
+    encoder.write_all(&nbt.serialize())?;
+    encoder.finish()?;
+    Ok(())
+  }
+}
+
+/// Converts a section to the 1.8 - 1.12 format, which stores a block id and
+/// metadata for every block. This also updates `heightmap` with the highest
+/// block in each column.
+fn export_section_old(
+  conv: &block::TypeConverter,
+  y: i32,
+  ids: &[u32],
+  ver: BlockVersion,
+  heightmap: &mut [i32],
+) -> Tag {
+  let mut blocks = vec![0; 4096];
+  let mut data = vec![0; 2048];
+  for (i, &id) in ids.iter().enumerate() {
+    let old = conv.to_old(id, ver);
+    blocks[i] = (old >> 4) as u8;
+    data[i / 2] |= ((old & 0x0f) as u8) << ((i % 2) * 4);
+    if old != 0 {
+      let height = &mut heightmap[i % 256];
+      *height = (*height).max(y * 16 + (i / 256) as i32 + 1);
+    }
+  }
+  Tag::compound(&[
+    ("Y", Tag::Byte(y as i8)),
+    ("Blocks", Tag::ByteArr(blocks)),
+    ("Data", Tag::ByteArr(data)),
+    ("BlockLight", Tag::ByteArr(vec![0; 2048])),
+    ("SkyLight", Tag::ByteArr(vec![0; 2048])),
+  ])
+}
+
+/// Converts a section to the 1.14+ format, which stores a palette of block
+/// names and properties. `biomes` are only used in 1.18+, where they are stored
+/// in each section.
+fn export_section(
+  conv: &block::TypeConverter,
+  y: i32,
+  ids: &[u32],
+  ver: BlockVersion,
+  biomes: &[u8; 64],
+) -> Tag {
+  let mut palette = vec![];
+  let mut palette_ids = HashMap::new();
+  let indices: Vec<u64> = ids
+    .iter()
+    .map(|&id| {
+      // Blocks that don't exist in this version are replaced with air.
+      let id = if conv.to_old(id, ver) == 0 { 0 } else { id };
+      *palette_ids.entry(id).or_insert_with(|| {
+        palette.push(state_nbt(conv, id));
+        palette.len() as u64 - 1
+      })
+    })
+    .collect();
+  // Vanilla never uses less than 4 bits per block.
+  let bpe = ((usize::BITS - (palette.len() - 1).leading_zeros()) as usize).max(4);
+  let data = pack(&indices, bpe, ver >= BlockVersion::V1_16);
+
+  if ver >= BlockVersion::V1_18 {
+    // If there is only one block in the palette, the data is left out.
+    let block_states = if palette.len() == 1 {
+      Tag::compound(&[("palette", Tag::List(palette))])
+    } else {
+      Tag::compound(&[("palette", Tag::List(palette)), ("data", Tag::LongArray(data))])
+    };
+    let biomes = export_biomes(biomes);
+    Tag::compound(&[("Y", Tag::Byte(y as i8)), ("block_states", block_states), ("biomes", biomes)])
+  } else {
+    Tag::compound(&[
+      ("Y", Tag::Byte(y as i8)),
+      ("Palette", Tag::List(palette)),
+      ("BlockStates", Tag::LongArray(data)),
+    ])
+  }
+}
+
+/// Converts the biomes in a section to the 1.18+ format, which is a palette of
+/// names. Unlike block states, there is no minimum number of bits.
+fn export_biomes(biomes: &[u8; 64]) -> Tag {
+  let mut palette = vec![];
+  let mut palette_ids = HashMap::new();
+  let indices: Vec<u64> = biomes
+    .iter()
+    .map(|&b| {
+      *palette_ids.entry(b).or_insert_with(|| {
+        palette.push(Tag::String(format!("minecraft:{}", biome::info(b).name)));
+        palette.len() as u64 - 1
+      })
+    })
+    .collect();
+  if palette.len() == 1 {
+    Tag::compound(&[("palette", Tag::List(palette))])
+  } else {
+    let bits = (usize::BITS - (palette.len() - 1).leading_zeros()) as usize;
+    let data = pack(&indices, bits, true);
+    Tag::compound(&[("palette", Tag::List(palette)), ("data", Tag::LongArray(data))])
+  }
+}
+
+/// Converts a block id into a palette entry.
+fn state_nbt(conv: &block::TypeConverter, id: u32) -> Tag {
+  let ty = conv.type_from_id(id, BlockVersion::latest());
+  let name = Tag::String(format!("minecraft:{}", ty.kind().to_str()));
+  let props = ty.props();
+  if props.is_empty() {
+    Tag::compound(&[("Name", name)])
+  } else {
+    let props = props.into_iter().map(|(k, v)| (k, Tag::String(v))).collect();
+    Tag::compound(&[("Name", name), ("Properties", Tag::Compound(props))])
+  }
+}
+
+/// Packs the given palette indices into a long array. If `padded` is set,
+/// entries never span across two longs (used in 1.16+). Otherwise, entries are
+/// packed together, and may be split between two longs.
+fn pack(indices: &[u64], bpe: usize, padded: bool) -> Vec<i64> {
+  let data = if padded {
+    let epl = 64 / bpe;
+    let mut data = vec![0_u64; (indices.len() + epl - 1) / epl];
+    for (i, &v) in indices.iter().enumerate() {
+      data[i / epl] |= v << ((i % epl) * bpe);
+    }
+    data
+  } else {
+    let mut data = vec![0_u64; (indices.len() * bpe + 63) / 64];
+    for (i, &v) in indices.iter().enumerate() {
+      let bit = i * bpe;
+      let (idx, shift) = (bit / 64, bit % 64);
+      data[idx] |= v << shift;
+      if shift + bpe > 64 {
+        data[idx + 1] |= v >> (64 - shift);
+      }
+    }
+    data
+  };
+  data.into_iter().map(|v| v as i64).collect()
+}
+
+/// Before 1.11, block entity ids were capitalized, and didn't have a
+/// namespace.
+fn old_te_id(id: &str) -> String {
+  let name = id.strip_prefix("minecraft:").unwrap_or(id);
+  let mut chars = name.chars();
+  match chars.next() {
+    Some(c) => c.to_uppercase().chain(chars).collect(),
+    None => String::new(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{
+    super::{decode_biomes, decode_section, get, parse_state, tests::expand},
+    *,
+  };
+
+  /// Exports a section, and then imports it again.
+  fn round_trip(conv: &block::TypeConverter, ids: &[u32], ver: BlockVersion) -> Vec<u32> {
+    let section = export_section(conv, 0, ids, ver, &[biome::VOID; 64]);
+    let section = section.as_compound().unwrap();
+    let (palette, data) = if ver >= BlockVersion::V1_18 {
+      let states = get(section, "block_states", Tag::as_compound).unwrap();
+      (
+        get(states, "palette", Tag::as_list).unwrap(),
+        get(states, "data", Tag::as_long_arr).unwrap(),
+      )
+    } else {
+      (
+        get(section, "Palette", Tag::as_list).unwrap(),
+        get(section, "BlockStates", Tag::as_long_arr).unwrap(),
+      )
+    };
+    let palette = palette.iter().map(|it| parse_state(conv, it).unwrap()).collect();
+    expand(decode_section(0, palette, data).unwrap())
+  }
+
+  #[test]
+  fn export_and_import() {
+    let conv = block::TypeConverter::new();
+    // 20 different blocks needs 5 bits per block, which doesn't divide 64
+    // evenly.
+    let ids: Vec<u32> = (0..4096).map(|i| (i * 7 % 20) as u32 + 1).collect();
+    assert_eq!(round_trip(&conv, &ids, BlockVersion::latest()), ids);
+    // Before 1.16, entries can span across two longs.
+    assert_eq!(round_trip(&conv, &ids, BlockVersion::V1_14), ids);
+
+    // Properties should come back unchanged.
+    let log = conv.get(block::Kind::OakLog).default_type();
+    let mut x_log = log;
+    x_log.set_prop("axis", "x");
+    let ids: Vec<u32> = (0..4096).map(|i| if i % 2 == 0 { log.id() } else { x_log.id() }).collect();
+    assert_eq!(round_trip(&conv, &ids, BlockVersion::latest()), ids);
+  }
+
+  #[test]
+  fn export_and_import_biomes() {
+    let import = |biomes: &[u8; 64]| {
+      let tag = export_biomes(biomes);
+      let tag = tag.as_compound().unwrap();
+      let palette: Vec<u8> = get(tag, "palette", Tag::as_list)
+        .unwrap()
+        .iter()
+        .map(|it| biome::from_name(it.as_string().unwrap()).unwrap())
+        .collect();
+      let data = tag.get("data").map(|d| d.as_long_arr().unwrap().as_slice()).unwrap_or(&[]);
+      decode_biomes(0, &palette, data).unwrap()
+    };
+    // A single biome has no data.
+    let plains = biome::from_name("plains").unwrap();
+    assert!(export_biomes(&[plains; 64]).as_compound().unwrap().get("data").is_none());
+    assert_eq!(import(&[plains; 64]), vec![plains; 64]);
+
+    // 5 biomes needs 3 bits, which doesn't divide 64 evenly.
+    let mut biomes = [0; 64];
+    for (i, b) in biomes.iter_mut().enumerate() {
+      *b = (i % 5) as u8 + 1;
+    }
+    assert_eq!(import(&biomes), biomes.to_vec());
+  }
+}
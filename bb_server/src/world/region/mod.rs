@@ -5,16 +5,76 @@
 //! Not to be confused with `bbr` (bamboo region), which is for a custom world
 //! format.
 
-use crate::block;
+mod export;
+
+pub use export::parse_export_version;
+
+use super::{MultiChunk, World};
+use crate::{block, entity};
 use bb_common::{
-  chunk::Section,
-  math::{ChunkPos, SectionRelPos},
-  nbt::{Tag, NBT},
+  chunk::{
+    biome::{self, BiomeChunk},
+    Section,
+  },
+  math::{ChunkPos, FPos, RelPos, SectionRelPos},
+  nbt::{ParseError, Tag, NBT},
   util::ThreadPool,
+  version::BlockVersion,
 };
-use std::{fs, io, path::Path, str::FromStr, sync::Arc};
+use std::{collections::HashMap, fmt, fs, io, path::Path, str::FromStr, sync::Arc};
+
+type Compound = HashMap<String, Tag>;
+
+/// An error while loading a single chunk from a region file. These are logged,
+/// and the chunk is skipped, so that one corrupted chunk doesn't stop the
+/// whole world from loading.
+#[derive(Debug)]
+enum ChunkError {
+  /// The chunk header in the region file was invalid.
+  Header(&'static str),
+  /// The chunk's NBT data could not be parsed.
+  Nbt(ParseError),
+  /// The given NBT field was missing, or it had the wrong type.
+  Field(&'static str),
+  /// The block data in the section at the given Y did not match its palette.
+  BlockData(i32),
+  /// The biome data in the section at the given Y did not match its palette.
+  BiomeData(i32),
+}
+
+impl fmt::Display for ChunkError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Self::Header(msg) => write!(f, "invalid chunk header: {msg}"),
+      Self::Nbt(e) => write!(f, "invalid nbt: {e}"),
+      Self::Field(name) => write!(f, "missing or invalid field `{name}`"),
+      Self::BlockData(y) => write!(f, "invalid block data in section {y}"),
+      Self::BiomeData(y) => write!(f, "invalid biome data in section {y}"),
+    }
+  }
+}
+
+/// Looks up `key` in the given compound, and converts it with `f`. This is
+/// used with the `Tag::as_*` functions, like so: `get(level, "xPos",
+/// Tag::as_int)`.
+fn get<'a, T>(
+  compound: &'a Compound,
+  key: &'static str,
+  f: impl FnOnce(&'a Tag) -> Option<T>,
+) -> Result<T, ChunkError> {
+  compound.get(key).and_then(f).ok_or(ChunkError::Field(key))
+}
 
-use super::World;
+/// The blocks in a single section, parsed from a vanilla chunk.
+#[derive(Debug)]
+enum SectionBlocks {
+  /// The whole section is filled with this block.
+  Single(u32),
+  /// A palette and data array, which can be copied directly into a section.
+  Paletted(Vec<u32>, Vec<u64>),
+  /// A list of 4096 block ids, which must be set one at a time.
+  Each(Vec<u32>),
+}
 
 fn parse_region_name(name: &str) -> Option<(i32, i32)> {
   let mut sections = name.split('.');
@@ -33,34 +93,50 @@ fn parse_region_name(name: &str) -> Option<(i32, i32)> {
 }
 
 impl World {
+  /// Loads a vanilla world from the given path. The path should be the world
+  /// folder, which contains the `region` folder. If there is an `entities`
+  /// folder (added in 1.17), entities will be loaded from there as well.
+  ///
+  /// Blocks, biomes, block entities and entities are imported. Biomes that
+  /// Bamboo doesn't know about are replaced with `the_void`.
   pub fn load_from_disk(self: &Arc<Self>, path: &Path) -> io::Result<()> {
-    let chunks = path.join("region");
     let pool = ThreadPool::auto("vanilla regions", || ());
-    for f in fs::read_dir(chunks)? {
-      let f = f?;
-      if f.metadata()?.is_file() {
-        let path = f.path();
-        let name = match path.file_name().unwrap().to_str() {
-          Some(s) => s,
-          None => continue,
-        };
-        let (_x, _z) = match parse_region_name(name) {
-          Some(v) => v,
-          None => continue,
-        };
-        let w = self.clone();
-        pool.execute(move |_| match w.load_region_file(&path) {
-          Ok(_) => {}
-          Err(e) => error!("invalid vanilla region file at {}: {}", path.display(), e),
-        });
+    for (dir, entities) in [("region", false), ("entities", true)] {
+      let dir = path.join(dir);
+      if entities && !dir.is_dir() {
+        continue;
+      }
+      for f in fs::read_dir(dir)? {
+        let f = f?;
+        if f.metadata()?.is_file() {
+          let path = f.path();
+          let name = match path.file_name().unwrap().to_str() {
+            Some(s) => s,
+            None => continue,
+          };
+          let (_x, _z) = match parse_region_name(name) {
+            Some(v) => v,
+            None => continue,
+          };
+          let w = self.clone();
+          pool.execute(move |_| match w.load_region_file(&path, entities) {
+            Ok(_) => {}
+            Err(e) => error!("invalid vanilla region file at {}: {}", path.display(), e),
+          });
+        }
       }
     }
     pool.wait();
     Ok(())
   }
 
-  fn load_region_file(&self, path: &Path) -> io::Result<()> {
+  /// Loads all the chunks in the given region file. If `entities` is set, this
+  /// is an entity region file, and only entities will be loaded.
+  fn load_region_file(self: &Arc<Self>, path: &Path, entities: bool) -> io::Result<()> {
     let data = fs::read(path)?;
+    if data.len() < 8192 {
+      return Err(io::Error::new(io::ErrorKind::InvalidData, "region file is too short"));
+    }
     let header = &data[..8192];
     // `offset` is an offset into the file, not an offset into the chunks table.
     let chunks = &data;
@@ -73,127 +149,478 @@ impl World {
         continue;
       }
 
-      if offset + size > chunks.len() {
-        size = chunks.len() - offset;
-      } else if offset >= chunks.len() {
+      if offset < header.len() || offset >= chunks.len() {
         error!("section had invalid index: {offset:#x} size {size:#x} (len: {:#x})", chunks.len());
         continue;
+      } else if offset + size > chunks.len() {
+        size = chunks.len() - offset;
       }
 
-      let chunk = &chunks[offset..offset + size];
-      let header = &chunk[..5];
-      let len = u32::from_be_bytes(header[..4].try_into().unwrap()) as usize;
-      let _compression = header[4];
-      let nbt = NBT::deserialize_file(chunk[5..5 + len - 1].to_vec()).unwrap().into_tag();
-
-      // 1.8 uses capitalized names
-      // 1.12.2 uses lowercase names.
-      // 1.18 uses a mix of both (wtf???)
-      let is_capital_names;
-      let nbt = if nbt.unwrap_compound().contains_key("Level") {
-        is_capital_names = true;
-        &nbt.unwrap_compound()["Level"]
-      } else {
-        is_capital_names = false;
-        &nbt
-      };
-      let level = nbt.unwrap_compound();
-      let sections_key = if is_capital_names { "Sections" } else { "sections" };
-      if !level.contains_key(sections_key) {
-        continue;
+      let res = read_chunk(&chunks[offset..offset + size]).and_then(|nbt| {
+        if entities {
+          self.load_entity_chunk(&nbt)
+        } else {
+          self.load_chunk(&nbt)
+        }
+      });
+      if let Err(e) = res {
+        error!("skipping chunk {id} in {}: {e}", path.display());
       }
+    }
+    Ok(())
+  }
 
-      // the chunk_x and chunk_z values are absolute.
-      let chunk_x = level["xPos"].unwrap_int();
-      let chunk_z = level["zPos"].unwrap_int();
-      let pos = ChunkPos::new(chunk_x, chunk_z);
+  /// Loads the blocks, block entities, and entities (before 1.17) from the
+  /// given chunk.
+  fn load_chunk(self: &Arc<Self>, nbt: &Tag) -> Result<(), ChunkError> {
+    let root = nbt.as_compound().ok_or(ChunkError::Field("root"))?;
+    // 1.8 uses capitalized names
+    // 1.12.2 uses lowercase names.
+    // 1.18 uses a mix of both (wtf???)
+    let is_capital_names;
+    let level = if root.contains_key("Level") {
+      is_capital_names = true;
+      get(root, "Level", Tag::as_compound)?
+    } else {
+      is_capital_names = false;
+      root
+    };
+    let sections_key = if is_capital_names { "Sections" } else { "sections" };
+    if !level.contains_key(sections_key) {
+      return Ok(());
+    }
 
-      self.chunk(pos, |mut chunk| {
-        for s in level[sections_key].unwrap_list() {
-          let section = s.unwrap_compound();
-          let y = section["Y"].unwrap_byte();
-          if y < 0 {
-            // TODO: Handle negative chunks
-            continue;
+    // the chunk_x and chunk_z values are absolute.
+    let chunk_x = get(level, "xPos", Tag::as_int)?;
+    let chunk_z = get(level, "zPos", Tag::as_int)?;
+    let pos = ChunkPos::new(chunk_x, chunk_z);
+
+    // We parse every section before touching the world, so that an invalid
+    // chunk is skipped entirely, instead of being half loaded.
+    let mut sections = vec![];
+    for s in get(level, sections_key, Tag::as_list)? {
+      if let Some(section) = self.parse_section(s, is_capital_names)? {
+        sections.push(section);
+      }
+    }
+    let tes_key = if is_capital_names { "TileEntities" } else { "block_entities" };
+    let tes = level.get(tes_key).and_then(Tag::as_list).map(Vec::as_slice).unwrap_or_default();
+    let biomes = self.parse_biomes(level, is_capital_names)?;
+
+    self.chunk(pos, |mut chunk| {
+      *chunk.biomes_mut() = biomes;
+      for (y, blocks) in sections {
+        let section = chunk.inner_mut().section_mut(y);
+        match blocks {
+          SectionBlocks::Single(id) => {
+            section.fill(SectionRelPos::new(0, 0, 0), SectionRelPos::new(15, 15, 15), id)
           }
-          let y = y as u32;
-          if is_capital_names {
-            if section.contains_key("Blocks") {
-              // is 1.8
-              let blocks = section["Blocks"].unwrap_byte_arr();
-              let data = section["Data"].unwrap_byte_arr();
-              let section = chunk.inner_mut().section_mut(y);
-              for y in 0..16 {
-                for z in 0..16 {
-                  for x in 0..16 {
-                    let index = ((y as usize * 16) + z as usize) * 16 + x as usize;
-                    let mask = 0x0f << ((x as u8 % 2) * 4);
-                    let id = (blocks[index] as u32) << 4 | (data[index / 2] & mask) as u32;
-                    let old =
-                      self.block_converter().to_latest(id, bb_common::version::BlockVersion::V1_8);
-                    section.set_block(SectionRelPos::new(x, y, z), old);
-                  }
-                }
-              }
-            } else {
-              // is 1.12+
-              // Skip air sections
-              if !section.contains_key("BlockStates") {
-                continue;
-              }
-              let block_states =
-                section["BlockStates"].unwrap_long_arr().iter().map(|v| *v as u64).collect();
-              let palette: Vec<_> = section["Palette"]
-                .unwrap_list()
-                .iter()
-                .map(|it| parse_state(self.block_converter(), it))
-                .collect();
-              let section = chunk.inner_mut().section_mut(y);
-              section.set_from(palette, block_states);
+          SectionBlocks::Paletted(palette, data) => section.set_from(palette, data),
+          SectionBlocks::Each(ids) => {
+            for (i, id) in ids.into_iter().enumerate() {
+              let (x, y, z) = (i % 16, i / 256, (i / 16) % 16);
+              section.set_block(SectionRelPos::new(x as u8, y as u8, z as u8), id);
             }
-          } else {
-            // is 1.12+
-            let block_states = section["block_states"].unwrap_compound();
-            let palette: Vec<_> = block_states["palette"]
-              .unwrap_list()
-              .iter()
-              .map(|it| parse_state(self.block_converter(), it))
-              .collect();
-            // The section will be full of one type
-            let section = chunk.inner_mut().section_mut(y);
-            if !block_states.contains_key("data") {
-              assert_eq!(palette.len(), 1);
-              section.fill(SectionRelPos::new(0, 0, 0), SectionRelPos::new(15, 15, 15), palette[0]);
-            } else {
-              let data = block_states["data"].unwrap_long_arr().iter().map(|v| *v as u64).collect();
-              section.set_from(palette, data);
+          }
+        }
+      }
+      for te in tes.iter().filter_map(Tag::as_compound) {
+        if let Err(e) = self.load_block_entity(&mut chunk, te) {
+          warn!("skipping block entity in chunk {pos:?}: {e}");
+        }
+      }
+    });
+
+    // Before 1.17, entities were stored with the chunk.
+    if let Some(entities) = level.get("Entities").and_then(Tag::as_list) {
+      self.load_entities(entities);
+    }
+    Ok(())
+  }
+
+  /// Loads the entities from a chunk in an entity region file. These were
+  /// split out from the chunk data in 1.17.
+  fn load_entity_chunk(self: &Arc<Self>, nbt: &Tag) -> Result<(), ChunkError> {
+    let root = nbt.as_compound().ok_or(ChunkError::Field("root"))?;
+    if let Some(entities) = root.get("Entities") {
+      self.load_entities(entities.as_list().ok_or(ChunkError::Field("Entities"))?);
+    }
+    Ok(())
+  }
+
+  /// Parses a single section. Returns the index of the section within the
+  /// chunk, and the blocks in the section. If the section is empty, or if it
+  /// is outside the world, this returns `None`.
+  fn parse_section(
+    &self,
+    s: &Tag,
+    is_capital_names: bool,
+  ) -> Result<Option<(u32, SectionBlocks)>, ChunkError> {
+    let section = s.as_compound().ok_or(ChunkError::Field("section"))?;
+    let y = get(section, "Y", Tag::as_byte)? as i32;
+    // Vanilla stores sections above and below the world for lighting, so this
+    // isn't an error.
+    let idx = y - (self.min_y >> 4);
+    if idx < 0 || idx >= (self.height / 16) as i32 {
+      return Ok(None);
+    }
+    let blocks = if is_capital_names {
+      if section.contains_key("Blocks") {
+        // is 1.8
+        let blocks = get(section, "Blocks", Tag::as_byte_arr)?;
+        let data = get(section, "Data", Tag::as_byte_arr)?;
+        if blocks.len() != 4096 || data.len() != 2048 {
+          return Err(ChunkError::BlockData(y));
+        }
+        let ids = blocks
+          .iter()
+          .enumerate()
+          .map(|(i, &block)| {
+            let meta = (data[i / 2] >> ((i % 2) * 4)) & 0x0f;
+            let id = (block as u32) << 4 | meta as u32;
+            self.block_converter().to_latest(id, BlockVersion::V1_8)
+          })
+          .collect();
+        SectionBlocks::Each(ids)
+      } else {
+        // is 1.12+
+        // Skip air sections
+        if !section.contains_key("BlockStates") {
+          return Ok(None);
+        }
+        let block_states = get(section, "BlockStates", Tag::as_long_arr)?;
+        let palette = get(section, "Palette", Tag::as_list)?
+          .iter()
+          .map(|it| parse_state(self.block_converter(), it))
+          .collect::<Result<Vec<_>, _>>()?;
+        decode_section(y, palette, block_states)?
+      }
+    } else {
+      // is 1.18+
+      let block_states = get(section, "block_states", Tag::as_compound)?;
+      let palette = get(block_states, "palette", Tag::as_list)?
+        .iter()
+        .map(|it| parse_state(self.block_converter(), it))
+        .collect::<Result<Vec<_>, _>>()?;
+      match block_states.get("data") {
+        Some(data) => {
+          decode_section(y, palette, data.as_long_arr().ok_or(ChunkError::Field("data"))?)?
+        }
+        // The section will be full of one type
+        None if palette.len() == 1 => SectionBlocks::Single(palette[0]),
+        None => return Err(ChunkError::BlockData(y)),
+      }
+    };
+    Ok(Some((idx as u32, blocks)))
+  }
+
+  /// Parses the biomes for a whole chunk. Before 1.18, these are stored in
+  /// `Level.Biomes`, and in 1.18+ they are stored in each section.
+  fn parse_biomes(
+    &self,
+    level: &Compound,
+    is_capital_names: bool,
+  ) -> Result<BiomeChunk, ChunkError> {
+    let mut biomes = BiomeChunk::new();
+    let cells = self.height / 4;
+    if !is_capital_names {
+      // is 1.18+
+      for s in get(level, "sections", Tag::as_list)? {
+        let section = s.as_compound().ok_or(ChunkError::Field("section"))?;
+        let y = get(section, "Y", Tag::as_byte)? as i32;
+        let idx = y - (self.min_y >> 4);
+        if idx < 0 || idx >= (self.height / 16) as i32 {
+          continue;
+        }
+        let section_biomes = match section.get("biomes") {
+          Some(b) => b.as_compound().ok_or(ChunkError::Field("biomes"))?,
+          None => continue,
+        };
+        let palette = get(section_biomes, "palette", Tag::as_list)?
+          .iter()
+          .map(|it| {
+            let name = it.as_string().ok_or(ChunkError::Field("palette"))?;
+            Ok(biome::from_name(name).unwrap_or(biome::VOID))
+          })
+          .collect::<Result<Vec<_>, _>>()?;
+        let data: &[i64] = match section_biomes.get("data") {
+          Some(data) => data.as_long_arr().ok_or(ChunkError::Field("data"))?,
+          None => &[],
+        };
+        for (i, id) in decode_biomes(y, &palette, data)?.into_iter().enumerate() {
+          let (x, cy, z) = (i % 4, i / 16, (i / 4) % 4);
+          biomes.set(x as u8, idx as u32 * 4 + cy as u32, z as u8, id);
+        }
+      }
+    } else if let Some(tag) = level.get("Biomes") {
+      // 1.8-1.12 stores a byte per column, 1.13-1.14 stores an int per column, and
+      // 1.15-1.17 stores an int per 4x4x4 cell, starting at Y 0.
+      let ids: Vec<i32> = match tag {
+        Tag::ByteArr(arr) => arr.iter().map(|&v| v as i32).collect(),
+        Tag::IntArray(arr) => arr.clone(),
+        _ => return Err(ChunkError::Field("Biomes")),
+      };
+      let id = |v: i32| biome::from_legacy(v).unwrap_or(biome::VOID);
+      if ids.len() == 256 {
+        for cz in 0..4 {
+          for cx in 0..4 {
+            let b = id(ids[(cz * 4 + 2) * 16 + cx * 4 + 2]);
+            for cy in 0..cells {
+              biomes.set(cx as u8, cy, cz as u8, b);
             }
           }
         }
-      });
+      } else if !ids.is_empty() && ids.len() % 16 == 0 {
+        let offset = self.min_y / 4;
+        for (i, &v) in ids.iter().enumerate() {
+          let cy = (i / 16) as i32 - offset;
+          if cy < 0 || cy >= cells as i32 {
+            continue;
+          }
+          biomes.set((i % 4) as u8, cy as u32, ((i / 4) % 4) as u8, id(v));
+        }
+      } else {
+        return Err(ChunkError::Field("Biomes"));
+      }
+    }
+    Ok(biomes)
+  }
+
+  /// Loads a single block entity into the given chunk. The block must already
+  /// be placed in the chunk.
+  fn load_block_entity(&self, chunk: &mut MultiChunk, te: &Compound) -> Result<(), ChunkError> {
+    let x = get(te, "x", Tag::as_int)?;
+    let y = get(te, "y", Tag::as_int)?;
+    let z = get(te, "z", Tag::as_int)?;
+    let pos = RelPos::new((x & 15) as u8, y, (z & 15) as u8);
+    let kind = chunk.get_kind(pos).map_err(|_| ChunkError::Field("y"))?;
+    let te = self.world_manager().block_behaviors().call(kind, |b| b.load_te_nbt(te)).flatten();
+    if let Some(te) = te {
+      let pos = chunk.transform_pos(pos).unwrap();
+      chunk.tes_mut().insert(pos, te);
     }
     Ok(())
   }
+
+  /// Summons all the given entities. Entities that Bamboo doesn't know about
+  /// are skipped.
+  fn load_entities(self: &Arc<Self>, entities: &[Tag]) {
+    for ent in entities.iter().filter_map(Tag::as_compound) {
+      let id = match ent.get("id").and_then(Tag::as_string) {
+        Some(id) => id,
+        None => continue,
+      };
+      let ty = match entity::Type::from_str(id.strip_prefix("minecraft:").unwrap_or(id)) {
+        Ok(ty) => ty,
+        Err(_) => {
+          debug!("skipping unknown entity {id}");
+          continue;
+        }
+      };
+      let pos = ent
+        .get("Pos")
+        .and_then(Tag::as_list)
+        .map(|pos| pos.iter().filter_map(Tag::as_double).collect::<Vec<_>>());
+      match pos.as_deref() {
+        Some(&[x, y, z]) => {
+          self.summon(ty, FPos::new(x, y, z));
+        }
+        _ => continue,
+      }
+    }
+  }
 }
 
-fn parse_state(conv: &block::TypeConverter, item: &Tag) -> u32 {
-  let item = item.unwrap_compound();
-  let name = item["Name"].unwrap_string();
-  let name = name.strip_prefix("minecraft:").unwrap();
-  let mut ty = conv.get(block::Kind::from_str(name).unwrap()).default_type();
+/// Reads the NBT data for a chunk. `chunk` is the chunk's data in the region
+/// file, starting with the 5 byte header.
+fn read_chunk(chunk: &[u8]) -> Result<Tag, ChunkError> {
+  if chunk.len() < 5 {
+    return Err(ChunkError::Header("chunk is too short"));
+  }
+  // The length includes the compression byte.
+  let len = u32::from_be_bytes(chunk[..4].try_into().unwrap()) as usize;
+  let compression = chunk[4];
+  if len == 0 || 4 + len > chunk.len() {
+    return Err(ChunkError::Header("invalid length"));
+  }
+  if compression & 0x80 != 0 {
+    return Err(ChunkError::Header("chunk is stored in a separate file, which is not supported"));
+  }
+  NBT::deserialize_file(chunk[5..4 + len].to_vec()).map(NBT::into_tag).map_err(ChunkError::Nbt)
+}
 
-  if item.contains_key("Properties") {
-    let props = item["Properties"].unwrap_compound();
+/// Decodes the block states for a section. If the palette indices don't match
+/// the palette, this returns an error instead of creating an invalid section.
+fn decode_section(y: i32, palette: Vec<u32>, data: &[i64]) -> Result<SectionBlocks, ChunkError> {
+  if palette.is_empty() {
+    return Err(ChunkError::BlockData(y));
+  }
+  // Vanilla never uses less than 4 bits per block.
+  let bpe = ((usize::BITS - (palette.len() - 1).leading_zeros()) as usize).max(4);
+  let mask = (1_u64 << bpe) - 1;
+  let data: Vec<u64> = data.iter().map(|v| *v as u64).collect();
+
+  // In 1.16+, entries never span across two longs. Before that, entries were
+  // packed together, and could be split between two longs. We can tell which
+  // format is used from the length of the data.
+  let epl = 64 / bpe;
+  let ids: Vec<u64> = if data.len() == (4096 + epl - 1) / epl {
+    (0..4096).map(|i| (data[i / epl] >> ((i % epl) * bpe)) & mask).collect()
+  } else if data.len() == 4096 * bpe / 64 {
+    (0..4096)
+      .map(|i| {
+        let bit = i * bpe;
+        let (idx, shift) = (bit / 64, bit % 64);
+        let mut v = data[idx] >> shift;
+        if shift + bpe > 64 {
+          v |= data[idx + 1] << (64 - shift);
+        }
+        v & mask
+      })
+      .collect()
+  } else {
+    return Err(ChunkError::BlockData(y));
+  };
+  if ids.iter().any(|&id| id as usize >= palette.len()) {
+    return Err(ChunkError::BlockData(y));
+  }
+
+  // Our sections use the same layout as 1.16+, as long as the palette isn't too
+  // large. So, in that case, we can copy the data directly.
+  if palette.len() <= 256 && data.len() == (4096 + epl - 1) / epl {
+    Ok(SectionBlocks::Paletted(palette, data))
+  } else {
+    Ok(SectionBlocks::Each(ids.into_iter().map(|id| palette[id as usize]).collect()))
+  }
+}
+
+/// Decodes the 64 biomes in a 1.18+ section. Unlike block states, biomes have
+/// no minimum number of bits, and a single entry palette has no data at all.
+fn decode_biomes(y: i32, palette: &[u8], data: &[i64]) -> Result<Vec<u8>, ChunkError> {
+  if palette.is_empty() {
+    return Err(ChunkError::BiomeData(y));
+  }
+  if palette.len() == 1 {
+    return Ok(vec![palette[0]; 64]);
+  }
+  let bits = (usize::BITS - (palette.len() - 1).leading_zeros()) as usize;
+  let mask = (1_u64 << bits) - 1;
+  let epl = 64 / bits;
+  if data.len() != (64 + epl - 1) / epl {
+    return Err(ChunkError::BiomeData(y));
+  }
+  (0..64)
+    .map(|i| {
+      let idx = (data[i / epl] as u64 >> ((i % epl) * bits)) & mask;
+      palette.get(idx as usize).copied().ok_or(ChunkError::BiomeData(y))
+    })
+    .collect()
+}
+
+/// Parses a block state from a palette entry. If the block doesn't exist in
+/// this version, it will be replaced with air.
+fn parse_state(conv: &block::TypeConverter, item: &Tag) -> Result<u32, ChunkError> {
+  let item = item.as_compound().ok_or(ChunkError::Field("Palette"))?;
+  let name = get(item, "Name", Tag::as_string)?;
+  let kind = match block::Kind::from_str(name.strip_prefix("minecraft:").unwrap_or(name)) {
+    Ok(kind) => kind,
+    Err(_) => {
+      warn!("unknown block {name}, replacing with air");
+      block::Kind::Air
+    }
+  };
+  let mut ty = conv.get(kind).default_type();
+
+  if let Some(props) = item.get("Properties").and_then(Tag::as_compound) {
     for (key, val) in props {
-      match val {
-        Tag::String(v) if v == "true" => ty.set_prop(key, true),
-        Tag::String(v) if v == "false" => ty.set_prop(key, false),
-        Tag::String(v) if v.parse::<u32>().is_ok() => ty.set_prop(key, v.parse::<u32>().unwrap()),
-        Tag::String(v) => ty.set_prop(key, v.to_uppercase().as_str()),
-        _ => unreachable!(),
+      // Properties that don't exist in this version are ignored.
+      let valid = match val.as_string() {
+        Some(v) => ty.try_set_prop_str(key, v),
+        None => false,
+      };
+      if !valid {
+        debug!("ignoring invalid property {key}={val:?} on {name}");
+      }
+    }
+  }
+
+  Ok(ty.id())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Packs the given palette indices in the 1.16+ format, where entries never
+  /// span two longs.
+  fn pack_padded(ids: &[u64], bpe: usize) -> Vec<i64> {
+    let epl = 64 / bpe;
+    let mut data = vec![0_u64; (ids.len() + epl - 1) / epl];
+    for (i, &id) in ids.iter().enumerate() {
+      data[i / epl] |= id << ((i % epl) * bpe);
+    }
+    data.into_iter().map(|v| v as i64).collect()
+  }
+  /// Packs the given palette indices in the pre-1.16 format, where entries are
+  /// packed together, and can span two longs.
+  fn pack_tight(ids: &[u64], bpe: usize) -> Vec<i64> {
+    let mut data = vec![0_u64; ids.len() * bpe / 64];
+    for (i, &id) in ids.iter().enumerate() {
+      let bit = i * bpe;
+      let (idx, shift) = (bit / 64, bit % 64);
+      data[idx] |= id << shift;
+      if shift + bpe > 64 {
+        data[idx + 1] |= id >> (64 - shift);
       }
     }
+    data.into_iter().map(|v| v as i64).collect()
   }
+  /// Returns the block id at every position in the section.
+  pub(super) fn expand(blocks: SectionBlocks) -> Vec<u32> {
+    match blocks {
+      SectionBlocks::Single(id) => vec![id; 4096],
+      SectionBlocks::Each(ids) => ids,
+      SectionBlocks::Paletted(palette, data) => {
+        let bpe = ((usize::BITS - (palette.len() - 1).leading_zeros()) as usize).max(4);
+        let epl = 64 / bpe;
+        (0..4096)
+          .map(|i| palette[((data[i / epl] >> ((i % epl) * bpe)) & ((1 << bpe) - 1)) as usize])
+          .collect()
+      }
+    }
+  }
+
+  #[test]
+  fn decode() {
+    // 20 entries needs 5 bits per block, which doesn't divide 64 evenly.
+    let palette: Vec<u32> = (100..120).collect();
+    let ids: Vec<u64> = (0..4096).map(|i| (i * 7 % 20) as u64).collect();
+    let expected: Vec<u32> = ids.iter().map(|&id| palette[id as usize]).collect();
+
+    let padded = pack_padded(&ids, 5);
+    assert_eq!(padded.len(), 342);
+    let blocks = decode_section(0, palette.clone(), &padded).unwrap();
+    assert!(matches!(blocks, SectionBlocks::Paletted(..)));
+    assert_eq!(expand(blocks), expected);
+
+    let tight = pack_tight(&ids, 5);
+    assert_eq!(tight.len(), 320);
+    let blocks = decode_section(0, palette.clone(), &tight).unwrap();
+    assert_eq!(expand(blocks), expected);
 
-  ty.id()
+    // Small palettes still use 4 bits per block.
+    let ids: Vec<u64> = (0..4096).map(|i| (i % 3) as u64).collect();
+    let blocks = decode_section(0, vec![1, 2, 3], &pack_padded(&ids, 4)).unwrap();
+    assert_eq!(expand(blocks), ids.iter().map(|&id| id as u32 + 1).collect::<Vec<_>>());
+  }
+
+  #[test]
+  fn decode_invalid() {
+    assert!(decode_section(0, vec![], &[0; 256]).is_err());
+    // The wrong number of longs.
+    assert!(decode_section(0, vec![1, 2], &[0; 100]).is_err());
+    // An index past the end of the palette.
+    let ids = vec![5_u64; 4096];
+    assert!(decode_section(0, vec![1, 2], &pack_padded(&ids, 4)).is_err());
+  }
 }
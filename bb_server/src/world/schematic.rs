@@ -1,126 +1,719 @@
-use super::CountedChunk;
+//! Reads and writes schematic files. This supports Sponge schematics (`.schem`,
+//! versions 1 through 3), and legacy MCEdit schematics (`.schematic`). Legacy
+//! schematics can only be read, as they can only store pre-1.13 blocks.
+//!
+//! Schematics are written as Sponge version 2, as that is what most tools
+//! support.
+
+use super::World;
 use crate::block;
 use bb_common::{
-  math::{ChunkPos, Pos},
-  nbt::{ParseError, NBT},
+  flate2::{write::GzEncoder, Compression},
+  math::{ChunkPos, Pos, PosError, RelPos},
+  nbt::{ParseError, Tag, NBT},
   version::BlockVersion,
 };
-use std::{collections::HashMap, fs, fs::File, io::Read, str::FromStr};
-
-pub fn load_from_file(
-  chunks: &mut HashMap<ChunkPos, CountedChunk>,
-  path: &str,
-  types: &block::TypeConverter,
-  new_func: impl Fn() -> CountedChunk + Copy,
-) -> Result<(), ParseError> {
-  let mut f = File::open(path).expect("no file found");
-  let metadata = fs::metadata(path).expect("unable to read metadata");
-  let mut buf = vec![0; metadata.len() as usize];
-  f.read_exact(&mut buf).expect("file was too large");
-
-  let tag = NBT::deserialize_file(buf)?;
-  let compound = tag.compound();
-  // dbg!(&compound.keys());
-  //
-  // dbg!(&compound["Width"]);
-  // dbg!(&compound["Length"]);
-  // dbg!(&compound["Height"]);
-  //
-  // dbg!(&compound["WEOriginX"]);
-  // dbg!(&compound["WEOriginY"]);
-  // dbg!(&compound["WEOriginZ"]);
-  //
-  // dbg!(&compound["WEOffsetX"]);
-  // dbg!(&compound["WEOffsetY"]);
-  // dbg!(&compound["WEOffsetZ"]);
-  //
-  // dbg!(&compound["TileEntities"]);
-  // dbg!(&compound["Entities"]);
-  //
-  // dbg!(&compound["Materials"]);
-  // dbg!(&compound["Platform"]);
-  // dbg!(&compound["Data"]);
-  // dbg!(&compound["Blocks"]);
-
-  let width: usize = compound["Width"].unwrap_short().try_into().unwrap();
-  let length: usize = compound["Length"].unwrap_short().try_into().unwrap();
-  let height: usize = compound["Height"].unwrap_short().try_into().unwrap();
-
-  let material = tag.compound()["Materials"].unwrap_string();
-  match material {
-    "Alpha" => {
-      if compound.contains_key("SchematicaMapping") {
-        let names: HashMap<i16, String> = compound["SchematicaMapping"]
-          .unwrap_compound()
-          .iter()
-          .map(|(name, val)| {
-            (
-              val.unwrap_short(),
-              convert_alpha_name(name.strip_prefix("minecraft:").unwrap().into()),
-            )
-          })
-          .collect();
-        let blocks = tag.compound()["Blocks"].unwrap_byte_arr().to_vec();
-        // TODO: `data` should be used! It should look something like this:
-        // ```
-        // let name = names[blocks[i]];
-        // let v1_8_id = id_from_name(name) << 4 | data[i];
-        // let new_id = convert_id(v1_8_id, ProtocolVersion::V1_8);
-        // ```
-        //
-        // let data = tag.compound()["Data"].unwrap_byte_arr().to_vec();
-        for y in 0..height {
-          for z in 0..length {
-            for x in 0..width {
-              let i = ((y * length + z) * width) + x;
-              let bid = blocks[i] as i16;
-              let pos = Pos::new(x as i32, y as i32, z as i32);
-              let name = &names[&bid];
-              chunks
-                .entry(pos.chunk())
-                .or_insert_with(new_func)
-                .chunk
-                .lock()
-                .set_kind(pos.chunk_rel(), block::Kind::from_str(name).unwrap())
-                .unwrap();
+use std::{
+  collections::HashMap, error::Error, fmt, fs, fs::File, io, io::Write, path::Path, str::FromStr,
+};
+
+type Compound = HashMap<String, Tag>;
+
+/// The `DataVersion` for the latest block version. This is stored in saved
+/// schematics, so that other tools know which version the blocks are from.
+const DATA_VERSION: i32 = 3105;
+
+#[derive(Debug)]
+pub enum SchematicError {
+  Io(io::Error),
+  Nbt(ParseError),
+  /// The given NBT field was missing, or it had the wrong type.
+  Field(&'static str),
+  /// The schematic has a version we don't know how to read.
+  Version(i32),
+  /// The block data doesn't match the size of the schematic, or it contains
+  /// an invalid palette index.
+  BlockData,
+  /// The schematic has too many blocks to be loaded.
+  TooLarge,
+}
+
+impl fmt::Display for SchematicError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Self::Io(e) => write!(f, "{e}"),
+      Self::Nbt(e) => write!(f, "invalid nbt: {e}"),
+      Self::Field(name) => write!(f, "missing or invalid field `{name}`"),
+      Self::Version(v) => write!(f, "unsupported schematic version {v}"),
+      Self::BlockData => write!(f, "invalid block data"),
+      Self::TooLarge => write!(f, "schematic is too large"),
+    }
+  }
+}
+
+impl Error for SchematicError {}
+
+impl From<io::Error> for SchematicError {
+  fn from(e: io::Error) -> Self { SchematicError::Io(e) }
+}
+impl From<ParseError> for SchematicError {
+  fn from(e: ParseError) -> Self { SchematicError::Nbt(e) }
+}
+
+/// Returns the number of blocks in a schematic of the given size. Blocks are
+/// indexed with a `u32`, so any schematic with more blocks than that is
+/// rejected as well.
+fn volume(width: u32, height: u32, length: u32) -> Result<usize, SchematicError> {
+  (width as usize)
+    .checked_mul(height as usize)
+    .and_then(|v| v.checked_mul(length as usize))
+    .filter(|&v| u32::try_from(v).is_ok())
+    .ok_or(SchematicError::TooLarge)
+}
+
+fn get<'a, T>(
+  compound: &'a Compound,
+  key: &'static str,
+  f: impl FnOnce(&'a Tag) -> Option<T>,
+) -> Result<T, SchematicError> {
+  compound.get(key).and_then(f).ok_or(SchematicError::Field(key))
+}
+
+/// A cuboid of blocks, which can be pasted into a world. All blocks are stored
+/// as latest block ids.
+#[derive(Debug, Clone)]
+pub struct Schematic {
+  width:  u32,
+  height: u32,
+  length: u32,
+  /// The position of the minimum corner, relative to where this schematic was
+  /// copied from. This is added to the paste position.
+  offset: Pos,
+  /// The block ids, indexed by `x + z * width + y * width * length`.
+  blocks: Vec<u32>,
+  /// The vanilla NBT data for each block entity, relative to the minimum
+  /// corner. This includes the `id` of the block entity, but not the position.
+  tes:    HashMap<Pos, Compound>,
+}
+
+/// A rotation around the Y axis, looking down from above.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rotation {
+  None,
+  Clockwise90,
+  Clockwise180,
+  Clockwise270,
+}
+
+/// Flips a schematic along an axis. `X` flips the X coordinate, so east and
+/// west are swapped. `Z` flips the Z coordinate, so north and south are
+/// swapped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mirror {
+  None,
+  X,
+  Z,
+}
+
+/// Options for [`World::paste_schematic`]. Mirroring is applied before
+/// rotating.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PasteOptions {
+  pub rotation: Rotation,
+  pub mirror:   Mirror,
+  /// If set, air in the schematic will not replace blocks in the world.
+  pub skip_air: bool,
+}
+
+impl Default for PasteOptions {
+  fn default() -> Self {
+    PasteOptions { rotation: Rotation::None, mirror: Mirror::None, skip_air: false }
+  }
+}
+
+impl Rotation {
+  /// Returns the rotation for the given number of degrees clockwise. Returns
+  /// `None` if the angle is not a multiple of 90.
+  pub fn from_degrees(degrees: i32) -> Option<Rotation> {
+    match degrees.rem_euclid(360) {
+      0 => Some(Rotation::None),
+      90 => Some(Rotation::Clockwise90),
+      180 => Some(Rotation::Clockwise180),
+      270 => Some(Rotation::Clockwise270),
+      _ => None,
+    }
+  }
+
+  /// The number of clockwise quarter turns.
+  fn turns(&self) -> u32 {
+    match self {
+      Rotation::None => 0,
+      Rotation::Clockwise90 => 1,
+      Rotation::Clockwise180 => 2,
+      Rotation::Clockwise270 => 3,
+    }
+  }
+}
+
+impl PasteOptions {
+  /// Transforms a position relative to the paste position.
+  fn transform_pos(&self, p: Pos) -> Pos {
+    let (mut x, mut z) = (p.x, p.z);
+    match self.mirror {
+      Mirror::None => {}
+      Mirror::X => x = -x,
+      Mirror::Z => z = -z,
+    }
+    for _ in 0..self.rotation.turns() {
+      (x, z) = (-z, x);
+    }
+    Pos::new(x, p.y, z)
+  }
+
+  /// Transforms a horizontal direction, like `north`. Returns `None` if this
+  /// isn't a horizontal direction.
+  fn transform_dir(&self, dir: &str) -> Option<&'static str> {
+    const DIRS: [&str; 4] = ["north", "east", "south", "west"];
+    let mut i = DIRS.iter().position(|d| *d == dir)?;
+    match self.mirror {
+      Mirror::None => {}
+      Mirror::X if i % 2 == 1 => i = 4 - i,
+      Mirror::Z if i % 2 == 0 => i = 2 - i,
+      _ => {}
+    }
+    Some(DIRS[(i + self.rotation.turns() as usize) % 4])
+  }
+
+  /// Rotates and mirrors the properties of a block, so that stairs, logs, and
+  /// the like face the right way after pasting.
+  fn transform_block<'a>(&self, mut ty: block::Type<'a>) -> block::Type<'a> {
+    if self.rotation == Rotation::None && self.mirror == Mirror::None {
+      return ty;
+    }
+    let mirrored = self.mirror != Mirror::None;
+    // We read from the original properties, so that swapping properties like
+    // `north` and `south` doesn't overwrite one of them.
+    for (key, val) in ty.props() {
+      match key.as_str() {
+        "facing" => {
+          if let Some(dir) = self.transform_dir(&val) {
+            ty.try_set_prop_str(&key, dir);
+          }
+        }
+        "axis" if self.rotation.turns() % 2 == 1 => {
+          let axis = match val.as_str() {
+            "x" => "z",
+            "z" => "x",
+            v => v,
+          };
+          ty.try_set_prop_str(&key, axis);
+        }
+        // Signs and banners, where 0 is south, and each step is 1/16 of a turn
+        // clockwise.
+        "rotation" => {
+          if let Ok(mut r) = val.parse::<u32>() {
+            match self.mirror {
+              Mirror::None => {}
+              Mirror::X => r = (16 - r) % 16,
+              Mirror::Z => r = (24 - r) % 16,
             }
+            r = (r + self.rotation.turns() * 4) % 16;
+            ty.try_set_prop_str(&key, &r.to_string());
           }
         }
-      } else {
-        // World edit
-        let blocks = tag.compound()["Blocks"].unwrap_byte_arr().to_vec();
-        // let data = tag.compound()["Data"].unwrap_byte_arr().to_vec();
-        for y in 0..height {
-          for z in 0..length {
-            for x in 0..width {
-              let i = ((y * length + z) * width) + x;
-              let bid = (blocks[i] as u32) << 4;
-              if bid == 0 {
-                continue;
-              }
-              let pos = Pos::new(x as i32, y as i32, z as i32);
-              chunks
-                .entry(pos.chunk())
-                .or_insert_with(new_func)
-                .chunk
-                .lock()
-                .set_kind(pos.chunk_rel(), types.kind_from_id(bid, BlockVersion::V1_12))
-                .unwrap();
+        // Fences, walls, glass panes, etc.
+        "north" | "east" | "south" | "west" => {
+          ty.try_set_prop_str(self.transform_dir(&key).unwrap(), &val);
+        }
+        // Doors and chests.
+        "hinge" | "type" if mirrored => {
+          let swapped = match val.as_str() {
+            "left" => "right",
+            "right" => "left",
+            v => v,
+          };
+          ty.try_set_prop_str(&key, swapped);
+        }
+        "shape" => {
+          if val.contains("left") || val.contains("right") {
+            // Stairs
+            if mirrored {
+              let swapped = if val.contains("left") {
+                val.replace("left", "right")
+              } else {
+                val.replace("right", "left")
+              };
+              ty.try_set_prop_str(&key, &swapped);
+            }
+          } else {
+            // Rails, like `north_east` or `ascending_south`. Vanilla only
+            // has one order for each corner, so we try both.
+            let words: Vec<&str> =
+              val.split('_').map(|w| self.transform_dir(w).unwrap_or(w)).collect();
+            if !ty.try_set_prop_str(&key, &words.join("_")) {
+              let reversed: Vec<&str> = words.into_iter().rev().collect();
+              ty.try_set_prop_str(&key, &reversed.join("_"));
             }
           }
         }
+        _ => {}
+      }
+    }
+    ty
+  }
+}
+
+impl Schematic {
+  /// Reads a schematic from the given file. This can be a Sponge schematic or
+  /// a legacy MCEdit schematic.
+  pub fn load(path: &Path, conv: &block::TypeConverter) -> Result<Schematic, SchematicError> {
+    let nbt = NBT::deserialize_file(fs::read(path)?)?.into_tag();
+    Schematic::from_nbt(nbt.as_compound().ok_or(SchematicError::Field("root"))?, conv)
+  }
+
+  /// Writes this schematic to the given file, as a gzipped Sponge schematic.
+  pub fn save(&self, path: &Path, conv: &block::TypeConverter) -> io::Result<()> {
+    let mut encoder = GzEncoder::new(File::create(path)?, Compression::default());
+    encoder.write_all(&self.to_nbt(conv).serialize())?;
+    encoder.finish()?;
+    Ok(())
+  }
+
+  /// Parses a schematic from the root NBT compound.
+  pub fn from_nbt(
+    root: &Compound,
+    conv: &block::TypeConverter,
+  ) -> Result<Schematic, SchematicError> {
+    // Version 3 wraps everything in a `Schematic` compound.
+    let root = match root.get("Schematic").and_then(Tag::as_compound) {
+      Some(inner) => inner,
+      None => root,
+    };
+    if root.contains_key("Materials") {
+      return Schematic::from_legacy(root, conv);
+    }
+    let width = get(root, "Width", Tag::as_short)? as u16 as u32;
+    let height = get(root, "Height", Tag::as_short)? as u16 as u32;
+    let length = get(root, "Length", Tag::as_short)? as u16 as u32;
+    let offset = match root.get("Offset").and_then(Tag::as_int_arr).map(Vec::as_slice) {
+      Some(&[x, y, z]) => Pos::new(x, y, z),
+      _ => Pos::new(0, 0, 0),
+    };
+
+    let version = get(root, "Version", Tag::as_int)?;
+    let (container, palette_key, data_key, tes_key) = match version {
+      1 => (root, "Palette", "BlockData", "TileEntities"),
+      2 => (root, "Palette", "BlockData", "BlockEntities"),
+      3 => (get(root, "Blocks", Tag::as_compound)?, "Palette", "Data", "BlockEntities"),
+      v => return Err(SchematicError::Version(v)),
+    };
+
+    let mut palette = vec![];
+    for (name, id) in get(container, palette_key, Tag::as_compound)? {
+      let id = id.as_int().ok_or(SchematicError::Field("Palette"))?;
+      let id = usize::try_from(id).map_err(|_| SchematicError::Field("Palette"))?;
+      if palette.len() <= id {
+        palette.resize(id + 1, 0);
+      }
+      palette[id] = parse_block(conv, name);
+    }
+
+    let volume = volume(width, height, length)?;
+    let mut data = get(container, data_key, Tag::as_byte_arr)?;
+    // Every block takes at least one byte, so this avoids allocating a huge
+    // buffer for a schematic with an invalid size.
+    let mut blocks = Vec::with_capacity(volume.min(data.len()));
+    while !data.is_empty() {
+      let idx = read_varint(&mut data).ok_or(SchematicError::BlockData)?;
+      blocks.push(*palette.get(idx as usize).ok_or(SchematicError::BlockData)?);
+    }
+    if blocks.len() != volume {
+      return Err(SchematicError::BlockData);
+    }
+
+    let mut tes = HashMap::new();
+    let list = container.get(tes_key).and_then(Tag::as_list).map(Vec::as_slice).unwrap_or_default();
+    for te in list.iter().filter_map(Tag::as_compound) {
+      let pos = match te.get("Pos").and_then(Tag::as_int_arr).map(Vec::as_slice) {
+        Some(&[x, y, z]) => Pos::new(x, y, z),
+        _ => continue,
+      };
+      // Version 3 stores the data in a separate compound. We store it flat, in
+      // the same format as vanilla.
+      let mut nbt = match te.get("Data").and_then(Tag::as_compound) {
+        Some(data) => data.clone(),
+        None => te.clone(),
+      };
+      nbt.remove("Pos");
+      nbt.remove("Id");
+      if let Some(id) = te.get("Id") {
+        nbt.insert("id".into(), id.clone());
+      }
+      tes.insert(pos, nbt);
+    }
+
+    Ok(Schematic { width, height, length, offset, blocks, tes })
+  }
+
+  /// Parses a legacy MCEdit schematic. These store a block id and data value
+  /// for each block, which are from 1.12.
+  fn from_legacy(
+    root: &Compound,
+    conv: &block::TypeConverter,
+  ) -> Result<Schematic, SchematicError> {
+    let material = get(root, "Materials", Tag::as_string)?;
+    if material != "Alpha" {
+      return Err(SchematicError::Field("Materials"));
+    }
+    let width = get(root, "Width", Tag::as_short)? as u16 as u32;
+    let height = get(root, "Height", Tag::as_short)? as u16 as u32;
+    let length = get(root, "Length", Tag::as_short)? as u16 as u32;
+    let volume = volume(width, height, length)?;
+    let ids = get(root, "Blocks", Tag::as_byte_arr)?;
+    let data = get(root, "Data", Tag::as_byte_arr)?;
+    // Block ids above 255 store their upper 4 bits here, packed two blocks to a
+    // byte.
+    let add = root.get("AddBlocks").and_then(Tag::as_byte_arr);
+    if ids.len() != volume
+      || data.len() != volume
+      || add.map(|add| add.len() < (volume + 1) / 2).unwrap_or(false)
+    {
+      return Err(SchematicError::BlockData);
+    }
+    // Schematica also stores a `SchematicaMapping`, which is only needed for
+    // modded blocks. Vanilla blocks always use their vanilla ids, so we ignore
+    // it.
+    let blocks = (0..volume)
+      .map(|i| {
+        let mut id = ids[i] as u32;
+        if let Some(add) = add {
+          let shift = if i % 2 == 0 { 4 } else { 0 };
+          id |= (((add[i / 2] >> shift) & 0x0f) as u32) << 8;
+        }
+        conv.to_latest(id << 4 | (data[i] & 0x0f) as u32, BlockVersion::V1_12)
+      })
+      .collect();
+    let offset = Pos::new(
+      root.get("WEOffsetX").and_then(Tag::as_int).unwrap_or(0),
+      root.get("WEOffsetY").and_then(Tag::as_int).unwrap_or(0),
+      root.get("WEOffsetZ").and_then(Tag::as_int).unwrap_or(0),
+    );
+
+    let mut tes = HashMap::new();
+    let list =
+      root.get("TileEntities").and_then(Tag::as_list).map(Vec::as_slice).unwrap_or_default();
+    for te in list.iter().filter_map(Tag::as_compound) {
+      let pos = match (
+        te.get("x").and_then(Tag::as_int),
+        te.get("y").and_then(Tag::as_int),
+        te.get("z").and_then(Tag::as_int),
+      ) {
+        (Some(x), Some(y), Some(z)) => Pos::new(x, y, z),
+        _ => continue,
+      };
+      let mut nbt = te.clone();
+      nbt.remove("x");
+      nbt.remove("y");
+      nbt.remove("z");
+      tes.insert(pos, nbt);
+    }
+
+    Ok(Schematic { width, height, length, offset, blocks, tes })
+  }
+
+  /// Converts this schematic to a Sponge version 2 schematic.
+  pub fn to_nbt(&self, conv: &block::TypeConverter) -> NBT {
+    let mut palette = HashMap::new();
+    let mut data = vec![];
+    for &id in &self.blocks {
+      let len = palette.len() as i32;
+      let idx = *palette.entry(id).or_insert(len);
+      write_varint(&mut data, idx as u32);
+    }
+    let palette_max = palette.len() as i32;
+    let palette: Compound = palette
+      .into_iter()
+      .map(|(id, idx)| {
+        let ty = conv.type_from_id(id, BlockVersion::latest());
+        (format!("minecraft:{ty}"), Tag::Int(idx))
+      })
+      .collect();
+    let tes = self
+      .tes
+      .iter()
+      .map(|(pos, nbt)| {
+        let mut nbt = nbt.clone();
+        if let Some(id) = nbt.remove("id") {
+          nbt.insert("Id".into(), id);
+        }
+        nbt.insert("Pos".into(), Tag::IntArray(vec![pos.x, pos.y, pos.z]));
+        Tag::Compound(nbt)
+      })
+      .collect();
+    NBT::new(
+      "Schematic",
+      Tag::compound(&[
+        ("Version", Tag::Int(2)),
+        ("DataVersion", Tag::Int(DATA_VERSION)),
+        ("Width", Tag::Short(self.width as i16)),
+        ("Height", Tag::Short(self.height as i16)),
+        ("Length", Tag::Short(self.length as i16)),
+        ("Offset", Tag::IntArray(vec![self.offset.x, self.offset.y, self.offset.z])),
+        ("PaletteMax", Tag::Int(palette_max)),
+        ("Palette", Tag::Compound(palette)),
+        ("BlockData", Tag::ByteArr(data)),
+        ("BlockEntities", Tag::List(tes)),
+      ]),
+    )
+  }
+
+  /// Returns the size of this schematic, as `(width, height, length)`. These
+  /// are the sizes along the X, Y, and Z axis.
+  pub fn size(&self) -> (u32, u32, u32) { (self.width, self.height, self.length) }
+
+  /// Returns the block id at the given position, relative to the minimum
+  /// corner. Returns `None` if the position is outside this schematic.
+  pub fn get(&self, pos: Pos) -> Option<u32> {
+    if pos.x < 0 || pos.y < 0 || pos.z < 0 {
+      return None;
+    }
+    let (x, y, z) = (pos.x as u32, pos.y as u32, pos.z as u32);
+    if x >= self.width || y >= self.height || z >= self.length {
+      return None;
+    }
+    Some(self.blocks[(x + z * self.width + y * self.width * self.length) as usize])
+  }
+}
+
+impl World {
+  /// Pastes the given schematic into the world. The schematic's offset is added
+  /// to `pos`, so that schematics saved by WorldEdit are pasted relative to
+  /// where they were copied from. Rotating and mirroring is done around `pos`.
+  ///
+  /// This will return an error if any part of the schematic is outside the
+  /// world. In that case, no blocks will be changed.
+  pub fn paste_schematic(
+    &self,
+    schem: &Schematic,
+    pos: Pos,
+    opts: PasteOptions,
+  ) -> Result<(), PosError> {
+    let corner = Pos::new(schem.width as i32 - 1, schem.height as i32 - 1, schem.length as i32 - 1);
+    let min = pos + opts.transform_pos(schem.offset);
+    let max = pos + opts.transform_pos(schem.offset + corner);
+    self.check_pos(min)?;
+    self.check_pos(max)?;
+
+    // Blocks and block entities, grouped by chunk.
+    let mut chunks: HashMap<ChunkPos, (Vec<(RelPos, u32)>, Vec<(RelPos, &Compound)>)> =
+      HashMap::new();
+    for y in 0..schem.height as i32 {
+      for z in 0..schem.length as i32 {
+        for x in 0..schem.width as i32 {
+          let rel = Pos::new(x, y, z);
+          let id = schem.get(rel).unwrap();
+          if opts.skip_air && id == 0 {
+            continue;
+          }
+          let p = pos + opts.transform_pos(schem.offset + rel);
+          chunks.entry(p.chunk()).or_default().0.push((p.chunk_rel(), id));
+        }
+      }
+    }
+    for (rel, nbt) in &schem.tes {
+      let p = pos + opts.transform_pos(schem.offset + *rel);
+      chunks.entry(p.chunk()).or_default().1.push((p.chunk_rel(), nbt));
+    }
+
+    for (chunk_pos, (blocks, tes)) in chunks {
+      let mut min_y = i32::MAX;
+      let mut max_y = i32::MIN;
+      self.chunk(chunk_pos, |mut c| {
+        for (rel, id) in blocks {
+          min_y = min_y.min(rel.y());
+          max_y = max_y.max(rel.y());
+          let ty = self.block_converter.type_from_id(id, BlockVersion::latest());
+          c.set_type(rel, opts.transform_block(ty))?;
+        }
+        for (rel, nbt) in tes {
+          let kind = c.get_kind(rel)?;
+          let te =
+            self.world_manager().block_behaviors().call(kind, |b| b.load_te_nbt(nbt)).flatten();
+          if let Some(te) = te {
+            let rel = c.transform_pos(rel)?;
+            c.tes_mut().insert(rel, te);
+          }
+        }
+        Ok(())
+      })?;
+      if min_y > max_y {
+        continue;
       }
+
+      let min_section = ((min_y - self.min_y) / 16) as u32;
+      let max_section = ((max_y - self.min_y) / 16) as u32;
+      let serialized = self.serialize_partial_chunk(chunk_pos, min_section, max_section);
+      for p in self.players().iter().in_view(chunk_pos) {
+        p.send(serialized.clone());
+      }
+    }
+    Ok(())
+  }
+
+  /// Copies the blocks between `min` and `max` (inclusive) into a schematic.
+  /// The returned schematic has no offset, so it will be pasted with `min` at
+  /// the paste position.
+  pub fn save_schematic(&self, min: Pos, max: Pos) -> Result<Schematic, PosError> {
+    let (min, max) = Pos::min_max(min, max);
+    self.check_pos(min)?;
+    self.check_pos(max)?;
+    let size = max - min + Pos::new(1, 1, 1);
+    let mut schem = Schematic {
+      width:  size.x as u32,
+      height: size.y as u32,
+      length: size.z as u32,
+      offset: Pos::new(0, 0, 0),
+      blocks: Vec::with_capacity((size.x * size.y * size.z) as usize),
+      tes:    HashMap::new(),
+    };
+    for y in 0..size.y {
+      for z in 0..size.z {
+        for x in 0..size.x {
+          let id = self.get_block(min + Pos::new(x, y, z))?.id();
+          schem.blocks.push(self.block_converter.custom_to_vanilla(id));
+        }
+      }
+    }
+
+    for x in min.chunk_x()..=max.chunk_x() {
+      for z in min.chunk_z()..=max.chunk_z() {
+        let chunk_pos = ChunkPos::new(x, z);
+        self.chunk(chunk_pos, |c| {
+          for (rel, te) in c.tes() {
+            // `rel` has already been transformed, so we need to undo that here.
+            let p = chunk_pos.block()
+              + Pos::new(rel.x().into(), rel.y() + self.min_y, rel.z().into())
+              - min;
+            let id = match schem.get(p) {
+              Some(id) => id,
+              None => continue,
+            };
+            let kind = self.block_converter.kind_from_id(id, BlockVersion::latest());
+            let nbt = self
+              .world_manager()
+              .block_behaviors()
+              .call(kind, |b| b.save_te_nbt(te.as_ref()))
+              .flatten();
+            if let Some(nbt) = nbt {
+              schem.tes.insert(p, nbt);
+            }
+          }
+        });
+      }
+    }
+
+    Ok(schem)
+  }
+}
+
+/// Parses a block state, like `minecraft:oak_stairs[facing=north]`. Unknown
+/// blocks are replaced with air, and unknown properties are ignored.
+fn parse_block(conv: &block::TypeConverter, state: &str) -> u32 {
+  let (name, props) = match state.split_once('[') {
+    Some((name, props)) => (name, props.strip_suffix(']').unwrap_or(props)),
+    None => (state, ""),
+  };
+  let kind = match block::Kind::from_str(name.strip_prefix("minecraft:").unwrap_or(name)) {
+    Ok(kind) => kind,
+    Err(_) => {
+      warn!("unknown block {name}, replacing with air");
+      return 0;
+    }
+  };
+  let mut ty = conv.get(kind).default_type();
+  for prop in props.split(',').filter(|p| !p.is_empty()) {
+    let valid = match prop.split_once('=') {
+      Some((key, val)) => ty.try_set_prop_str(key, val),
+      None => false,
+    };
+    if !valid {
+      debug!("ignoring invalid property {prop} on {name}");
     }
-    _ => panic!("invalid schematic file {}: unknown material {}", path, material),
   }
+  ty.id()
+}
 
-  Ok(())
+fn read_varint(data: &mut &[u8]) -> Option<u32> {
+  let mut out = 0;
+  for i in 0..5 {
+    let (&b, rest) = data.split_first()?;
+    *data = rest;
+    out |= ((b & 0x7f) as u32) << (i * 7);
+    if b & 0x80 == 0 {
+      return Some(out);
+    }
+  }
+  None
 }
 
-fn convert_alpha_name(name: String) -> String {
-  match name.as_str() {
-    "grass" => "grass_block",
-    _ => return name,
+fn write_varint(data: &mut Vec<u8>, mut v: u32) {
+  loop {
+    if v & !0x7f == 0 {
+      data.push(v as u8);
+      return;
+    }
+    data.push((v & 0x7f) as u8 | 0x80);
+    v >>= 7;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn varint() {
+    for v in [0, 1, 127, 128, 300, 16384, u32::MAX] {
+      let mut data = vec![];
+      write_varint(&mut data, v);
+      assert_eq!(read_varint(&mut data.as_slice()), Some(v));
+    }
+  }
+
+  #[test]
+  fn transform() {
+    let opts = PasteOptions { rotation: Rotation::Clockwise90, ..Default::default() };
+    assert_eq!(opts.transform_pos(Pos::new(0, 5, -1)), Pos::new(1, 5, 0));
+    assert_eq!(opts.transform_dir("north"), Some("east"));
+    assert_eq!(opts.transform_dir("up"), None);
+
+    let opts = PasteOptions { mirror: Mirror::X, ..Default::default() };
+    assert_eq!(opts.transform_pos(Pos::new(2, 0, 3)), Pos::new(-2, 0, 3));
+    assert_eq!(opts.transform_dir("east"), Some("west"));
+    assert_eq!(opts.transform_dir("north"), Some("north"));
+
+    let opts =
+      PasteOptions { rotation: Rotation::Clockwise180, mirror: Mirror::Z, skip_air: false };
+    assert_eq!(opts.transform_dir("north"), Some("north"));
+    assert_eq!(opts.transform_dir("east"), Some("west"));
+  }
+
+  #[test]
+  fn too_large() {
+    assert_eq!(volume(2, 3, 4).unwrap(), 24);
+    assert!(matches!(volume(65535, 65535, 65535), Err(SchematicError::TooLarge)));
+
+    let conv = block::TypeConverter::new();
+    // The size is stored as a short, so -1 is 65535.
+    let root = Tag::compound(&[
+      ("Version", Tag::Int(2)),
+      ("Width", Tag::Short(-1)),
+      ("Height", Tag::Short(-1)),
+      ("Length", Tag::Short(-1)),
+      ("Palette", Tag::compound(&[("minecraft:air", Tag::Int(0))])),
+      ("BlockData", Tag::ByteArr(vec![0; 8])),
+    ]);
+    let res = Schematic::from_nbt(root.as_compound().unwrap(), &conv);
+    assert!(matches!(res, Err(SchematicError::TooLarge)));
   }
-  .into()
 }
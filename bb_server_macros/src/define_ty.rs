@@ -29,14 +29,23 @@ pub fn define_ty(args: TokenStream, input: TokenStream) -> TokenStream {
       _ => abort!(v, "unknown arg {:?}", v),
     }
   }
-  let block = parse_macro_input!(input as ItemImpl);
+  let mut block = parse_macro_input!(input as ItemImpl);
   let ty = &block.self_ty;
   let mut python_funcs = vec![];
-  for it in &block.items {
+  for it in &mut block.items {
     match it {
       syn::ImplItem::Method(method) => {
+        // Functions marked with `#[panda_only]` use things that only exist in
+        // Panda (like panda callbacks), so they are not added to python. The
+        // attribute must be removed, as `panda::define_ty` doesn't know about it.
+        let len = method.attrs.len();
+        method.attrs.retain(|a| !a.path.is_ident("panda_only"));
+        if method.attrs.len() != len {
+          continue;
+        }
         let name = &method.sig.ident;
         let py_name = Ident::new(&format!("py_{}", method.sig.ident), name.span());
+        let name_str = name.to_string();
         let py_args = python_args(method.sig.inputs.iter());
         let py_arg_names = python_arg_names(method.sig.inputs.iter());
         let (py_ret, conv_ret) = python_ret(&method.sig.output);
@@ -50,14 +59,17 @@ pub fn define_ty(args: TokenStream, input: TokenStream) -> TokenStream {
         } else if method.sig.receiver().is_none() {
           python_funcs.push(quote!(
             #[staticmethod]
-            fn #py_name(#(#py_args),*) {
-              // Self::#name(#arg_names)
+            #[pyo3(name = #name_str)]
+            fn #py_name(#(#py_args),*) #py_ret {
+              Self::#name(#(#py_arg_names),*) #conv_ret
             }
           ));
         } else {
+          let py_arg_names = py_arg_names.iter().skip(1);
           python_funcs.push(quote!(
-            fn #py_name(#(#py_args),*) {
-              // Self::#name(#arg_names)
+            #[pyo3(name = #name_str)]
+            fn #py_name(#(#py_args),*) #py_ret {
+              self.#name(#(#py_arg_names),*) #conv_ret
             }
           ));
         }
@@ -87,6 +99,45 @@ pub fn define_ty(args: TokenStream, input: TokenStream) -> TokenStream {
   out.into()
 }
 
+/// Returns true if the given type is one of the plugin types (like `PPos`),
+/// which are all python classes.
+fn is_plugin_ty(name: &str) -> bool {
+  name == "Self"
+    || name == "Bamboo"
+    || (name.starts_with('P') && name.chars().nth(1).map_or(false, |c| c.is_ascii_uppercase()))
+}
+
+fn last_ident(path: &syn::TypePath) -> String {
+  path.path.segments.last().unwrap().ident.to_string()
+}
+
+fn is_value_ty(name: &str) -> bool {
+  matches!(
+    name,
+    "u8"
+      | "i8"
+      | "u16"
+      | "i16"
+      | "u32"
+      | "i32"
+      | "u64"
+      | "i64"
+      | "u128"
+      | "i128"
+      | "f32"
+      | "f64"
+      | "bool"
+      | "String"
+      | "Vec"
+  )
+}
+
+const UNSUPPORTED: &str =
+  "cannot pass this type to python. add `#[panda_only]` to the function if it should not be \
+   callable from python";
+
+/// Returns the arguments for the python function. This will fail to compile if
+/// any of the arguments cannot be passed from python.
 fn python_args<'a>(args: impl Iterator<Item = &'a FnArg>) -> Vec<impl quote::ToTokens> {
   args
     .map(|a| match a {
@@ -94,25 +145,23 @@ fn python_args<'a>(args: impl Iterator<Item = &'a FnArg>) -> Vec<impl quote::ToT
       FnArg::Typed(ty) => {
         let name = &ty.pat;
         match &*ty.ty {
-          Type::Path(path) => match path.path.segments[0].ident.to_string().as_str() {
-            "u8" | "i8" | "u16" | "i16" | "u32" | "i32" | "u64" | "i64" | "u128" | "i128"
-            | "f32" | "f64" | "Vec" => {
-              quote!(#name: #path)
-            }
+          Type::Path(path) => match last_ident(path).as_str() {
+            n if is_value_ty(n) || is_plugin_ty(n) => quote!(#name: #path),
             // Assume this is a Box<dyn Callback>
             "Box" => quote!(#name: ::pyo3::PyObject),
-            "Var" => quote!(#name: i32),
-            "Callback" => quote!(#name: Callback),
-            _ => abort!(ty.ty, "cannot handle type"),
+            "Var" => quote!(#name: &::pyo3::PyAny),
+            _ => abort!(ty.ty, UNSUPPORTED),
           },
-          Type::Reference(path) => match &*path.elem {
-            Type::Path(path) => match path.path.segments[0].ident.to_string().as_str() {
+          Type::Reference(r) if r.mutability.is_none() => match &*r.elem {
+            Type::Path(path) => match last_ident(path).as_str() {
               "str" => quote!(#name: String),
-              _ => quote!(#name: #path),
+              // Plugin types are all `Clone`, so we can take them by value.
+              n if is_plugin_ty(n) => quote!(#name: #path),
+              _ => abort!(ty.ty, UNSUPPORTED),
             },
-            _ => abort!(ty.ty, "cannot handle type"),
+            _ => abort!(ty.ty, UNSUPPORTED),
           },
-          _ => abort!(ty.ty, "cannot handle type"),
+          _ => abort!(ty.ty, UNSUPPORTED),
         }
       }
     })
@@ -125,23 +174,12 @@ fn python_arg_names<'a>(args: impl Iterator<Item = &'a FnArg>) -> Vec<impl quote
       FnArg::Typed(ty) => {
         let name = &ty.pat;
         match &*ty.ty {
-          Type::Path(path) => match path.path.segments[0].ident.to_string().as_str() {
-            "u8" | "i8" | "u16" | "i16" | "u32" | "i32" | "u64" | "i64" | "u128" | "i128"
-            | "f32" | "f64" | "Vec" => {
-              quote!(#name)
-            }
+          Type::Path(path) => match last_ident(path).as_str() {
             "Box" => quote!(Box::new(#name)),
-            "Var" => quote!(#name),
-            "Callback" => quote!(#name),
-            _ => abort!(ty.ty, "cannot handle type"),
-          },
-          Type::Reference(path) => match &*path.elem {
-            Type::Path(path) => match path.path.segments[0].ident.to_string().as_str() {
-              "str" => quote!(#name.as_str()),
-              _ => quote!(#name),
-            },
-            _ => abort!(ty.ty, "cannot handle type"),
+            "Var" => quote!(crate::plugin::python::var_from_py(#name)),
+            _ => quote!(#name),
           },
+          Type::Reference(_) => quote!(&#name),
           _ => abort!(ty.ty, "cannot handle type"),
         }
       }
@@ -149,28 +187,39 @@ fn python_arg_names<'a>(args: impl Iterator<Item = &'a FnArg>) -> Vec<impl quote
     .collect()
 }
 
+/// Returns the return type of the python function, and any conversion that
+/// needs to happen on the returned value. This will fail to compile if the
+/// return type cannot be passed to python.
 fn python_ret(out: &ReturnType) -> (impl quote::ToTokens, Option<impl quote::ToTokens>) {
-  (
-    match &out {
-      ReturnType::Type(_, ty) => match &**ty {
-        Type::Path(path) => match path.path.segments[0].ident.to_string().as_str() {
-          "Result" => {
-            let arg = match &path.path.segments[0].arguments {
-              PathArguments::AngleBracketed(args) => args.args.first().cloned().unwrap(),
-              _ => unreachable!(),
-            };
-            return (
-              quote!(-> ::pyo3::PyResult<#arg>),
-              Some(quote!(.map_err(crate::plugin::python::conv_err))),
-            );
-          }
-          "Var" => quote!(-> ::pyo3::PyObject),
-          _ => quote!(#out),
-        },
-        _ => abort!(out, "cannot handle ret"),
-      },
-      _ => quote!(),
-    },
-    None,
-  )
+  let ty = match out {
+    ReturnType::Type(_, ty) => ty,
+    ReturnType::Default => return (quote!(), None),
+  };
+  let path = match &**ty {
+    Type::Path(path) => path,
+    Type::Tuple(t) if t.elems.is_empty() => return (quote!(), None),
+    _ => abort!(ty, UNSUPPORTED),
+  };
+  match last_ident(path).as_str() {
+    "Result" => {
+      let arg = match &path.path.segments.last().unwrap().arguments {
+        PathArguments::AngleBracketed(args) => args.args.first().cloned().unwrap(),
+        _ => unreachable!(),
+      };
+      let supported = match &arg {
+        syn::GenericArgument::Type(Type::Tuple(t)) => t.elems.is_empty(),
+        syn::GenericArgument::Type(Type::Path(p)) => {
+          let n = last_ident(p);
+          is_value_ty(&n) || is_plugin_ty(&n)
+        }
+        _ => false,
+      };
+      if !supported {
+        abort!(arg, UNSUPPORTED);
+      }
+      (quote!(-> ::pyo3::PyResult<#arg>), Some(quote!(.map_err(crate::plugin::python::conv_err))))
+    }
+    n if is_value_ty(n) || is_plugin_ty(n) => (quote!(#out), None),
+    _ => abort!(ty, UNSUPPORTED),
+  }
 }
@@ -7,7 +7,7 @@ use std::{
   hash::{BuildHasher, Hash},
   io::Write,
   marker::PhantomData,
-  net::{Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6},
+  net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6},
 };
 
 impl<T> MessageWrite for &T
@@ -269,6 +269,35 @@ impl<T> MessageWrite for PhantomData<T> {
   }
 }
 
+impl MessageRead<'_> for IpAddr {
+  fn read(m: &mut MessageReader) -> Result<Self, ReadError> { m.read_enum() }
+}
+
+impl EnumRead<'_> for IpAddr {
+  fn read_enum(mut m: EnumReader) -> Result<Self, ReadError> {
+    match m.variant() {
+      0 => Ok(IpAddr::V4(m.must_read(0)?)),
+      1 => Ok(IpAddr::V6(m.must_read(0)?)),
+      _ => Err(m.invalid_variant()),
+    }
+  }
+}
+impl MessageWrite for IpAddr {
+  fn write<W: Write>(&self, m: &mut MessageWriter<W>) -> Result<(), WriteError> {
+    m.write_enum(
+      match self {
+        IpAddr::V4(_) => 0,
+        IpAddr::V6(_) => 1,
+      },
+      1,
+      |m| match self {
+        IpAddr::V4(addr) => m.write(addr),
+        IpAddr::V6(addr) => m.write(addr),
+      },
+    )
+  }
+}
+
 impl MessageRead<'_> for SocketAddr {
   fn read(m: &mut MessageReader) -> Result<Self, ReadError> { m.read_enum() }
 }
@@ -1,2 +1,15 @@
-def init():
+import bamboo
+
+def init(bb):
     print("Hello from python!")
+    bb.add_command(bamboo.Command("pyhello", on_hello))
+
+def on_hello(bb, player, args):
+    if player is not None:
+        player.send_message(bamboo.Chat("Hello from python!"))
+
+def on_player_join(bb, player):
+    print(player.username() + " joined")
+
+def on_block_place(bb, player, pos, kind):
+    return True
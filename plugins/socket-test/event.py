@@ -5,8 +5,11 @@ def read(b):
     match blob["kind"]:
         case "Event":
             match blob["type"]:
-                case "BlockPlace": return BlockPlace.from_json(blob)
                 case other: print("unknown event " + other)
+        case "Request":
+            match blob["type"]:
+                case "BlockPlace": return BlockPlace.from_json(blob)
+                case other: print("unknown request " + other)
         case "Reply":
             reply_id = blob["reply_id"]
             match blob["type"]:
@@ -41,6 +44,17 @@ class SendChat(Event):
             "text": self.text,
         }
 
+class Register(Event):
+    def __init__(self, ty):
+        self.ty = ty
+
+    def to_json(self):
+        return {
+            "kind": "Event",
+            "type": "Register",
+            "ty": self.ty,
+        }
+
 class GetBlock(Request):
     def __init__(self, reply_id, pos):
         super().__init__(reply_id)
@@ -3,6 +3,7 @@ import server
 
 server = server.Server()
 
+server.send(event.Register("BlockPlace"))
 server.send(event.Ready())
 
 while True: